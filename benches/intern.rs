@@ -0,0 +1,79 @@
+//! Benchmarks of interning the strings in the constant pool of `java/lang/String`, with a fresh
+//! `Interner` each time, as if every class were loaded by a new class loader, and with one which
+//! has already seen them, as when a class loader loads many classes naming the same classes,
+//! methods and descriptors.
+//!
+//! Before the timings, the number of allocations made by a single run of each is printed: with an
+//! interner which has already seen the strings, interning them allocates nothing.
+
+#[macro_use]
+extern crate criterion;
+extern crate rust_jvm;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::Criterion;
+
+use rust_jvm::intern::Interner;
+use rust_jvm::model::class_file::constant_pool::{ConstantPool, ConstantPoolInfo};
+use rust_jvm::parser::class_file::parse_class_file;
+use rust_jvm::util::modified_utf8;
+
+/// The global allocator, counting every allocation made.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations made by `f`.
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Interns every `Utf8` entry of the constant pool, as the class loader does when it creates the
+/// runtime constant pool of a class.
+fn intern_strings(constant_pool: &ConstantPool, interner: &mut Interner) {
+    for info in constant_pool {
+        if let ConstantPoolInfo::Utf8 { ref bytes } = *info {
+            criterion::black_box(interner.intern(&modified_utf8::decode(bytes).unwrap()));
+        }
+    }
+}
+
+fn bench_intern(c: &mut Criterion) {
+    let class_file = parse_class_file(include_bytes!("../data/String.class")).unwrap();
+    let constant_pool = &class_file.constant_pool;
+    let mut interner = Interner::new();
+    intern_strings(constant_pool, &mut interner);
+
+    println!("allocations interning into a new interner: {}",
+             count_allocations(|| intern_strings(constant_pool, &mut Interner::new())));
+    println!("allocations interning into a used interner: {}",
+             count_allocations(|| intern_strings(constant_pool, &mut interner)));
+
+    c.bench_function("intern String.class into a new interner", |b| {
+        b.iter(|| intern_strings(constant_pool, &mut Interner::new()))
+    });
+    c.bench_function("intern String.class into a used interner", |b| {
+        b.iter(|| intern_strings(constant_pool, &mut interner))
+    });
+}
+
+criterion_group!(benches, bench_intern);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+abstract class Abstract {
+    abstract int area();
+
+    native int perimeter();
+}
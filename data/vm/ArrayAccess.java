@@ -0,0 +1,35 @@
+class ArrayAccess {
+    static int fill() {
+        int[] array = new int[10];
+        for (int i = 0; i < array.length; i++) {
+            array[i] = i * i;
+        }
+        int sum = 0;
+        for (int i = 0; i < array.length; i++) {
+            sum += array[i];
+        }
+        return sum;
+    }
+
+    static int read(int[] array, int index) {
+        return array[index];
+    }
+
+    static int outOfBounds() {
+        return read(new int[3], 5);
+    }
+
+    static int storeOutOfBounds() {
+        int[] array = new int[3];
+        array[-1] = 7;
+        return 0;
+    }
+
+    static int catchOutOfBounds() {
+        try {
+            return outOfBounds();
+        } catch (ArrayIndexOutOfBoundsException e) {
+            return -1;
+        }
+    }
+}
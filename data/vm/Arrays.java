@@ -0,0 +1,140 @@
+class Arrays {
+    static int intArray() {
+        int[] array = new int[5];
+        array[3] = 42;
+        return array[3] + array.length;
+    }
+
+    static int objectArray() {
+        Arrays[] array = new Arrays[3];
+        array[1] = new Arrays();
+        return array.length + (array[0] == null ? 10 : 0) + (array[1] != null ? 100 : 0);
+    }
+
+    static int multiArray() {
+        int[][] array = new int[3][4];
+        array[2][3] = 7;
+        return array.length * 100 + array[2].length * 10 + array[2][3];
+    }
+
+    static int partialMultiArray() {
+        int[][][] array = new int[2][3][];
+        return array.length * 10 + array[1].length + (array[1][2] == null ? 100 : 0);
+    }
+
+    static int negative() {
+        try {
+            return new int[-1].length;
+        } catch (NegativeArraySizeException e) {
+            return -1;
+        }
+    }
+
+    static int negativeObject() {
+        try {
+            return new Arrays[-2].length;
+        } catch (NegativeArraySizeException e) {
+            return -2;
+        }
+    }
+
+    static int negativeMulti() {
+        try {
+            return new int[0][-1].length;
+        } catch (NegativeArraySizeException e) {
+            return -3;
+        }
+    }
+
+    static int negativeUncaught() {
+        return new int[-5].length;
+    }
+
+    static int copy() {
+        int[] array = { 1, 2, 3, 4 };
+        System.arraycopy(array, 0, array, 1, 3);
+        return array[0] * 1000 + array[1] * 100 + array[2] * 10 + array[3];
+    }
+
+    static int copyToNonArray() {
+        try {
+            System.arraycopy(new int[1], 0, "x", 0, 1);
+            return 0;
+        } catch (ArrayStoreException e) {
+            String message = "arraycopy: destination type java.lang.String is not an array";
+            return message.equals(e.getMessage()) ? -1 : -2;
+        }
+    }
+
+    static int copyFromNonArray() {
+        try {
+            System.arraycopy(new Object(), 0, new int[1], 0, 1);
+            return 0;
+        } catch (ArrayStoreException e) {
+            String message = "arraycopy: source type java.lang.Object is not an array";
+            return message.equals(e.getMessage()) ? -1 : -2;
+        }
+    }
+
+    static int copyNull() {
+        try {
+            System.arraycopy(null, 0, new int[1], 0, 1);
+            return 0;
+        } catch (NullPointerException e) {
+            return -1;
+        }
+    }
+
+    static int copyNegativeSource() {
+        System.arraycopy(new int[3], -1, new int[3], 0, 1);
+        return 0;
+    }
+
+    static int copyNegativeDestination() {
+        System.arraycopy(new int[3], 0, new int[3], -2, 1);
+        return 0;
+    }
+
+    static int copyNegativeLength() {
+        System.arraycopy(new int[3], 0, new int[3], 0, -1);
+        return 0;
+    }
+
+    static int copyPastSource() {
+        System.arraycopy(new int[3], 2, new int[5], 0, 2);
+        return 0;
+    }
+
+    static int copyPastDestination() {
+        System.arraycopy(new Object[5], 0, new Object[3], 2, 2);
+        return 0;
+    }
+
+    static int copyPrimitiveMismatch() {
+        System.arraycopy(new int[1], 0, new long[1], 0, 1);
+        return 0;
+    }
+
+    static int copyPrimitiveToObjects() {
+        System.arraycopy(new int[1], 0, new Object[1], 0, 1);
+        return 0;
+    }
+
+    static int copyElementMismatch() {
+        Object[] src = { "a", new Object(), "c" };
+        String[] dest = new String[3];
+        System.arraycopy(src, 0, dest, 0, 3);
+        return 0;
+    }
+
+    static int copyElementMismatchPartially() {
+        Object[] src = { "a", new Object(), "c" };
+        String[] dest = new String[3];
+        try {
+            System.arraycopy(src, 0, dest, 0, 3);
+            return 0;
+        } catch (ArrayStoreException e) {
+            return dest[0] == src[0] && dest[1] == null && dest[2] == null ? -1 : -2;
+        }
+    }
+}
@@ -0,0 +1,11 @@
+class BrokenException extends RuntimeException {
+    BrokenException(String message) {
+        super(message);
+        throw new ArithmeticException("broken");
+    }
+}
+
+class SilentException extends RuntimeException {
+    SilentException() {
+    }
+}
@@ -0,0 +1,50 @@
+interface CastsShape {}
+
+class CastsA {}
+
+class CastsB extends CastsA implements CastsShape {}
+
+class CastsC extends CastsB {}
+
+class Casts {
+    static int instanceOf() {
+        Object a = new CastsA();
+        Object c = new CastsC();
+        Object n = null;
+        Object ints = new int[1];
+        Object cs = new CastsC[1];
+        int result = 0;
+        if (a instanceof CastsA) result |= 1;
+        if (a instanceof CastsB) result |= 2;
+        if (c instanceof CastsA) result |= 4;
+        if (c instanceof CastsShape) result |= 8;
+        if (a instanceof CastsShape) result |= 16;
+        if (n instanceof Object) result |= 32;
+        if (ints instanceof int[]) result |= 64;
+        if (ints instanceof Object) result |= 128;
+        if (cs instanceof CastsA[]) result |= 256;
+        if (cs instanceof CastsShape[]) result |= 512;
+        if (ints instanceof long[]) result |= 1024;
+        if (cs instanceof int[]) result |= 2048;
+        return result;
+    }
+
+    static int checkCastNull() {
+        Object n = null;
+        CastsB b = (CastsB) n;
+        return b == null ? 1 : 0;
+    }
+
+    static int checkCastSubclass() {
+        Object c = new CastsC();
+        CastsA a = (CastsA) c;
+        CastsShape s = (CastsShape) c;
+        return a == s ? 1 : 0;
+    }
+
+    static int checkCastSuperclass() {
+        Object a = new CastsA();
+        CastsB b = (CastsB) a;
+        return 0;
+    }
+}
@@ -0,0 +1,24 @@
+public class ClassLiterals {
+    public static String name() {
+        return ClassLiterals.class.getName();
+    }
+
+    public static String arrayName() {
+        return String[].class.getName();
+    }
+
+    public static String getClassName() {
+        return new ClassLiterals().getClass().getName();
+    }
+
+    public static boolean same() {
+        return ClassLiterals.class == new ClassLiterals().getClass();
+    }
+
+    public static String otherName() {
+        return ClassLiteralsOther.class.getName();
+    }
+}
+
+class ClassLiteralsOther {
+}
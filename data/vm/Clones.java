@@ -0,0 +1,41 @@
+class Clones implements Cloneable {
+    int x;
+    int y;
+
+    static int cloneObject() throws CloneNotSupportedException {
+        Clones original = new Clones();
+        original.x = 3;
+        original.y = 4;
+        Clones copy = (Clones) original.clone();
+        copy.x = 5;
+        if (copy == original) {
+            return -1;
+        }
+        return original.x * 1000 + original.y * 100 + copy.x * 10 + copy.y;
+    }
+
+    static int cloneArray() {
+        int[] original = { 1, 2 };
+        int[] copy = original.clone();
+        copy[0] = 3;
+        if (copy == original) {
+            return -1;
+        }
+        return original[0] * 1000 + original[1] * 100 + copy[0] * 10 + copy[1];
+    }
+
+    static int cloneUncloneable() {
+        try {
+            new Uncloneable().copy();
+            return 0;
+        } catch (CloneNotSupportedException e) {
+            return "Uncloneable".equals(e.getMessage()) ? -1 : -2;
+        }
+    }
+}
+
+class Uncloneable {
+    Object copy() throws CloneNotSupportedException {
+        return clone();
+    }
+}
@@ -0,0 +1,114 @@
+class Exceptions {
+    static int catchLocal() {
+        int x = 1;
+        try {
+            x = 2;
+            throw new RuntimeException();
+        } catch (RuntimeException e) {
+            return x * 10;
+        }
+    }
+
+    static void thrower() {
+        throw new RuntimeException();
+    }
+
+    static int catchFromCallee() {
+        try {
+            thrower();
+            return 0;
+        } catch (RuntimeException e) {
+            return 1;
+        }
+    }
+
+    static int skipNonMatching() {
+        try {
+            try {
+                thrower();
+            } catch (ExceptionsOther e) {
+                return 1;
+            }
+            return 2;
+        } catch (RuntimeException e) {
+            return 3;
+        }
+    }
+
+    static int rethrow() {
+        try {
+            thrower();
+        } catch (RuntimeException e) {
+            throw e;
+        }
+        return 0;
+    }
+
+    static int catchRethrown() {
+        try {
+            return rethrow();
+        } catch (Throwable t) {
+            return 4;
+        }
+    }
+
+    static int finallyRuns() {
+        int x = 0;
+        try {
+            try {
+                thrower();
+            } finally {
+                x = 5;
+            }
+        } catch (RuntimeException e) {
+            x += 1;
+        }
+        return x;
+    }
+
+    static int uncaught() {
+        thrower();
+        return 0;
+    }
+
+    static int throwNull() {
+        RuntimeException e = null;
+        throw e;
+    }
+
+    int value() {
+        return 1;
+    }
+
+    static int invokeNull() {
+        Exceptions e = null;
+        return e.value();
+    }
+
+    static int catchInvokeNull() {
+        try {
+            return invokeNull();
+        } catch (NullPointerException e) {
+            return 6;
+        }
+    }
+
+    static int catchArrayLengthNull() {
+        int[] array = null;
+        try {
+            return array.length;
+        } catch (NullPointerException e) {
+            return 7;
+        }
+    }
+
+    static int synchronizeNull() {
+        Object lock = null;
+        synchronized (lock) {
+            return 1;
+        }
+    }
+}
+
+class ExceptionsOther extends RuntimeException {
+}
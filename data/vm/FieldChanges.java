@@ -0,0 +1,40 @@
+// FieldChanges was compiled against an earlier version of FieldChangesTarget, which declared
+//
+//     static int missing;
+//     static int counter;
+//     int value;
+//     int gone;
+//
+// so that every access below is now incompatible with FieldChangesTarget.
+class FieldChanges {
+    static int getMissing() {
+        return FieldChangesTarget.missing;
+    }
+
+    static int putMissing() {
+        FieldChangesTarget.missing = 1;
+        return 0;
+    }
+
+    static int getStaticOfInstance() {
+        return FieldChangesTarget.counter;
+    }
+
+    static int putStaticOfInstance() {
+        FieldChangesTarget.counter = 1;
+        return 0;
+    }
+
+    static int getFieldOfStatic() {
+        return new FieldChangesTarget().value;
+    }
+
+    static int putFieldOfStatic() {
+        new FieldChangesTarget().value = 1;
+        return 0;
+    }
+
+    static int getFieldMissing() {
+        return new FieldChangesTarget().gone;
+    }
+}
@@ -0,0 +1,4 @@
+class FieldChangesTarget {
+    int counter;
+    static int value;
+}
@@ -0,0 +1,60 @@
+interface FieldsTable {
+    int[] TABLE = { 1, 2, 3 };
+}
+
+class Fields {
+    static int counter;
+    static Object object;
+    int value;
+
+    static int increment() {
+        counter++;
+        return counter;
+    }
+
+    static int incrementTwice() {
+        increment();
+        return increment();
+    }
+
+    static int instance() {
+        Fields fields = new Fields();
+        fields.value = 5;
+        fields.value += 3;
+        return fields.value;
+    }
+
+    static int inherited() {
+        FieldsSub.counter = 10;
+        FieldsSub sub = new FieldsSub();
+        sub.value = 2;
+        return counter + sub.value;
+    }
+
+    static int interfaceField() {
+        return FieldsSub.TABLE[1];
+    }
+
+    static Object storeObject() {
+        object = new Object();
+        return object;
+    }
+
+    static Object loadObject() {
+        return object;
+    }
+
+    static int getNull() {
+        Fields fields = null;
+        return fields.value;
+    }
+
+    static int putNull() {
+        Fields fields = null;
+        fields.value = 1;
+        return 0;
+    }
+}
+
+class FieldsSub extends Fields implements FieldsTable {
+}
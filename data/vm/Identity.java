@@ -0,0 +1,32 @@
+class Identity {
+    static int equalsSelf() {
+        Object o = new Object();
+        return o.equals(o) ? 1 : 0;
+    }
+
+    static int equalsOther() {
+        Object o = new Object();
+        Object other = new Object();
+        return o.equals(other) ? 1 : 0;
+    }
+
+    static int equalsNull() {
+        return new Object().equals(null) ? 1 : 0;
+    }
+
+    static int hashCodeConsistent() {
+        Object o = new Object();
+        return o.hashCode() == o.hashCode() ? 1 : 0;
+    }
+
+    static int hashCodeDistinct() {
+        Object o = new Object();
+        Object other = new Object();
+        return o.hashCode() != other.hashCode() ? 1 : 0;
+    }
+
+    static int arrayHashCode() {
+        int[] a = new int[1];
+        return a.hashCode() == a.hashCode() && a.equals(a) ? 1 : 0;
+    }
+}
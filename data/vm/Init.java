@@ -0,0 +1,45 @@
+class Init {
+    static int value;
+    static int observed;
+    static int initializations;
+
+    static {
+        initializations++;
+        value = 6 * 7;
+        observed = InitOther.read();
+    }
+
+    static int getValue() {
+        return value;
+    }
+
+    static int getObserved() {
+        return observed;
+    }
+
+    static int getInitializations() {
+        return initializations;
+    }
+}
+
+class InitOther {
+    // Init is still being initialized when this runs, so this is a recursive request to
+    // initialize Init, which must see the value it has set so far
+    static int seen = Init.value;
+
+    static int read() {
+        return seen;
+    }
+}
+
+class InitSub extends Init {
+    static int seven() {
+        return 7;
+    }
+}
+
+class InitCaller {
+    static int callSubclass() {
+        return InitSub.seven();
+    }
+}
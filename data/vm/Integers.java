@@ -0,0 +1,45 @@
+class Integers {
+    static int parse() {
+        return Integer.parseInt("-123") + Integer.parseInt("+ff", 16);
+    }
+
+    static int parseInvalid() {
+        try {
+            Integer.parseInt("12a");
+            return 0;
+        } catch (NumberFormatException e) {
+            return 1;
+        }
+    }
+
+    static int parseOverflow() {
+        try {
+            Integer.parseInt("2147483648");
+            return 0;
+        } catch (NumberFormatException e) {
+            return 1;
+        }
+    }
+
+    static int parseInvalidRadix() {
+        try {
+            Integer.parseInt("1", 37);
+            return 0;
+        } catch (NumberFormatException e) {
+            return 1;
+        }
+    }
+
+    static int valueOfCached() {
+        return Integer.valueOf(-128) == Integer.valueOf(-128)
+            && Integer.valueOf(127) == Integer.valueOf(127) ? 1 : 0;
+    }
+
+    static int valueOfUncached() {
+        return Integer.valueOf(128) != Integer.valueOf(128) ? 1 : 0;
+    }
+
+    static int intValue() {
+        return Integer.valueOf(-5).intValue() + Integer.valueOf(1000).intValue();
+    }
+}
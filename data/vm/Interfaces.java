@@ -0,0 +1,55 @@
+interface InterfacesShape {
+    int sides();
+
+    default int corners() {
+        return sides();
+    }
+}
+
+interface InterfacesPolygon extends InterfacesShape {}
+
+class InterfacesTriangle implements InterfacesPolygon {
+    public int sides() {
+        return 3;
+    }
+}
+
+class InterfacesSquare implements InterfacesShape {
+    public int sides() {
+        return 4;
+    }
+
+    public int corners() {
+        return 40;
+    }
+}
+
+class InterfacesBigTriangle extends InterfacesTriangle {}
+
+abstract class InterfacesAbstract implements InterfacesShape {}
+
+class Interfaces {
+    static int sides(InterfacesShape shape) {
+        return shape.sides();
+    }
+
+    static int corners(InterfacesShape shape) {
+        return shape.corners();
+    }
+
+    static int polymorphic() {
+        return sides(new InterfacesTriangle()) * 10 + sides(new InterfacesSquare());
+    }
+
+    static int defaultMethod() {
+        return corners(new InterfacesBigTriangle()) * 100 + corners(new InterfacesSquare());
+    }
+
+    static int nullReceiver() {
+        return sides(null);
+    }
+
+    static int abstractSides(InterfacesAbstract shape) {
+        return shape.sides();
+    }
+}
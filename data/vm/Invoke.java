@@ -0,0 +1,27 @@
+class Invoke {
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    static int callStatic() {
+        return add(3, 4) * 2;
+    }
+
+    int describe() {
+        return 1;
+    }
+
+    static int describe(Invoke invoke) {
+        return invoke.describe();
+    }
+
+    static int callVirtual() {
+        return describe(new Invoke()) * 10 + describe(new InvokeSub());
+    }
+}
+
+class InvokeSub extends Invoke {
+    int describe() {
+        return 2;
+    }
+}
@@ -0,0 +1,21 @@
+class Literals {
+    static int integer() {
+        return 100000;
+    }
+
+    static float floating() {
+        return -1.5f;
+    }
+
+    static long wide() {
+        return -1234567890123L;
+    }
+
+    static double precise() {
+        return 0.1;
+    }
+
+    static String string() {
+        return "h\u00e9llo, \u4e16\u754c\ud83d\ude00";
+    }
+}
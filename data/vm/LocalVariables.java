@@ -0,0 +1,14 @@
+class LocalVariables {
+    static int sum(int n) {
+        int total = 0;
+        for (int i = 0; i < n; i++) {
+            total += i;
+        }
+        return total;
+    }
+
+    static <T> T first(T[] items) {
+        T item = items[0];
+        return item;
+    }
+}
@@ -0,0 +1,13 @@
+class Maths {
+    static int absMinValue() {
+        return Math.abs(0x80000000) == 0x80000000 ? 1 : 0;
+    }
+
+    static int roundings() {
+        return (int) Math.floor(Math.sqrt(10.0)) * 10 + (int) Math.ceil(Math.pow(2.0, 0.5));
+    }
+
+    static int maxMin() {
+        return (int) Math.max(3L, Math.min(7L, 5L)) * 10 + Math.max(-1, -2);
+    }
+}
@@ -0,0 +1,27 @@
+class Monitors {
+    static int count;
+
+    static int increment(Object lock) {
+        synchronized (lock) {
+            count++;
+        }
+        return count;
+    }
+
+    static int throwing(Object lock) {
+        try {
+            synchronized (lock) {
+                throw new RuntimeException();
+            }
+        } catch (RuntimeException e) {
+            return -1;
+        }
+    }
+
+    static int nullLock() {
+        Object lock = null;
+        synchronized (lock) {
+            return 0;
+        }
+    }
+}
@@ -0,0 +1,9 @@
+class Natives {
+    static native int answer();
+
+    native int plus(int n);
+
+    static int callNatives() {
+        return new Natives().plus(answer());
+    }
+}
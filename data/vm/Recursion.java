@@ -0,0 +1,13 @@
+class Recursion {
+    static int fib(int n) {
+        return n < 2 ? n : fib(n - 1) + fib(n - 2);
+    }
+
+    static int overflow() {
+        try {
+            return fib(100000);
+        } catch (StackOverflowError e) {
+            return -1;
+        }
+    }
+}
@@ -0,0 +1,35 @@
+interface ResolutionNamed {
+    default int name() {
+        return 11;
+    }
+}
+
+class ResolutionBase {
+    int value() {
+        return 7;
+    }
+
+    static int twice(int n) {
+        return 2 * n;
+    }
+}
+
+class ResolutionMiddle extends ResolutionBase implements ResolutionNamed {}
+
+class ResolutionLeaf extends ResolutionMiddle {}
+
+class Resolution {
+    static int inherited() {
+        ResolutionLeaf leaf = new ResolutionLeaf();
+        return leaf.value();
+    }
+
+    static int inheritedStatic() {
+        return ResolutionLeaf.twice(21);
+    }
+
+    static int inheritedDefault() {
+        ResolutionLeaf leaf = new ResolutionLeaf();
+        return leaf.name();
+    }
+}
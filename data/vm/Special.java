@@ -0,0 +1,41 @@
+class Special {
+    int value;
+
+    Special(int value) {
+        this.value = value;
+    }
+
+    private int secret() {
+        return 7;
+    }
+
+    int describe() {
+        return 1;
+    }
+
+    static int construct() {
+        return new Special(42).value;
+    }
+
+    static int callPrivate() {
+        return new Special(0).secret();
+    }
+
+    static int callSuper() {
+        return new SpecialSub().describeSuper();
+    }
+}
+
+class SpecialSub extends Special {
+    SpecialSub() {
+        super(5);
+    }
+
+    int describe() {
+        return 2;
+    }
+
+    int describeSuper() {
+        return super.describe() * 10 + describe() + value * 100;
+    }
+}
@@ -0,0 +1,18 @@
+class StackTraces {
+    static int divide(int a, int b) {
+        return a / b;
+    }
+
+    static int twoLevels() {
+        int result = divide(1, 0);
+        return result;
+    }
+
+    static int caught() {
+        try {
+            return twoLevels();
+        } catch (ArithmeticException e) {
+            return -1;
+        }
+    }
+}
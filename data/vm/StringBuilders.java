@@ -0,0 +1,20 @@
+class StringBuilders {
+    static String hello() {
+        StringBuilder builder = new StringBuilder();
+        builder.append("Hello").append(',').append(" World").append('!');
+        return builder.toString();
+    }
+
+    static String numbers() {
+        StringBuilder builder = new StringBuilder(8);
+        builder.append(42).append(' ').append(-7000000000L).append((String) null);
+        return builder.toString();
+    }
+
+    static int length() {
+        StringBuilder builder = new StringBuilder();
+        int empty = builder.length();
+        builder.append(12).append(-3L).append("x");
+        return empty * 10 + builder.length();
+    }
+}
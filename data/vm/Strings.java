@@ -0,0 +1,36 @@
+class Strings {
+    static int length() {
+        return "hello".length() * 10 + "".length();
+    }
+
+    static int charAt() {
+        return "h\u00e9llo".charAt(1);
+    }
+
+    static int charAtOutOfBounds() {
+        try {
+            return "abc".charAt(3);
+        } catch (StringIndexOutOfBoundsException e) {
+            return "String index out of range: 3".equals(e.getMessage()) ? -1 : -2;
+        }
+    }
+
+    static int compare() {
+        String abc = new String(new char[] { 'a', 'b', 'c' });
+        int result = 0;
+        if ("abc".equals(abc)) result |= 1;
+        if ("abc".equals("abd")) result |= 2;
+        if ("abc".equals("ab")) result |= 4;
+        if ("abc".equals(null)) result |= 8;
+        if ("abc".equals(new Object())) result |= 16;
+        return result;
+    }
+
+    static int helloHashCode() {
+        return "hello".hashCode();
+    }
+
+    static int emptyHashCode() {
+        return "".hashCode();
+    }
+}
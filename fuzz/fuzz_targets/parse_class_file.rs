@@ -0,0 +1,16 @@
+//! Checks that the class file parser returns an error, rather than panicking, for any input.
+//!
+//! Run with `cargo fuzz run parse_class_file` from the project root. The class files in `data/`
+//! make a good initial corpus.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate rust_jvm;
+
+use rust_jvm::parser::class_file::parse_class_file;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_class_file(data);
+});
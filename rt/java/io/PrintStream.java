@@ -78,8 +78,6 @@ public class PrintStream extends FilterOutputStream {
         write('\n');
     }
 
-    public void println(String x) {
-        print(x);
-        println();
-    }
+    // implemented natively, writing directly to the standard output of the virtual machine
+    public native void println(String x);
 }
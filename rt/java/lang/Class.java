@@ -1,6 +1,12 @@
 package java.lang;
 
 public final class Class<T> {
+    private String name;
+
     private Class() {
     }
+
+    public String getName() {
+        return name;
+    }
 }
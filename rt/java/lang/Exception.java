@@ -3,4 +3,8 @@ package java.lang;
 public class Exception extends Throwable {
     public Exception() {
     }
+
+    public Exception(String message) {
+        super(message);
+    }
 }
@@ -0,0 +1,21 @@
+package java.lang;
+
+public final class Integer {
+    public static final int MIN_VALUE = 0x80000000;
+
+    public static final int MAX_VALUE = 0x7fffffff;
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static native int parseInt(String s) throws NumberFormatException;
+
+    public static native int parseInt(String s, int radix) throws NumberFormatException;
+
+    public static native Integer valueOf(int i);
+
+    public native int intValue();
+}
@@ -3,4 +3,12 @@ package java.lang;
 public class Object {
     public Object() {
     }
+
+    public final native Class<?> getClass();
+
+    public native boolean equals(Object obj);
+
+    public native int hashCode();
+
+    protected native Object clone() throws CloneNotSupportedException;
 }
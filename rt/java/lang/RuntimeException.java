@@ -3,4 +3,8 @@ package java.lang;
 public class RuntimeException extends Exception {
     public RuntimeException() {
     }
+
+    public RuntimeException(String message) {
+        super(message);
+    }
 }
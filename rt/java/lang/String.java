@@ -8,6 +8,15 @@ public final class String {
         System.arraycopy(value, 0, this.value, 0, value.length);
     }
 
+    // these are implemented natively
+    public native int length();
+
+    public native char charAt(int index);
+
+    public native boolean equals(Object anObject);
+
+    public native int hashCode();
+
     public byte[] getBytes() {
         // TODO we're assuming UTF-16 here
         byte[] b = new byte[2 * value.length];
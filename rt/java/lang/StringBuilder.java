@@ -0,0 +1,24 @@
+package java.lang;
+
+public final class StringBuilder {
+    // the contents of the builder are held by the virtual machine, outside the object, and the
+    // constructors are bound to native code which empties them
+
+    public StringBuilder() {
+    }
+
+    public StringBuilder(int capacity) {
+    }
+
+    public native StringBuilder append(String str);
+
+    public native StringBuilder append(int i);
+
+    public native StringBuilder append(long l);
+
+    public native StringBuilder append(char c);
+
+    public native int length();
+
+    public native String toString();
+}
@@ -0,0 +1,366 @@
+//! Static analysis of class files, without loading or executing them.
+
+use std::collections::{HashMap, HashSet};
+
+use generic_sig::{self, ClassTypeSignature, FieldTypeSignature, TypeArgument, TypeParameter,
+                  TypeSignature};
+use model::class_file::{AttributeInfo, ClassFile};
+use model::class_file::constant_pool::ConstantPoolInfo;
+use vm::{sig, symref};
+use vm::bytecode::{Instruction, InstructionIterator};
+
+/// A function recording the names of the classes mentioned in a descriptor or signature.
+type AddNames = fn(&str, &mut HashSet<String>);
+
+fn add_type(ty: &TypeSignature, names: &mut HashSet<String>) {
+    if let TypeSignature::Reference(ref field_type) = *ty {
+        add_field_type(field_type, names);
+    }
+}
+
+fn add_field_type(field_type: &FieldTypeSignature, names: &mut HashSet<String>) {
+    match *field_type {
+        FieldTypeSignature::Class(ref class_type) => add_class_type(class_type, names),
+        FieldTypeSignature::TypeVariable(_) => (),
+        FieldTypeSignature::Array(ref component_type) => add_type(component_type, names),
+    }
+}
+
+/// Records the name of a class type and of each class enclosing it, along with the classes
+/// mentioned in their type arguments.
+fn add_class_type(class_type: &ClassTypeSignature, names: &mut HashSet<String>) {
+    let mut name = String::new();
+    for class in Some(&class_type.class).into_iter().chain(&class_type.inner_classes) {
+        if !name.is_empty() {
+            name.push('$');
+        }
+        name.push_str(&class.name);
+        names.insert(name.clone());
+        for type_argument in &class.type_arguments {
+            match *type_argument {
+                TypeArgument::Any => (),
+                TypeArgument::Exact(ref bound) | TypeArgument::Extends(ref bound) |
+                TypeArgument::Super(ref bound) => add_field_type(bound, names),
+            }
+        }
+    }
+}
+
+fn add_type_parameters(type_parameters: &[TypeParameter], names: &mut HashSet<String>) {
+    for type_parameter in type_parameters {
+        let bounds = type_parameter.class_bound.iter().chain(&type_parameter.interface_bounds);
+        for bound in bounds {
+            add_field_type(bound, names);
+        }
+    }
+}
+
+/// Records the classes mentioned in a field or method descriptor (§4.3). Malformed descriptors
+/// are ignored.
+fn add_descriptor(descriptor: &str, names: &mut HashSet<String>) {
+    // method descriptors are a special case of method signatures
+    if descriptor.starts_with('(') {
+        add_method_signature(descriptor, names);
+    } else if let Ok(ty) = generic_sig::parse_type_signature(descriptor) {
+        add_type(&ty, names);
+    }
+}
+
+/// Records the classes mentioned in a class signature (§4.7.9.1), ignoring it if it is malformed.
+fn add_class_signature(signature: &str, names: &mut HashSet<String>) {
+    if let Ok(signature) = generic_sig::parse_class_signature(signature) {
+        add_type_parameters(&signature.type_parameters, names);
+        for class_type in Some(&signature.superclass).into_iter().chain(&signature.interfaces) {
+            add_class_type(class_type, names);
+        }
+    }
+}
+
+/// Like `add_class_signature`, but for a method signature, which may also be a method descriptor.
+fn add_method_signature(signature: &str, names: &mut HashSet<String>) {
+    if let Ok(signature) = generic_sig::parse_method_signature(signature) {
+        add_type_parameters(&signature.type_parameters, names);
+        for ty in signature.parameters.iter().chain(&signature.return_type) {
+            add_type(ty, names);
+        }
+        for exception in &signature.throws {
+            add_field_type(exception, names);
+        }
+    }
+}
+
+/// Like `add_class_signature`, but for a field signature.
+fn add_field_signature(signature: &str, names: &mut HashSet<String>) {
+    if let Ok(field_type) = generic_sig::parse_field_type_signature(signature) {
+        add_field_type(&field_type, names);
+    }
+}
+
+/// Returns the binary names of the classes which `class` refers to: those named by its constant
+/// pool, including the classes mentioned in the descriptors of its field and method references,
+/// along with the classes mentioned in the descriptors and generic signatures of its fields and
+/// methods and in their `throws` clauses. Primitive types are not included, and neither is the
+/// class itself.
+pub fn dependencies(class: &ClassFile) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let read = |index: u16, add: AddNames, names: &mut HashSet<String>| {
+        if let Some(string) = class.utf8_at(index) {
+            add(&string, names);
+        }
+    };
+
+    for (_, info) in class.constant_pool.real_entries() {
+        match *info {
+            ConstantPoolInfo::Class { name_index } => {
+                // array classes are named by their descriptors
+                match class.utf8_at(name_index) {
+                    Some(ref name) if name.starts_with('[') => add_descriptor(name, &mut names),
+                    Some(name) => { names.insert(name); },
+                    None => (),
+                }
+            },
+            ConstantPoolInfo::NameAndType { descriptor_index, .. }
+                    | ConstantPoolInfo::MethodType { descriptor_index } =>
+                read(descriptor_index, add_descriptor, &mut names),
+            _ => (),
+        }
+    }
+
+    let fields = class.fields.iter().map(|field| {
+        (field.descriptor_index, &field.attributes, add_field_signature as AddNames)
+    });
+    let methods = class.methods.iter().map(|method| {
+        (method.descriptor_index, &method.attributes, add_method_signature as AddNames)
+    });
+    for (descriptor_index, attributes, add_signature) in fields.chain(methods) {
+        read(descriptor_index, add_descriptor, &mut names);
+        for attr in attributes {
+            match *attr {
+                AttributeInfo::Signature { signature_index } =>
+                    read(signature_index, add_signature, &mut names),
+                AttributeInfo::Exceptions { ref exception_index_table } => {
+                    names.extend(exception_index_table.iter().filter_map(|&index| {
+                        class.class_name_at(index)
+                    }));
+                },
+                _ => (),
+            }
+        }
+    }
+    for attr in &class.attributes {
+        if let AttributeInfo::Signature { signature_index } = *attr {
+            read(signature_index, add_class_signature, &mut names);
+        }
+    }
+
+    if let Some(name) = class.class_name_at(class.this_class) {
+        names.remove(&name);
+    }
+    names
+}
+
+/// Returns a symbolic reference to the method with the given name and descriptor in the class
+/// `class_name`, or `None` if the descriptor is malformed.
+fn method_symref(class_name: &str, name: &str, descriptor: &str) -> Option<symref::Method> {
+    let (params, return_ty) = try_opt!(sig::parse_descriptor(descriptor).ok());
+    Some(symref::Method {
+        class: symref::Class { sig: sig::Class::new(class_name) },
+        sig: sig::Method { name: String::from(name), params: params, return_ty: return_ty },
+    })
+}
+
+/// Returns the method named by the `MethodRef` or `InterfaceMethodRef` entry at `index` in the
+/// constant pool of `class`, or `None` if there is no such entry or it is malformed.
+fn method_ref_at(class: &ClassFile, index: u16) -> Option<symref::Method> {
+    let cp = &class.constant_pool;
+    let (class_index, name_and_type_index) = match cp.get(index as usize) {
+        Some(&ConstantPoolInfo::MethodRef { class_index, name_and_type_index })
+            | Some(&ConstantPoolInfo::InterfaceMethodRef { class_index, name_and_type_index }) =>
+            (class_index, name_and_type_index),
+        _ => return None,
+    };
+    let (name_index, descriptor_index) = match cp.get(name_and_type_index as usize) {
+        Some(&ConstantPoolInfo::NameAndType { name_index, descriptor_index }) =>
+            (name_index, descriptor_index),
+        _ => return None,
+    };
+    method_symref(&try_opt!(class.class_name_at(class_index)),
+                  &try_opt!(class.utf8_at(name_index)),
+                  &try_opt!(class.utf8_at(descriptor_index)))
+}
+
+#[derive(Debug, Default)]
+/// A directed graph whose nodes are methods, with an edge from each method to every method which
+/// its bytecode invokes by `invokevirtual`, `invokespecial`, `invokestatic` or `invokeinterface`.
+///
+/// The target of each edge is the method named by the symbolic reference in the invoking
+/// instruction. Invocations are not resolved against the class hierarchy, so a call to a method
+/// which is inherited, or which is overridden in a subclass, leads only to the method as it was
+/// named at the call site.
+pub struct CallGraph {
+    callees: HashMap<symref::Method, Vec<symref::Method>>,
+    callers: HashMap<symref::Method, Vec<symref::Method>>,
+}
+
+impl CallGraph {
+    /// Builds the call graph of the methods declared in `classes`.
+    pub fn build(classes: &[ClassFile]) -> CallGraph {
+        let mut graph = CallGraph::default();
+        for class in classes {
+            let class_name = match class.class_name_at(class.this_class) {
+                Some(name) => name,
+                None => continue,
+            };
+            for method in &class.methods {
+                let name = class.utf8_at(method.name_index).unwrap_or_default();
+                let descriptor = class.utf8_at(method.descriptor_index).unwrap_or_default();
+                let caller = match method_symref(&class_name, &name, &descriptor) {
+                    Some(caller) => caller,
+                    None => continue,
+                };
+                for attr in &method.attributes {
+                    if let AttributeInfo::Code { ref code, .. } = *attr {
+                        for (_, instruction) in InstructionIterator::new(code) {
+                            let index = match instruction {
+                                Instruction::Invokevirtual(index)
+                                    | Instruction::Invokespecial(index)
+                                    | Instruction::Invokestatic(index)
+                                    | Instruction::Invokeinterface { index, .. } => index,
+                                _ => continue,
+                            };
+                            if let Some(callee) = method_ref_at(class, index) {
+                                graph.add_edge(&caller, &callee);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    fn add_edge(&mut self, caller: &symref::Method, callee: &symref::Method) {
+        let callees = self.callees.entry(caller.clone()).or_default();
+        if callees.contains(callee) {
+            return;
+        }
+        callees.push(callee.clone());
+        self.callers.entry(callee.clone()).or_default().push(caller.clone());
+    }
+
+    /// Returns the methods invoked by the given method, in the order in which they are first
+    /// invoked in its bytecode.
+    pub fn callees_of(&self, class: &str, method: &str, descriptor: &str) -> Vec<symref::Method> {
+        method_symref(class, method, descriptor)
+            .and_then(|key| self.callees.get(&key).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Returns the methods which invoke the given method, in the order in which the classes and
+    /// methods containing the invocations were given to `build`.
+    pub fn callers_of(&self, class: &str, method: &str, descriptor: &str) -> Vec<symref::Method> {
+        method_symref(class, method, descriptor)
+            .and_then(|key| self.callers.get(&key).cloned())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use builder::class_file::ClassFileBuilder;
+    use model::class_file::method_access_flags;
+    use parser::class_file::parse_class_file;
+    use vm::bytecode::opcode;
+
+    fn read(add: AddNames, signature: &str) -> Vec<String> {
+        let mut names = HashSet::new();
+        add(signature, &mut names);
+        let mut names = names.into_iter().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_descriptors() {
+        assert_eq!(read(add_descriptor, "I"), Vec::<String>::new());
+        assert_eq!(read(add_descriptor, "[[Ljava/lang/Long;"), ["java/lang/Long"]);
+        assert_eq!(read(add_descriptor,
+                        "(ILjava/lang/String;[Ljava/util/List;)Ljava/lang/Object;"),
+                   ["java/lang/Object", "java/lang/String", "java/util/List"]);
+        assert_eq!(read(add_descriptor, "(Ljava/lang/String;"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_signatures() {
+        assert_eq!(read(add_class_signature,
+                        "<T:Ljava/lang/Object;L::Ljava/lang/Comparable<-TL;>;>\
+                         Ljava/util/AbstractList<TT;>;Ljava/util/RandomAccess;"),
+                   ["java/lang/Comparable", "java/lang/Object", "java/util/AbstractList",
+                    "java/util/RandomAccess"]);
+        assert_eq!(read(add_method_signature,
+                        "<X:Ljava/lang/Throwable;>\
+                         (Ljava/util/Map$Entry<+TX;*>;)V^TX;^Ljava/io/IOException;"),
+                   ["java/io/IOException", "java/lang/Throwable", "java/util/Map$Entry"]);
+        assert_eq!(read(add_field_signature, "Lpkg/Outer<Ljava/lang/String;>.Inner<TT;>;"),
+                   ["java/lang/String", "pkg/Outer", "pkg/Outer$Inner"]);
+    }
+
+    #[test]
+    fn test_hello_world() {
+        let class = parse_class_file(include_bytes!("../data/HelloWorld.class")).unwrap();
+        let mut names = dependencies(&class).into_iter().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["java/io/PrintStream", "java/lang/Object", "java/lang/String",
+                           "java/lang/System"]);
+    }
+
+    #[test]
+    fn test_string() {
+        let class = parse_class_file(include_bytes!("../data/String.class")).unwrap();
+        let names = dependencies(&class);
+        assert!(!names.contains("java/lang/String"));
+        for name in &["java/lang/StringBuilder", "java/util/Comparator", "java/lang/CharSequence",
+                      "java/io/UnsupportedEncodingException", "java/nio/charset/Charset"] {
+            assert!(names.contains(*name), "missing {}", name);
+        }
+        assert_eq!(names.len(), 38);
+    }
+
+    #[test]
+    fn test_call_graph() {
+        let mut main = ClassFileBuilder::new("Main", "java/lang/Object");
+        let class_index = main.add_class("Helper");
+        let name_index = main.add_utf8("doWork");
+        let descriptor_index = main.add_utf8("()V");
+        let name_and_type_index = main.push_constant(ConstantPoolInfo::NameAndType {
+            name_index: name_index,
+            descriptor_index: descriptor_index,
+        });
+        let method_index = main.push_constant(ConstantPoolInfo::MethodRef {
+            class_index: class_index,
+            name_and_type_index: name_and_type_index,
+        });
+        let (method_hi, method_lo) = ((method_index >> 8) as u8, method_index as u8);
+        let main = main
+            .add_method("main", "([Ljava/lang/String;)V", method_access_flags::ACC_STATIC, vec![
+                opcode::INVOKESTATIC, method_hi, method_lo,
+                opcode::INVOKESTATIC, method_hi, method_lo,
+                opcode::RETURN,
+            ])
+            .add_method("unused", "()V", method_access_flags::ACC_STATIC, vec![opcode::RETURN])
+            .build();
+        let helper = ClassFileBuilder::new("Helper", "java/lang/Object")
+            .add_method("doWork", "()V", method_access_flags::ACC_STATIC, vec![opcode::RETURN])
+            .build();
+
+        let graph = CallGraph::build(&[main, helper]);
+        let main_method = method_symref("Main", "main", "([Ljava/lang/String;)V").unwrap();
+        let do_work = method_symref("Helper", "doWork", "()V").unwrap();
+        assert_eq!(graph.callees_of("Main", "main", "([Ljava/lang/String;)V"), vec![do_work]);
+        assert_eq!(graph.callers_of("Helper", "doWork", "()V"), vec![main_method]);
+        assert_eq!(graph.callees_of("Helper", "doWork", "()V"), vec![]);
+        assert_eq!(graph.callers_of("Main", "unused", "()V"), vec![]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Annotations (§4.7.16) and the default values of annotation elements (§4.7.22), with every
+//! reference into the constant pool resolved, so that they can be inspected without loading the
+//! class.
+
+use std::collections::HashMap;
+
+use model::class_file::{constant_pool_index, AttributeInfo, ClassFile};
+use model::class_file::attribute::annotation::{Annotation, ElementValue};
+use model::class_file::constant_pool::ConstantPoolInfo;
+
+#[derive(Debug, Clone, PartialEq)]
+/// The value of an element of an annotation. This mirrors `ElementValue`, but holds the values
+/// themselves rather than the indices of constant pool entries.
+pub enum AnnotationValue {
+    Byte(i8),
+    /// A UTF-16 code unit, as Java's `char` is.
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(String),
+    /// An enum constant, given by the field descriptor of the enum type, such as
+    /// `Ljava/lang/annotation/ElementType;`, and the name of the constant.
+    Enum { type_name: String, const_name: String },
+    /// A class literal, given by its return descriptor, such as `Ljava/lang/Integer;` or `V`.
+    Class(String),
+    Annotation(ResolvedAnnotation),
+    Array(Vec<AnnotationValue>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An annotation together with the values of the elements given explicitly where it is applied.
+/// Elements which take their default values are not included.
+pub struct ResolvedAnnotation {
+    /// The binary name of the annotation interface, such as `java/lang/Deprecated`.
+    pub type_name: String,
+    /// The values of the elements of the annotation, by name.
+    pub elements: HashMap<String, AnnotationValue>,
+}
+
+/// Returns the binary name of the class in a field descriptor such as `Ljava/lang/Deprecated;`.
+fn binary_name(descriptor: &str) -> Option<String> {
+    if descriptor.len() > 2 && descriptor.starts_with('L') && descriptor.ends_with(';') {
+        Some(String::from(&descriptor[1..descriptor.len() - 1]))
+    } else {
+        None
+    }
+}
+
+/// Returns the value of the `CONSTANT_Integer` entry at the given index.
+fn int_at(class: &ClassFile, index: constant_pool_index) -> Option<i32> {
+    match class.constant_pool.get(index as usize) {
+        Some(&ConstantPoolInfo::Integer { bytes }) => Some(bytes as i32),
+        _ => None,
+    }
+}
+
+/// Resolves the constant pool entries to which an element value refers. Returns `None` if any of
+/// them is missing or of the wrong kind.
+fn resolve_value(class: &ClassFile, value: &ElementValue) -> Option<AnnotationValue> {
+    let constant = |index: constant_pool_index| class.constant_pool.get(index as usize);
+    let resolved = match *value {
+        ElementValue::Byte { const_value_index } =>
+            AnnotationValue::Byte(try_opt!(int_at(class, const_value_index)) as i8),
+        ElementValue::Char { const_value_index } =>
+            AnnotationValue::Char(try_opt!(int_at(class, const_value_index)) as u16),
+        ElementValue::Int { const_value_index } =>
+            AnnotationValue::Int(try_opt!(int_at(class, const_value_index))),
+        ElementValue::Short { const_value_index } =>
+            AnnotationValue::Short(try_opt!(int_at(class, const_value_index)) as i16),
+        ElementValue::Boolean { const_value_index } =>
+            AnnotationValue::Boolean(try_opt!(int_at(class, const_value_index)) != 0),
+        ElementValue::Float { const_value_index } => match constant(const_value_index) {
+            Some(&ConstantPoolInfo::Float { bytes }) =>
+                AnnotationValue::Float(f32::from_bits(bytes)),
+            _ => return None,
+        },
+        ElementValue::Long { const_value_index } => match constant(const_value_index) {
+            Some(&ConstantPoolInfo::Long { high_bytes, low_bytes }) =>
+                AnnotationValue::Long(((high_bytes as u64) << 32 | low_bytes as u64) as i64),
+            _ => return None,
+        },
+        ElementValue::Double { const_value_index } => match constant(const_value_index) {
+            Some(&ConstantPoolInfo::Double { high_bytes, low_bytes }) => {
+                let bits = (high_bytes as u64) << 32 | low_bytes as u64;
+                AnnotationValue::Double(f64::from_bits(bits))
+            },
+            _ => return None,
+        },
+        ElementValue::String { const_value_index } =>
+            AnnotationValue::String(try_opt!(class.utf8_at(const_value_index))),
+        ElementValue::Enum { type_name_index, const_name_index } => AnnotationValue::Enum {
+            type_name: try_opt!(class.utf8_at(type_name_index)),
+            const_name: try_opt!(class.utf8_at(const_name_index)),
+        },
+        ElementValue::Class { class_info_index } =>
+            AnnotationValue::Class(try_opt!(class.utf8_at(class_info_index))),
+        ElementValue::Annotation { ref annotation_value } =>
+            AnnotationValue::Annotation(try_opt!(resolve_annotation(class, annotation_value))),
+        ElementValue::Array { ref values } => {
+            let mut resolved_values = vec![];
+            for value in values {
+                resolved_values.push(try_opt!(resolve_value(class, value)));
+            }
+            AnnotationValue::Array(resolved_values)
+        },
+    };
+    Some(resolved)
+}
+
+/// Resolves the type and the element names and values of an annotation. Returns `None` if any of
+/// the constant pool entries to which it refers is missing or of the wrong kind.
+fn resolve_annotation(class: &ClassFile, annotation: &Annotation) -> Option<ResolvedAnnotation> {
+    let type_name = try_opt!(class.utf8_at(annotation.type_index).and_then(|t| binary_name(&t)));
+    let mut elements = HashMap::new();
+    for pair in &annotation.element_value_pairs {
+        let name = try_opt!(class.utf8_at(pair.element_name_index));
+        elements.insert(name, try_opt!(resolve_value(class, &pair.value)));
+    }
+    Some(ResolvedAnnotation { type_name: type_name, elements: elements })
+}
+
+/// Returns the default value recorded by an `AnnotationDefault` attribute of a method of an
+/// annotation interface in the given class, or `None` if the attribute is of another kind or
+/// refers to invalid constant pool entries.
+pub fn extract_annotation_default(attribute: &AttributeInfo, class: &ClassFile)
+                                  -> Option<AnnotationValue> {
+    match *attribute {
+        AttributeInfo::AnnotationDefault { ref default_value } =>
+            resolve_value(class, default_value),
+        _ => None,
+    }
+}
+
+/// Returns the annotations in the `RuntimeVisibleAnnotations` attribute of a class, in the order
+/// in which they appear. Annotations which refer to invalid constant pool entries are skipped.
+pub fn extract_runtime_visible_annotations(class: &ClassFile) -> Vec<ResolvedAnnotation> {
+    class.attributes.iter().flat_map(|attribute| match *attribute {
+        AttributeInfo::RuntimeVisibleAnnotations { ref annotations } => &annotations[..],
+        _ => &[],
+    }).filter_map(|annotation| resolve_annotation(class, annotation)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use parser::class_file::parse_class_file;
+
+    // `Annotated` and `Config` are compiled from the following source:
+    //
+    //     @Retention(RetentionPolicy.RUNTIME)
+    //     @interface Config {
+    //         String name() default "config";
+    //         int size() default 4;
+    //         long big() default 1L << 40;
+    //         double ratio() default 0.5;
+    //         char letter() default 'x';
+    //         boolean enabled() default true;
+    //         ElementType target() default ElementType.FIELD;
+    //         Class<?> type() default Integer.class;
+    //         int[] sizes() default {1, 2};
+    //         Retention retention() default @Retention(RetentionPolicy.CLASS);
+    //     }
+    //
+    //     @Config(name = "annotated", sizes = {3})
+    //     @Deprecated
+    //     class Annotated {
+    //     }
+
+    fn retention(policy: &str) -> AnnotationValue {
+        AnnotationValue::Enum {
+            type_name: String::from("Ljava/lang/annotation/RetentionPolicy;"),
+            const_name: String::from(policy),
+        }
+    }
+
+    #[test]
+    fn test_runtime_visible_annotations() {
+        let class = parse_class_file(include_bytes!("../data/Annotated.class")).unwrap();
+        let annotations = extract_runtime_visible_annotations(&class);
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].type_name, "Config");
+        assert_eq!(annotations[0].elements.len(), 2);
+        assert_eq!(annotations[0].elements["name"],
+                   AnnotationValue::String(String::from("annotated")));
+        assert_eq!(annotations[0].elements["sizes"],
+                   AnnotationValue::Array(vec![AnnotationValue::Int(3)]));
+        assert_eq!(annotations[1].type_name, "java/lang/Deprecated");
+        assert!(annotations[1].elements.is_empty());
+
+        let config = parse_class_file(include_bytes!("../data/Config.class")).unwrap();
+        let annotations = extract_runtime_visible_annotations(&config);
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].type_name, "java/lang/annotation/Retention");
+        assert_eq!(annotations[0].elements["value"], retention("RUNTIME"));
+    }
+
+    #[test]
+    fn test_annotation_defaults() {
+        let class = parse_class_file(include_bytes!("../data/Config.class")).unwrap();
+        let default = |name: &str| {
+            let method = class.methods.iter().find(|method| {
+                class.utf8_at(method.name_index).map_or(false, |n| n == name)
+            }).unwrap();
+            method.attributes.iter()
+                .filter_map(|attribute| extract_annotation_default(attribute, &class))
+                .next()
+        };
+        assert_eq!(default("name"), Some(AnnotationValue::String(String::from("config"))));
+        assert_eq!(default("size"), Some(AnnotationValue::Int(4)));
+        assert_eq!(default("big"), Some(AnnotationValue::Long(1 << 40)));
+        assert_eq!(default("ratio"), Some(AnnotationValue::Double(0.5)));
+        assert_eq!(default("letter"), Some(AnnotationValue::Char('x' as u16)));
+        assert_eq!(default("enabled"), Some(AnnotationValue::Boolean(true)));
+        assert_eq!(default("target"), Some(AnnotationValue::Enum {
+            type_name: String::from("Ljava/lang/annotation/ElementType;"),
+            const_name: String::from("FIELD"),
+        }));
+        assert_eq!(default("type"),
+                   Some(AnnotationValue::Class(String::from("Ljava/lang/Integer;"))));
+        assert_eq!(default("sizes"), Some(AnnotationValue::Array(vec![
+            AnnotationValue::Int(1),
+            AnnotationValue::Int(2),
+        ])));
+        let mut elements = HashMap::new();
+        elements.insert(String::from("value"), retention("CLASS"));
+        assert_eq!(default("retention"), Some(AnnotationValue::Annotation(ResolvedAnnotation {
+            type_name: String::from("java/lang/annotation/Retention"),
+            elements: elements,
+        })));
+        assert_eq!(extract_annotation_default(&AttributeInfo::Synthetic, &class), None);
+    }
+}
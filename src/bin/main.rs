@@ -1,16 +1,84 @@
 extern crate rust_jvm;
 
 use std::io::Read;
+use std::path::PathBuf;
+use std::thread;
 
+use rust_jvm::disassembler;
 use rust_jvm::parser::class_file;
 use rust_jvm::logging::SimpleLogger;
+use rust_jvm::vm::{self, Interpreter};
+use rust_jvm::vm::class_path::{ClassPath, ClassPathEntry};
+use rust_jvm::vm::context::STACK_SIZE;
 
-fn main() {
-    SimpleLogger::init().unwrap();
-    let file_name = std::env::args().nth(1).unwrap();
+const USAGE: &'static str = "usage: main [-cp <class path>] <main class> [args...]
+       main (--disassemble | --dump) <class file>";
+
+/// Parse a class path of entries separated by `:`. Entries ending in `.jar` are JAR files, and
+/// all others are directories.
+fn parse_class_path(class_path: &str) -> ClassPath {
+    let entries = class_path.split(':').filter(|entry| !entry.is_empty()).map(|entry| {
+        if entry.ends_with(".jar") {
+            ClassPathEntry::JarFile(PathBuf::from(entry))
+        } else {
+            ClassPathEntry::Directory(PathBuf::from(entry))
+        }
+    }).collect();
+    ClassPath::new(entries)
+}
+
+/// Print the class file at the given path, either disassembled or as its parsed structure.
+fn print_class_file(file_name: &str, disassemble: bool) {
     let mut file = std::fs::File::open(file_name).unwrap();
     let mut bytes = vec![];
     file.read_to_end(&mut bytes).unwrap();
-    let class = class_file::parse_class_file(&bytes);
-    println!("{:#?}", class);
+    match class_file::parse_class_file(&bytes) {
+        Ok(ref class) if disassemble => print!("{}", disassembler::disassemble(class)),
+        Ok(class) => println!("{:#?}", class),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        },
+    }
+}
+
+fn main() {
+    SimpleLogger::init().unwrap();
+    let mut args = std::env::args().skip(1);
+    let mut class_path = String::from(".");
+    let main_class = loop {
+        match args.next() {
+            Some(ref arg) if arg == "--disassemble" || arg == "--dump" => {
+                let file_name = args.next().expect(USAGE);
+                print_class_file(&file_name, arg == "--disassemble");
+                return;
+            },
+            Some(ref arg) if arg == "-cp" || arg == "-classpath" => {
+                class_path = args.next().expect(USAGE);
+            },
+            Some(arg) => break arg,
+            None => panic!("{}", USAGE),
+        }
+    };
+    let class_path = parse_class_path(&class_path);
+    let args = args.collect();
+    // the interpreter recurses for every invocation, so it needs a larger stack than the main
+    // thread may have
+    let interpreter = thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+        let interpreter = Interpreter::new(class_path);
+        interpreter.run_main(&main_class, args)
+    }).unwrap();
+    match interpreter.join().unwrap() {
+        Ok(()) => (),
+        Err(error) => {
+            match error {
+                vm::Error::UncaughtException { ref stack_trace, .. } => {
+                    eprintln!("Exception in thread \"main\" {}", error);
+                    eprint!("{}", Interpreter::format_stack_trace(stack_trace));
+                },
+                _ => eprintln!("{}", error),
+            }
+            std::process::exit(1);
+        },
+    }
 }
@@ -0,0 +1,283 @@
+//! A builder for a Java class file.
+
+use std::collections::HashMap;
+
+use model::class_file::{u1, u2};
+use model::class_file::{AttributeInfo, ClassFile, FieldInfo, MethodInfo};
+use model::class_file::{class_access_flags, field_access_flags, method_access_flags};
+use model::class_file::constant_pool::{constant_pool_index, ConstantPool, ConstantPoolInfo};
+use util::modified_utf8;
+use vm::sig;
+
+/// Computes the number of local variable slots occupied by the parameters of a method with the
+/// given descriptor, including the slot for `this` if the method is not static.
+fn parameter_slots(descriptor: &str, flags: method_access_flags::t) -> u2 {
+    let (params, _) = sig::parse_descriptor(descriptor).unwrap_or_else(|error| {
+        panic!("invalid method descriptor {}: {:?}", descriptor, error)
+    });
+    let this_slots = if flags & method_access_flags::ACC_STATIC == 0 { 1 } else { 0 };
+    params.iter().fold(this_slots, |slots, ty| {
+        match *ty {
+            sig::Type::Long | sig::Type::Double => slots + 2,
+            _ => slots + 1,
+        }
+    })
+}
+
+/// Constructs a `ClassFile`, managing the entries of its constant pool automatically. Each
+/// distinct string and class name is added to the constant pool only once.
+#[derive(Debug)]
+pub struct ClassFileBuilder {
+    minor_version: u2,
+    major_version: u2,
+    access_flags: class_access_flags::t,
+    constant_pool: Vec<ConstantPoolInfo>,
+    /// The indices of the `ConstantPoolInfo::Utf8` entries added so far, by their contents.
+    utf8_indices: HashMap<String, constant_pool_index>,
+    /// The indices of the `ConstantPoolInfo::Class` entries added so far, by class name.
+    class_indices: HashMap<String, constant_pool_index>,
+    this_class: constant_pool_index,
+    super_class: constant_pool_index,
+    interfaces: Vec<constant_pool_index>,
+    fields: Vec<FieldInfo>,
+    methods: Vec<MethodInfo>,
+}
+
+impl ClassFileBuilder {
+    /// Begin building a public class with the given binary name (such as `java/lang/String`)
+    /// and direct superclass. The class file has version 50.0 unless `set_major_version` is
+    /// called.
+    pub fn new(class_name: &str, super_name: &str) -> Self {
+        let mut builder = ClassFileBuilder {
+            minor_version: 0,
+            major_version: 50,
+            access_flags: class_access_flags::ACC_PUBLIC | class_access_flags::ACC_SUPER,
+            constant_pool: vec![],
+            utf8_indices: HashMap::new(),
+            class_indices: HashMap::new(),
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+        };
+        builder.this_class = builder.add_class(class_name);
+        builder.super_class = builder.add_class(super_name);
+        builder
+    }
+
+    pub fn set_major_version(&mut self, major_version: u2) -> &mut Self {
+        self.major_version = major_version;
+        self
+    }
+
+    pub fn set_access_flags(&mut self, access_flags: class_access_flags::t) -> &mut Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    /// Add a `ConstantPoolInfo::Utf8` entry for the given string to the constant pool, unless
+    /// there is one already, and return its index.
+    pub fn add_utf8(&mut self, s: &str) -> constant_pool_index {
+        if let Some(&index) = self.utf8_indices.get(s) {
+            return index;
+        }
+        let bytes = modified_utf8::to_modified_utf8(s);
+        let index = self.push_constant(ConstantPoolInfo::Utf8 { bytes: bytes });
+        self.utf8_indices.insert(String::from(s), index);
+        index
+    }
+
+    /// Add a `ConstantPoolInfo::Class` entry for the class with the given binary name to the
+    /// constant pool, unless there is one already, and return its index.
+    pub fn add_class(&mut self, name: &str) -> constant_pool_index {
+        if let Some(&index) = self.class_indices.get(name) {
+            return index;
+        }
+        let name_index = self.add_utf8(name);
+        let index = self.push_constant(ConstantPoolInfo::Class { name_index: name_index });
+        self.class_indices.insert(String::from(name), index);
+        index
+    }
+
+    /// Add an arbitrary entry to the end of the constant pool and return its index. `long` and
+    /// `double` constants are followed by the unusable entry which they require.
+    pub fn push_constant(&mut self, info: ConstantPoolInfo) -> constant_pool_index {
+        let is_wide = match info {
+            ConstantPoolInfo::Long { .. } | ConstantPoolInfo::Double { .. } => true,
+            _ => false,
+        };
+        self.constant_pool.push(info);
+        let index = self.constant_pool.len() as constant_pool_index;
+        if is_wide {
+            self.constant_pool.push(ConstantPoolInfo::Unusable);
+        }
+        index
+    }
+
+    /// Add a direct superinterface with the given binary name.
+    pub fn add_interface(&mut self, name: &str) -> &mut Self {
+        let index = self.add_class(name);
+        self.interfaces.push(index);
+        self
+    }
+
+    pub fn add_field(&mut self, name: &str, descriptor: &str, flags: field_access_flags::t)
+                     -> &mut Self {
+        let field = FieldInfo {
+            access_flags: flags,
+            name_index: self.add_utf8(name),
+            descriptor_index: self.add_utf8(descriptor),
+            attributes: vec![],
+        };
+        self.fields.push(field);
+        self
+    }
+
+    /// Add a method with the given code. The `max_locals` of the method is the number of slots
+    /// taken by its parameters, so the code may not use any other local variables; methods which
+    /// do should be added with `add_method_with_limits`. The `max_stack` is an upper bound
+    /// derived from the length of the code. Methods which are `abstract` or `native` are given no
+    /// `Code` attribute, and `code` must be empty.
+    pub fn add_method(&mut self, name: &str, descriptor: &str, flags: method_access_flags::t,
+                      code: Vec<u1>) -> &mut Self {
+        // no instruction pushes more than two words onto the operand stack
+        let max_stack = ::std::cmp::min(code.len() * 2, u2::max_value() as usize) as u2;
+        let max_locals = parameter_slots(descriptor, flags);
+        self.add_method_with_limits(name, descriptor, flags, max_stack, max_locals, code)
+    }
+
+    /// Add a method with the given code, operand stack depth, and number of local variables.
+    pub fn add_method_with_limits(&mut self, name: &str, descriptor: &str,
+                                  flags: method_access_flags::t, max_stack: u2, max_locals: u2,
+                                  code: Vec<u1>) -> &mut Self {
+        let mut attributes = vec![];
+        if flags & (method_access_flags::ACC_ABSTRACT | method_access_flags::ACC_NATIVE) == 0 {
+            // the Code attribute refers to its name in the constant pool
+            self.add_utf8("Code");
+            attributes.push(AttributeInfo::Code {
+                max_stack: max_stack,
+                max_locals: max_locals,
+                code: code,
+                exception_table: vec![],
+                attributes: vec![],
+            });
+        } else if !code.is_empty() {
+            panic!("abstract and native methods cannot have code");
+        }
+        let method = MethodInfo {
+            access_flags: flags,
+            name_index: self.add_utf8(name),
+            descriptor_index: self.add_utf8(descriptor),
+            attributes: attributes,
+        };
+        self.methods.push(method);
+        self
+    }
+
+    /// Create the class file. The builder may continue to be used afterwards.
+    pub fn build(&self) -> ClassFile {
+        ClassFile {
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            constant_pool: ConstantPool::from_zero_indexed_vec(self.constant_pool.clone()),
+            access_flags: self.access_flags,
+            this_class: self.this_class,
+            super_class: self.super_class,
+            interfaces: self.interfaces.clone(),
+            fields: self.fields.clone(),
+            methods: self.methods.clone(),
+            attributes: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use model::class_file;
+    use parser::class_file::parse_class_file;
+    use vm::bytecode::opcode;
+    use writer::class_file::write_class_file;
+
+    /// Write a class file out and parse it back in.
+    fn round_trip(class: &ClassFile) -> ClassFile {
+        let parsed = parse_class_file(&write_class_file(class)).unwrap();
+        assert_eq!(&parsed, class);
+        parsed
+    }
+
+    #[test]
+    fn test_static_field() {
+        let class = ClassFileBuilder::new("Counter", "java/lang/Object")
+            .add_field("count", "I", field_access_flags::ACC_STATIC)
+            .build();
+        let class = round_trip(&class);
+        assert_eq!(class.class_name(), "Counter");
+        assert_eq!(class.super_class_name(), Some(String::from("java/lang/Object")));
+        let field = class.get_field("count").unwrap();
+        assert!(field.is_static());
+        assert_eq!(class.utf8_at(field.name_index), Some(String::from("count")));
+        assert_eq!(class.utf8_at(field.descriptor_index), Some(String::from("I")));
+        assert_eq!(class_file::validate(&class), Ok(()));
+    }
+
+    #[test]
+    fn test_methods() {
+        let class = ClassFileBuilder::new("Shape", "java/lang/Object")
+            .add_method("sides", "(JI)I", method_access_flags::ACC_STATIC,
+                        vec![opcode::ILOAD_2, opcode::IRETURN])
+            .add_method("area", "()D", method_access_flags::ACC_ABSTRACT, vec![])
+            .add_method_with_limits("twice", "(I)I", method_access_flags::ACC_STATIC, 2, 2,
+                                    vec![opcode::ILOAD_0, opcode::DUP, opcode::IADD,
+                                         opcode::ISTORE_1, opcode::ILOAD_1, opcode::IRETURN])
+            .build();
+        let class = round_trip(&class);
+        let sides = class.get_method("sides", "(JI)I").unwrap();
+        match sides.attributes[..] {
+            [AttributeInfo::Code { max_stack: 4, max_locals: 3, ref code, .. }] =>
+                assert_eq!(code, &vec![opcode::ILOAD_2, opcode::IRETURN]),
+            ref attributes => panic!("unexpected attributes {:?}", attributes),
+        }
+        assert!(class.get_method("area", "()D").unwrap().attributes.is_empty());
+        assert!(class.get_method("twice", "(I)I").is_some());
+        assert_eq!(class_file::validate(&class), Ok(()));
+    }
+
+    #[test]
+    fn test_interfaces() {
+        let class = ClassFileBuilder::new("Task", "java/lang/Object")
+            .add_interface("java/lang/Runnable")
+            .add_interface("java/lang/Cloneable")
+            .build();
+        let class = round_trip(&class);
+        let names = class.interfaces.iter().map(|&index| {
+            class.class_name_at(index).unwrap()
+        }).collect::<Vec<_>>();
+        assert_eq!(names, ["java/lang/Runnable", "java/lang/Cloneable"]);
+    }
+
+    #[test]
+    fn test_deduplicate_constants() {
+        let mut builder = ClassFileBuilder::new("Point", "java/lang/Object");
+        builder.add_field("x", "I", 0).add_field("y", "I", 0);
+        builder.add_interface("java/lang/Object");
+        builder.set_major_version(52);
+        let class = builder.build();
+        // Point, its Class, java/lang/Object, its Class, x, I, and y
+        assert_eq!(class.constant_pool.len(), 7);
+        assert_eq!(class.interfaces, vec![class.super_class]);
+        assert_eq!(class.major_version, 52);
+    }
+
+    #[test]
+    fn test_wide_constants() {
+        let mut builder = ClassFileBuilder::new("Wide", "java/lang/Object");
+        let long_index = builder.push_constant(ConstantPoolInfo::Long { high_bytes: 0,
+                                                                        low_bytes: 1 });
+        let next_index = builder.add_utf8("next");
+        assert_eq!(next_index, long_index + 2);
+        round_trip(&builder.build());
+    }
+}
@@ -0,0 +1,19 @@
+//! Contains a builder for constructing Java class files programmatically.
+//!
+//! # Examples
+//!
+//! Basic usage:
+//! ```
+//! use rust_jvm::builder::class_file::ClassFileBuilder;
+//! use rust_jvm::model::class_file::field_access_flags;
+//! use rust_jvm::parser::class_file::parse_class_file;
+//! use rust_jvm::writer::class_file::write_class_file;
+//!
+//! let class = ClassFileBuilder::new("Counter", "java/lang/Object")
+//!     .add_field("count", "I", field_access_flags::ACC_STATIC)
+//!     .build();
+//! let data = write_class_file(&class);
+//! assert_eq!(parse_class_file(&data).unwrap(), class);
+//! ```
+
+pub mod class_file;
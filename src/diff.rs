@@ -0,0 +1,174 @@
+//! Structural comparison of two class files, such as two versions of the same class from
+//! different releases of a library.
+//!
+//! Fields and methods are matched by name and descriptor, which are looked up in the constant pool
+//! of each class file, so the two class files need not share a constant pool layout.
+
+use std::fmt;
+
+use model::class_file::{AttributeInfo, ClassFile, MethodInfo};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A difference between two class files.
+pub enum ClassDiff {
+    /// The `(major, minor)` version of the class file changed.
+    VersionChanged { from: (u16, u16), to: (u16, u16) },
+    /// The access flags of the class changed.
+    AccessFlagsChanged { from: u16, to: u16 },
+    /// The direct superclass changed.
+    SuperclassChanged { from: Option<String>, to: Option<String> },
+    FieldAdded { name: String, descriptor: String },
+    FieldRemoved { name: String, descriptor: String },
+    MethodAdded { name: String, descriptor: String },
+    MethodRemoved { name: String, descriptor: String },
+    /// The bytecode of a method present in both class files differs. The code is compared byte
+    /// for byte, so a method whose instructions refer to constant pool entries which have moved
+    /// is reported as changed.
+    MethodBodyChanged { name: String, descriptor: String },
+}
+
+impl fmt::Display for ClassDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClassDiff::VersionChanged { from: (from_major, from_minor),
+                                        to: (to_major, to_minor) } =>
+                write!(f, "version changed from {}.{} to {}.{}",
+                       from_major, from_minor, to_major, to_minor),
+            ClassDiff::AccessFlagsChanged { from, to } =>
+                write!(f, "access flags changed from 0x{:04x} to 0x{:04x}", from, to),
+            ClassDiff::SuperclassChanged { ref from, ref to } =>
+                write!(f, "superclass changed from {} to {}",
+                       from.as_ref().map_or("none", |name| name),
+                       to.as_ref().map_or("none", |name| name)),
+            ClassDiff::FieldAdded { ref name, ref descriptor } =>
+                write!(f, "field added: {}:{}", name, descriptor),
+            ClassDiff::FieldRemoved { ref name, ref descriptor } =>
+                write!(f, "field removed: {}:{}", name, descriptor),
+            ClassDiff::MethodAdded { ref name, ref descriptor } =>
+                write!(f, "method added: {}{}", name, descriptor),
+            ClassDiff::MethodRemoved { ref name, ref descriptor } =>
+                write!(f, "method removed: {}{}", name, descriptor),
+            ClassDiff::MethodBodyChanged { ref name, ref descriptor } =>
+                write!(f, "method body changed: {}{}", name, descriptor),
+        }
+    }
+}
+
+/// Returns the name and descriptor of a field or method, as found in the constant pool of `class`.
+fn member_key(class: &ClassFile, name_index: u16, descriptor_index: u16) -> (String, String) {
+    let name = class.utf8_at(name_index).unwrap_or_default();
+    let descriptor = class.utf8_at(descriptor_index).unwrap_or_default();
+    (name, descriptor)
+}
+
+fn code(method: &MethodInfo) -> Option<&[u8]> {
+    method.attributes.iter().filter_map(|attr| match *attr {
+        AttributeInfo::Code { ref code, .. } => Some(code.as_slice()),
+        _ => None,
+    }).next()
+}
+
+/// Lists the differences between the class file `a` and the class file `b`, in the order: version,
+/// access flags, superclass, fields, then methods. Removed members are listed in their order in
+/// `a`, and added members in their order in `b`.
+pub fn diff_class_files(a: &ClassFile, b: &ClassFile) -> Vec<ClassDiff> {
+    let mut diffs = vec![];
+    let (a_version, b_version) = ((a.major_version, a.minor_version),
+                                  (b.major_version, b.minor_version));
+    if a_version != b_version {
+        diffs.push(ClassDiff::VersionChanged { from: a_version, to: b_version });
+    }
+    if a.access_flags != b.access_flags {
+        diffs.push(ClassDiff::AccessFlagsChanged { from: a.access_flags, to: b.access_flags });
+    }
+    let (a_super, b_super) = (a.super_class_name(), b.super_class_name());
+    if a_super != b_super {
+        diffs.push(ClassDiff::SuperclassChanged { from: a_super, to: b_super });
+    }
+
+    let a_fields = a.fields.iter().map(|field| {
+        member_key(a, field.name_index, field.descriptor_index)
+    }).collect::<Vec<_>>();
+    let b_fields = b.fields.iter().map(|field| {
+        member_key(b, field.name_index, field.descriptor_index)
+    }).collect::<Vec<_>>();
+    for &(ref name, ref descriptor) in a_fields.iter().filter(|key| !b_fields.contains(key)) {
+        diffs.push(ClassDiff::FieldRemoved { name: name.clone(), descriptor: descriptor.clone() });
+    }
+    for &(ref name, ref descriptor) in b_fields.iter().filter(|key| !a_fields.contains(key)) {
+        diffs.push(ClassDiff::FieldAdded { name: name.clone(), descriptor: descriptor.clone() });
+    }
+
+    let a_methods = a.methods.iter().map(|method| {
+        (member_key(a, method.name_index, method.descriptor_index), method)
+    }).collect::<Vec<_>>();
+    let b_methods = b.methods.iter().map(|method| {
+        (member_key(b, method.name_index, method.descriptor_index), method)
+    }).collect::<Vec<_>>();
+    for &(ref key, a_method) in &a_methods {
+        let (name, descriptor) = (key.0.clone(), key.1.clone());
+        match b_methods.iter().find(|&&(ref b_key, _)| b_key == key) {
+            Some(&(_, b_method)) => if code(a_method) != code(b_method) {
+                diffs.push(ClassDiff::MethodBodyChanged { name: name, descriptor: descriptor });
+            },
+            None => diffs.push(ClassDiff::MethodRemoved { name: name, descriptor: descriptor }),
+        }
+    }
+    for &(ref key, _) in b_methods.iter().filter(|&&(ref key, _)| {
+        !a_methods.iter().any(|&(ref a_key, _)| a_key == key)
+    }) {
+        diffs.push(ClassDiff::MethodAdded { name: key.0.clone(), descriptor: key.1.clone() });
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use builder::class_file::ClassFileBuilder;
+    use model::class_file::{class_access_flags, method_access_flags};
+    use parser::class_file::parse_class_file;
+    use vm::bytecode::opcode;
+
+    #[test]
+    fn test_identical() {
+        let class = parse_class_file(include_bytes!("../data/String.class")).unwrap();
+        assert_eq!(diff_class_files(&class, &class.clone()), vec![]);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = ClassFileBuilder::new("Shape", "java/lang/Object")
+            .add_field("sides", "I", 0)
+            .add_field("name", "Ljava/lang/String;", 0)
+            .add_method("area", "()I", 0, vec![opcode::ICONST_0, opcode::IRETURN])
+            .add_method("sides", "()I", 0, vec![opcode::ICONST_3, opcode::IRETURN])
+            .add_method("draw", "()V", 0, vec![opcode::RETURN])
+            .build();
+        let new = ClassFileBuilder::new("Shape", "java/lang/Number")
+            .set_major_version(52)
+            .set_access_flags(class_access_flags::ACC_PUBLIC | class_access_flags::ACC_ABSTRACT)
+            .add_field("name", "Ljava/lang/String;", 0)
+            .add_field("sides", "J", 0)
+            .add_method("sides", "()I", 0, vec![opcode::ICONST_3, opcode::IRETURN])
+            .add_method("area", "()I", 0, vec![opcode::ICONST_1, opcode::IRETURN])
+            .add_method("perimeter", "()I", method_access_flags::ACC_ABSTRACT, vec![])
+            .build();
+        let str = |s: &str| String::from(s);
+        assert_eq!(diff_class_files(&old, &new), vec![
+            ClassDiff::VersionChanged { from: (50, 0), to: (52, 0) },
+            ClassDiff::AccessFlagsChanged { from: 0x0021, to: 0x0401 },
+            ClassDiff::SuperclassChanged {
+                from: Some(str("java/lang/Object")),
+                to: Some(str("java/lang/Number")),
+            },
+            ClassDiff::FieldRemoved { name: str("sides"), descriptor: str("I") },
+            ClassDiff::FieldAdded { name: str("sides"), descriptor: str("J") },
+            ClassDiff::MethodBodyChanged { name: str("area"), descriptor: str("()I") },
+            ClassDiff::MethodRemoved { name: str("draw"), descriptor: str("()V") },
+            ClassDiff::MethodAdded { name: str("perimeter"), descriptor: str("()I") },
+        ]);
+        assert_eq!(diff_class_files(&old, &new)[5].to_string(), "method body changed: area()I");
+    }
+}
@@ -0,0 +1,292 @@
+//! A disassembler producing a human-readable listing of the bytecode of a Java class file, in the
+//! spirit of `javap -c`.
+
+use std::fmt::Write;
+
+use model::class_file::{AttributeInfo, ClassFile, MethodInfo};
+use model::class_file::constant_pool::{ConstantPool, ConstantPoolInfo};
+use model::class_file::method_access_flags;
+use util::modified_utf8;
+use vm::bytecode::{mnemonic, opcode, Instruction, InstructionIterator};
+
+/// Disassembles the bytecode of every method of `class`.
+///
+/// The listing of each method begins with its access flags, name and descriptor, followed by one
+/// line per instruction of the form `<offset>: <mnemonic> [operands]`. Operands which refer to the
+/// constant pool are followed by a comment describing the referenced entry.
+pub fn disassemble(class: &ClassFile) -> String {
+    let cp = &class.constant_pool;
+    let mut out = String::new();
+    writeln!(out, "class {}", class_name(cp, class.this_class)).unwrap();
+    for method in &class.methods {
+        out.push('\n');
+        disassemble_method(&mut out, method, cp);
+    }
+    out
+}
+
+fn disassemble_method(out: &mut String, method: &MethodInfo, cp: &ConstantPool) {
+    const FLAGS: &'static [(u16, &'static str)] = &[
+        (method_access_flags::ACC_PUBLIC, "public"),
+        (method_access_flags::ACC_PRIVATE, "private"),
+        (method_access_flags::ACC_PROTECTED, "protected"),
+        (method_access_flags::ACC_STATIC, "static"),
+        (method_access_flags::ACC_FINAL, "final"),
+        (method_access_flags::ACC_SYNCHRONIZED, "synchronized"),
+        (method_access_flags::ACC_NATIVE, "native"),
+        (method_access_flags::ACC_ABSTRACT, "abstract"),
+        (method_access_flags::ACC_STRICT, "strictfp"),
+    ];
+    for &(flag, name) in FLAGS {
+        if method.access_flags & flag != 0 {
+            write!(out, "{} ", name).unwrap();
+        }
+    }
+    writeln!(out, "{}{}", utf8(cp, method.name_index), utf8(cp, method.descriptor_index)).unwrap();
+    for attr in &method.attributes {
+        if let AttributeInfo::Code { ref code, .. } = *attr {
+            disassemble_code(out, code, cp);
+        }
+    }
+}
+
+/// Writes one line for each instruction in `code`. Decoding stops at the first instruction which
+/// has an unknown opcode or which extends beyond the end of `code`.
+pub fn disassemble_code(out: &mut String, code: &[u8], cp: &ConstantPool) {
+    let mut instructions = InstructionIterator::new(code);
+    for (pc, instruction) in &mut instructions {
+        write!(out, "{:>6}: ", pc).unwrap();
+        // an instruction modified by wide decodes to the same variant as the unmodified one
+        if code[pc as usize] == opcode::WIDE {
+            out.push_str("wide ");
+        }
+        out.push_str(instruction.mnemonic());
+        let (operands, comment) = describe(&instruction, pc, cp);
+        if !operands.is_empty() {
+            write!(out, " {}", operands).unwrap();
+        }
+        if let Some(comment) = comment {
+            write!(out, " // {}", comment).unwrap();
+        }
+        out.push('\n');
+    }
+    let pc = instructions.offset();
+    if pc < code.len() {
+        match mnemonic(code[pc]) {
+            Some(_) => writeln!(out, "{:>6}: <truncated>", pc).unwrap(),
+            None => writeln!(out, "{:>6}: <unknown 0x{:02x}>", pc, code[pc]).unwrap(),
+        }
+    }
+}
+
+/// Returns the operands of an instruction at offset `pc`, and a comment describing the constant
+/// pool entry they refer to, if any.
+fn describe(instruction: &Instruction, pc: u32, cp: &ConstantPool) -> (String, Option<String>) {
+    use vm::bytecode::Instruction::*;
+    let branch = |offset: i32| (pc as i64 + offset as i64).to_string();
+    let switch = |cases: Vec<(i64, i32)>, default: i32| {
+        let mut cases = cases.into_iter().map(|(key, offset)| {
+            format!("{}: {}", key, branch(offset))
+        }).collect::<Vec<_>>();
+        cases.push(format!("default: {}", branch(default)));
+        format!("{{ {} }}", cases.join(", "))
+    };
+    match *instruction {
+        Bipush(value) => (value.to_string(), None),
+        Sipush(value) => (value.to_string(), None),
+
+        Ldc(index) | LdcW(index) | Ldc2W(index) =>
+            (format!("#{}", index), Some(literal(cp, index))),
+
+        Iload(index) | Lload(index) | Fload(index) | Dload(index) | Aload(index) |
+        Istore(index) | Lstore(index) | Fstore(index) | Dstore(index) | Astore(index) |
+        Ret(index) => (index.to_string(), None),
+
+        Iinc { index, constant } => (format!("{}, {}", index, constant), None),
+
+        Ifeq(offset) | Ifne(offset) | Iflt(offset) | Ifge(offset) | Ifgt(offset) | Ifle(offset) |
+        IfIcmpeq(offset) | IfIcmpne(offset) | IfIcmplt(offset) | IfIcmpge(offset) |
+        IfIcmpgt(offset) | IfIcmple(offset) | IfAcmpeq(offset) | IfAcmpne(offset) |
+        Goto(offset) | Jsr(offset) | Ifnull(offset) | Ifnonnull(offset) =>
+            (branch(offset as i32), None),
+
+        GotoW(offset) | JsrW(offset) => (branch(offset), None),
+
+        Tableswitch { default, low, ref offsets, .. } => {
+            let cases = offsets.iter().enumerate().map(|(i, &offset)| {
+                (low as i64 + i as i64, offset)
+            }).collect();
+            (switch(cases, default), None)
+        },
+
+        Lookupswitch { default, ref pairs } => {
+            let cases = pairs.iter().map(|&(key, offset)| (key as i64, offset)).collect();
+            (switch(cases, default), None)
+        },
+
+        Getstatic(index) | Putstatic(index) | Getfield(index) | Putfield(index) |
+        Invokevirtual(index) | Invokespecial(index) | Invokestatic(index) =>
+            (format!("#{}", index), Some(member_ref(cp, index))),
+
+        Invokeinterface { index, count } =>
+            (format!("#{}, {}", index, count), Some(member_ref(cp, index))),
+
+        Invokedynamic(index) => (format!("#{}", index), Some(invoke_dynamic(cp, index))),
+
+        New(index) | Anewarray(index) | Checkcast(index) | Instanceof(index) =>
+            (format!("#{}", index), Some(class_name(cp, index))),
+
+        Newarray(atype) => {
+            let name = match atype {
+                4 => "boolean".to_owned(),
+                5 => "char".to_owned(),
+                6 => "float".to_owned(),
+                7 => "double".to_owned(),
+                8 => "byte".to_owned(),
+                9 => "short".to_owned(),
+                10 => "int".to_owned(),
+                11 => "long".to_owned(),
+                _ => format!("<unknown type {}>", atype),
+            };
+            (name, None)
+        },
+
+        Multianewarray { index, dimensions } =>
+            (format!("#{}, {}", index, dimensions), Some(class_name(cp, index))),
+
+        _ => (String::new(), None),
+    }
+}
+
+fn entry(cp: &ConstantPool, index: u16) -> Option<&ConstantPoolInfo> {
+    cp.get(index as usize)
+}
+
+/// Describes a constant pool index which does not refer to an entry of the kind expected.
+pub fn invalid(index: u16) -> String {
+    format!("<invalid constant #{}>", index)
+}
+
+fn utf8(cp: &ConstantPool, index: u16) -> String {
+    match entry(cp, index) {
+        Some(&ConstantPoolInfo::Utf8 { ref bytes }) => {
+            match modified_utf8::from_modified_utf8(bytes) {
+                Ok(s) => s,
+                Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+            }
+        },
+        _ => invalid(index),
+    }
+}
+
+/// Returns the name of the class in the `ConstantPoolInfo::Class` entry of the constant pool at the
+/// given index, or describes the index as invalid if there is no such entry.
+pub fn class_name(cp: &ConstantPool, index: u16) -> String {
+    match entry(cp, index) {
+        Some(&ConstantPoolInfo::Class { name_index }) => utf8(cp, name_index),
+        _ => invalid(index),
+    }
+}
+
+fn name_and_type(cp: &ConstantPool, index: u16) -> String {
+    match entry(cp, index) {
+        Some(&ConstantPoolInfo::NameAndType { name_index, descriptor_index }) =>
+            format!("{}:{}", utf8(cp, name_index), utf8(cp, descriptor_index)),
+        _ => invalid(index),
+    }
+}
+
+fn member_ref(cp: &ConstantPool, index: u16) -> String {
+    match entry(cp, index) {
+        Some(&ConstantPoolInfo::FieldRef { class_index, name_and_type_index }) |
+        Some(&ConstantPoolInfo::MethodRef { class_index, name_and_type_index }) |
+        Some(&ConstantPoolInfo::InterfaceMethodRef { class_index, name_and_type_index }) =>
+            format!("{}.{}", class_name(cp, class_index), name_and_type(cp, name_and_type_index)),
+        _ => invalid(index),
+    }
+}
+
+fn invoke_dynamic(cp: &ConstantPool, index: u16) -> String {
+    match entry(cp, index) {
+        Some(&ConstantPoolInfo::InvokeDynamic { bootstrap_method_attr_index,
+                                                name_and_type_index }) =>
+            format!("#{}:{}", bootstrap_method_attr_index, name_and_type(cp, name_and_type_index)),
+        _ => invalid(index),
+    }
+}
+
+fn literal(cp: &ConstantPool, index: u16) -> String {
+    match entry(cp, index) {
+        Some(&ConstantPoolInfo::Integer { bytes }) => (bytes as i32).to_string(),
+        Some(&ConstantPoolInfo::Float { bytes }) => format!("{:?}f", f32::from_bits(bytes)),
+        Some(&ConstantPoolInfo::Long { high_bytes, low_bytes }) =>
+            format!("{}l", (((high_bytes as u64) << 32) | low_bytes as u64) as i64),
+        Some(&ConstantPoolInfo::Double { high_bytes, low_bytes }) =>
+            format!("{:?}d", f64::from_bits(((high_bytes as u64) << 32) | low_bytes as u64)),
+        Some(&ConstantPoolInfo::String { string_index }) => format!("{:?}", utf8(cp, string_index)),
+        Some(&ConstantPoolInfo::Class { name_index }) => format!("class {}", utf8(cp, name_index)),
+        Some(&ConstantPoolInfo::MethodType { descriptor_index }) => utf8(cp, descriptor_index),
+        Some(&ConstantPoolInfo::MethodHandle { .. }) => "MethodHandle".to_owned(),
+        _ => invalid(index),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::class_file::parse_class_file;
+
+    #[test]
+    fn test_hello_world() {
+        let data = include_bytes!("../data/HelloWorld.class");
+        let output = disassemble(&parse_class_file(data).unwrap());
+        assert!(output.starts_with("class HelloWorld\n"));
+        assert!(output.contains("public static main([Ljava/lang/String;)V\n"));
+        assert!(output.contains(
+            "getstatic #2 // java/lang/System.out:Ljava/io/PrintStream;"));
+        assert!(output.contains("ldc #3 // \"Hello, World\""));
+        assert!(output.contains(
+            "invokevirtual #4 // java/io/PrintStream.println:(Ljava/lang/String;)V"));
+        assert!(output.contains("return\n"));
+    }
+
+    #[test]
+    fn test_switches() {
+        let cp = ConstantPool::from_zero_indexed_vec(vec![]);
+        let code = [
+            opcode::NOP,
+            opcode::TABLESWITCH, 0, 0,
+            0, 0, 0, 30,
+            0, 0, 0, 1,
+            0, 0, 0, 2,
+            0, 0, 0, 20,
+            0, 0, 0, 25,
+            opcode::LOOKUPSWITCH, 0, 0, 0,
+            0, 0, 0, 10,
+            0, 0, 0, 1,
+            0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xe8,
+            opcode::WIDE, opcode::IINC, 0x01, 0x00, 0xff, 0xff,
+            opcode::RETURN,
+        ];
+        let mut output = String::new();
+        disassemble_code(&mut output, &code, &cp);
+        assert_eq!("     0: nop\n\
+                    \x20    1: tableswitch { 1: 21, 2: 26, default: 31 }\n\
+                    \x20   24: lookupswitch { -1: 0, default: 34 }\n\
+                    \x20   44: wide iinc 256, -1\n\
+                    \x20   50: return\n",
+                   output);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let cp = ConstantPool::from_zero_indexed_vec(vec![]);
+        let mut output = String::new();
+        disassemble_code(&mut output, &[opcode::ICONST_0, opcode::SIPUSH, 0], &cp);
+        assert_eq!("     0: iconst_0\n     1: <truncated>\n", output);
+        let mut output = String::new();
+        disassemble_code(&mut output, &[opcode::ICONST_1, 0xee, opcode::RETURN], &cp);
+        assert_eq!("     0: iconst_1\n     1: <unknown 0xee>\n", output);
+    }
+}
@@ -0,0 +1,430 @@
+//! Parsing of the generic signatures recorded in `Signature` attributes
+//! [§4.7.9.1](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.9.1).
+//!
+//! Signatures extend descriptors with the type parameters, type arguments, and type variables of
+//! the Java language, which the JVM itself otherwise ignores. Class names are kept in their
+//! internal form, as in `java/util/Map`.
+
+use vm::sig::Type;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The reasons for which a signature may be invalid.
+pub enum SigError {
+    /// The signature ended where more was expected.
+    UnexpectedEnd,
+    /// A character appeared where it is not allowed.
+    UnexpectedChar(char),
+    /// The name of a class, type variable, or type parameter was empty.
+    EmptyIdentifier,
+    /// The signature continued after a complete signature.
+    TrailingContent(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A type which may appear as a parameter, return type, or array component: either a base type or
+/// a reference type.
+pub enum TypeSignature {
+    Base(Type),
+    Reference(FieldTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A reference type, which is the signature of a field.
+pub enum FieldTypeSignature {
+    Class(ClassTypeSignature),
+    /// A use of a type variable, such as `T` in `TT;`.
+    TypeVariable(String),
+    Array(Box<TypeSignature>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A class type, such as `Ljava/util/Map<TK;TV;>.Entry<TK;TV;>;`.
+pub struct ClassTypeSignature {
+    /// The outermost class, whose name includes its package.
+    pub class: SimpleClassTypeSignature,
+    /// Each nested inner class in turn, whose names are relative to the class enclosing them.
+    pub inner_classes: Vec<SimpleClassTypeSignature>,
+}
+
+impl ClassTypeSignature {
+    /// The binary name of the class, with inner classes separated by `$`.
+    pub fn name(&self) -> String {
+        self.inner_classes.iter().fold(self.class.name.clone(), |name, inner| {
+            format!("{}${}", name, inner.name)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A class name with the type arguments applied to it.
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A type argument of a parameterized class type.
+pub enum TypeArgument {
+    /// The unbounded wildcard `*`, as in `List<?>`.
+    Any,
+    /// A type given exactly, as in `List<String>`.
+    Exact(FieldTypeSignature),
+    /// A wildcard with an upper bound `+`, as in `List<? extends Number>`.
+    Extends(FieldTypeSignature),
+    /// A wildcard with a lower bound `-`, as in `List<? super Integer>`.
+    Super(FieldTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A formal type parameter of a generic class or method, such as `K` in `HashMap<K, V>`.
+pub struct TypeParameter {
+    pub name: String,
+    /// The class bound, which is absent if the parameter is bounded only by interfaces.
+    pub class_bound: Option<FieldTypeSignature>,
+    pub interface_bounds: Vec<FieldTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The signature of a class.
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub superclass: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The signature of a method.
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<TypeSignature>,
+    /// The return type, which is `None` if the method is `void`.
+    pub return_type: Option<TypeSignature>,
+    /// The thrown exceptions, each a class type or a type variable.
+    pub throws: Vec<FieldTypeSignature>,
+}
+
+/// Parse a class signature, such as
+/// `<T:Ljava/lang/Object;>Ljava/lang/Object;Ljava/util/List<TT;>;`.
+pub fn parse_class_signature(s: &str) -> Result<ClassSignature, SigError> {
+    let mut parser = Parser { rest: s };
+    let type_parameters = try!(parser.type_parameters());
+    let superclass = try!(parser.class_type());
+    let mut interfaces = vec![];
+    while !parser.rest.is_empty() {
+        interfaces.push(try!(parser.class_type()));
+    }
+    Ok(ClassSignature {
+        type_parameters: type_parameters,
+        superclass: superclass,
+        interfaces: interfaces,
+    })
+}
+
+/// Parse a method signature, such as `<T:Ljava/lang/Object;>(TT;I)TT;^Ljava/io/IOException;`.
+pub fn parse_method_signature(s: &str) -> Result<MethodSignature, SigError> {
+    let mut parser = Parser { rest: s };
+    let type_parameters = try!(parser.type_parameters());
+    try!(parser.expect('('));
+    let mut parameters = vec![];
+    while try!(parser.peek()) != ')' {
+        parameters.push(try!(parser.type_signature()));
+    }
+    parser.next();
+    let return_type = if parser.rest.starts_with('V') {
+        parser.next();
+        None
+    } else {
+        Some(try!(parser.type_signature()))
+    };
+    let mut throws = vec![];
+    while !parser.rest.is_empty() {
+        try!(parser.expect('^'));
+        throws.push(try!(parser.field_type()));
+    }
+    Ok(MethodSignature {
+        type_parameters: type_parameters,
+        parameters: parameters,
+        return_type: return_type,
+        throws: throws,
+    })
+}
+
+/// Parse the signature of a field, such as `Ljava/util/List<+Ljava/lang/Number;>;`.
+pub fn parse_field_type_signature(s: &str) -> Result<FieldTypeSignature, SigError> {
+    let mut parser = Parser { rest: s };
+    let field_type = try!(parser.field_type());
+    try!(parser.end());
+    Ok(field_type)
+}
+
+/// Parse a type which may be a base type, such as a field descriptor (§4.3.2) like `I` or
+/// `[Ljava/lang/String;`.
+pub fn parse_type_signature(s: &str) -> Result<TypeSignature, SigError> {
+    let mut parser = Parser { rest: s };
+    let ty = try!(parser.type_signature());
+    try!(parser.end());
+    Ok(ty)
+}
+
+/// A recursive descent parser over the part of a signature which remains to be read.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Result<char, SigError> {
+        self.rest.chars().next().ok_or(SigError::UnexpectedEnd)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let mut chars = self.rest.chars();
+        let c = chars.next();
+        self.rest = chars.as_str();
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SigError> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(SigError::UnexpectedChar(c)),
+            None => Err(SigError::UnexpectedEnd),
+        }
+    }
+
+    /// Checks that the whole signature has been read.
+    fn end(&self) -> Result<(), SigError> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(SigError::TrailingContent(String::from(self.rest)))
+        }
+    }
+
+    /// Reads characters up to, but not including, the first of `terminators`.
+    fn identifier(&mut self, terminators: &[char]) -> Result<String, SigError> {
+        let end = try!(self.rest.find(terminators).ok_or(SigError::UnexpectedEnd));
+        if end == 0 {
+            return Err(SigError::EmptyIdentifier);
+        }
+        let (identifier, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(String::from(identifier))
+    }
+
+    /// Reads the optional formal type parameters which begin class and method signatures.
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, SigError> {
+        let mut type_parameters = vec![];
+        if !self.rest.starts_with('<') {
+            return Ok(type_parameters);
+        }
+        self.next();
+        while try!(self.peek()) != '>' {
+            let name = try!(self.identifier(&[':']));
+            try!(self.expect(':'));
+            let class_bound = match try!(self.peek()) {
+                ':' => None,
+                _ => Some(try!(self.field_type())),
+            };
+            let mut interface_bounds = vec![];
+            while self.rest.starts_with(':') {
+                self.next();
+                interface_bounds.push(try!(self.field_type()));
+            }
+            type_parameters.push(TypeParameter {
+                name: name,
+                class_bound: class_bound,
+                interface_bounds: interface_bounds,
+            });
+        }
+        self.next();
+        Ok(type_parameters)
+    }
+
+    fn type_signature(&mut self) -> Result<TypeSignature, SigError> {
+        let ty = match try!(self.peek()) {
+            'B' => Type::Byte,
+            'C' => Type::Char,
+            'D' => Type::Double,
+            'F' => Type::Float,
+            'I' => Type::Int,
+            'J' => Type::Long,
+            'S' => Type::Short,
+            'Z' => Type::Boolean,
+            _ => return self.field_type().map(TypeSignature::Reference),
+        };
+        self.next();
+        Ok(TypeSignature::Base(ty))
+    }
+
+    fn field_type(&mut self) -> Result<FieldTypeSignature, SigError> {
+        match try!(self.peek()) {
+            'L' => self.class_type().map(FieldTypeSignature::Class),
+            'T' => {
+                self.next();
+                let name = try!(self.identifier(&[';']));
+                self.next();
+                Ok(FieldTypeSignature::TypeVariable(name))
+            },
+            '[' => {
+                self.next();
+                let component_type = try!(self.type_signature());
+                Ok(FieldTypeSignature::Array(Box::new(component_type)))
+            },
+            c => Err(SigError::UnexpectedChar(c)),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature, SigError> {
+        try!(self.expect('L'));
+        let class = try!(self.simple_class_type());
+        let mut inner_classes = vec![];
+        loop {
+            match self.next() {
+                Some(';') => break,
+                Some('.') => inner_classes.push(try!(self.simple_class_type())),
+                Some(c) => return Err(SigError::UnexpectedChar(c)),
+                None => return Err(SigError::UnexpectedEnd),
+            }
+        }
+        Ok(ClassTypeSignature { class: class, inner_classes: inner_classes })
+    }
+
+    fn simple_class_type(&mut self) -> Result<SimpleClassTypeSignature, SigError> {
+        let name = try!(self.identifier(&[';', '<', '.']));
+        let mut type_arguments = vec![];
+        if self.rest.starts_with('<') {
+            self.next();
+            while try!(self.peek()) != '>' {
+                let type_argument = match try!(self.peek()) {
+                    '*' => {
+                        self.next();
+                        TypeArgument::Any
+                    },
+                    '+' => {
+                        self.next();
+                        TypeArgument::Extends(try!(self.field_type()))
+                    },
+                    '-' => {
+                        self.next();
+                        TypeArgument::Super(try!(self.field_type()))
+                    },
+                    _ => TypeArgument::Exact(try!(self.field_type())),
+                };
+                type_arguments.push(type_argument);
+            }
+            self.next();
+        }
+        Ok(SimpleClassTypeSignature { name: name, type_arguments: type_arguments })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use vm::sig::Type;
+
+    fn class_type(name: &str, type_arguments: Vec<TypeArgument>) -> ClassTypeSignature {
+        ClassTypeSignature {
+            class: SimpleClassTypeSignature {
+                name: String::from(name),
+                type_arguments: type_arguments,
+            },
+            inner_classes: vec![],
+        }
+    }
+
+    fn type_variable(name: &str) -> FieldTypeSignature {
+        FieldTypeSignature::TypeVariable(String::from(name))
+    }
+
+    #[test]
+    fn test_class_signature() {
+        let signature = parse_class_signature(
+            "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;\
+             Ljava/util/Map<TK;TV;>;Ljava/lang/Cloneable;Ljava/io/Serializable;").unwrap();
+        let object = FieldTypeSignature::Class(class_type("java/lang/Object", vec![]));
+        assert_eq!(signature.type_parameters, vec![
+            TypeParameter {
+                name: String::from("K"),
+                class_bound: Some(object.clone()),
+                interface_bounds: vec![],
+            },
+            TypeParameter {
+                name: String::from("V"),
+                class_bound: Some(object),
+                interface_bounds: vec![],
+            },
+        ]);
+        let k_v = || {
+            vec![TypeArgument::Exact(type_variable("K")), TypeArgument::Exact(type_variable("V"))]
+        };
+        assert_eq!(signature.superclass, class_type("java/util/AbstractMap", k_v()));
+        assert_eq!(signature.interfaces, vec![
+            class_type("java/util/Map", k_v()),
+            class_type("java/lang/Cloneable", vec![]),
+            class_type("java/io/Serializable", vec![]),
+        ]);
+    }
+
+    #[test]
+    fn test_method_signature() {
+        let signature = parse_method_signature(
+            "<T::Ljava/lang/Comparable<-TT;>;>([TT;ILjava/util/Map$Entry<*+[I>;)\
+             Ljava/util/Map<TT;TT;>.Entry<TT;TT;>;^Ljava/io/IOException;^TX;").unwrap();
+        let comparable = class_type("java/lang/Comparable",
+                                    vec![TypeArgument::Super(type_variable("T"))]);
+        assert_eq!(signature.type_parameters, vec![TypeParameter {
+            name: String::from("T"),
+            class_bound: None,
+            interface_bounds: vec![FieldTypeSignature::Class(comparable)],
+        }]);
+        let int_array = FieldTypeSignature::Array(Box::new(TypeSignature::Base(Type::Int)));
+        assert_eq!(signature.parameters, vec![
+            TypeSignature::Reference(FieldTypeSignature::Array(Box::new(
+                TypeSignature::Reference(type_variable("T"))))),
+            TypeSignature::Base(Type::Int),
+            TypeSignature::Reference(FieldTypeSignature::Class(class_type(
+                "java/util/Map$Entry", vec![TypeArgument::Any, TypeArgument::Extends(int_array)])))
+        ]);
+        let t_t = || {
+            vec![TypeArgument::Exact(type_variable("T")), TypeArgument::Exact(type_variable("T"))]
+        };
+        let entry = ClassTypeSignature {
+            inner_classes: vec![SimpleClassTypeSignature {
+                name: String::from("Entry"),
+                type_arguments: t_t(),
+            }],
+            ..class_type("java/util/Map", t_t())
+        };
+        assert_eq!(entry.name(), "java/util/Map$Entry");
+        assert_eq!(signature.return_type,
+                   Some(TypeSignature::Reference(FieldTypeSignature::Class(entry))));
+        assert_eq!(signature.throws, vec![
+            FieldTypeSignature::Class(class_type("java/io/IOException", vec![])),
+            type_variable("X"),
+        ]);
+        assert_eq!(parse_method_signature("()V").unwrap().return_type, None);
+    }
+
+    #[test]
+    fn test_type_signature() {
+        assert_eq!(parse_type_signature("J"), Ok(TypeSignature::Base(Type::Long)));
+        assert_eq!(parse_type_signature("[Ljava/lang/String;"),
+                   Ok(TypeSignature::Reference(FieldTypeSignature::Array(Box::new(
+                       TypeSignature::Reference(FieldTypeSignature::Class(
+                           class_type("java/lang/String", vec![]))))))));
+        assert_eq!(parse_type_signature("IJ"), Err(SigError::TrailingContent(String::from("J"))));
+    }
+
+    #[test]
+    fn test_invalid_signatures() {
+        assert_eq!(parse_class_signature("<K:Ljava/lang/Object;>"), Err(SigError::UnexpectedEnd));
+        assert_eq!(parse_class_signature("<:Ljava/lang/Object;>Ljava/lang/Object;"),
+                   Err(SigError::EmptyIdentifier));
+        assert_eq!(parse_method_signature("(I)Q"), Err(SigError::UnexpectedChar('Q')));
+        assert_eq!(parse_field_type_signature("TT;I"),
+                   Err(SigError::TrailingContent(String::from("I"))));
+        assert_eq!(parse_field_type_signature("Ljava/util/List<TT;>"),
+                   Err(SigError::UnexpectedEnd));
+    }
+}
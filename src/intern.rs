@@ -0,0 +1,61 @@
+//! Interning of strings shared between class files.
+//!
+//! The constant pools of the classes in a class library repeat the same strings many times over:
+//! the names of common classes such as `java/lang/Object` and `java/lang/String`, the names of
+//! methods such as `<init>`, and descriptors such as `()V`. An `Interner` keeps a single shared
+//! copy of each distinct string, so that loading a class whose constant pool refers to a string
+//! that has been seen before does not allocate a new copy of it.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+#[derive(Debug, Default)]
+/// A set of interned strings.
+pub struct Interner {
+    /// The interned strings, which are looked up by their contents.
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner { strings: HashSet::new() }
+    }
+
+    /// Returns the interned copy of `s`, interning it first if this interner has not seen it
+    /// before. Interning the same contents twice returns two pointers to the same allocation.
+    pub fn intern(&mut self, s: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = Rc::from(s);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// The number of distinct strings which have been interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+        assert!(interner.is_empty());
+        let first = interner.intern("java/lang/Object");
+        let second = interner.intern(&String::from("java/lang/Object"));
+        let other = interner.intern("()V");
+        assert_eq!(&*first, "java/lang/Object");
+        assert!(Rc::ptr_eq(&first, &second));
+        assert!(!Rc::ptr_eq(&first, &other));
+        assert_eq!(interner.len(), 2);
+    }
+}
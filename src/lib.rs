@@ -8,8 +8,22 @@ extern crate log;
 #[macro_use]
 extern crate nom;
 
+extern crate zip;
+
+#[macro_use]
+mod macros;
+
+pub mod analysis;
+pub mod annotations;
+pub mod builder;
+pub mod diff;
+pub mod disassembler;
+pub mod generic_sig;
+pub mod intern;
 pub mod logging;
 pub mod model;
 pub mod parser;
+pub mod printer;
 pub mod util;
 pub mod vm;
+pub mod writer;
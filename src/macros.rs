@@ -0,0 +1,10 @@
+//! Macros used throughout the crate. This module is declared before every other module, so that
+//! the macros are in scope in all of them.
+
+/// Like `try!`, but for `Option`.
+macro_rules! try_opt {
+    ($e: expr) => (match $e {
+        Some(v) => v,
+        None => return None,
+    });
+}
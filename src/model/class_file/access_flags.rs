@@ -141,3 +141,68 @@ pub mod parameter_access_flags {
     /// language specification, so all compilers for the language must emit it.)
     pub const ACC_MANDATED: t = 0x8000;
 }
+
+/// Values of access flags for a module.
+pub mod module_access_flags {
+    #[allow(non_camel_case_types)]
+    pub type access_flag = super::u2;
+    #[allow(non_camel_case_types)]
+    pub type t = access_flag;
+
+    /// Indicates that this module is open.
+    pub const ACC_OPEN: t = 0x0020;
+    /// Indicates that this module was not explicitly or implicitly declared.
+    pub const ACC_SYNTHETIC: t = 0x1000;
+    /// Indicates that this module was implicitly declared.
+    pub const ACC_MANDATED: t = 0x8000;
+}
+
+/// Values of access flags for a dependence of a module.
+pub mod requires_access_flags {
+    #[allow(non_camel_case_types)]
+    pub type access_flag = super::u2;
+    #[allow(non_camel_case_types)]
+    pub type t = access_flag;
+
+    /// Indicates that any module which depends on the current module, implicitly declares a
+    /// dependence on the module indicated by this entry.
+    pub const ACC_TRANSITIVE: t = 0x0020;
+    /// Indicates that this dependence is mandatory in the static phase, i.e., at compile time, but
+    /// is optional in the dynamic phase, i.e., at run time.
+    pub const ACC_STATIC_PHASE: t = 0x0040;
+    /// Indicates that this dependence was not explicitly or implicitly declared in the source of
+    /// the module declaration.
+    pub const ACC_SYNTHETIC: t = 0x1000;
+    /// Indicates that this dependence was implicitly declared in the source of the module
+    /// declaration.
+    pub const ACC_MANDATED: t = 0x8000;
+}
+
+/// Values of access flags for a package exported by a module.
+pub mod exports_access_flags {
+    #[allow(non_camel_case_types)]
+    pub type access_flag = super::u2;
+    #[allow(non_camel_case_types)]
+    pub type t = access_flag;
+
+    /// Indicates that this export was not explicitly or implicitly declared in the source of the
+    /// module declaration.
+    pub const ACC_SYNTHETIC: t = 0x1000;
+    /// Indicates that this export was implicitly declared in the source of the module declaration.
+    pub const ACC_MANDATED: t = 0x8000;
+}
+
+/// Values of access flags for a package opened by a module.
+pub mod opens_access_flags {
+    #[allow(non_camel_case_types)]
+    pub type access_flag = super::u2;
+    #[allow(non_camel_case_types)]
+    pub type t = access_flag;
+
+    /// Indicates that this opening was not explicitly or implicitly declared in the source of the
+    /// module declaration.
+    pub const ACC_SYNTHETIC: t = 0x1000;
+    /// Indicates that this opening was implicitly declared in the source of the module
+    /// declaration.
+    pub const ACC_MANDATED: t = 0x8000;
+}
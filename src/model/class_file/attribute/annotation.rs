@@ -6,7 +6,7 @@ pub use self::target_type::TargetInfo;
 pub mod element_value {
     use model::class_file::{constant_pool_index, u1};
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, PartialEq)]
     pub enum ElementValue {
         Byte { const_value_index: constant_pool_index },
         Char { const_value_index: constant_pool_index },
@@ -62,13 +62,13 @@ pub mod element_value {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ElementValuePair {
     pub element_name_index: constant_pool_index,
     pub value: ElementValue,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LocalVariableTargetInfo {
     pub start_pc: u2,
     pub length: u2,
@@ -78,7 +78,7 @@ pub struct LocalVariableTargetInfo {
 pub mod target_type {
     use model::class_file::{u1, u2};
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, PartialEq)]
     pub enum TargetInfo {
         TypeParameter { type_parameter_index: u1 },
         Supertype { supertype_index: u2 },
@@ -127,26 +127,29 @@ pub mod target_type {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Annotation {
     /// An index into the `constant_pool` table for a `ConstantPoolInfo::Utf8` structure.
     pub type_index: constant_pool_index,
     pub element_value_pairs: Vec<ElementValuePair>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypePathPart {
     pub type_path_kind: u1,
     pub type_argument_index: u1,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypePath {
     pub path: Vec<TypePathPart>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypeAnnotation {
+    /// Denotes the kind of target on which the annotation appears. Several kinds of target share
+    /// the same `TargetInfo` variant, so this value is retained to distinguish between them.
+    pub target_type: u1,
     pub target_info: TargetInfo,
     pub target_path: TypePath,
     pub type_index: u2,
@@ -5,13 +5,17 @@ use super::u2;
 use super::constant_pool_index;
 use super::access_flags::inner_class_access_flags;
 use super::access_flags::parameter_access_flags;
+use super::access_flags::module_access_flags;
+use super::access_flags::requires_access_flags;
+use super::access_flags::exports_access_flags;
+use super::access_flags::opens_access_flags;
 
 pub use self::stack_map_frame::StackMapFrame;
 
 /// Each `ExceptionTableEntry` describes one exception handler in the `code`
 /// array. The order of the handlers in an `exception_table` array is
 /// significant (§2.10).
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExceptionTableEntry {
     /// Indicates the (inclusive) start of the range in the `code` array at
     /// which the exception handler is active. The value of `start_pc` must be a
@@ -48,7 +52,7 @@ pub mod stack_map_frame {
     /// types for the operand stack. Each variant stores a bytecode offset _relative
     /// to the previous_ `StackMapFrame`. The actual bytecode offset can be
     /// calculated as described in (§4.7.4).
-    #[derive(Debug)]
+    #[derive(Debug, Clone, PartialEq)]
     pub enum StackMapFrame {
         SameFrame { offset_delta: u1 },
         SameLocals1StackItemFrame { offset_delta: u1, stack_item: VerificationTypeInfo },
@@ -97,7 +101,7 @@ pub mod stack_map_frame {
         use super::super::super::u2;
         use super::super::super::constant_pool_index;
 
-        #[derive(Debug)]
+        #[derive(Debug, Clone, PartialEq)]
         pub enum VerificationTypeInfo {
             Top,
             Integer,
@@ -148,19 +152,19 @@ pub mod stack_map_frame {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BootstrapMethod {
     /// An index into the `constant_pool` to a `ConstantPoolInfo::MethodHandle` structure.
-    bootstrap_method_ref: constant_pool_index,
+    pub bootstrap_method_ref: constant_pool_index,
     /// The indices into the `constant_pool` to `ConstantPoolInfo::String`,
     /// `ConstantPoolInfo::Class`, `ConstantPoolInfo::Integer`,
     /// `ConstantPoolInfo::Long`, `ConstantPoolInfo::Float`,
     /// `ConstantPoolInfo::Double`, `ConstantPoolInfo::MethodHandle`, or
     /// `ConstantPoolInfo::MethodType`.
-    bootstrap_arguments: Vec<constant_pool_index>,
+    pub bootstrap_arguments: Vec<constant_pool_index>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct InnerClass {
     pub inner_class_info_index: constant_pool_index,
     pub outer_class_info_index: constant_pool_index,
@@ -168,19 +172,86 @@ pub struct InnerClass {
     pub inner_class_access_flags: inner_class_access_flags::t,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MethodParameter {
     pub name_index: constant_pool_index,
     pub access_flags: parameter_access_flags::t,
 }
 
-#[derive(Debug)]
+/// A dependence of a module, as described by an entry of the `requires` table of the `Module`
+/// attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleRequires {
+    /// A valid index into the `constant_pool` to a `ConstantPoolInfo::Module` structure denoting a
+    /// module on which the current module depends.
+    pub requires_index: constant_pool_index,
+    pub requires_flags: requires_access_flags::t,
+    /// Either zero, or a valid index into the `constant_pool` to a `ConstantPoolInfo::Utf8`
+    /// structure representing the version of the module specified by `requires_index`.
+    pub requires_version_index: constant_pool_index,
+}
+
+/// A package exported by a module, as described by an entry of the `exports` table of the
+/// `Module` attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleExports {
+    /// A valid index into the `constant_pool` to a `ConstantPoolInfo::Package` structure
+    /// representing a package exported by the current module.
+    pub exports_index: constant_pool_index,
+    pub exports_flags: exports_access_flags::t,
+    /// The indices into the `constant_pool` to `ConstantPoolInfo::Module` structures denoting the
+    /// modules whose code can access the types and members in this exported package. If empty,
+    /// the package is exported in an unqualified fashion.
+    pub exports_to_index: Vec<constant_pool_index>,
+}
+
+/// A package opened by a module, as described by an entry of the `opens` table of the `Module`
+/// attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleOpens {
+    /// A valid index into the `constant_pool` to a `ConstantPoolInfo::Package` structure
+    /// representing a package opened by the current module.
+    pub opens_index: constant_pool_index,
+    pub opens_flags: opens_access_flags::t,
+    /// The indices into the `constant_pool` to `ConstantPoolInfo::Module` structures denoting the
+    /// modules whose code can reflectively access the types and members in this opened package.
+    /// If empty, the package is opened in an unqualified fashion.
+    pub opens_to_index: Vec<constant_pool_index>,
+}
+
+/// A service implementation provided by a module, as described by an entry of the `provides`
+/// table of the `Module` attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleProvides {
+    /// A valid index into the `constant_pool` to a `ConstantPoolInfo::Class` structure
+    /// representing a service interface for which the current module provides an implementation.
+    pub provides_index: constant_pool_index,
+    /// The indices into the `constant_pool` to `ConstantPoolInfo::Class` structures representing
+    /// the service implementations for the service interface specified by `provides_index`.
+    pub provides_with_index: Vec<constant_pool_index>,
+}
+
+/// A component of a record class, as described by an entry of the `components` table of the
+/// `Record` attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordComponent {
+    /// A valid index into the `constant_pool` table to a `ConstantPoolInfo::Utf8` structure
+    /// representing a valid unqualified name denoting the record component.
+    pub name_index: constant_pool_index,
+    /// A valid index into the `constant_pool` table to a `ConstantPoolInfo::Utf8` structure
+    /// representing a field descriptor which encodes the type of the record component.
+    pub descriptor_index: constant_pool_index,
+    /// The attributes associated with this record component.
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct LineNumberInfo {
     pub start_pc: u2,
     pub line_number: u2,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LocalVariableInfo {
     pub start_pc: u2,
     pub length: u2,
@@ -189,7 +260,7 @@ pub struct LocalVariableInfo {
     pub index: u2,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LocalVariableTypeInfo {
     pub start_pc: u2,
     pub length: u2,
@@ -201,7 +272,7 @@ pub struct LocalVariableTypeInfo {
 /// Attributes are used in the `ClassFile`, `FieldInfo`, `MethodInfo`, and
 /// `AttributeInfo::Code` structures of the class file format (§4.1, §4.5, §4.6,
 /// §4.7.3).
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AttributeInfo {
     ConstantValue { constant_value_index: constant_pool_index },
     Code {
@@ -228,6 +299,9 @@ pub enum AttributeInfo {
     },
     EnclosingMethod {
         class_index: constant_pool_index,
+        /// The index of a `ConstantPoolInfo::NameAndType` structure for the enclosing method, or
+        /// zero if the class is not immediately enclosed by a method, such as a local class
+        /// declared in an initializer.
         method_index: constant_pool_index,
     },
     Synthetic,
@@ -276,6 +350,53 @@ pub enum AttributeInfo {
         local_variable_type_table: Vec<LocalVariableTypeInfo>,
     },
     Deprecated,
+    /// The `Module` attribute
+    /// [§4.7.25](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.7.25).
+    Module {
+        /// A valid index into the `constant_pool` table to a `ConstantPoolInfo::Module` structure
+        /// denoting the current module.
+        module_name_index: constant_pool_index,
+        module_flags: module_access_flags::t,
+        /// Either zero, or a valid index into the `constant_pool` table to a
+        /// `ConstantPoolInfo::Utf8` structure representing the version of the current module.
+        module_version_index: constant_pool_index,
+        requires: Vec<ModuleRequires>,
+        exports: Vec<ModuleExports>,
+        opens: Vec<ModuleOpens>,
+        /// The indices into the `constant_pool` table to `ConstantPoolInfo::Class` structures
+        /// representing the service interfaces which the current module may discover.
+        uses_index: Vec<constant_pool_index>,
+        provides: Vec<ModuleProvides>,
+    },
+    /// The `NestHost` attribute
+    /// [§4.7.28](https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.7.28).
+    NestHost {
+        /// A valid index into the `constant_pool` table to a `ConstantPoolInfo::Class` structure
+        /// representing the class or interface which is the nest host for the current class or
+        /// interface.
+        host_class_index: constant_pool_index,
+    },
+    /// The `NestMembers` attribute
+    /// [§4.7.29](https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-4.html#jvms-4.7.29).
+    NestMembers {
+        /// The indices into the `constant_pool` table to `ConstantPoolInfo::Class` structures
+        /// representing the classes and interfaces which are members of the nest hosted by the
+        /// current class or interface.
+        classes: Vec<constant_pool_index>,
+    },
+    /// The `Record` attribute
+    /// [§4.7.30](https://docs.oracle.com/javase/specs/jvms/se16/html/jvms-4.html#jvms-4.7.30).
+    Record {
+        components: Vec<RecordComponent>,
+    },
+    /// The `PermittedSubclasses` attribute
+    /// [§4.7.31](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.31).
+    PermittedSubclasses {
+        /// The indices into the `constant_pool` table to `ConstantPoolInfo::Class` structures
+        /// representing the classes and interfaces which are authorized to directly extend or
+        /// implement the current class or interface.
+        classes: Vec<constant_pool_index>,
+    },
     Unknown {
         /// A valid index into the `constant_pool` table. The `constant_pool`
         /// entry at that index must be a valid `ConstantPoolInfo::Utf8`
@@ -285,3 +406,46 @@ pub enum AttributeInfo {
         info: Vec<u1>,
     },
 }
+
+impl AttributeInfo {
+    /// The name of the attribute in the class file, or `None` for an `AttributeInfo::Unknown`,
+    /// whose name is only known from its `attribute_name_index`.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            AttributeInfo::ConstantValue { .. } => Some("ConstantValue"),
+            AttributeInfo::Code { .. } => Some("Code"),
+            AttributeInfo::StackMapTable { .. } => Some("StackMapTable"),
+            AttributeInfo::Exceptions { .. } => Some("Exceptions"),
+            AttributeInfo::BootstrapMethods { .. } => Some("BootstrapMethods"),
+            AttributeInfo::InnerClasses { .. } => Some("InnerClasses"),
+            AttributeInfo::EnclosingMethod { .. } => Some("EnclosingMethod"),
+            AttributeInfo::Synthetic => Some("Synthetic"),
+            AttributeInfo::Signature { .. } => Some("Signature"),
+            AttributeInfo::RuntimeVisibleAnnotations { .. } => Some("RuntimeVisibleAnnotations"),
+            AttributeInfo::RuntimeInvisibleAnnotations { .. } =>
+                Some("RuntimeInvisibleAnnotations"),
+            AttributeInfo::RuntimeVisibleParameterAnnotations { .. } =>
+                Some("RuntimeVisibleParameterAnnotations"),
+            AttributeInfo::RuntimeInvisibleParameterAnnotations { .. } =>
+                Some("RuntimeInvisibleParameterAnnotations"),
+            AttributeInfo::RuntimeVisibleTypeAnnotations { .. } =>
+                Some("RuntimeVisibleTypeAnnotations"),
+            AttributeInfo::RuntimeInvisibleTypeAnnotations { .. } =>
+                Some("RuntimeInvisibleTypeAnnotations"),
+            AttributeInfo::AnnotationDefault { .. } => Some("AnnotationDefault"),
+            AttributeInfo::MethodParameters { .. } => Some("MethodParameters"),
+            AttributeInfo::SourceFile { .. } => Some("SourceFile"),
+            AttributeInfo::SourceDebugExtension { .. } => Some("SourceDebugExtension"),
+            AttributeInfo::LineNumberTable { .. } => Some("LineNumberTable"),
+            AttributeInfo::LocalVariableTable { .. } => Some("LocalVariableTable"),
+            AttributeInfo::LocalVariableTypeTable { .. } => Some("LocalVariableTypeTable"),
+            AttributeInfo::Deprecated => Some("Deprecated"),
+            AttributeInfo::Module { .. } => Some("Module"),
+            AttributeInfo::NestHost { .. } => Some("NestHost"),
+            AttributeInfo::NestMembers { .. } => Some("NestMembers"),
+            AttributeInfo::Record { .. } => Some("Record"),
+            AttributeInfo::PermittedSubclasses { .. } => Some("PermittedSubclasses"),
+            AttributeInfo::Unknown { .. } => None,
+        }
+    }
+}
@@ -28,11 +28,13 @@ pub mod tags {
     pub const METHOD_HANDLE: u1 = 15;
     pub const METHOD_TYPE: u1 = 16;
     pub const INVOKE_DYNAMIC: u1 = 18;
+    pub const MODULE: u1 = 19;
+    pub const PACKAGE: u1 = 20;
 }
 
 /// Type-safe representation of constant pool tags [Table
 /// 4.4-A](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4-140).
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Tag {
     Class,
     FieldRef,
@@ -48,6 +50,8 @@ pub enum Tag {
     MethodHandle,
     MethodType,
     InvokeDynamic,
+    Module,
+    Package,
     Unknown(u1),
 }
 
@@ -68,6 +72,8 @@ impl From<u1> for Tag {
             tags::METHOD_HANDLE => Tag::MethodHandle,
             tags::METHOD_TYPE => Tag::MethodType,
             tags::INVOKE_DYNAMIC => Tag::InvokeDynamic,
+            tags::MODULE => Tag::Module,
+            tags::PACKAGE => Tag::Package,
             _ => Tag::Unknown(tag),
         }
     }
@@ -121,7 +127,7 @@ pub mod reference_kind {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MethodReference {
     GetField { reference_index: constant_pool_index },
     GetStatic { reference_index: constant_pool_index },
@@ -134,7 +140,9 @@ pub enum MethodReference {
     InvokeInterface { reference_index: constant_pool_index },
 }
 
-#[derive(Debug, PartialEq)]
+/// A constant pool entry. Floating-point constants are kept as their raw bytes, so two entries for
+/// the same `NaN` are equal, and every entry can be hashed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ConstantPoolInfo {
     /// The `CONSTANT_Class_info` structure
     /// [§4.4.1](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.1).
@@ -165,6 +173,21 @@ pub enum ConstantPoolInfo {
         /// attribute.
         name_and_type_index: constant_pool_index,
     },
+    /// The `CONSTANT_Module_info` structure
+    /// [§4.4.11](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.4.11).
+    Module {
+        /// A valid index into the `constant_pool` table. The `constant_pool` entry at that index
+        /// must be a valid `ConstantPoolInfo::Utf8` structure representing a valid module name.
+        name_index: constant_pool_index,
+    },
+    /// The `CONSTANT_Package_info` structure
+    /// [§4.4.12](https://docs.oracle.com/javase/specs/jvms/se9/html/jvms-4.html#jvms-4.4.12).
+    Package {
+        /// A valid index into the `constant_pool` table. The `constant_pool` entry at that index
+        /// must be a valid `ConstantPoolInfo::Utf8` structure representing a valid package name
+        /// encoded in internal form.
+        name_index: constant_pool_index,
+    },
     /// Indicates an unusable constant pool entry.
     ///
     /// All 8-byte constants take up two entries in the constant_pool table of the class file. If a
@@ -191,6 +214,8 @@ impl ConstantPoolInfo {
             ConstantPoolInfo::MethodHandle { .. } => Tag::MethodHandle,
             ConstantPoolInfo::MethodType { .. } => Tag::MethodType,
             ConstantPoolInfo::InvokeDynamic { .. } => Tag::InvokeDynamic,
+            ConstantPoolInfo::Module { .. } => Tag::Module,
+            ConstantPoolInfo::Package { .. } => Tag::Package,
             ConstantPoolInfo::Unusable =>
                 panic!("unusable constant pool entry does not have a valid tag"),
         }
@@ -205,4 +230,20 @@ impl ConstantPool {
     pub fn from_zero_indexed_vec(vec: Vec<ConstantPoolInfo>) -> Self {
         OneIndexedVec::from(vec)
     }
+
+    /// Returns an iterator over the entries of the constant pool along with their one-based
+    /// indices, skipping the `Unusable` slots which follow `Long` and `Double` entries.
+    pub fn real_entries(&self) -> impl Iterator<Item = (u2, &ConstantPoolInfo)> {
+        self.iter().enumerate().filter_map(|(zero_index, entry)| {
+            match *entry {
+                ConstantPoolInfo::Unusable => None,
+                _ => Some((zero_index as u2 + 1, entry)),
+            }
+        })
+    }
+
+    /// Returns the number of entries in the constant pool, not counting `Unusable` slots.
+    pub fn count_real_entries(&self) -> usize {
+        self.real_entries().count()
+    }
 }
@@ -4,6 +4,7 @@
 pub mod access_flags;
 pub mod attribute;
 pub mod constant_pool;
+pub mod validation;
 
 pub use self::access_flags::class_access_flags;
 pub use self::access_flags::field_access_flags;
@@ -11,6 +12,9 @@ pub use self::access_flags::method_access_flags;
 pub use self::attribute::AttributeInfo;
 pub use self::constant_pool::ConstantPoolInfo;
 pub use self::constant_pool::ConstantPool;
+pub use self::validation::{validate, ValidationError};
+
+use util::modified_utf8;
 
 /// Represents an unsigned one-byte quantity.
 #[allow(non_camel_case_types)]
@@ -28,7 +32,7 @@ pub type u4 = u32;
 #[allow(non_camel_case_types)]
 pub type constant_pool_index = constant_pool::constant_pool_index;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FieldInfo {
     /// Mask of flags used to denote access permissions to and properties of
     /// this field.
@@ -45,7 +49,56 @@ pub struct FieldInfo {
     pub attributes: Vec<AttributeInfo>,
 }
 
-#[derive(Debug)]
+impl FieldInfo {
+    fn has_flag(&self, flag: field_access_flags::t) -> bool {
+        self.access_flags & flag != 0
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_PUBLIC)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_PRIVATE)
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_PROTECTED)
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_STATIC)
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_FINAL)
+    }
+
+    pub fn is_volatile(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_VOLATILE)
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_TRANSIENT)
+    }
+
+    /// Returns true if the field does not appear in the source code, either because it has the
+    /// `ACC_SYNTHETIC` flag or because it has a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_SYNTHETIC)
+            || self.attributes.contains(&AttributeInfo::Synthetic)
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes.contains(&AttributeInfo::Deprecated)
+    }
+
+    pub fn is_enum(&self) -> bool {
+        self.has_flag(field_access_flags::ACC_ENUM)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct MethodInfo {
     /// Mask of flags used to denote access permissions to and properties of
     /// this class or interface. See the documentation for `ClassAccessFlags`
@@ -63,7 +116,68 @@ pub struct MethodInfo {
     pub attributes: Vec<AttributeInfo>,
 }
 
-#[derive(Debug)]
+impl MethodInfo {
+    fn has_flag(&self, flag: method_access_flags::t) -> bool {
+        self.access_flags & flag != 0
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_PUBLIC)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_PRIVATE)
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_PROTECTED)
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_STATIC)
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_FINAL)
+    }
+
+    pub fn is_synchronized(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_SYNCHRONIZED)
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_BRIDGE)
+    }
+
+    pub fn is_varargs(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_VARARGS)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_NATIVE)
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_ABSTRACT)
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_STRICT)
+    }
+
+    /// Returns true if the method does not appear in the source code, either because it has the
+    /// `ACC_SYNTHETIC` flag or because it has a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.has_flag(method_access_flags::ACC_SYNTHETIC)
+            || self.attributes.contains(&AttributeInfo::Synthetic)
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes.contains(&AttributeInfo::Deprecated)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ClassFile {
     /// Minor version number
     pub minor_version: u2,
@@ -107,3 +221,212 @@ pub struct ClassFile {
     /// Contains the attributes of this class.
     pub attributes: Vec<AttributeInfo>,
 }
+
+impl ClassFile {
+    /// Returns the string in the `ConstantPoolInfo::Utf8` entry of the constant pool at the given
+    /// index, or `None` if there is no such entry or it is not valid modified UTF-8.
+    pub fn utf8_at(&self, index: constant_pool_index) -> Option<String> {
+        match self.constant_pool.get(index as usize) {
+            Some(&ConstantPoolInfo::Utf8 { ref bytes }) =>
+                modified_utf8::from_modified_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the class in the `ConstantPoolInfo::Class` entry of the constant pool
+    /// at the given index, or `None` if there is no such entry.
+    pub fn class_name_at(&self, index: constant_pool_index) -> Option<String> {
+        match self.constant_pool.get(index as usize) {
+            Some(&ConstantPoolInfo::Class { name_index }) => self.utf8_at(name_index),
+            _ => None,
+        }
+    }
+
+    /// Returns the method declared by this class with the given name and descriptor, if there is
+    /// one.
+    pub fn get_method(&self, name: &str, descriptor: &str) -> Option<&MethodInfo> {
+        self.methods.iter().find(|method| {
+            self.utf8_at(method.name_index).map_or(false, |n| n == name)
+                && self.utf8_at(method.descriptor_index).map_or(false, |d| d == descriptor)
+        })
+    }
+
+    /// Returns the field declared by this class with the given name, if there is one.
+    pub fn get_field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| {
+            self.utf8_at(field.name_index).map_or(false, |n| n == name)
+        })
+    }
+
+    /// Returns the binary name of this class, such as `java/lang/String`. Panics if `this_class`
+    /// does not refer to a valid `ConstantPoolInfo::Class` entry.
+    pub fn class_name(&self) -> String {
+        self.class_name_at(self.this_class).expect("this_class does not refer to a valid class")
+    }
+
+    /// Returns the binary name of the direct superclass of this class, or `None` if it has no
+    /// superclass (as is the case only for `java/lang/Object`).
+    pub fn super_class_name(&self) -> Option<String> {
+        self.class_name_at(self.super_class)
+    }
+
+    /// Returns the binary names of the direct superinterfaces of this class, in the order in
+    /// which they are declared. Entries of `interfaces` which do not refer to a valid
+    /// `ConstantPoolInfo::Class` entry are skipped.
+    pub fn interface_names(&self) -> Vec<String> {
+        self.interfaces.iter().filter_map(|&index| self.class_name_at(index)).collect()
+    }
+
+    /// Returns the name and descriptor of each method declared by this class, such as
+    /// `("main", "([Ljava/lang/String;)V")`. Methods whose name or descriptor is not a valid
+    /// `ConstantPoolInfo::Utf8` entry are skipped.
+    pub fn method_names_and_descriptors(&self) -> Vec<(String, String)> {
+        self.methods.iter().filter_map(|method| {
+            self.name_and_descriptor(method.name_index, method.descriptor_index)
+        }).collect()
+    }
+
+    /// Returns the name and descriptor of each field declared by this class, such as
+    /// `("value", "[C")`. Fields whose name or descriptor is not a valid
+    /// `ConstantPoolInfo::Utf8` entry are skipped.
+    pub fn field_names_and_descriptors(&self) -> Vec<(String, String)> {
+        self.fields.iter().filter_map(|field| {
+            self.name_and_descriptor(field.name_index, field.descriptor_index)
+        }).collect()
+    }
+
+    fn name_and_descriptor(&self, name_index: constant_pool_index,
+                           descriptor_index: constant_pool_index) -> Option<(String, String)> {
+        match (self.utf8_at(name_index), self.utf8_at(descriptor_index)) {
+            (Some(name), Some(descriptor)) => Some((name, descriptor)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use parser::class_file::parse_class_file;
+
+    fn hello_world() -> ClassFile {
+        parse_class_file(include_bytes!("../../../data/HelloWorld.class")).unwrap()
+    }
+
+    #[test]
+    fn test_method_access_flags() {
+        let class = hello_world();
+        let main = class.get_method("main", "([Ljava/lang/String;)V").unwrap();
+        assert!(main.is_public());
+        assert!(main.is_static());
+        assert!(!main.is_private());
+        assert!(!main.is_abstract());
+        let init = class.get_method("<init>", "()V").unwrap();
+        assert!(!init.is_static());
+    }
+
+    #[test]
+    fn test_field_access_flags() {
+        let class = parse_class_file(include_bytes!("../../../data/String.class")).unwrap();
+        let value = class.get_field("value").unwrap();
+        assert!(value.is_private());
+        assert!(value.is_final());
+        assert!(!value.is_static());
+    }
+
+    #[test]
+    fn test_deprecated_and_synthetic() {
+        let class = parse_class_file(include_bytes!("../../../data/String.class")).unwrap();
+        assert!(class.get_method("<init>", "([BI)V").unwrap().is_deprecated());
+        assert!(!class.get_method("<init>", "([B)V").unwrap().is_deprecated());
+        assert!(!class.get_field("value").unwrap().is_deprecated());
+
+        let mut field = class.get_field("hash").unwrap().clone();
+        assert!(!field.is_synthetic());
+        field.attributes.push(AttributeInfo::Synthetic);
+        assert!(field.is_synthetic());
+        let hello_world = hello_world();
+        let mut method = hello_world.get_method("main", "([Ljava/lang/String;)V").unwrap().clone();
+        assert!(!method.is_synthetic());
+        method.access_flags |= method_access_flags::ACC_SYNTHETIC;
+        assert!(method.is_synthetic());
+    }
+
+    #[test]
+    fn test_get_method() {
+        let class = hello_world();
+        assert!(class.get_method("main", "([Ljava/lang/String;)V").is_some());
+        assert!(class.get_method("main", "()V").is_none());
+        assert!(class.get_method("missing", "()V").is_none());
+    }
+
+    #[test]
+    fn test_get_field() {
+        let class = parse_class_file(include_bytes!("../../../data/String.class")).unwrap();
+        assert!(class.get_field("hash").is_some());
+        assert!(class.get_field("missing").is_none());
+        assert!(hello_world().get_field("value").is_none());
+    }
+
+    #[test]
+    fn test_real_constant_pool_entries() {
+        let class = parse_class_file(include_bytes!("../../../data/String.class")).unwrap();
+        let constant_pool = &class.constant_pool;
+        assert_eq!(constant_pool.len(), 536);
+        assert_eq!(constant_pool.count_real_entries(), 535);
+        for (index, entry) in constant_pool.real_entries() {
+            assert_eq!(&constant_pool[index as usize], entry);
+            assert!(*entry != ConstantPoolInfo::Unusable);
+        }
+    }
+
+    #[test]
+    fn test_constant_pool_strings() {
+        let class = hello_world();
+        assert_eq!(class.class_name_at(class.this_class), Some(String::from("HelloWorld")));
+        let name_index = match class.constant_pool[class.this_class as usize] {
+            ConstantPoolInfo::Class { name_index } => name_index,
+            ref info => panic!("expected a Class, but was {:?}", info),
+        };
+        assert_eq!(class.utf8_at(name_index), Some(String::from("HelloWorld")));
+        // the wrong kind of entry, the unused index 0, and an index beyond the constant pool
+        assert_eq!(class.utf8_at(class.this_class), None);
+        assert_eq!(class.class_name_at(name_index), None);
+        assert_eq!(class.utf8_at(0), None);
+        assert_eq!(class.class_name_at(0), None);
+        let len = class.constant_pool.len() as u16;
+        assert_eq!(class.utf8_at(len + 1), None);
+    }
+
+    #[test]
+    fn test_class_names() {
+        let class = hello_world();
+        assert_eq!(class.class_name(), "HelloWorld");
+        assert_eq!(class.super_class_name(), Some(String::from("java/lang/Object")));
+        let object = parse_class_file(include_bytes!("../../../data/vm/java/lang/Object.class"))
+            .unwrap();
+        assert_eq!(object.class_name(), "java/lang/Object");
+        assert_eq!(object.super_class_name(), None);
+    }
+
+    #[test]
+    fn test_interface_names() {
+        let class = parse_class_file(include_bytes!("../../../data/String.class")).unwrap();
+        assert_eq!(class.interface_names(),
+                   vec!["java/io/Serializable", "java/lang/Comparable", "java/lang/CharSequence"]);
+        assert!(hello_world().interface_names().is_empty());
+    }
+
+    #[test]
+    fn test_member_names_and_descriptors() {
+        let pair = |name: &str, descriptor: &str| (String::from(name), String::from(descriptor));
+        let class = hello_world();
+        assert_eq!(class.method_names_and_descriptors(),
+                   vec![pair("<init>", "()V"), pair("main", "([Ljava/lang/String;)V")]);
+        assert!(class.field_names_and_descriptors().is_empty());
+        let string = parse_class_file(include_bytes!("../../../data/String.class")).unwrap();
+        assert_eq!(&string.field_names_and_descriptors()[..2],
+                   &[pair("value", "[C"), pair("hash", "I")]);
+    }
+}
@@ -0,0 +1,411 @@
+//! A validation pass which checks the consistency of a parsed class file.
+//!
+//! The parser checks only that a class file is well-formed. It does not check that the entries of
+//! the constant pool refer to one another correctly: for example, that the `name_index` of a
+//! `ConstantPoolInfo::Class` is the index of a `ConstantPoolInfo::Utf8`
+//! [§4.4](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4). Running
+//! `validate` on a class file after parsing it performs these checks, along with those of
+//! `ClassFile::validate_counts`.
+
+use std::{error, fmt};
+
+use super::{AttributeInfo, ClassFile, ConstantPool, ConstantPoolInfo, MethodInfo};
+use super::constant_pool::{constant_pool_index, MethodReference, Tag};
+
+/// An inconsistency found in a class file by `validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// The constant pool entry at `index` refers to `referenced_index`, which is not the index of
+    /// an entry in the constant pool.
+    IndexOutOfBounds { index: usize, referenced_index: constant_pool_index },
+    /// The constant pool entry at `index` refers to the entry at `referenced_index`, which should
+    /// have the tag `expected`. The `actual` tag is `None` if the referenced entry is the unusable
+    /// second half of a `long` or `double` constant.
+    UnexpectedType {
+        index: usize,
+        referenced_index: constant_pool_index,
+        expected: Tag,
+        actual: Option<Tag>,
+    },
+    /// The `ConstantPoolInfo::InvokeDynamic` entry at `index` refers to a bootstrap method which
+    /// is not in the `BootstrapMethods` attribute of the class file.
+    BootstrapMethodIndexOutOfBounds {
+        index: usize,
+        bootstrap_method_attr_index: constant_pool_index,
+        num_bootstrap_methods: usize,
+    },
+    /// The class file has more fields than its `fields_count` item can count.
+    TooManyFields { count: usize },
+    /// The class file has more methods than its `methods_count` item can count.
+    TooManyMethods { count: usize },
+    /// The class file has more direct superinterfaces than its `interfaces_count` item can count.
+    TooManyInterfaces { count: usize },
+    /// The constant pool has more entries than its `constant_pool_count` item can count.
+    TooManyConstants { count: usize },
+    /// The `Code` attribute of the method at `method_index` in `methods` has a `code` array of
+    /// `length` bytes, which is not between 1 and 65535.
+    InvalidCodeLength { method_index: usize, length: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::IndexOutOfBounds { index, referenced_index } =>
+                write!(f, "constant pool entry #{} refers to #{}, which is out of bounds",
+                       index, referenced_index),
+            ValidationError::UnexpectedType {
+                index, referenced_index, ref expected, ref actual
+            } => {
+                try!(write!(f, "constant pool entry #{} refers to #{}, which should be {:?}",
+                            index, referenced_index, expected));
+                match *actual {
+                    Some(ref actual) => write!(f, ", but was {:?}", actual),
+                    None => write!(f, ", but was unusable"),
+                }
+            },
+            ValidationError::BootstrapMethodIndexOutOfBounds {
+                index, bootstrap_method_attr_index, num_bootstrap_methods
+            } =>
+                write!(f, "constant pool entry #{} refers to bootstrap method {}, but there are \
+                           only {} bootstrap methods",
+                       index, bootstrap_method_attr_index, num_bootstrap_methods),
+            ValidationError::TooManyFields { count } =>
+                write!(f, "{} fields, but there may be at most {}", count, MAX_COUNT),
+            ValidationError::TooManyMethods { count } =>
+                write!(f, "{} methods, but there may be at most {}", count, MAX_COUNT),
+            ValidationError::TooManyInterfaces { count } =>
+                write!(f, "{} interfaces, but there may be at most {}", count, MAX_COUNT),
+            ValidationError::TooManyConstants { count } =>
+                write!(f, "{} constant pool entries, but there may be at most {}",
+                       count, MAX_COUNT - 1),
+            ValidationError::InvalidCodeLength { method_index, length } =>
+                write!(f, "method {} has {} bytes of code, but must have between 1 and {}",
+                       method_index, length, MAX_COUNT),
+        }
+    }
+}
+
+impl error::Error for ValidationError {
+    fn description(&self) -> &str {
+        "inconsistent class file"
+    }
+}
+
+/// The greatest value of the two-byte counts which prefix the tables of a class file.
+const MAX_COUNT: usize = 65535;
+
+impl ClassFile {
+    /// Check that the tables of the class file are no longer than the class file format can
+    /// represent
+    /// [§4.11](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.11).
+    /// The constant pool is limited to one entry fewer than the other tables, because
+    /// `constant_pool_count` is one greater than the number of entries. The `max_stack` and
+    /// `max_locals` of a `Code` attribute cannot exceed their limits in the model, but its `code`
+    /// array can.
+    pub fn validate_counts(&self) -> Result<(), ValidationError> {
+        if self.fields.len() > MAX_COUNT {
+            return Err(ValidationError::TooManyFields { count: self.fields.len() });
+        }
+        if self.methods.len() > MAX_COUNT {
+            return Err(ValidationError::TooManyMethods { count: self.methods.len() });
+        }
+        if self.interfaces.len() > MAX_COUNT {
+            return Err(ValidationError::TooManyInterfaces { count: self.interfaces.len() });
+        }
+        if self.constant_pool.len() >= MAX_COUNT {
+            return Err(ValidationError::TooManyConstants { count: self.constant_pool.len() });
+        }
+        for (method_index, method) in self.methods.iter().enumerate() {
+            if let Some(length) = code_length(method) {
+                if length == 0 || length > MAX_COUNT {
+                    return Err(ValidationError::InvalidCodeLength {
+                        method_index: method_index,
+                        length: length,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn code_length(method: &MethodInfo) -> Option<usize> {
+    method.attributes.iter().filter_map(|attribute| match *attribute {
+        AttributeInfo::Code { ref code, .. } => Some(code.len()),
+        _ => None,
+    }).next()
+}
+
+/// Check that the entry at `referenced_index` in the constant pool has the `expected` tag, on
+/// behalf of the entry at `index`.
+fn check_entry(constant_pool: &ConstantPool, index: usize, referenced_index: constant_pool_index,
+               expected: Tag) -> Result<(), ValidationError> {
+    let actual = match find_entry(constant_pool, index, referenced_index) {
+        Ok(&ConstantPoolInfo::Unusable) => None,
+        Ok(entry) => Some(entry.tag()),
+        Err(error) => return Err(error),
+    };
+    if actual == Some(expected) {
+        Ok(())
+    } else {
+        Err(ValidationError::UnexpectedType {
+            index: index,
+            referenced_index: referenced_index,
+            expected: expected,
+            actual: actual,
+        })
+    }
+}
+
+fn find_entry(constant_pool: &ConstantPool, index: usize, referenced_index: constant_pool_index)
+              -> Result<&ConstantPoolInfo, ValidationError> {
+    constant_pool.get(referenced_index as usize).ok_or(ValidationError::IndexOutOfBounds {
+        index: index,
+        referenced_index: referenced_index,
+    })
+}
+
+/// Check the entry referred to by a `ConstantPoolInfo::MethodHandle`. Fields are referred to by
+/// `FieldRef` entries, and methods by `MethodRef` or `InterfaceMethodRef` entries according to the
+/// kind of the method handle
+/// [§4.4.8](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.8).
+fn check_method_reference(constant_pool: &ConstantPool, index: usize, reference: &MethodReference)
+                          -> Result<(), ValidationError> {
+    match *reference {
+        MethodReference::GetField { reference_index }
+                | MethodReference::GetStatic { reference_index }
+                | MethodReference::PutField { reference_index }
+                | MethodReference::PutStatic { reference_index } =>
+            check_entry(constant_pool, index, reference_index, Tag::FieldRef),
+        MethodReference::InvokeVirtual { reference_index }
+                | MethodReference::NewInvokeSpecial { reference_index } =>
+            check_entry(constant_pool, index, reference_index, Tag::MethodRef),
+        MethodReference::InvokeStatic { reference_index }
+                | MethodReference::InvokeSpecial { reference_index } => {
+            // these may also refer to interface methods in class files of version 52.0 or above
+            match try!(find_entry(constant_pool, index, reference_index)) {
+                &ConstantPoolInfo::InterfaceMethodRef { .. } => Ok(()),
+                _ => check_entry(constant_pool, index, reference_index, Tag::MethodRef),
+            }
+        },
+        MethodReference::InvokeInterface { reference_index } =>
+            check_entry(constant_pool, index, reference_index, Tag::InterfaceMethodRef),
+    }
+}
+
+/// Check that every cross-reference between the entries of the constant pool of a class file
+/// refers to an entry of the right type, and that every `ConstantPoolInfo::InvokeDynamic` entry
+/// refers to a bootstrap method of the class file.
+pub fn validate(class: &ClassFile) -> Result<(), ValidationError> {
+    try!(class.validate_counts());
+    let constant_pool = &class.constant_pool;
+    let num_bootstrap_methods = class.attributes.iter().filter_map(|attribute| {
+        match *attribute {
+            AttributeInfo::BootstrapMethods { ref bootstrap_methods } =>
+                Some(bootstrap_methods.len()),
+            _ => None,
+        }
+    }).next().unwrap_or(0);
+
+    for (index, entry) in constant_pool.real_entries() {
+        let index = index as usize;
+        match *entry {
+            ConstantPoolInfo::Class { name_index } =>
+                try!(check_entry(constant_pool, index, name_index, Tag::Utf8)),
+            ConstantPoolInfo::FieldRef { class_index, name_and_type_index }
+                    | ConstantPoolInfo::MethodRef { class_index, name_and_type_index }
+                    | ConstantPoolInfo::InterfaceMethodRef { class_index, name_and_type_index } => {
+                try!(check_entry(constant_pool, index, class_index, Tag::Class));
+                try!(check_entry(constant_pool, index, name_and_type_index, Tag::NameAndType));
+            },
+            ConstantPoolInfo::String { string_index } =>
+                try!(check_entry(constant_pool, index, string_index, Tag::Utf8)),
+            ConstantPoolInfo::NameAndType { name_index, descriptor_index } => {
+                try!(check_entry(constant_pool, index, name_index, Tag::Utf8));
+                try!(check_entry(constant_pool, index, descriptor_index, Tag::Utf8));
+            },
+            ConstantPoolInfo::MethodHandle { ref reference } =>
+                try!(check_method_reference(constant_pool, index, reference)),
+            ConstantPoolInfo::MethodType { descriptor_index } =>
+                try!(check_entry(constant_pool, index, descriptor_index, Tag::Utf8)),
+            ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index, name_and_type_index
+            } => {
+                if bootstrap_method_attr_index as usize >= num_bootstrap_methods {
+                    return Err(ValidationError::BootstrapMethodIndexOutOfBounds {
+                        index: index,
+                        bootstrap_method_attr_index: bootstrap_method_attr_index,
+                        num_bootstrap_methods: num_bootstrap_methods,
+                    });
+                }
+                try!(check_entry(constant_pool, index, name_and_type_index, Tag::NameAndType));
+            },
+            ConstantPoolInfo::Module { name_index } | ConstantPoolInfo::Package { name_index } =>
+                try!(check_entry(constant_pool, index, name_index, Tag::Utf8)),
+            ConstantPoolInfo::Integer { .. } | ConstantPoolInfo::Float { .. }
+                    | ConstantPoolInfo::Long { .. } | ConstantPoolInfo::Double { .. }
+                    | ConstantPoolInfo::Utf8 { .. } | ConstantPoolInfo::Unusable => (),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use model::class_file::FieldInfo;
+    use model::class_file::access_flags::class_access_flags;
+
+    fn new_class_file(entries: Vec<ConstantPoolInfo>) -> ClassFile {
+        ClassFile {
+            minor_version: 0,
+            major_version: 52,
+            constant_pool: ConstantPool::from_zero_indexed_vec(entries),
+            access_flags: class_access_flags::ACC_SUPER,
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+        }
+    }
+
+    fn utf8(s: &str) -> ConstantPoolInfo {
+        ConstantPoolInfo::Utf8 { bytes: s.as_bytes().to_vec() }
+    }
+
+    #[test]
+    fn test_valid() {
+        let class = new_class_file(vec![
+            ConstantPoolInfo::MethodRef { class_index: 2, name_and_type_index: 4 },
+            ConstantPoolInfo::Class { name_index: 3 },
+            utf8("java/lang/Object"),
+            ConstantPoolInfo::NameAndType { name_index: 5, descriptor_index: 6 },
+            utf8("<init>"),
+            utf8("()V"),
+            ConstantPoolInfo::Long { high_bytes: 0, low_bytes: 1 },
+            ConstantPoolInfo::Unusable,
+            ConstantPoolInfo::MethodHandle {
+                reference: MethodReference::InvokeSpecial { reference_index: 1 },
+            },
+        ]);
+        assert_eq!(validate(&class), Ok(()));
+    }
+
+    #[test]
+    fn test_unexpected_type() {
+        let class = new_class_file(vec![
+            ConstantPoolInfo::Class { name_index: 2 },
+            ConstantPoolInfo::Integer { bytes: 0 },
+        ]);
+        assert_eq!(validate(&class), Err(ValidationError::UnexpectedType {
+            index: 1,
+            referenced_index: 2,
+            expected: Tag::Utf8,
+            actual: Some(Tag::Integer),
+        }));
+    }
+
+    #[test]
+    fn test_unusable() {
+        let class = new_class_file(vec![
+            ConstantPoolInfo::Double { high_bytes: 0, low_bytes: 0 },
+            ConstantPoolInfo::Unusable,
+            ConstantPoolInfo::String { string_index: 2 },
+        ]);
+        assert_eq!(validate(&class), Err(ValidationError::UnexpectedType {
+            index: 3,
+            referenced_index: 2,
+            expected: Tag::Utf8,
+            actual: None,
+        }));
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        let class = new_class_file(vec![ConstantPoolInfo::MethodType { descriptor_index: 2 }]);
+        assert_eq!(validate(&class), Err(ValidationError::IndexOutOfBounds {
+            index: 1,
+            referenced_index: 2,
+        }));
+        let class = new_class_file(vec![ConstantPoolInfo::Class { name_index: 0 }]);
+        assert_eq!(validate(&class), Err(ValidationError::IndexOutOfBounds {
+            index: 1,
+            referenced_index: 0,
+        }));
+    }
+
+    #[test]
+    fn test_bootstrap_method_out_of_bounds() {
+        let class = new_class_file(vec![
+            ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index: 0,
+                name_and_type_index: 2,
+            },
+            ConstantPoolInfo::NameAndType { name_index: 3, descriptor_index: 4 },
+            utf8("run"),
+            utf8("()Ljava/lang/Runnable;"),
+        ]);
+        assert_eq!(validate(&class), Err(ValidationError::BootstrapMethodIndexOutOfBounds {
+            index: 1,
+            bootstrap_method_attr_index: 0,
+            num_bootstrap_methods: 0,
+        }));
+    }
+
+    #[test]
+    fn test_too_many_fields() {
+        let mut class = new_class_file(vec![utf8("field"), utf8("I")]);
+        let field = FieldInfo {
+            access_flags: 0,
+            name_index: 1,
+            descriptor_index: 2,
+            attributes: vec![],
+        };
+        class.fields = vec![field; 65535];
+        assert_eq!(class.validate_counts(), Ok(()));
+        class.fields.push(class.fields[0].clone());
+        assert_eq!(class.validate_counts(), Err(ValidationError::TooManyFields { count: 65536 }));
+        assert_eq!(validate(&class), Err(ValidationError::TooManyFields { count: 65536 }));
+    }
+
+    #[test]
+    fn test_too_many_constants() {
+        let class = new_class_file(vec![ConstantPoolInfo::Integer { bytes: 0 }; 65534]);
+        assert_eq!(class.validate_counts(), Ok(()));
+        let class = new_class_file(vec![ConstantPoolInfo::Integer { bytes: 0 }; 65535]);
+        assert_eq!(class.validate_counts(),
+                   Err(ValidationError::TooManyConstants { count: 65535 }));
+    }
+
+    #[test]
+    fn test_invalid_code_length() {
+        let code = |length| MethodInfo {
+            access_flags: 0,
+            name_index: 1,
+            descriptor_index: 2,
+            attributes: vec![AttributeInfo::Code {
+                max_stack: 0,
+                max_locals: 0,
+                code: vec![0; length],
+                exception_table: vec![],
+                attributes: vec![],
+            }],
+        };
+        let mut class = new_class_file(vec![utf8("run"), utf8("()V")]);
+        class.methods = vec![code(1), code(65535)];
+        assert_eq!(class.validate_counts(), Ok(()));
+        class.methods.push(code(65536));
+        assert_eq!(class.validate_counts(), Err(ValidationError::InvalidCodeLength {
+            method_index: 2,
+            length: 65536,
+        }));
+        class.methods[0] = code(0);
+        assert_eq!(validate(&class), Err(ValidationError::InvalidCodeLength {
+            method_index: 0,
+            length: 0,
+        }));
+    }
+}
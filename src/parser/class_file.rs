@@ -1,5 +1,7 @@
 //! A parser for a Java class file.
 
+use std::{error, fmt};
+
 use nom::{be_u8, be_u16, be_u32, ErrorKind};
 use nom;
 
@@ -25,11 +27,13 @@ pub type ConstantPoolIndex = class_file::constant_pool_index;
 pub enum Error {
     ClassFile,
     Magic,
+    TrailingBytes { length: usize },
     ConstantPool { constant_pool_count: usize },
     ConstantPoolEntry { index: usize },
     ConstantPoolInfo,
     UnknownConstantPoolTag { tag: u8 },
     ConstantPoolIndexOutOfBounds { index: usize },
+    UnusableConstantPoolEntry { index: usize },
     UnexpectedConstantPoolType {
         index: usize,
         expected: constant_pool::Tag,
@@ -38,6 +42,7 @@ pub enum Error {
 
     IllegalModifiedUtf8 { byte: u8 },
     ModifiedUtf8 { length: usize },
+    MalformedModifiedUtf8 { offset: usize },
     UnknownConstantPoolMethodReferenceTag { tag: u8 },
     Interfaces { interfaces_count: usize },
     Fields { fields_count: usize },
@@ -60,6 +65,9 @@ pub enum Error {
     VerificationTypeInfo,
     UnknownVerificationTypeInfoTag { tag: u8 },
 
+    BootstrapMethods { num_bootstrap_methods: usize },
+    BootstrapMethod,
+    BootstrapArguments { num_bootstrap_arguments: usize },
     InnerClasses { number_of_classes: usize },
     InnerClass,
     Signature,
@@ -85,6 +93,171 @@ pub enum Error {
     LocalVariableInfo,
     LocalVariableTypeTable { table_length: usize },
     LocalVariableTypeInfo,
+
+    Module,
+    ModuleRequires { requires_count: usize },
+    ModuleExports { exports_count: usize },
+    ModuleExportsTo { exports_to_count: usize },
+    ModuleOpens { opens_count: usize },
+    ModuleOpensTo { opens_to_count: usize },
+    ModuleUses { uses_count: usize },
+    ModuleProvides { provides_count: usize },
+    ModuleProvidesWith { provides_with_count: usize },
+    NestHost,
+    NestMembers { number_of_classes: usize },
+    Record { components_count: usize },
+    RecordComponent,
+    RecordComponentAttributes { attributes_count: usize },
+    PermittedSubclasses { number_of_classes: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ClassFile => write!(f, "class file"),
+            Error::Magic => write!(f, "bad magic number (expected 0xCAFEBABE)"),
+            Error::TrailingBytes { length } =>
+                write!(f, "{} unexpected bytes after the end of the class file", length),
+            Error::ConstantPool { constant_pool_count } =>
+                write!(f, "constant pool (constant_pool_count {})", constant_pool_count),
+            Error::ConstantPoolEntry { index } => write!(f, "constant pool entry #{}", index),
+            Error::ConstantPoolInfo => write!(f, "constant pool info"),
+            Error::UnknownConstantPoolTag { tag } =>
+                write!(f, "unknown constant pool tag: 0x{:02x}", tag),
+            Error::ConstantPoolIndexOutOfBounds { index } =>
+                write!(f, "constant pool index #{} is out of bounds", index),
+            Error::UnusableConstantPoolEntry { index } =>
+                write!(f, "constant pool entry #{} follows a long or double and is unusable",
+                       index),
+            Error::UnexpectedConstantPoolType { index, ref expected, ref actual } =>
+                write!(f, "constant pool entry #{} should be {:?}, but was {:?}",
+                       index, expected, actual),
+
+            Error::IllegalModifiedUtf8 { byte } =>
+                write!(f, "illegal byte in modified UTF-8 string: 0x{:02x}", byte),
+            Error::ModifiedUtf8 { length } =>
+                write!(f, "modified UTF-8 string of {} bytes", length),
+            Error::MalformedModifiedUtf8 { offset } =>
+                write!(f, "malformed modified UTF-8 sequence at byte {}", offset),
+            Error::UnknownConstantPoolMethodReferenceTag { tag } =>
+                write!(f, "unknown method handle reference kind: {}", tag),
+            Error::Interfaces { interfaces_count } =>
+                write!(f, "interfaces (interfaces_count {})", interfaces_count),
+            Error::Fields { fields_count } => write!(f, "fields (fields_count {})", fields_count),
+            Error::FieldInfo => write!(f, "field info"),
+            Error::FieldAttributes { attributes_count } =>
+                write!(f, "field attributes (attributes_count {})", attributes_count),
+            Error::Methods { methods_count } =>
+                write!(f, "methods (methods_count {})", methods_count),
+            Error::MethodInfo => write!(f, "method info"),
+            Error::MethodAttributes { attributes_count } =>
+                write!(f, "method attributes (attributes_count {})", attributes_count),
+            Error::ClassAttributes { attributes_count } =>
+                write!(f, "class attributes (attributes_count {})", attributes_count),
+            Error::Attribute => write!(f, "attribute"),
+            Error::AttributeInfo { ref attribute_name, attribute_name_index, attribute_length } =>
+                write!(f, "{} attribute (attribute_name_index #{}, attribute_length {})",
+                       attribute_name, attribute_name_index, attribute_length),
+            Error::AttributeInfoNameIndexOutOfBounds { attribute_name_index } =>
+                write!(f, "attribute name index #{} is out of bounds", attribute_name_index),
+
+            Error::CodeAttributes { attributes_count } =>
+                write!(f, "code attributes (attributes_count {})", attributes_count),
+            Error::ExceptionTableEntry => write!(f, "exception table entry"),
+            Error::StackMapTable { number_of_entries } =>
+                write!(f, "stack map table (number_of_entries {})", number_of_entries),
+            Error::StackMapFrame => write!(f, "stack map frame"),
+            Error::UnknownStackMapFrameTag { tag } =>
+                write!(f, "unknown stack map frame type: {}", tag),
+            Error::ReservedStackMapFrameTag { tag } =>
+                write!(f, "reserved stack map frame type: {}", tag),
+            Error::VerificationTypeInfo => write!(f, "verification type info"),
+            Error::UnknownVerificationTypeInfoTag { tag } =>
+                write!(f, "unknown verification type info tag: {}", tag),
+
+            Error::BootstrapMethods { num_bootstrap_methods } =>
+                write!(f, "bootstrap methods (num_bootstrap_methods {})", num_bootstrap_methods),
+            Error::BootstrapMethod => write!(f, "bootstrap method"),
+            Error::BootstrapArguments { num_bootstrap_arguments } =>
+                write!(f, "bootstrap arguments (num_bootstrap_arguments {})",
+                       num_bootstrap_arguments),
+            Error::InnerClasses { number_of_classes } =>
+                write!(f, "inner classes (number_of_classes {})", number_of_classes),
+            Error::InnerClass => write!(f, "inner class"),
+            Error::Signature => write!(f, "signature"),
+            Error::MethodParameters { parameters_count } =>
+                write!(f, "method parameters (parameters_count {})", parameters_count),
+            Error::MethodParameter => write!(f, "method parameter"),
+            Error::ElementValuePair => write!(f, "element value pair"),
+            Error::ElementValuePairs { num_element_value_pairs } =>
+                write!(f, "element value pairs (num_element_value_pairs {})",
+                       num_element_value_pairs),
+            Error::ElementValue => write!(f, "element value"),
+            Error::UnknownElementValueTag { tag } =>
+                write!(f, "unknown element value tag: 0x{:02x}", tag),
+            Error::ElementValueArray { num_values } =>
+                write!(f, "element value array (num_values {})", num_values),
+            Error::Annotations { num_annotations } =>
+                write!(f, "annotations (num_annotations {})", num_annotations),
+            Error::ParameterAnnotations { num_parameters } =>
+                write!(f, "parameter annotations (num_parameters {})", num_parameters),
+            Error::TypeAnnotations { num_annotations } =>
+                write!(f, "type annotations (num_annotations {})", num_annotations),
+            Error::UnknownTargetTypeTag { tag } =>
+                write!(f, "unknown type annotation target type: 0x{:02x}", tag),
+            Error::LocalVariableTarget { table_length } =>
+                write!(f, "local variable target (table_length {})", table_length),
+            Error::TypePath { path_length } =>
+                write!(f, "type path (path_length {})", path_length),
+
+            Error::SourceFile => write!(f, "source file"),
+            Error::SourceDebugExtension => write!(f, "source debug extension"),
+            Error::LineNumberTable { table_length } =>
+                write!(f, "line number table (line_number_table_length {})", table_length),
+            Error::LineNumberInfo => write!(f, "line number info"),
+            Error::LocalVariableTable { table_length } =>
+                write!(f, "local variable table (local_variable_table_length {})", table_length),
+            Error::LocalVariableInfo => write!(f, "local variable info"),
+            Error::LocalVariableTypeTable { table_length } =>
+                write!(f, "local variable type table (local_variable_type_table_length {})",
+                       table_length),
+            Error::LocalVariableTypeInfo => write!(f, "local variable type info"),
+
+            Error::Module => write!(f, "module"),
+            Error::ModuleRequires { requires_count } =>
+                write!(f, "module requires (requires_count {})", requires_count),
+            Error::ModuleExports { exports_count } =>
+                write!(f, "module exports (exports_count {})", exports_count),
+            Error::ModuleExportsTo { exports_to_count } =>
+                write!(f, "module exports to (exports_to_count {})", exports_to_count),
+            Error::ModuleOpens { opens_count } =>
+                write!(f, "module opens (opens_count {})", opens_count),
+            Error::ModuleOpensTo { opens_to_count } =>
+                write!(f, "module opens to (opens_to_count {})", opens_to_count),
+            Error::ModuleUses { uses_count } =>
+                write!(f, "module uses (uses_count {})", uses_count),
+            Error::ModuleProvides { provides_count } =>
+                write!(f, "module provides (provides_count {})", provides_count),
+            Error::ModuleProvidesWith { provides_with_count } =>
+                write!(f, "module provides with (provides_with_count {})", provides_with_count),
+            Error::NestHost => write!(f, "nest host"),
+            Error::NestMembers { number_of_classes } =>
+                write!(f, "nest members (number_of_classes {})", number_of_classes),
+            Error::Record { components_count } =>
+                write!(f, "record (components_count {})", components_count),
+            Error::RecordComponent => write!(f, "record component"),
+            Error::RecordComponentAttributes { attributes_count } =>
+                write!(f, "record component attributes (attributes_count {})", attributes_count),
+            Error::PermittedSubclasses { number_of_classes } =>
+                write!(f, "permitted subclasses (number_of_classes {})", number_of_classes),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        "invalid class file"
+    }
 }
 
 macro_rules! p {
@@ -102,6 +275,8 @@ macro_rules! check_cp_index_tag {
     ($constant_pool: expr, $i: expr, $tag: expr) => ({
         match $constant_pool.get($i) {
             None => p_fail!(Error::ConstantPoolIndexOutOfBounds { index: $i }),
+            Some(&ConstantPoolInfo::Unusable) =>
+                p_fail!(Error::UnusableConstantPoolEntry { index: $i }),
             Some(r) if r.tag() == $tag => Ok(()),
             Some(r) => p_fail!(Error::UnexpectedConstantPoolType {
                 index: $i,
@@ -158,6 +333,16 @@ macro_rules! take_modified_utf8 {
     ($i: expr, $n: expr) => (p_cut!($i, Error::ModifiedUtf8 { length: $n }, count!(c!(modified_utf8), $n)))
 }
 
+/// Parses a modified UTF-8 string of `length` bytes. The bytes are checked individually as they
+/// are read, and then checked to form complete multi-byte sequences.
+fn modified_utf8_bytes(input: Input, length: usize) -> ParseResult<Vec<u8>> {
+    let (input, bytes) = p_try!(input, p_wrap_nom!(take_modified_utf8!(length)));
+    if let Err(error) = modified_utf8::validate(&bytes) {
+        p_fail!(Error::MalformedModifiedUtf8 { offset: error.offset });
+    }
+    Ok(done!(input, bytes))
+}
+
 n!(reference_kind<Input, constant_pool::reference_kind::Tag, Error>, map!(
     p!(be_u8),
     constant_pool::reference_kind::Tag::from));
@@ -271,7 +456,7 @@ fn cp_info_info(input: Input, tag: constant_pool::Tag) -> ParseResult<ConstantPo
 
         constant_pool::Tag::Utf8 => chain!(input,
                                            len: p!(be_u16) ~
-                                           bs: take_modified_utf8!(len as usize),
+                                           bs: c!(modified_utf8_bytes, len as usize),
                                            || ConstantPoolInfo::Utf8 { bytes: bs }),
 
         constant_pool::Tag::MethodHandle => chain!(input,
@@ -294,6 +479,12 @@ fn cp_info_info(input: Input, tag: constant_pool::Tag) -> ParseResult<ConstantPo
                                                         name_and_type_index: nti,
                                                     }),
 
+        constant_pool::Tag::Module => map!(input, c!(cp_index),
+                                           |ni| ConstantPoolInfo::Module { name_index: ni }),
+
+        constant_pool::Tag::Package => map!(input, c!(cp_index),
+                                            |ni| ConstantPoolInfo::Package { name_index: ni }),
+
         constant_pool::Tag::Unknown(t) => p_nom_error!(Error::UnknownConstantPoolTag { tag: t }),
     };
     wrap_nom!(r)
@@ -494,6 +685,10 @@ fn attribute_info<'a, 'b>(input: Input<'a>, attribute_name_index: ConstantPoolIn
                        constant_pool))
             },
 
+            ConstantPoolInfo::Unusable => p_fail!(Error::UnusableConstantPoolEntry {
+                index: attribute_name_index as usize,
+            }),
+
             ref cp_entry => p_fail!(Error::UnexpectedConstantPoolType {
                 index: attribute_name_index as usize,
                 expected: constant_pool::Tag::Utf8,
@@ -504,6 +699,25 @@ fn attribute_info<'a, 'b>(input: Input<'a>, attribute_name_index: ConstantPoolIn
     Ok(r)
 }
 
+fn bootstrap_method<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
+                            -> ParseResult<'a, attribute::BootstrapMethod> {
+    wrap_nom!(p_cut!(
+        input,
+        Error::BootstrapMethod,
+        chain!(bootstrap_method_ref: c!(cp_index_tag, constant_pool,
+                                        constant_pool::Tag::MethodHandle) ~
+               num_bootstrap_arguments: p!(be_u16) ~
+               bootstrap_arguments: p_cut!(
+                   Error::BootstrapArguments {
+                       num_bootstrap_arguments: num_bootstrap_arguments as usize
+                   },
+                   count!(c!(cp_index), num_bootstrap_arguments as usize)),
+               || attribute::BootstrapMethod {
+                   bootstrap_method_ref: bootstrap_method_ref,
+                   bootstrap_arguments: bootstrap_arguments,
+               })))
+}
+
 fn inner_class<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
                        -> ParseResult<'a, attribute::InnerClass> {
     wrap_nom!(p_cut!(
@@ -534,6 +748,96 @@ fn method_parameter<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
                })))
 }
 
+/// Parses for a `u2` count followed by that many constant pool indices whose entries in the
+/// constant pool match the specified tag.
+fn cp_index_tag_table<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool,
+                              tag: constant_pool::Tag, error: fn(usize) -> Error)
+                              -> ParseResult<'a, Vec<ConstantPoolIndex>> {
+    wrap_nom!(
+        chain!(input,
+               count: p!(be_u16) ~
+               table: p_cut!(
+                   error(count as usize),
+                   count!(c!(cp_index_tag, constant_pool, tag), count as usize)),
+               || table))
+}
+
+fn module_requires<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
+                           -> ParseResult<'a, attribute::ModuleRequires> {
+    wrap_nom!(
+        chain!(input,
+               requires_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Module) ~
+               requires_flags: p!(be_u16) ~
+               requires_version_index: c!(maybe_cp_index_tag, constant_pool,
+                                          constant_pool::Tag::Utf8),
+               || attribute::ModuleRequires {
+                   requires_index: requires_index,
+                   requires_flags: requires_flags,
+                   requires_version_index: requires_version_index,
+               }))
+}
+
+fn module_exports<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
+                          -> ParseResult<'a, attribute::ModuleExports> {
+    wrap_nom!(
+        chain!(input,
+               exports_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Package) ~
+               exports_flags: p!(be_u16) ~
+               exports_to_index: c!(cp_index_tag_table, constant_pool, constant_pool::Tag::Module,
+                                    |n| Error::ModuleExportsTo { exports_to_count: n }),
+               || attribute::ModuleExports {
+                   exports_index: exports_index,
+                   exports_flags: exports_flags,
+                   exports_to_index: exports_to_index,
+               }))
+}
+
+fn module_opens<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
+                        -> ParseResult<'a, attribute::ModuleOpens> {
+    wrap_nom!(
+        chain!(input,
+               opens_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Package) ~
+               opens_flags: p!(be_u16) ~
+               opens_to_index: c!(cp_index_tag_table, constant_pool, constant_pool::Tag::Module,
+                                  |n| Error::ModuleOpensTo { opens_to_count: n }),
+               || attribute::ModuleOpens {
+                   opens_index: opens_index,
+                   opens_flags: opens_flags,
+                   opens_to_index: opens_to_index,
+               }))
+}
+
+fn module_provides<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
+                           -> ParseResult<'a, attribute::ModuleProvides> {
+    wrap_nom!(
+        chain!(input,
+               provides_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Class) ~
+               provides_with_index: c!(cp_index_tag_table, constant_pool, constant_pool::Tag::Class,
+                                       |n| Error::ModuleProvidesWith { provides_with_count: n }),
+               || attribute::ModuleProvides {
+                   provides_index: provides_index,
+                   provides_with_index: provides_with_index,
+               }))
+}
+
+fn record_component<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
+                            -> ParseResult<'a, attribute::RecordComponent> {
+    wrap_nom!(p_cut!(
+        input,
+        Error::RecordComponent,
+        chain!(name_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Utf8) ~
+               descriptor_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Utf8) ~
+               attributes_count: p!(be_u16) ~
+               attributes: p_cut!(
+                   Error::RecordComponentAttributes { attributes_count: attributes_count as usize },
+                   count!(c!(attribute, constant_pool), attributes_count as usize)),
+               || attribute::RecordComponent {
+                   name_index: name_index,
+                   descriptor_index: descriptor_index,
+                   attributes: attributes,
+               })))
+}
+
 fn element_value_pair<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
                               -> ParseResult<'a, attribute::annotation::ElementValuePair> {
     wrap_nom!(p_cut!(input,
@@ -645,19 +949,18 @@ fn parameter_annotations<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPo
                                  -> ParseResult<'a, Vec<Vec<attribute::annotation::Annotation>>> {
     p_wrap_nom!(
         input,
-        chain!(num_parameters: p!(be_u16) ~
+        chain!(num_parameters: p!(be_u8) ~
                parameter_annotations: p_cut!(
                    Error::ParameterAnnotations { num_parameters: num_parameters as usize },
                    count!(c!(annotations, constant_pool), num_parameters as usize)),
                || parameter_annotations))
 }
 
-fn target_info(input: Input) -> ParseResult<attribute::annotation::TargetInfo> {
+fn target_info(input: Input, target_type: u8) -> ParseResult<attribute::annotation::TargetInfo> {
     use model::class_file::attribute::annotation::target_type::Tag;
     use model::class_file::attribute::annotation::TargetInfo;
     use model::class_file::attribute::annotation::LocalVariableTargetInfo;
-    let (input, tag) = p_try!(input, p_wrap_nom!(map!(p!(be_u8), Tag::from)));
-    let r = match tag {
+    let r = match Tag::from(target_type) {
         Tag::TypeParameter =>
             map!(input, p!(be_u8), |i| TargetInfo::TypeParameter { type_parameter_index: i }),
 
@@ -729,11 +1032,13 @@ fn type_annotation<'a, 'b>(input: Input<'a>, constant_pool: &'b ConstantPool)
                            -> ParseResult<'a, attribute::annotation::TypeAnnotation> {
     p_wrap_nom!(
         input,
-        chain!(target_info: c!(target_info) ~
+        chain!(target_type: p!(be_u8) ~
+               target_info: c!(target_info, target_type) ~
                target_path: c!(type_path) ~
                type_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Utf8) ~
                element_value_pairs: c!(element_value_pairs, constant_pool),
                || attribute::annotation::TypeAnnotation {
+                   target_type: target_type,
                    target_info: target_info,
                    target_path: target_path,
                    type_index: type_index,
@@ -806,6 +1111,16 @@ fn attribute_info_switch<'a, 'b>(input: Input<'a>, attribute_name: &[u8],
                        exception_index_table: exception_index_table,
                    }),
 
+        b"BootstrapMethods" =>
+            chain!(input,
+                   num_bootstrap_methods: p!(be_u16) ~
+                   bootstrap_methods: p_cut!(
+                       Error::BootstrapMethods {
+                           num_bootstrap_methods: num_bootstrap_methods as usize
+                       },
+                       count!(c!(bootstrap_method, constant_pool), num_bootstrap_methods as usize)),
+                   || AttributeInfo::BootstrapMethods { bootstrap_methods: bootstrap_methods }),
+
         b"InnerClasses" =>
             chain!(input,
                    number_of_classes: p!(be_u16) ~
@@ -817,7 +1132,8 @@ fn attribute_info_switch<'a, 'b>(input: Input<'a>, attribute_name: &[u8],
         b"EnclosingMethod" =>
             chain!(input,
                    class_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Class) ~
-                   method_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::NameAndType),
+                   method_index: c!(maybe_cp_index_tag, constant_pool,
+                                    constant_pool::Tag::NameAndType),
                    || AttributeInfo::EnclosingMethod {
                        class_index: class_index,
                        method_index: method_index,
@@ -869,7 +1185,7 @@ fn attribute_info_switch<'a, 'b>(input: Input<'a>, attribute_name: &[u8],
 
         b"MethodParameters" =>
             chain!(input,
-                   parameters_count: p!(be_u16) ~
+                   parameters_count: p!(be_u8) ~
                    parameters: p_cut!(
                        Error::MethodParameters { parameters_count: parameters_count as usize },
                        count!(c!(method_parameter, constant_pool), parameters_count as usize)),
@@ -921,6 +1237,69 @@ fn attribute_info_switch<'a, 'b>(input: Input<'a>, attribute_name: &[u8],
 
         b"Deprecated" => done!(input, AttributeInfo::Deprecated),
 
+        b"Module" => p_cut!(
+            input,
+            Error::Module,
+            chain!(module_name_index: c!(cp_index_tag, constant_pool, constant_pool::Tag::Module) ~
+                   module_flags: p!(be_u16) ~
+                   module_version_index: c!(maybe_cp_index_tag, constant_pool,
+                                            constant_pool::Tag::Utf8) ~
+                   requires_count: p!(be_u16) ~
+                   requires: p_cut!(
+                       Error::ModuleRequires { requires_count: requires_count as usize },
+                       count!(c!(module_requires, constant_pool), requires_count as usize)) ~
+                   exports_count: p!(be_u16) ~
+                   exports: p_cut!(
+                       Error::ModuleExports { exports_count: exports_count as usize },
+                       count!(c!(module_exports, constant_pool), exports_count as usize)) ~
+                   opens_count: p!(be_u16) ~
+                   opens: p_cut!(
+                       Error::ModuleOpens { opens_count: opens_count as usize },
+                       count!(c!(module_opens, constant_pool), opens_count as usize)) ~
+                   uses_index: c!(cp_index_tag_table, constant_pool, constant_pool::Tag::Class,
+                                  |n| Error::ModuleUses { uses_count: n }) ~
+                   provides_count: p!(be_u16) ~
+                   provides: p_cut!(
+                       Error::ModuleProvides { provides_count: provides_count as usize },
+                       count!(c!(module_provides, constant_pool), provides_count as usize)),
+                   || AttributeInfo::Module {
+                       module_name_index: module_name_index,
+                       module_flags: module_flags,
+                       module_version_index: module_version_index,
+                       requires: requires,
+                       exports: exports,
+                       opens: opens,
+                       uses_index: uses_index,
+                       provides: provides,
+                   })),
+
+        b"NestHost" => p_cut!(
+            input, Error::NestHost,
+            map!(c!(cp_index_tag, constant_pool, constant_pool::Tag::Class),
+                 |hci| AttributeInfo::NestHost { host_class_index: hci })),
+
+        b"NestMembers" =>
+            map!(input, c!(cp_index_tag_table, constant_pool, constant_pool::Tag::Class,
+                           |n| Error::NestMembers { number_of_classes: n }),
+                 |classes| AttributeInfo::NestMembers { classes: classes }),
+
+        b"Record" =>
+            chain!(input,
+                   components_count: p!(be_u16) ~
+                   components: p_cut!(
+                       Error::Record { components_count: components_count as usize },
+                       count!(c!(record_component, constant_pool), components_count as usize)),
+                   || AttributeInfo::Record { components: components }),
+
+        b"PermittedSubclasses" =>
+            chain!(input,
+                   number_of_classes: p!(be_u16) ~
+                   classes: p_cut!(
+                       Error::PermittedSubclasses { number_of_classes: number_of_classes as usize },
+                       count!(c!(cp_index_tag, constant_pool, constant_pool::Tag::Class),
+                              number_of_classes as usize)),
+                   || AttributeInfo::PermittedSubclasses { classes: classes }),
+
         _ => map!(input, p!(take!(attribute_length)), |bs: Input| AttributeInfo::Unknown {
             attribute_name_index: attribute_name_index,
             info: bs.to_vec()
@@ -1002,7 +1381,7 @@ macro_rules! constant_pool_special_count {
     });
 }
 
-/// `parser::class_file::parse_class_file(&[u8]) -> ParseResult<model::class_file::ClassFile>)`
+/// `parser::class_file::class_file_parser(&[u8]) -> ParseResult<model::class_file::ClassFile>)`
 n!(class_file_parser<Input, ClassFile, Error>, p_cut!(
     Error::ClassFile,
     chain!(c!(magic) ~
@@ -1013,7 +1392,8 @@ n!(class_file_parser<Input, ClassFile, Error>, p_cut!(
                Error::ConstantPool {
                    constant_pool_count: constant_pool_count as usize
                },
-               map!(constant_pool_special_count!(c!(cp_info), constant_pool_count as usize - 1),
+               map!(constant_pool_special_count!(
+                        c!(cp_info), (constant_pool_count as usize).saturating_sub(1)),
                     ConstantPool::from_zero_indexed_vec)) ~
            access_flags: p!(be_u16) ~
            this_class: c!(cp_index_tag, &constant_pool, constant_pool::Tag::Class) ~
@@ -1045,10 +1425,108 @@ n!(class_file_parser<Input, ClassFile, Error>, p_cut!(
            })));
 
 /// Parses a Java class file.
-pub fn parse_class_file(input: Input) -> nom::IResult<Input, ClassFile, Error> {
-    match class_file_parser(input) {
-        Ok(r) => r,
-        Err(e) => nom::IResult::Error(e),
+pub fn parse_class_file(input: Input) -> Result<ClassFile, ParseError> {
+    class_file_parser(input)
+        .map_err(|e| ParseError::invalid(input, e))
+        .and_then(|result| into_result(input, result))
+}
+
+/// Converts the `nom::IResult` of a parser into a `Result`. Input which the parser did not consume
+/// follows the end of the class file, and so is an error. Since the whole class file is in memory,
+/// an `Incomplete` result means that the class file is truncated. `input` is the whole of the input
+/// given to the parser.
+fn into_result<'a, O>(input: Input<'a>, result: nom::IResult<Input<'a>, O, Error>)
+                      -> Result<O, ParseError> {
+    match result {
+        nom::IResult::Done(remaining, _) if !remaining.is_empty() => {
+            let kind = ErrorKind::Custom(Error::TrailingBytes { length: remaining.len() });
+            Err(ParseError::invalid(input, nom::Err::Position(kind, remaining)))
+        },
+        nom::IResult::Done(_, output) => Ok(output),
+        nom::IResult::Incomplete(needed) => Err(ParseError::Incomplete(needed)),
+        nom::IResult::Error(e) => Err(ParseError::invalid(input, e)),
+    }
+}
+
+/// An error encountered while parsing a class file with `parse_class_file`.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The input ended before a complete class file could be parsed.
+    Incomplete(nom::Needed),
+    /// The input is not a valid class file.
+    Invalid {
+        /// The chain of errors reported by the parser, ordered from the outermost structure of
+        /// the class file to the innermost structure in which the failure occurred.
+        kinds: Vec<ErrorKind<Error>>,
+        /// The offset in bytes from the start of the class file of the innermost structure in
+        /// which the failure occurred.
+        offset: usize,
+    },
+}
+
+impl ParseError {
+    /// Creates a `ParseError::Invalid` from an error returned by the parser. `input` is the whole
+    /// of the input given to the parser, which the positions recorded in `err` are slices of.
+    fn invalid<'a>(input: Input<'a>, err: nom::Err<Input<'a>, Error>) -> Self {
+        let offset_of = |position: Input| position.as_ptr() as usize - input.as_ptr() as usize;
+        let mut kinds = vec![];
+        let mut offset = 0;
+        let mut next = Some(err);
+        while let Some(err) = next {
+            next = match err {
+                nom::Err::Code(kind) => {
+                    kinds.push(kind);
+                    None
+                },
+                nom::Err::Position(kind, position) => {
+                    kinds.push(kind);
+                    offset = offset_of(position);
+                    None
+                },
+                nom::Err::Node(kind, inner) => {
+                    kinds.push(kind);
+                    Some(*inner)
+                },
+                nom::Err::NodePosition(kind, position, inner) => {
+                    kinds.push(kind);
+                    offset = offset_of(position);
+                    Some(*inner)
+                },
+            };
+        }
+        ParseError::Invalid { kinds: kinds, offset: offset }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Incomplete(nom::Needed::Size(n)) =>
+                write!(f, "unexpected end of class file: {} more bytes needed", n),
+            ParseError::Incomplete(nom::Needed::Unknown) =>
+                write!(f, "unexpected end of class file"),
+            ParseError::Invalid { ref kinds, offset } => {
+                try!(write!(f, "invalid class file at offset {}", offset));
+                for kind in kinds {
+                    match *kind {
+                        ErrorKind::Custom(ref e) => try!(write!(f, ": {}", e)),
+                        // added by `fix_error!`, which converts nom's errors into our own
+                        ErrorKind::Fix => (),
+                        ref kind => try!(write!(f, ": {}", kind.description())),
+                    }
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseError::Incomplete(_) => "unexpected end of class file",
+            ParseError::Invalid { .. } => "invalid class file",
+        }
     }
 }
 
@@ -1056,19 +1534,469 @@ pub fn parse_class_file(input: Input) -> nom::IResult<Input, ClassFile, Error> {
 mod test {
     use super::*;
 
+    use std::collections::HashSet;
+
+    fn utf8_at(constant_pool: &ConstantPool, index: ConstantPoolIndex) -> String {
+        match constant_pool[index as usize] {
+            ConstantPoolInfo::Utf8 { ref bytes } => String::from_utf8(bytes.clone()).unwrap(),
+            ref info => panic!("Expected a Utf8, but was {:?}", info),
+        }
+    }
+
+    /// Returns the name referenced by the `Class`, `Module` or `Package` constant at `index`.
+    fn name_at(constant_pool: &ConstantPool, index: ConstantPoolIndex) -> String {
+        match constant_pool[index as usize] {
+            ConstantPoolInfo::Class { name_index } |
+            ConstantPoolInfo::Module { name_index } |
+            ConstantPoolInfo::Package { name_index } => utf8_at(constant_pool, name_index),
+            ref info => panic!("Expected a Class, Module or Package, but was {:?}", info),
+        }
+    }
+
     #[test]
     fn test_hello_world() {
         let data = include_bytes!("../../data/HelloWorld.class");
-        assert!(parse_class_file(data).is_done());
+        assert!(parse_class_file(data).is_ok());
+    }
+
+    #[test]
+    fn test_equality() {
+        let data = include_bytes!("../../data/HelloWorld.class");
+        let class = parse_class_file(data).unwrap();
+        assert_eq!(class, parse_class_file(data).unwrap());
+        assert_eq!(class, class.clone());
+        let other = parse_class_file(include_bytes!("../../data/String.class")).unwrap();
+        assert!(class != other);
+    }
+
+    #[test]
+    fn test_hash_constant_pool() {
+        let data = include_bytes!("../../data/HelloWorld.class");
+        let class = parse_class_file(data).unwrap();
+        let entries = class.constant_pool.iter().collect::<HashSet<_>>();
+        assert!(entries.contains(&ConstantPoolInfo::Utf8 { bytes: b"main".to_vec() }));
+        assert!(!entries.contains(&ConstantPoolInfo::Utf8 { bytes: b"missing".to_vec() }));
     }
 
     #[test]
     fn test_java_lang_string() {
         let data = include_bytes!("../../data/String.class"); // java.lang.String
         match parse_class_file(data) {
-            ::nom::IResult::Done(_, class) => assert_eq!(536, class.constant_pool.len()),
-            _ => panic!("Failed to parse."),
+            Ok(class) => assert_eq!(536, class.constant_pool.len()),
+            Err(e) => panic!("Failed to parse: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_methods() {
+        let data = include_bytes!("../../data/Lambda.class");
+        let class = parse_class_file(data).unwrap();
+        let bootstrap_methods = class.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::BootstrapMethods { ref bootstrap_methods } => Some(bootstrap_methods),
+            _ => None,
+        }).next().expect("missing BootstrapMethods attribute");
+        assert_eq!(1, bootstrap_methods.len());
+        let bootstrap_method = &bootstrap_methods[0];
+        match class.constant_pool[bootstrap_method.bootstrap_method_ref as usize] {
+            ConstantPoolInfo::MethodHandle { .. } => (),
+            ref info => panic!("Expected a MethodHandle, but was {:?}", info),
+        }
+        assert_eq!(3, bootstrap_method.bootstrap_arguments.len());
+    }
+
+    #[test]
+    fn test_module() {
+        use model::class_file::access_flags::requires_access_flags;
+
+        let data = include_bytes!("../../data/module-info.class");
+        let class = parse_class_file(data).unwrap();
+        let name = |index| name_at(&class.constant_pool, index);
+        match *class.attributes.iter().find(|attr| match **attr {
+            AttributeInfo::Module { .. } => true,
+            _ => false,
+        }).expect("missing Module attribute") {
+            AttributeInfo::Module {
+                module_name_index, module_version_index, ref requires, ref exports, ref opens,
+                ref uses_index, ref provides, ..
+            } => {
+                assert_eq!("com.example.hello", name(module_name_index));
+                assert_eq!(0, module_version_index);
+
+                assert_eq!(2, requires.len());
+                assert_eq!("java.base", name(requires[0].requires_index));
+                assert_eq!("java.logging", name(requires[1].requires_index));
+                assert_eq!(requires_access_flags::ACC_TRANSITIVE, requires[1].requires_flags);
+
+                assert_eq!(1, exports.len());
+                assert_eq!("com/example/hello", name(exports[0].exports_index));
+                assert!(exports[0].exports_to_index.is_empty());
+
+                assert_eq!(1, opens.len());
+                assert_eq!("com/example/internal", name(opens[0].opens_index));
+                assert_eq!(vec!["java.base".to_owned()],
+                           opens[0].opens_to_index.iter().map(|&i| name(i)).collect::<Vec<_>>());
+
+                assert_eq!(vec!["java/lang/Runnable".to_owned()],
+                           uses_index.iter().map(|&i| name(i)).collect::<Vec<_>>());
+
+                assert_eq!(1, provides.len());
+                assert_eq!("java/lang/Runnable", name(provides[0].provides_index));
+                let provides_with = provides[0].provides_with_index.iter().map(|&i| name(i));
+                assert_eq!(vec!["com/example/hello/Hello".to_owned()],
+                           provides_with.collect::<Vec<_>>());
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_nest_members() {
+        let data = include_bytes!("../../data/Outer.class");
+        let class = parse_class_file(data).unwrap();
+        let classes = class.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::NestMembers { ref classes } => Some(classes),
+            _ => None,
+        }).next().expect("missing NestMembers attribute");
+        let mut names =
+            classes.iter().map(|&i| name_at(&class.constant_pool, i)).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(vec!["Outer$Inner", "Outer$Nested"], names);
+    }
+
+    #[test]
+    fn test_nest_host() {
+        let data = include_bytes!("../../data/Outer$Inner.class");
+        let class = parse_class_file(data).unwrap();
+        let host_class_index = class.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::NestHost { host_class_index } => Some(host_class_index),
+            _ => None,
+        }).next().expect("missing NestHost attribute");
+        assert_eq!("Outer", name_at(&class.constant_pool, host_class_index));
+    }
+
+    #[test]
+    fn test_enclosing_method() {
+        // A local class declared in a static initializer, which is not a method.
+        let data = include_bytes!("../../data/Registry$1Local.class");
+        let class = parse_class_file(data).unwrap();
+        let (class_index, method_index) = class.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::EnclosingMethod { class_index, method_index } =>
+                Some((class_index, method_index)),
+            _ => None,
+        }).next().expect("missing EnclosingMethod attribute");
+        assert_eq!("Registry", name_at(&class.constant_pool, class_index));
+        assert_eq!(0, method_index);
+    }
+
+    #[test]
+    fn test_record() {
+        let data = include_bytes!("../../data/Point.class");
+        let class = parse_class_file(data).unwrap();
+        let components = class.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::Record { ref components } => Some(components),
+            _ => None,
+        }).next().expect("missing Record attribute");
+        let cp = &class.constant_pool;
+        assert_eq!(vec![("x".to_owned(), "I".to_owned()),
+                        ("y".to_owned(), "I".to_owned()),
+                        ("labels".to_owned(), "Ljava/util/List;".to_owned())],
+                   components.iter()
+                       .map(|c| (utf8_at(cp, c.name_index), utf8_at(cp, c.descriptor_index)))
+                       .collect::<Vec<_>>());
+        assert!(components[0].attributes.is_empty());
+        match components[2].attributes[..] {
+            [AttributeInfo::Signature { signature_index }] =>
+                assert_eq!("Ljava/util/List<Ljava/lang/String;>;", utf8_at(cp, signature_index)),
+            ref attrs => panic!("Expected a single Signature attribute, but was {:?}", attrs),
+        }
+    }
+
+    #[test]
+    fn test_permitted_subclasses() {
+        let data = include_bytes!("../../data/Shape.class");
+        let class = parse_class_file(data).unwrap();
+        let classes = class.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::PermittedSubclasses { ref classes } => Some(classes),
+            _ => None,
+        }).next().expect("missing PermittedSubclasses attribute");
+        assert_eq!(vec!["Circle", "Square"],
+                   classes.iter().map(|&i| name_at(&class.constant_pool, i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_stack_map_frames() {
+        use model::class_file::attribute::StackMapFrame;
+        use model::class_file::attribute::stack_map_frame::VerificationTypeInfo;
+        let constant_pool = ConstantPool::from_zero_indexed_vec(vec![]);
+        let data = [5,
+                    67, 1,
+                    247, 1, 44, 1,
+                    249, 0, 7,
+                    251, 3, 232,
+                    252, 0, 2, 1,
+                    255, 0, 4, 0, 0, 0, 1, 2];
+        let mut input = &data[..];
+        let mut frames = vec![];
+        while !input.is_empty() {
+            match stack_map_frame(input, &constant_pool) {
+                Ok(nom::IResult::Done(rest, frame)) => {
+                    input = rest;
+                    frames.push(frame);
+                },
+                r => panic!("Expected a stack map frame, but was {:?}", r),
+            }
+        }
+        assert_eq!(frames, vec![
+            StackMapFrame::SameFrame { offset_delta: 5 },
+            StackMapFrame::SameLocals1StackItemFrame {
+                offset_delta: 3,
+                stack_item: VerificationTypeInfo::Integer,
+            },
+            StackMapFrame::SameLocals1StackItemFrameExtended {
+                offset_delta: 300,
+                stack_item: VerificationTypeInfo::Integer,
+            },
+            StackMapFrame::ChopFrame { offset_delta: 7, num_chopped: 2 },
+            StackMapFrame::SameFrameExtended { offset_delta: 1000 },
+            StackMapFrame::AppendFrame {
+                offset_delta: 2,
+                locals: vec![VerificationTypeInfo::Integer],
+            },
+            StackMapFrame::FullFrame {
+                offset_delta: 4,
+                locals: vec![],
+                stack: vec![VerificationTypeInfo::Float],
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = include_bytes!("../../data/HelloWorld.class");
+        match parse_class_file(&data[..data.len() - 1]) {
+            Err(ParseError::Incomplete(_)) => (),
+            r => panic!("Expected incomplete input, but was {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let data = [0xCA, 0xFE, 0xD0, 0x0D, 0x00, 0x00, 0x00, 0x32];
+        match parse_class_file(&data) {
+            Err(ParseError::Invalid { kinds, .. }) => {
+                assert!(kinds.iter().any(|kind| match *kind {
+                    ErrorKind::Custom(Error::Magic) => true,
+                    _ => false,
+                }));
+            },
+            r => panic!("Expected invalid class file, but was {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_zero_constant_pool_index() {
+        // a class whose this_class index is 0, which is never a valid constant pool index
+        let data = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x32,
+                    0x00, 0x02, 0x01, 0x00, 0x01, b'A',
+                    0x00, 0x21, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        match parse_class_file(&data) {
+            Err(ParseError::Invalid { kinds, .. }) => {
+                assert!(kinds.iter().any(|kind| match *kind {
+                    ErrorKind::Custom(Error::ConstantPoolIndexOutOfBounds { index: 0 }) => true,
+                    _ => false,
+                }));
+            },
+            r => panic!("Expected invalid class file, but was {:?}", r),
+        }
+    }
+
+    fn assert_invalid(data: &[u8], error: Error) {
+        match parse_class_file(data) {
+            Err(ParseError::Invalid { kinds, .. }) => {
+                let expected = format!("{:?}", error);
+                assert!(kinds.iter().any(|kind| match *kind {
+                    ErrorKind::Custom(ref e) => format!("{:?}", e) == expected,
+                    _ => false,
+                }), "expected {}, but was {:?}", expected, kinds);
+            },
+            r => panic!("Expected invalid class file, but was {:?}", r),
         }
     }
 
+    #[test]
+    fn test_zero_constant_pool_count() {
+        // a constant_pool_count of 0 is invalid, since the constant pool count is one more than
+        // the number of entries
+        let data = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x32,
+                    0x00, 0x00,
+                    0x00, 0x21, 0x00, 0x01, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_invalid(&data, Error::ConstantPoolIndexOutOfBounds { index: 1 });
+    }
+
+    #[test]
+    fn test_unusable_constant_pool_index() {
+        // a class whose this_class index is the unusable entry following a long constant
+        let data = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x32,
+                    0x00, 0x04, 0x05, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x01, 0x00, 0x01, b'A',
+                    0x00, 0x21, 0x00, 0x02, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_invalid(&data, Error::UnusableConstantPoolEntry { index: 2 });
+    }
+
+    #[test]
+    fn test_into_result() {
+        let input = [0; 8];
+        let done: nom::IResult<Input, u8, Error> = nom::IResult::Done(&input[8..], 3);
+        assert_eq!(into_result(&input, done).unwrap(), 3);
+        let trailing: nom::IResult<Input, u8, Error> = nom::IResult::Done(&input[6..], 3);
+        let error = into_result(&input, trailing).unwrap_err();
+        assert_eq!(error.to_string(), "invalid class file at offset 6: \
+                                       2 unexpected bytes after the end of the class file");
+        let incomplete: nom::IResult<Input, u8, Error> =
+            nom::IResult::Incomplete(nom::Needed::Size(4));
+        match into_result(&[], incomplete) {
+            Err(ParseError::Incomplete(nom::Needed::Size(4))) => (),
+            r => panic!("Expected incomplete input, but was {:?}", r),
+        }
+        let inner = Error::ConstantPoolIndexOutOfBounds { index: 7 };
+        let error: nom::IResult<Input, u8, Error> = nom::IResult::Error(nom::Err::NodePosition(
+            ErrorKind::Custom(Error::ClassFile), &input[3..],
+            Box::new(nom::Err::Code(ErrorKind::Custom(inner)))));
+        let error = into_result(&input, error).unwrap_err();
+        assert_eq!(error.to_string(), "invalid class file at offset 3: class file: \
+                                       constant pool index #7 is out of bounds");
+    }
+
+    #[test]
+    fn test_error_offset() {
+        let mut data = include_bytes!("../../data/HelloWorld.class").to_vec();
+        // #1 is a five-byte Methodref at offset 10, so #2 begins at offset 15
+        assert_eq!(data[15], 9);
+        data[15] = 0xee;
+        match parse_class_file(&data) {
+            Err(ParseError::Invalid { kinds, offset }) => {
+                assert!(kinds.iter().any(|kind| match *kind {
+                    ErrorKind::Custom(Error::ConstantPoolEntry { index: 1 }) => true,
+                    _ => false,
+                }));
+                assert_eq!(offset, 15);
+            },
+            r => panic!("Expected invalid class file, but was {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_truncated_modified_utf8() {
+        // a constant pool whose only entry is a Utf8 constant ending partway through a two-byte
+        // sequence
+        let data = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x32, 0x00, 0x02,
+                    0x01, 0x00, 0x02, 0x61, 0xc3];
+        let error = parse_class_file(&data).unwrap_err();
+        assert!(error.to_string().ends_with("malformed modified UTF-8 sequence at byte 1"),
+                "{}", error);
+    }
+
+    #[test]
+    fn test_display_bad_magic() {
+        let data = [0xCA, 0xFE, 0xD0, 0x0D, 0x00, 0x00, 0x00, 0x32];
+        let error = parse_class_file(&data).unwrap_err();
+        assert_eq!(error.to_string(),
+                   "invalid class file at offset 0: class file: \
+                    bad magic number (expected 0xCAFEBABE)");
+    }
+
+    #[test]
+    fn test_display_errors() {
+        let errors = vec![
+            Error::ClassFile,
+            Error::Magic,
+            Error::ConstantPool { constant_pool_count: 1 },
+            Error::ConstantPoolEntry { index: 1 },
+            Error::ConstantPoolInfo,
+            Error::UnknownConstantPoolTag { tag: 2 },
+            Error::ConstantPoolIndexOutOfBounds { index: 1 },
+            Error::UnusableConstantPoolEntry { index: 2 },
+            Error::UnexpectedConstantPoolType {
+                index: 1,
+                expected: constant_pool::Tag::Utf8,
+                actual: constant_pool::Tag::Integer,
+            },
+            Error::IllegalModifiedUtf8 { byte: 0 },
+            Error::ModifiedUtf8 { length: 1 },
+            Error::MalformedModifiedUtf8 { offset: 1 },
+            Error::UnknownConstantPoolMethodReferenceTag { tag: 10 },
+            Error::Interfaces { interfaces_count: 1 },
+            Error::Fields { fields_count: 1 },
+            Error::FieldInfo,
+            Error::FieldAttributes { attributes_count: 1 },
+            Error::Methods { methods_count: 1 },
+            Error::MethodInfo,
+            Error::MethodAttributes { attributes_count: 1 },
+            Error::ClassAttributes { attributes_count: 1 },
+            Error::Attribute,
+            Error::AttributeInfo {
+                attribute_name: String::from("Code"),
+                attribute_name_index: 1,
+                attribute_length: 1,
+            },
+            Error::AttributeInfoNameIndexOutOfBounds { attribute_name_index: 1 },
+            Error::CodeAttributes { attributes_count: 1 },
+            Error::ExceptionTableEntry,
+            Error::StackMapTable { number_of_entries: 1 },
+            Error::StackMapFrame,
+            Error::UnknownStackMapFrameTag { tag: 1 },
+            Error::ReservedStackMapFrameTag { tag: 128 },
+            Error::VerificationTypeInfo,
+            Error::UnknownVerificationTypeInfoTag { tag: 9 },
+            Error::BootstrapMethods { num_bootstrap_methods: 1 },
+            Error::BootstrapMethod,
+            Error::BootstrapArguments { num_bootstrap_arguments: 1 },
+            Error::InnerClasses { number_of_classes: 1 },
+            Error::InnerClass,
+            Error::Signature,
+            Error::MethodParameters { parameters_count: 1 },
+            Error::MethodParameter,
+            Error::ElementValuePair,
+            Error::ElementValuePairs { num_element_value_pairs: 1 },
+            Error::ElementValue,
+            Error::UnknownElementValueTag { tag: 0 },
+            Error::ElementValueArray { num_values: 1 },
+            Error::Annotations { num_annotations: 1 },
+            Error::ParameterAnnotations { num_parameters: 1 },
+            Error::TypeAnnotations { num_annotations: 1 },
+            Error::UnknownTargetTypeTag { tag: 0xff },
+            Error::LocalVariableTarget { table_length: 1 },
+            Error::TypePath { path_length: 1 },
+            Error::SourceFile,
+            Error::SourceDebugExtension,
+            Error::LineNumberTable { table_length: 1 },
+            Error::LineNumberInfo,
+            Error::LocalVariableTable { table_length: 1 },
+            Error::LocalVariableInfo,
+            Error::LocalVariableTypeTable { table_length: 1 },
+            Error::LocalVariableTypeInfo,
+            Error::Module,
+            Error::ModuleRequires { requires_count: 1 },
+            Error::ModuleExports { exports_count: 1 },
+            Error::ModuleExportsTo { exports_to_count: 1 },
+            Error::ModuleOpens { opens_count: 1 },
+            Error::ModuleOpensTo { opens_to_count: 1 },
+            Error::ModuleUses { uses_count: 1 },
+            Error::ModuleProvides { provides_count: 1 },
+            Error::ModuleProvidesWith { provides_with_count: 1 },
+            Error::NestHost,
+            Error::NestMembers { number_of_classes: 1 },
+            Error::Record { components_count: 1 },
+            Error::RecordComponent,
+            Error::RecordComponentAttributes { attributes_count: 1 },
+            Error::PermittedSubclasses { number_of_classes: 1 },
+        ];
+        for error in errors {
+            assert!(!error.to_string().is_empty());
+        }
+        assert_eq!(Error::UnknownConstantPoolTag { tag: 2 }.to_string(),
+                   "unknown constant pool tag: 0x02");
+    }
 }
@@ -4,8 +4,10 @@
 //!
 //! Basic usage:
 //! ```
+//! use rust_jvm::parser::class_file::parse_class_file;
+//!
 //! let data = include_bytes!("../../data/HelloWorld.class");
-//! assert!(parse_class_file(data).is_done()); // returns a nom::IResult
+//! assert!(parse_class_file(data).is_ok());
 //! ```
 
 #[macro_use]
@@ -279,7 +279,7 @@ mod test {
     n!(pub foo< bool >,
        chain!(
            tag!("a") ~
-               cut!($crate::nom::ErrorKind::Custom(42), tag!("b")) ,
+               cut!(ErrorKind::Custom(42), tag!("b")) ,
            || { true }
        )
     );
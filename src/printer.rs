@@ -0,0 +1,332 @@
+//! A printer producing a detailed textual description of a class file, in the spirit of
+//! `javap -verbose`.
+//!
+//! Where the disassembler only lists the bytecode of each method, the printer describes the whole
+//! structure of the class file: its version and flags, every entry of its constant pool, and the
+//! fields and methods of the class along with their attributes.
+
+use std::fmt::Write;
+
+use model::class_file::{AttributeInfo, ClassFile, FieldInfo, MethodInfo};
+use model::class_file::{class_access_flags, field_access_flags, method_access_flags};
+use model::class_file::constant_pool::{ConstantPoolInfo, MethodReference};
+use disassembler::{class_name, invalid};
+use util::modified_utf8;
+
+const CLASS_FLAGS: &'static [(u16, &'static str)] = &[
+    (class_access_flags::ACC_PUBLIC, "ACC_PUBLIC"),
+    (class_access_flags::ACC_FINAL, "ACC_FINAL"),
+    (class_access_flags::ACC_SUPER, "ACC_SUPER"),
+    (class_access_flags::ACC_INTERFACE, "ACC_INTERFACE"),
+    (class_access_flags::ACC_ABSTRACT, "ACC_ABSTRACT"),
+    (class_access_flags::ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+    (class_access_flags::ACC_ANNOTATION, "ACC_ANNOTATION"),
+    (class_access_flags::ACC_ENUM, "ACC_ENUM"),
+];
+
+const FIELD_FLAGS: &'static [(u16, &'static str)] = &[
+    (field_access_flags::ACC_PUBLIC, "ACC_PUBLIC"),
+    (field_access_flags::ACC_PRIVATE, "ACC_PRIVATE"),
+    (field_access_flags::ACC_PROTECTED, "ACC_PROTECTED"),
+    (field_access_flags::ACC_STATIC, "ACC_STATIC"),
+    (field_access_flags::ACC_FINAL, "ACC_FINAL"),
+    (field_access_flags::ACC_VOLATILE, "ACC_VOLATILE"),
+    (field_access_flags::ACC_TRANSIENT, "ACC_TRANSIENT"),
+    (field_access_flags::ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+    (field_access_flags::ACC_ENUM, "ACC_ENUM"),
+];
+
+const METHOD_FLAGS: &'static [(u16, &'static str)] = &[
+    (method_access_flags::ACC_PUBLIC, "ACC_PUBLIC"),
+    (method_access_flags::ACC_PRIVATE, "ACC_PRIVATE"),
+    (method_access_flags::ACC_PROTECTED, "ACC_PROTECTED"),
+    (method_access_flags::ACC_STATIC, "ACC_STATIC"),
+    (method_access_flags::ACC_FINAL, "ACC_FINAL"),
+    (method_access_flags::ACC_SYNCHRONIZED, "ACC_SYNCHRONIZED"),
+    (method_access_flags::ACC_BRIDGE, "ACC_BRIDGE"),
+    (method_access_flags::ACC_VARARGS, "ACC_VARARGS"),
+    (method_access_flags::ACC_NATIVE, "ACC_NATIVE"),
+    (method_access_flags::ACC_ABSTRACT, "ACC_ABSTRACT"),
+    (method_access_flags::ACC_STRICT, "ACC_STRICT"),
+    (method_access_flags::ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+];
+
+/// Describes the whole of `class`: its version, access flags, constant pool, fields, methods and
+/// attributes. The bytecode of each method is given as a hex dump; use the disassembler for a
+/// listing of its instructions.
+pub fn verbose_print(class: &ClassFile) -> String {
+    let mut out = String::new();
+    let this_class_name = class_name(&class.constant_pool, class.this_class);
+    writeln!(out, "Classfile {}", this_class_name).unwrap();
+    writeln!(out, "  magic: 0xCAFEBABE").unwrap();
+    writeln!(out, "  minor version: {}", class.minor_version).unwrap();
+    writeln!(out, "  major version: {}", class.major_version).unwrap();
+    writeln!(out, "  flags: {}", flags(class.access_flags, CLASS_FLAGS)).unwrap();
+    let this_class = format!("  this_class: #{}", class.this_class);
+    writeln!(out, "{}", with_comment(this_class, &this_class_name)).unwrap();
+    if class.super_class != 0 {
+        let super_class = format!("  super_class: #{}", class.super_class);
+        let super_class_name = class_name(&class.constant_pool, class.super_class);
+        writeln!(out, "{}", with_comment(super_class, &super_class_name)).unwrap();
+    }
+    writeln!(out, "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+             class.interfaces.len(), class.fields.len(), class.methods.len(),
+             class.attributes.len()).unwrap();
+
+    writeln!(out, "Constant pool:").unwrap();
+    for (index, info) in class.constant_pool.real_entries() {
+        let (kind, operands, comment) = describe_entry(class, info);
+        let line = format!("{:>6} = {:<18} {:<14}", format!("#{}", index), kind, operands);
+        match comment {
+            Some(comment) => writeln!(out, "{}", with_comment(line, &comment)).unwrap(),
+            None => writeln!(out, "{}", line.trim_end()).unwrap(),
+        }
+    }
+
+    writeln!(out, "{{").unwrap();
+    for field in &class.fields {
+        print_field(&mut out, class, field);
+        out.push('\n');
+    }
+    for method in &class.methods {
+        print_method(&mut out, class, method);
+        out.push('\n');
+    }
+    writeln!(out, "}}").unwrap();
+    for attr in &class.attributes {
+        print_attribute(&mut out, class, attr, "");
+    }
+    out
+}
+
+fn print_field(out: &mut String, class: &ClassFile, field: &FieldInfo) {
+    writeln!(out, "  {}", utf8_or_invalid(class, field.name_index)).unwrap();
+    writeln!(out, "    descriptor: {}", utf8_or_invalid(class, field.descriptor_index)).unwrap();
+    writeln!(out, "    flags: {}", flags(field.access_flags, FIELD_FLAGS)).unwrap();
+    for attr in &field.attributes {
+        print_attribute(out, class, attr, "    ");
+    }
+}
+
+fn print_method(out: &mut String, class: &ClassFile, method: &MethodInfo) {
+    writeln!(out, "  {}", utf8_or_invalid(class, method.name_index)).unwrap();
+    writeln!(out, "    descriptor: {}", utf8_or_invalid(class, method.descriptor_index)).unwrap();
+    writeln!(out, "    flags: {}", flags(method.access_flags, METHOD_FLAGS)).unwrap();
+    for attr in &method.attributes {
+        print_attribute(out, class, attr, "    ");
+    }
+}
+
+/// Writes one attribute, with each line prefixed by `indent`. Attributes without a more readable
+/// form are listed by name only.
+fn print_attribute(out: &mut String, class: &ClassFile, attr: &AttributeInfo, indent: &str) {
+    match *attr {
+        AttributeInfo::Code { max_stack, max_locals, ref code, ref exception_table,
+                              ref attributes } => {
+            writeln!(out, "{}Code:", indent).unwrap();
+            writeln!(out, "{}  stack={}, locals={}, code_length={}",
+                     indent, max_stack, max_locals, code.len()).unwrap();
+            for (line, chunk) in code.chunks(16).enumerate() {
+                let bytes = chunk.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>();
+                writeln!(out, "{}  {:>6}: {}", indent, line * 16, bytes.join(" ")).unwrap();
+            }
+            if !exception_table.is_empty() {
+                writeln!(out, "{}  Exception table:", indent).unwrap();
+                writeln!(out, "{}     from    to  target type", indent).unwrap();
+                for entry in exception_table {
+                    let catch_type = if entry.catch_type == 0 {
+                        String::from("any")
+                    } else {
+                        format!("Class {}", class_name(&class.constant_pool, entry.catch_type))
+                    };
+                    writeln!(out, "{}    {:>5} {:>5} {:>7}   {}", indent, entry.start_pc,
+                             entry.end_pc, entry.handler_pc, catch_type).unwrap();
+                }
+            }
+            let nested_indent = format!("{}  ", indent);
+            for nested in attributes {
+                print_attribute(out, class, nested, &nested_indent);
+            }
+        },
+        AttributeInfo::LineNumberTable { ref line_number_table } => {
+            writeln!(out, "{}LineNumberTable:", indent).unwrap();
+            for entry in line_number_table {
+                writeln!(out, "{}  line {}: {}", indent, entry.line_number, entry.start_pc)
+                    .unwrap();
+            }
+        },
+        AttributeInfo::Exceptions { ref exception_index_table } => {
+            let names = exception_index_table.iter()
+                .map(|&index| class_name(&class.constant_pool, index))
+                .collect::<Vec<_>>();
+            writeln!(out, "{}Exceptions:", indent).unwrap();
+            writeln!(out, "{}  throws {}", indent, names.join(", ")).unwrap();
+        },
+        AttributeInfo::ConstantValue { constant_value_index } => {
+            let value = class.constant_pool.get(constant_value_index as usize)
+                .map_or_else(|| invalid(constant_value_index), |info| {
+                    let (kind, operands, _) = describe_entry(class, info);
+                    format!("{} {}", kind.to_lowercase(), operands)
+                });
+            writeln!(out, "{}ConstantValue: {}", indent, value).unwrap();
+        },
+        AttributeInfo::Signature { signature_index } => {
+            let line = format!("{}Signature: #{}", indent, signature_index);
+            writeln!(out, "{}", with_comment(line, &utf8_or_invalid(class, signature_index)))
+                .unwrap();
+        },
+        AttributeInfo::SourceFile { sourcefile_index } => {
+            writeln!(out, "{}SourceFile: \"{}\"", indent, utf8_or_invalid(class, sourcefile_index))
+                .unwrap();
+        },
+        AttributeInfo::Unknown { attribute_name_index, ref info } => {
+            writeln!(out, "{}{}: length = 0x{:x}", indent,
+                     utf8_or_invalid(class, attribute_name_index), info.len()).unwrap();
+        },
+        _ => writeln!(out, "{}{}", indent, attr.name().unwrap()).unwrap(),
+    }
+}
+
+/// Returns the kind of a constant pool entry, its operands, and a comment describing the entries
+/// it refers to, if it refers to any.
+fn describe_entry(class: &ClassFile, info: &ConstantPoolInfo) -> (&'static str, String,
+                                                                    Option<String>) {
+    match *info {
+        ConstantPoolInfo::Class { name_index } =>
+            ("Class", format!("#{}", name_index), Some(utf8_or_invalid(class, name_index))),
+        ConstantPoolInfo::FieldRef { class_index, name_and_type_index } =>
+            ("Fieldref", format!("#{}.#{}", class_index, name_and_type_index),
+             Some(member_ref(class, class_index, name_and_type_index))),
+        ConstantPoolInfo::MethodRef { class_index, name_and_type_index } =>
+            ("Methodref", format!("#{}.#{}", class_index, name_and_type_index),
+             Some(member_ref(class, class_index, name_and_type_index))),
+        ConstantPoolInfo::InterfaceMethodRef { class_index, name_and_type_index } =>
+            ("InterfaceMethodref", format!("#{}.#{}", class_index, name_and_type_index),
+             Some(member_ref(class, class_index, name_and_type_index))),
+        ConstantPoolInfo::String { string_index } =>
+            ("String", format!("#{}", string_index), Some(utf8_or_invalid(class, string_index))),
+        ConstantPoolInfo::Integer { bytes } => ("Integer", (bytes as i32).to_string(), None),
+        ConstantPoolInfo::Float { bytes } =>
+            ("Float", format!("{:?}f", f32::from_bits(bytes)), None),
+        ConstantPoolInfo::Long { high_bytes, low_bytes } =>
+            ("Long", format!("{}l", (((high_bytes as u64) << 32) | low_bytes as u64) as i64),
+             None),
+        ConstantPoolInfo::Double { high_bytes, low_bytes } => {
+            let value = f64::from_bits(((high_bytes as u64) << 32) | low_bytes as u64);
+            ("Double", format!("{:?}d", value), None)
+        },
+        ConstantPoolInfo::NameAndType { name_index, descriptor_index } =>
+            ("NameAndType", format!("#{}:#{}", name_index, descriptor_index),
+             Some(name_and_type(class, name_index, descriptor_index))),
+        ConstantPoolInfo::Utf8 { ref bytes } => {
+            let string = modified_utf8::from_modified_utf8(bytes)
+                .unwrap_or_else(|_| String::from_utf8_lossy(bytes).into_owned());
+            ("Utf8", string, None)
+        },
+        ConstantPoolInfo::MethodHandle { ref reference } => {
+            let (kind, reference_index) = method_reference(reference);
+            let comment = match class.constant_pool.get(reference_index as usize) {
+                Some(&ConstantPoolInfo::FieldRef { class_index, name_and_type_index })
+                        | Some(&ConstantPoolInfo::MethodRef { class_index, name_and_type_index })
+                        | Some(&ConstantPoolInfo::InterfaceMethodRef {
+                            class_index, name_and_type_index
+                        }) => member_ref(class, class_index, name_and_type_index),
+                _ => invalid(reference_index),
+            };
+            ("MethodHandle", format!("{}:#{}", kind, reference_index), Some(comment))
+        },
+        ConstantPoolInfo::MethodType { descriptor_index } =>
+            ("MethodType", format!("#{}", descriptor_index),
+             Some(utf8_or_invalid(class, descriptor_index))),
+        ConstantPoolInfo::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } =>
+            ("InvokeDynamic", format!("#{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+             Some(format!("#{}:{}", bootstrap_method_attr_index,
+                          name_and_type_at(class, name_and_type_index)))),
+        ConstantPoolInfo::Module { name_index } =>
+            ("Module", format!("#{}", name_index), Some(utf8_or_invalid(class, name_index))),
+        ConstantPoolInfo::Package { name_index } =>
+            ("Package", format!("#{}", name_index), Some(utf8_or_invalid(class, name_index))),
+        ConstantPoolInfo::Unusable => ("Unusable", String::new(), None),
+    }
+}
+
+fn utf8_or_invalid(class: &ClassFile, index: u16) -> String {
+    class.utf8_at(index).unwrap_or_else(|| invalid(index))
+}
+
+/// Describes a name and descriptor as `name:descriptor`, quoting special method names such as
+/// `"<init>"` as `javap` does.
+fn name_and_type(class: &ClassFile, name_index: u16, descriptor_index: u16) -> String {
+    let name = utf8_or_invalid(class, name_index);
+    let descriptor = utf8_or_invalid(class, descriptor_index);
+    if name.starts_with('<') && !name.starts_with("<invalid") {
+        format!("\"{}\":{}", name, descriptor)
+    } else {
+        format!("{}:{}", name, descriptor)
+    }
+}
+
+fn name_and_type_at(class: &ClassFile, index: u16) -> String {
+    match class.constant_pool.get(index as usize) {
+        Some(&ConstantPoolInfo::NameAndType { name_index, descriptor_index }) =>
+            name_and_type(class, name_index, descriptor_index),
+        _ => invalid(index),
+    }
+}
+
+fn member_ref(class: &ClassFile, class_index: u16, name_and_type_index: u16) -> String {
+    format!("{}.{}", class_name(&class.constant_pool, class_index),
+            name_and_type_at(class, name_and_type_index))
+}
+
+/// Returns the reference kind (§5.4.3.5) of a method handle, and the index of the entry it refers
+/// to.
+fn method_reference(reference: &MethodReference) -> (u8, u16) {
+    match *reference {
+        MethodReference::GetField { reference_index } => (1, reference_index),
+        MethodReference::GetStatic { reference_index } => (2, reference_index),
+        MethodReference::PutField { reference_index } => (3, reference_index),
+        MethodReference::PutStatic { reference_index } => (4, reference_index),
+        MethodReference::InvokeVirtual { reference_index } => (5, reference_index),
+        MethodReference::InvokeStatic { reference_index } => (6, reference_index),
+        MethodReference::InvokeSpecial { reference_index } => (7, reference_index),
+        MethodReference::NewInvokeSpecial { reference_index } => (8, reference_index),
+        MethodReference::InvokeInterface { reference_index } => (9, reference_index),
+    }
+}
+
+/// Describes access flags as their hexadecimal value followed by the names of the flags set.
+fn flags(access_flags: u16, names: &[(u16, &str)]) -> String {
+    let set = names.iter().filter(|&&(flag, _)| access_flags & flag != 0).map(|&(_, name)| name)
+        .collect::<Vec<_>>();
+    format!("(0x{:04x}) {}", access_flags, set.join(", ")).trim_end().to_owned()
+}
+
+/// Appends a `//` comment to a line, aligning the comments of consecutive lines.
+fn with_comment(line: String, comment: &str) -> String {
+    format!("{:<40} // {}", line, comment)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::class_file::parse_class_file;
+
+    #[test]
+    fn test_hello_world() {
+        let output = verbose_print(&parse_class_file(include_bytes!("../data/HelloWorld.class"))
+                                   .unwrap());
+        assert!(output.starts_with("Classfile HelloWorld\n  magic: 0xCAFEBABE\n"));
+        assert!(output.contains(&format!("{:<40} // java/lang/Object\n", "  super_class: #6")));
+        assert!(output.contains("  major version: 52\n"));
+        assert!(output.contains("  flags: (0x0021) ACC_PUBLIC, ACC_SUPER\n"));
+        assert!(output.contains(
+            "    #1 = Methodref          #6.#15         // java/lang/Object.\"<init>\":()V\n"));
+        assert!(output.contains("    #7 = Utf8               <init>\n"));
+        assert!(output.contains("  main\n    descriptor: ([Ljava/lang/String;)V\n"));
+        assert!(output.contains("    flags: (0x0009) ACC_PUBLIC, ACC_STATIC\n"));
+        assert!(output.contains("      stack=2, locals=1, code_length=9\n"));
+        assert!(output.contains("           0: b2 00 02 12 03 b6 00 04 b1\n"));
+        assert!(output.ends_with("}\nSourceFile: \"HelloWorld.java\"\n"));
+    }
+}
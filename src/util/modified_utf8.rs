@@ -2,104 +2,274 @@
 //!
 //! See [§4.4.7](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.7).
 
-use std::char;
+use std::{char, fmt, str};
+use std::borrow::Cow;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The ways in which a sequence of bytes can fail to be modified UTF-8.
+pub enum ModifiedUtf8ErrorKind {
+    /// A multi-byte sequence is cut short, either by the end of the string or by a byte which is
+    /// not a continuation byte.
+    InvalidSequence,
+    /// A three-byte sequence encoding a high surrogate is not followed by one encoding a low
+    /// surrogate, or one encoding a low surrogate is not preceded by one encoding a high surrogate.
+    InvalidSurrogatePair,
+    /// A continuation byte appears other than within a multi-byte sequence.
+    InvalidContinuationByte,
+    /// A byte which never appears in modified UTF-8: `0x00`, or `0xf0` through `0xff`.
+    IllegalByte,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Errors which can occur when attempting to interpret a sequence of `u8` as a modified UTF-8
 /// string.
-#[derive(Debug)]
 pub struct ModifiedUtf8Error {
-    valid_up_to: usize,
+    pub kind: ModifiedUtf8ErrorKind,
+    /// The offset of the byte at which decoding failed.
+    pub offset: usize,
+    /// The byte at which decoding failed. For a sequence cut short by the end of the string, this
+    /// is the first byte of the sequence.
+    pub byte: u8,
+}
+
+impl fmt::Display for ModifiedUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let problem = match self.kind {
+            ModifiedUtf8ErrorKind::InvalidSequence => "invalid sequence",
+            ModifiedUtf8ErrorKind::InvalidSurrogatePair => "invalid surrogate pair",
+            ModifiedUtf8ErrorKind::InvalidContinuationByte => "invalid continuation byte",
+            ModifiedUtf8ErrorKind::IllegalByte => "illegal byte",
+        };
+        write!(f, "error decoding modified UTF-8: {} 0x{:02x} at offset {}",
+               problem, self.byte, self.offset)
+    }
+}
+
+fn error(bytes: &[u8], kind: ModifiedUtf8ErrorKind, offset: usize) -> ModifiedUtf8Error {
+    ModifiedUtf8Error { kind: kind, offset: offset, byte: bytes[offset] }
+}
+
+/// Decodes the one-, two- or three-byte sequence beginning at offset `i`, returning the UTF-16
+/// code unit which it encodes along with the length of the sequence.
+fn decode_code_unit(bytes: &[u8], i: usize) -> Result<(u16, usize), ModifiedUtf8Error> {
+    let continuation = |offset: usize| match bytes.get(offset) {
+        Some(&byte) if byte & 0xc0 == 0x80 => Ok((byte & 0x3f) as u16),
+        Some(_) => Err(error(bytes, ModifiedUtf8ErrorKind::InvalidSequence, offset)),
+        None => Err(error(bytes, ModifiedUtf8ErrorKind::InvalidSequence, i)),
+    };
+    let byte = bytes[i];
+    match byte {
+        0x01 ... 0x7f => Ok((byte as u16, 1)),
+        // this includes 0xc0 0x80, the encoding of a null character
+        0xc0 ... 0xdf => {
+            let low = try!(continuation(i + 1));
+            Ok(((((byte & 0x1f) as u16) << 6) | low, 2))
+        },
+        0xe0 ... 0xef => {
+            let middle = try!(continuation(i + 1));
+            let low = try!(continuation(i + 2));
+            Ok(((((byte & 0x0f) as u16) << 12) | (middle << 6) | low, 3))
+        },
+        0x80 ... 0xbf => Err(error(bytes, ModifiedUtf8ErrorKind::InvalidContinuationByte, i)),
+        _ => Err(error(bytes, ModifiedUtf8ErrorKind::IllegalByte, i)),
+    }
 }
 
-/// Converts a slice of bytes in modified UTF-8 encoding to a string slice.
+/// Converts a slice of bytes in modified UTF-8 encoding to a string. Characters outside the Basic
+/// Multilingual Plane are encoded as surrogate pairs, each half of which must be present.
 pub fn from_modified_utf8(bytes: &[u8]) -> Result<String, ModifiedUtf8Error> {
     // Refer to §4.4.7 for more information about the modified UTF-8 encoding.
-    let mut result = String::new();
-    let mut offset = 0;
-    let len = bytes.len();
-    while offset < len {
-        let old_offset = offset;
-        macro_rules! err {
-            () => (return Err(ModifiedUtf8Error { valid_up_to: old_offset }));
-        }
-        macro_rules! next {
-            () => ({
-                offset += 1;
-                if offset >= len {
-                    err!();
+    let mut string = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (unit, len) = try!(decode_code_unit(bytes, i));
+        let code_point = match unit {
+            0xd800 ... 0xdbff => {
+                // check that the following sequence encodes a low surrogate
+                let next = if i + len < bytes.len() {
+                    decode_code_unit(bytes, i + len).ok()
+                } else {
+                    None
+                };
+                match next {
+                    Some((low @ 0xdc00 ... 0xdfff, low_len)) => {
+                        let high = (unit as u32 - 0xd800) << 10;
+                        i += low_len;
+                        high + (low as u32 - 0xdc00) + 0x10000
+                    },
+                    _ => return Err(error(bytes, ModifiedUtf8ErrorKind::InvalidSurrogatePair, i)),
                 }
-                bytes[offset]
-            });
+            },
+            0xdc00 ... 0xdfff =>
+                return Err(error(bytes, ModifiedUtf8ErrorKind::InvalidSurrogatePair, i)),
+            _ => unit as u32,
+        };
+        // every code point outside the surrogate range is a valid char
+        string.push(char::from_u32(code_point).unwrap());
+        i += len;
+    }
+    Ok(string)
+}
+
+/// Like `from_modified_utf8`, but borrows `bytes` rather than copying them if they are also valid
+/// UTF-8, as they are for most strings in class files. Modified UTF-8 differs from UTF-8 only in
+/// how it encodes the null character and characters outside the basic multilingual plane, and
+/// either encoding uses bytes which never appear in the other.
+pub fn decode<'a>(bytes: &'a [u8]) -> Result<Cow<'a, str>, ModifiedUtf8Error> {
+    if bytes.iter().all(|&b| b != 0x00 && b < 0xf0) {
+        if let Ok(s) = str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(s));
         }
+    }
+    from_modified_utf8(bytes).map(Cow::Owned)
+}
 
-        let x = bytes[offset];
-        if x < 0b0111_1111 { // pattern: 0xxxxxx
-            result.push(x as char);
-        } else if x < 0b1101_1111 { // pattern: 110xxxxx
-            let y = next!();
-            if y < 0b1011_111 {   // pattern: 10xxxxxx
-                let c = ((x & 0x1f) << 6) + (y & 0x3f);
-                result.push(c as char);
-            } else {
-                err!()
-            }
-        } else if x < 0b1110_1111 { // pattern: 1110xxxx
-            let y = next!();
-            if y < 0b1011_1111 { // pattern: 10xxxxxx
-                let z = next!();
-                if z < 0b1011_1111 { // pattern: 10xxxxxx
-                    let q: u32 = (((x & 0xf) as u32) << 12) + (((y & 0x3f) as u32) << 6)
-                        + ((z & 0x3f) as u32);
-                    let c = unsafe { char::from_u32_unchecked(q) };
-                    result.push(c);
-                } else {
-                    err!()
-                }
-            } else {
-                err!()
-            }
-        } else if x == 0b1110_1101 { // pattern: 11101101
-            let v = next!();
-            if v < 0b1010_1111 { // pattern: 10101111
-                let w = next!();
-                if w < 0b1011_1111 { // pattern: 10xxxxxx
-                    let xx = next!();
-                    if xx == 0b1110_1101 { // pattern: 11101101
-                        let y = next!();
-                        if y < 0b1011_1111 { // pattern: 1011xxxx
-                            let z = next!();
-                            if z < 0b1011_1111 { // pattern: 10xxxxxx
-                                let q: u32 = 0x10000u32 + (((v & 0x0f) as u32) << 16)
-                                    + (((w & 0x3f) as u32) << 10)
-                                    + (((y & 0x0f) as u32) << 6) + ((z & 0x3f) as u32);
-                                let c = unsafe { char::from_u32_unchecked(q) };
-                                result.push(c);
-                            } else {
-                                err!()
-                            }
-                        } else {
-                            err!()
-                        }
-                    } else {
-                        err!()
-                    }
-                } else {
-                    err!()
-                }
-            } else {
-                err!()
-            }
+/// Decodes each sequence of `bytes` in turn, passing the UTF-16 code unit it encodes to `f`.
+/// Surrogate pairs are not validated, as Java strings may contain unpaired surrogates.
+fn decode_code_units<F: FnMut(u16)>(bytes: &[u8], mut f: F) -> Result<(), ModifiedUtf8Error> {
+    let mut i = 0;
+    while i < bytes.len() {
+        let (unit, len) = try!(decode_code_unit(bytes, i));
+        f(unit);
+        i += len;
+    }
+    Ok(())
+}
+
+/// Checks that a slice of bytes is well-formed modified UTF-8, without allocating. Each byte must
+/// begin a one-, two-, or three-byte sequence, and each sequence must have as many continuation
+/// bytes as its first byte calls for. Characters outside the basic multilingual plane are encoded
+/// as two three-byte sequences, so a truncated surrogate pair is detected as a truncated
+/// three-byte sequence.
+pub fn validate(bytes: &[u8]) -> Result<(), ModifiedUtf8Error> {
+    decode_code_units(bytes, |_| ())
+}
+
+/// Converts a slice of bytes in modified UTF-8 encoding to UTF-16, the encoding of Java `String`
+/// objects. Unlike `from_modified_utf8`, this does not validate surrogate pairs.
+pub fn to_utf16(bytes: &[u8]) -> Result<Vec<u16>, ModifiedUtf8Error> {
+    let mut utf16 = vec![];
+    try!(decode_code_units(bytes, |unit| utf16.push(unit)));
+    Ok(utf16)
+}
+
+/// Converts a string slice to a sequence of bytes in modified UTF-8 encoding.
+pub fn to_modified_utf8(s: &str) -> Vec<u8> {
+    // characters outside the basic multilingual plane are encoded as surrogate pairs, each half
+    // of which is encoded separately
+    let mut bytes = vec![];
+    for unit in s.encode_utf16() {
+        if unit != 0 && unit < 0x80 {
+            bytes.push(unit as u8);
+        } else if unit < 0x800 {
+            bytes.push(0xc0 | (unit >> 6) as u8);
+            bytes.push(0x80 | (unit & 0x3f) as u8);
         } else {
-            err!()
+            bytes.push(0xe0 | (unit >> 12) as u8);
+            bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+            bytes.push(0x80 | (unit & 0x3f) as u8);
         }
-
-        offset += 1;
     }
-    Ok(result)
+    bytes
 }
 
-// TODO: Test implementation of from_modified_utf8
 #[cfg(test)]
 mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_modified_utf8() {
+        assert_eq!(to_modified_utf8("java/lang/Object"), b"java/lang/Object".to_vec());
+        assert_eq!(to_modified_utf8("\0"), vec![0xc0, 0x80]);
+        assert_eq!(to_modified_utf8("\u{e9}"), vec![0xc3, 0xa9]);
+        assert_eq!(to_modified_utf8("\u{4e16}"), vec![0xe4, 0xb8, 0x96]);
+        assert_eq!(to_modified_utf8("\u{1f600}"), vec![0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+    }
+
+    /// The modified UTF-8 encoding of U+1F600, as a surrogate pair of two three-byte sequences.
+    const SURROGATE_PAIR: [u8; 6] = [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+
+    #[test]
+    fn test_from_modified_utf8() {
+        assert_eq!(from_modified_utf8(&[0xc0, 0x80]).unwrap(), "\u{0}");
+        for s in &["java/lang/Object", "\u{e9}t\u{e9}", "\u{4e2d}\u{6587}"] {
+            assert_eq!(from_modified_utf8(s.as_bytes()).unwrap(), *s);
+        }
+        assert_eq!(from_modified_utf8(&SURROGATE_PAIR).unwrap(), "\u{1f600}");
+    }
 
+    #[test]
+    fn test_to_utf16() {
+        let expected = "a\u{e9}\u{4e2d}\u{1f600}";
+        let mut bytes = "a\u{e9}\u{4e2d}".as_bytes().to_vec();
+        bytes.extend_from_slice(&SURROGATE_PAIR);
+        assert_eq!(to_utf16(&bytes).unwrap(), expected.encode_utf16().collect::<Vec<_>>());
+        assert_eq!(to_utf16(&[0xc0, 0x80]).unwrap(), vec![0x0000]);
+        // surrogate pairs are not validated
+        assert_eq!(to_utf16(&SURROGATE_PAIR[3..]).unwrap(), vec![0xde00]);
+        assert_eq!(to_utf16(&[b'a', 0xc3]).unwrap_err().to_string(),
+                   "error decoding modified UTF-8: invalid sequence 0xc3 at offset 1");
+    }
+
+    #[test]
+    fn test_decode() {
+        match decode(b"java/lang/Object").unwrap() {
+            Cow::Borrowed(s) => assert_eq!(s, "java/lang/Object"),
+            Cow::Owned(_) => panic!("expected the bytes to be borrowed"),
+        }
+        assert_eq!(decode("\u{e9}t\u{e9}".as_bytes()).unwrap(), "\u{e9}t\u{e9}");
+        assert_eq!(decode(&[0x61, 0xc0, 0x80]).unwrap(), "a\u{0}");
+        assert_eq!(decode(&SURROGATE_PAIR).unwrap(), "\u{1f600}");
+        assert_eq!(decode(b"a\x00").unwrap_err().kind, ModifiedUtf8ErrorKind::IllegalByte);
+        assert_eq!(decode("\u{1f600}".as_bytes()).unwrap_err().kind,
+                   ModifiedUtf8ErrorKind::IllegalByte);
+    }
+
+    fn decode_error(bytes: &[u8]) -> (ModifiedUtf8ErrorKind, usize, u8) {
+        let error = from_modified_utf8(bytes).unwrap_err();
+        (error.kind, error.offset, error.byte)
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        use self::ModifiedUtf8ErrorKind::*;
+        // a null byte, which is always encoded as two bytes
+        assert_eq!(decode_error(b"a\x00"), (IllegalByte, 1, 0x00));
+        // the first byte of a four-byte sequence in standard UTF-8
+        assert_eq!(decode_error("a\u{1f600}".as_bytes()), (IllegalByte, 1, 0xf0));
+        assert_eq!(decode_error(&[0xff]), (IllegalByte, 0, 0xff));
+        assert_eq!(decode_error(&[b'a', 0x80]), (InvalidContinuationByte, 1, 0x80));
+        // a sequence cut short by the end of the string, or by a byte which is not a continuation
+        assert_eq!(decode_error(&[b'a', 0xc3]), (InvalidSequence, 1, 0xc3));
+        assert_eq!(decode_error(&[0xe4, 0xb8]), (InvalidSequence, 0, 0xe4));
+        assert_eq!(decode_error(&[0xe4, b'a', 0xad]), (InvalidSequence, 1, b'a'));
+        // a high surrogate alone, or followed by something other than a low surrogate
+        assert_eq!(decode_error(&SURROGATE_PAIR[..3]), (InvalidSurrogatePair, 0, 0xed));
+        assert_eq!(decode_error(&[0xed, 0xa0, 0xbd, b'a']), (InvalidSurrogatePair, 0, 0xed));
+        // a low surrogate alone
+        assert_eq!(decode_error(&SURROGATE_PAIR[3..]), (InvalidSurrogatePair, 0, 0xed));
+    }
+
+    #[test]
+    fn test_validate() {
+        for s in &["", "java/lang/Object", "\0", "\u{e9}", "\u{4e16}", "\u{1f600}"] {
+            assert!(validate(&to_modified_utf8(s)).is_ok());
+        }
+        // an unpaired low surrogate
+        assert!(validate(&[0xed, 0xb8, 0x80]).is_ok());
+        let offset = |bytes: &[u8]| validate(bytes).unwrap_err().offset;
+        // truncated two-byte sequence
+        assert_eq!(offset(&[0x61, 0xc3]), 1);
+        assert_eq!(offset(&[0x61, 0xc3, 0x61]), 2);
+        // truncated three-byte sequence
+        assert_eq!(offset(&[0xe4, 0xb8]), 0);
+        assert_eq!(offset(&[0xe4, 0xb8, 0x61]), 2);
+        // truncated six-byte surrogate pair
+        assert_eq!(offset(&[0xed, 0xa0, 0xbd, 0xed, 0xb8]), 3);
+        assert_eq!(offset(&[0xed, 0xa0, 0xbd, 0xed]), 3);
+        // bytes which cannot begin a sequence
+        assert_eq!(offset(&[0x61, 0x00]), 1);
+        assert_eq!(offset(&[0xb8]), 0);
+        assert_eq!(offset(&[0xf0, 0x9f, 0x98, 0x80]), 0);
+    }
 }
@@ -4,18 +4,30 @@ use std::ops::Index;
 use std::ops::IndexMut;
 
 /// Like a `std::vec::Vec`, but 1-indexed instead of 0-indexed.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct OneIndexedVec<T> {
     vec: Vec<T>,
 }
 
 impl<T> OneIndexedVec<T> {
     /// Returns the element of a slice at the given index, or None if the index is out of bounds.
+    /// Index 0 is always out of bounds.
     pub fn get(&self, index: usize) -> Option<&T> {
         if index == 0 {
-            panic!("index is 0");
+            None
+        } else {
+            self.vec.get(index - 1)
+        }
+    }
+
+    /// Returns a mutable reference to the element at the given index, or None if the index is out
+    /// of bounds. Index 0 is always out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index == 0 {
+            None
+        } else {
+            self.vec.get_mut(index - 1)
         }
-        self.vec.get(index - 1)
     }
 
     /// Returns the number of elements in the slice.
@@ -87,3 +99,28 @@ impl<'a, T> IntoIterator for &'a mut OneIndexedVec<T> {
         self.vec.iter_mut()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let mut vec = OneIndexedVec::from(vec!['a', 'b', 'c']);
+        assert_eq!(vec.get(0), None);
+        assert_eq!(vec.get(1), Some(&'a'));
+        assert_eq!(vec.get(3), Some(&'c'));
+        assert_eq!(vec.get(4), None);
+        assert_eq!(vec.get_mut(0), None);
+        *vec.get_mut(2).unwrap() = 'd';
+        assert_eq!(vec[2], 'd');
+        assert_eq!(vec.get_mut(4), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "index is 0")]
+    fn test_index_zero() {
+        let vec = OneIndexedVec::from(vec![1]);
+        let _ = vec[0];
+    }
+}
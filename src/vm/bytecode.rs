@@ -209,3 +209,1081 @@ pub mod opcode {
     pub const IMPDEP1: u8 = 0xfe;
     pub const IMPDEP2: u8 = 0xff;
 }
+
+/// Returns the mnemonic of the instruction with the given opcode, as it appears in the JVM
+/// specification, or `None` if no instruction has the opcode.
+pub fn mnemonic(op: u8) -> Option<&'static str> {
+    let name = match op {
+        opcode::NOP => "nop",
+        opcode::ACONST_NULL => "aconst_null",
+        opcode::ICONST_M1 => "iconst_m1",
+        opcode::ICONST_0 => "iconst_0",
+        opcode::ICONST_1 => "iconst_1",
+        opcode::ICONST_2 => "iconst_2",
+        opcode::ICONST_3 => "iconst_3",
+        opcode::ICONST_4 => "iconst_4",
+        opcode::ICONST_5 => "iconst_5",
+        opcode::LCONST_0 => "lconst_0",
+        opcode::LCONST_1 => "lconst_1",
+        opcode::FCONST_0 => "fconst_0",
+        opcode::FCONST_1 => "fconst_1",
+        opcode::FCONST_2 => "fconst_2",
+        opcode::DCONST_0 => "dconst_0",
+        opcode::DCONST_1 => "dconst_1",
+        opcode::BIPUSH => "bipush",
+        opcode::SIPUSH => "sipush",
+        opcode::LDC => "ldc",
+        opcode::LDC_W => "ldc_w",
+        opcode::LDC2_W => "ldc2_w",
+        opcode::ILOAD => "iload",
+        opcode::LLOAD => "lload",
+        opcode::FLOAD => "fload",
+        opcode::DLOAD => "dload",
+        opcode::ALOAD => "aload",
+        opcode::ILOAD_0 => "iload_0",
+        opcode::ILOAD_1 => "iload_1",
+        opcode::ILOAD_2 => "iload_2",
+        opcode::ILOAD_3 => "iload_3",
+        opcode::LLOAD_0 => "lload_0",
+        opcode::LLOAD_1 => "lload_1",
+        opcode::LLOAD_2 => "lload_2",
+        opcode::LLOAD_3 => "lload_3",
+        opcode::FLOAD_0 => "fload_0",
+        opcode::FLOAD_1 => "fload_1",
+        opcode::FLOAD_2 => "fload_2",
+        opcode::FLOAD_3 => "fload_3",
+        opcode::DLOAD_0 => "dload_0",
+        opcode::DLOAD_1 => "dload_1",
+        opcode::DLOAD_2 => "dload_2",
+        opcode::DLOAD_3 => "dload_3",
+        opcode::ALOAD_0 => "aload_0",
+        opcode::ALOAD_1 => "aload_1",
+        opcode::ALOAD_2 => "aload_2",
+        opcode::ALOAD_3 => "aload_3",
+        opcode::IALOAD => "iaload",
+        opcode::LALOAD => "laload",
+        opcode::FALOAD => "faload",
+        opcode::DALOAD => "daload",
+        opcode::AALOAD => "aaload",
+        opcode::BALOAD => "baload",
+        opcode::CALOAD => "caload",
+        opcode::SALOAD => "saload",
+        opcode::ISTORE => "istore",
+        opcode::LSTORE => "lstore",
+        opcode::FSTORE => "fstore",
+        opcode::DSTORE => "dstore",
+        opcode::ASTORE => "astore",
+        opcode::ISTORE_0 => "istore_0",
+        opcode::ISTORE_1 => "istore_1",
+        opcode::ISTORE_2 => "istore_2",
+        opcode::ISTORE_3 => "istore_3",
+        opcode::LSTORE_0 => "lstore_0",
+        opcode::LSTORE_1 => "lstore_1",
+        opcode::LSTORE_2 => "lstore_2",
+        opcode::LSTORE_3 => "lstore_3",
+        opcode::FSTORE_0 => "fstore_0",
+        opcode::FSTORE_1 => "fstore_1",
+        opcode::FSTORE_2 => "fstore_2",
+        opcode::FSTORE_3 => "fstore_3",
+        opcode::DSTORE_0 => "dstore_0",
+        opcode::DSTORE_1 => "dstore_1",
+        opcode::DSTORE_2 => "dstore_2",
+        opcode::DSTORE_3 => "dstore_3",
+        opcode::ASTORE_0 => "astore_0",
+        opcode::ASTORE_1 => "astore_1",
+        opcode::ASTORE_2 => "astore_2",
+        opcode::ASTORE_3 => "astore_3",
+        opcode::IASTORE => "iastore",
+        opcode::LASTORE => "lastore",
+        opcode::FASTORE => "fastore",
+        opcode::DASTORE => "dastore",
+        opcode::AASTORE => "aastore",
+        opcode::BASTORE => "bastore",
+        opcode::CASTORE => "castore",
+        opcode::SASTORE => "sastore",
+        opcode::POP => "pop",
+        opcode::POP2 => "pop2",
+        opcode::DUP => "dup",
+        opcode::DUP_X1 => "dup_x1",
+        opcode::DUP_X2 => "dup_x2",
+        opcode::DUP2 => "dup2",
+        opcode::DUP2_X1 => "dup2_x1",
+        opcode::DUP2_X2 => "dup2_x2",
+        opcode::SWAP => "swap",
+        opcode::IADD => "iadd",
+        opcode::LADD => "ladd",
+        opcode::FADD => "fadd",
+        opcode::DADD => "dadd",
+        opcode::ISUB => "isub",
+        opcode::LSUB => "lsub",
+        opcode::FSUB => "fsub",
+        opcode::DSUB => "dsub",
+        opcode::IMUL => "imul",
+        opcode::LMUL => "lmul",
+        opcode::FMUL => "fmul",
+        opcode::DMUL => "dmul",
+        opcode::IDIV => "idiv",
+        opcode::LDIV => "ldiv",
+        opcode::FDIV => "fdiv",
+        opcode::DDIV => "ddiv",
+        opcode::IREM => "irem",
+        opcode::LREM => "lrem",
+        opcode::FREM => "frem",
+        opcode::DREM => "drem",
+        opcode::INEG => "ineg",
+        opcode::LNEG => "lneg",
+        opcode::FNEG => "fneg",
+        opcode::DNEG => "dneg",
+        opcode::ISHL => "ishl",
+        opcode::LSHL => "lshl",
+        opcode::ISHR => "ishr",
+        opcode::LSHR => "lshr",
+        opcode::IUSHR => "iushr",
+        opcode::LUSHR => "lushr",
+        opcode::IAND => "iand",
+        opcode::LAND => "land",
+        opcode::IOR => "ior",
+        opcode::LOR => "lor",
+        opcode::IXOR => "ixor",
+        opcode::LXOR => "lxor",
+        opcode::IINC => "iinc",
+        opcode::I2L => "i2l",
+        opcode::I2F => "i2f",
+        opcode::I2D => "i2d",
+        opcode::L2I => "l2i",
+        opcode::L2F => "l2f",
+        opcode::L2D => "l2d",
+        opcode::F2I => "f2i",
+        opcode::F2L => "f2l",
+        opcode::F2D => "f2d",
+        opcode::D2I => "d2i",
+        opcode::D2L => "d2l",
+        opcode::D2F => "d2f",
+        opcode::I2B => "i2b",
+        opcode::I2C => "i2c",
+        opcode::I2S => "i2s",
+        opcode::LCMP => "lcmp",
+        opcode::FCMPL => "fcmpl",
+        opcode::FCMPG => "fcmpg",
+        opcode::DCMPL => "dcmpl",
+        opcode::DCMPG => "dcmpg",
+        opcode::IFEQ => "ifeq",
+        opcode::IFNE => "ifne",
+        opcode::IFLT => "iflt",
+        opcode::IFGE => "ifge",
+        opcode::IFGT => "ifgt",
+        opcode::IFLE => "ifle",
+        opcode::IF_ICMPEQ => "if_icmpeq",
+        opcode::IF_ICMPNE => "if_icmpne",
+        opcode::IF_ICMPLT => "if_icmplt",
+        opcode::IF_ICMPGE => "if_icmpge",
+        opcode::IF_ICMPGT => "if_icmpgt",
+        opcode::IF_ICMPLE => "if_icmple",
+        opcode::IF_ACMPEQ => "if_acmpeq",
+        opcode::IF_ACMPNE => "if_acmpne",
+        opcode::GOTO => "goto",
+        opcode::JSR => "jsr",
+        opcode::RET => "ret",
+        opcode::TABLESWITCH => "tableswitch",
+        opcode::LOOKUPSWITCH => "lookupswitch",
+        opcode::IRETURN => "ireturn",
+        opcode::LRETURN => "lreturn",
+        opcode::FRETURN => "freturn",
+        opcode::DRETURN => "dreturn",
+        opcode::ARETURN => "areturn",
+        opcode::RETURN => "return",
+        opcode::GETSTATIC => "getstatic",
+        opcode::PUTSTATIC => "putstatic",
+        opcode::GETFIELD => "getfield",
+        opcode::PUTFIELD => "putfield",
+        opcode::INVOKEVIRTUAL => "invokevirtual",
+        opcode::INVOKESPECIAL => "invokespecial",
+        opcode::INVOKESTATIC => "invokestatic",
+        opcode::INVOKEINTERFACE => "invokeinterface",
+        opcode::INVOKEDYNAMIC => "invokedynamic",
+        opcode::NEW => "new",
+        opcode::NEWARRAY => "newarray",
+        opcode::ANEWARRAY => "anewarray",
+        opcode::ARRAYLENGTH => "arraylength",
+        opcode::ATHROW => "athrow",
+        opcode::CHECKCAST => "checkcast",
+        opcode::INSTANCEOF => "instanceof",
+        opcode::MONITORENTER => "monitorenter",
+        opcode::MONITOREXIT => "monitorexit",
+        opcode::WIDE => "wide",
+        opcode::MULTIANEWARRAY => "multianewarray",
+        opcode::IFNULL => "ifnull",
+        opcode::IFNONNULL => "ifnonnull",
+        opcode::GOTO_W => "goto_w",
+        opcode::JSR_W => "jsr_w",
+        opcode::BREAKPOINT => "breakpoint",
+        opcode::IMPDEP1 => "impdep1",
+        opcode::IMPDEP2 => "impdep2",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A decoded instruction, together with its operands. Branch offsets are relative to the offset
+/// of the instruction itself, and constant pool and local variable indices are widened to `u16`.
+///
+/// An instruction modified by `wide` decodes to the same variant as the unmodified instruction,
+/// but with its wider operands; the `wide` prefix is not represented separately.
+pub enum Instruction {
+    Nop,
+    AconstNull,
+    IconstM1,
+    Iconst0,
+    Iconst1,
+    Iconst2,
+    Iconst3,
+    Iconst4,
+    Iconst5,
+    Lconst0,
+    Lconst1,
+    Fconst0,
+    Fconst1,
+    Fconst2,
+    Dconst0,
+    Dconst1,
+    Bipush(i8),
+    Sipush(i16),
+    Ldc(u16),
+    LdcW(u16),
+    Ldc2W(u16),
+    Iload(u16),
+    Lload(u16),
+    Fload(u16),
+    Dload(u16),
+    Aload(u16),
+    Iload0,
+    Iload1,
+    Iload2,
+    Iload3,
+    Lload0,
+    Lload1,
+    Lload2,
+    Lload3,
+    Fload0,
+    Fload1,
+    Fload2,
+    Fload3,
+    Dload0,
+    Dload1,
+    Dload2,
+    Dload3,
+    Aload0,
+    Aload1,
+    Aload2,
+    Aload3,
+    Iaload,
+    Laload,
+    Faload,
+    Daload,
+    Aaload,
+    Baload,
+    Caload,
+    Saload,
+    Istore(u16),
+    Lstore(u16),
+    Fstore(u16),
+    Dstore(u16),
+    Astore(u16),
+    Istore0,
+    Istore1,
+    Istore2,
+    Istore3,
+    Lstore0,
+    Lstore1,
+    Lstore2,
+    Lstore3,
+    Fstore0,
+    Fstore1,
+    Fstore2,
+    Fstore3,
+    Dstore0,
+    Dstore1,
+    Dstore2,
+    Dstore3,
+    Astore0,
+    Astore1,
+    Astore2,
+    Astore3,
+    Iastore,
+    Lastore,
+    Fastore,
+    Dastore,
+    Aastore,
+    Bastore,
+    Castore,
+    Sastore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+    Iadd,
+    Ladd,
+    Fadd,
+    Dadd,
+    Isub,
+    Lsub,
+    Fsub,
+    Dsub,
+    Imul,
+    Lmul,
+    Fmul,
+    Dmul,
+    Idiv,
+    Ldiv,
+    Fdiv,
+    Ddiv,
+    Irem,
+    Lrem,
+    Frem,
+    Drem,
+    Ineg,
+    Lneg,
+    Fneg,
+    Dneg,
+    Ishl,
+    Lshl,
+    Ishr,
+    Lshr,
+    Iushr,
+    Lushr,
+    Iand,
+    Land,
+    Ior,
+    Lor,
+    Ixor,
+    Lxor,
+    Iinc { index: u16, constant: i16 },
+    I2l,
+    I2f,
+    I2d,
+    L2i,
+    L2f,
+    L2d,
+    F2i,
+    F2l,
+    F2d,
+    D2i,
+    D2l,
+    D2f,
+    I2b,
+    I2c,
+    I2s,
+    Lcmp,
+    Fcmpl,
+    Fcmpg,
+    Dcmpl,
+    Dcmpg,
+    Ifeq(i16),
+    Ifne(i16),
+    Iflt(i16),
+    Ifge(i16),
+    Ifgt(i16),
+    Ifle(i16),
+    IfIcmpeq(i16),
+    IfIcmpne(i16),
+    IfIcmplt(i16),
+    IfIcmpge(i16),
+    IfIcmpgt(i16),
+    IfIcmple(i16),
+    IfAcmpeq(i16),
+    IfAcmpne(i16),
+    Goto(i16),
+    Jsr(i16),
+    Ret(u16),
+    Tableswitch { default: i32, low: i32, high: i32, offsets: Vec<i32> },
+    Lookupswitch { default: i32, pairs: Vec<(i32, i32)> },
+    Ireturn,
+    Lreturn,
+    Freturn,
+    Dreturn,
+    Areturn,
+    Return,
+    Getstatic(u16),
+    Putstatic(u16),
+    Getfield(u16),
+    Putfield(u16),
+    Invokevirtual(u16),
+    Invokespecial(u16),
+    Invokestatic(u16),
+    Invokeinterface { index: u16, count: u8 },
+    Invokedynamic(u16),
+    New(u16),
+    Newarray(u8),
+    Anewarray(u16),
+    Arraylength,
+    Athrow,
+    Checkcast(u16),
+    Instanceof(u16),
+    Monitorenter,
+    Monitorexit,
+    Multianewarray { index: u16, dimensions: u8 },
+    Ifnull(i16),
+    Ifnonnull(i16),
+    GotoW(i32),
+    JsrW(i32),
+    Breakpoint,
+    Impdep1,
+    Impdep2,
+}
+
+impl Instruction {
+    /// The opcode of this instruction.
+    pub fn opcode(&self) -> u8 {
+        use self::Instruction::*;
+        match *self {
+            Nop => opcode::NOP,
+            AconstNull => opcode::ACONST_NULL,
+            IconstM1 => opcode::ICONST_M1,
+            Iconst0 => opcode::ICONST_0,
+            Iconst1 => opcode::ICONST_1,
+            Iconst2 => opcode::ICONST_2,
+            Iconst3 => opcode::ICONST_3,
+            Iconst4 => opcode::ICONST_4,
+            Iconst5 => opcode::ICONST_5,
+            Lconst0 => opcode::LCONST_0,
+            Lconst1 => opcode::LCONST_1,
+            Fconst0 => opcode::FCONST_0,
+            Fconst1 => opcode::FCONST_1,
+            Fconst2 => opcode::FCONST_2,
+            Dconst0 => opcode::DCONST_0,
+            Dconst1 => opcode::DCONST_1,
+            Bipush(_) => opcode::BIPUSH,
+            Sipush(_) => opcode::SIPUSH,
+            Ldc(_) => opcode::LDC,
+            LdcW(_) => opcode::LDC_W,
+            Ldc2W(_) => opcode::LDC2_W,
+            Iload(_) => opcode::ILOAD,
+            Lload(_) => opcode::LLOAD,
+            Fload(_) => opcode::FLOAD,
+            Dload(_) => opcode::DLOAD,
+            Aload(_) => opcode::ALOAD,
+            Iload0 => opcode::ILOAD_0,
+            Iload1 => opcode::ILOAD_1,
+            Iload2 => opcode::ILOAD_2,
+            Iload3 => opcode::ILOAD_3,
+            Lload0 => opcode::LLOAD_0,
+            Lload1 => opcode::LLOAD_1,
+            Lload2 => opcode::LLOAD_2,
+            Lload3 => opcode::LLOAD_3,
+            Fload0 => opcode::FLOAD_0,
+            Fload1 => opcode::FLOAD_1,
+            Fload2 => opcode::FLOAD_2,
+            Fload3 => opcode::FLOAD_3,
+            Dload0 => opcode::DLOAD_0,
+            Dload1 => opcode::DLOAD_1,
+            Dload2 => opcode::DLOAD_2,
+            Dload3 => opcode::DLOAD_3,
+            Aload0 => opcode::ALOAD_0,
+            Aload1 => opcode::ALOAD_1,
+            Aload2 => opcode::ALOAD_2,
+            Aload3 => opcode::ALOAD_3,
+            Iaload => opcode::IALOAD,
+            Laload => opcode::LALOAD,
+            Faload => opcode::FALOAD,
+            Daload => opcode::DALOAD,
+            Aaload => opcode::AALOAD,
+            Baload => opcode::BALOAD,
+            Caload => opcode::CALOAD,
+            Saload => opcode::SALOAD,
+            Istore(_) => opcode::ISTORE,
+            Lstore(_) => opcode::LSTORE,
+            Fstore(_) => opcode::FSTORE,
+            Dstore(_) => opcode::DSTORE,
+            Astore(_) => opcode::ASTORE,
+            Istore0 => opcode::ISTORE_0,
+            Istore1 => opcode::ISTORE_1,
+            Istore2 => opcode::ISTORE_2,
+            Istore3 => opcode::ISTORE_3,
+            Lstore0 => opcode::LSTORE_0,
+            Lstore1 => opcode::LSTORE_1,
+            Lstore2 => opcode::LSTORE_2,
+            Lstore3 => opcode::LSTORE_3,
+            Fstore0 => opcode::FSTORE_0,
+            Fstore1 => opcode::FSTORE_1,
+            Fstore2 => opcode::FSTORE_2,
+            Fstore3 => opcode::FSTORE_3,
+            Dstore0 => opcode::DSTORE_0,
+            Dstore1 => opcode::DSTORE_1,
+            Dstore2 => opcode::DSTORE_2,
+            Dstore3 => opcode::DSTORE_3,
+            Astore0 => opcode::ASTORE_0,
+            Astore1 => opcode::ASTORE_1,
+            Astore2 => opcode::ASTORE_2,
+            Astore3 => opcode::ASTORE_3,
+            Iastore => opcode::IASTORE,
+            Lastore => opcode::LASTORE,
+            Fastore => opcode::FASTORE,
+            Dastore => opcode::DASTORE,
+            Aastore => opcode::AASTORE,
+            Bastore => opcode::BASTORE,
+            Castore => opcode::CASTORE,
+            Sastore => opcode::SASTORE,
+            Pop => opcode::POP,
+            Pop2 => opcode::POP2,
+            Dup => opcode::DUP,
+            DupX1 => opcode::DUP_X1,
+            DupX2 => opcode::DUP_X2,
+            Dup2 => opcode::DUP2,
+            Dup2X1 => opcode::DUP2_X1,
+            Dup2X2 => opcode::DUP2_X2,
+            Swap => opcode::SWAP,
+            Iadd => opcode::IADD,
+            Ladd => opcode::LADD,
+            Fadd => opcode::FADD,
+            Dadd => opcode::DADD,
+            Isub => opcode::ISUB,
+            Lsub => opcode::LSUB,
+            Fsub => opcode::FSUB,
+            Dsub => opcode::DSUB,
+            Imul => opcode::IMUL,
+            Lmul => opcode::LMUL,
+            Fmul => opcode::FMUL,
+            Dmul => opcode::DMUL,
+            Idiv => opcode::IDIV,
+            Ldiv => opcode::LDIV,
+            Fdiv => opcode::FDIV,
+            Ddiv => opcode::DDIV,
+            Irem => opcode::IREM,
+            Lrem => opcode::LREM,
+            Frem => opcode::FREM,
+            Drem => opcode::DREM,
+            Ineg => opcode::INEG,
+            Lneg => opcode::LNEG,
+            Fneg => opcode::FNEG,
+            Dneg => opcode::DNEG,
+            Ishl => opcode::ISHL,
+            Lshl => opcode::LSHL,
+            Ishr => opcode::ISHR,
+            Lshr => opcode::LSHR,
+            Iushr => opcode::IUSHR,
+            Lushr => opcode::LUSHR,
+            Iand => opcode::IAND,
+            Land => opcode::LAND,
+            Ior => opcode::IOR,
+            Lor => opcode::LOR,
+            Ixor => opcode::IXOR,
+            Lxor => opcode::LXOR,
+            Iinc { .. } => opcode::IINC,
+            I2l => opcode::I2L,
+            I2f => opcode::I2F,
+            I2d => opcode::I2D,
+            L2i => opcode::L2I,
+            L2f => opcode::L2F,
+            L2d => opcode::L2D,
+            F2i => opcode::F2I,
+            F2l => opcode::F2L,
+            F2d => opcode::F2D,
+            D2i => opcode::D2I,
+            D2l => opcode::D2L,
+            D2f => opcode::D2F,
+            I2b => opcode::I2B,
+            I2c => opcode::I2C,
+            I2s => opcode::I2S,
+            Lcmp => opcode::LCMP,
+            Fcmpl => opcode::FCMPL,
+            Fcmpg => opcode::FCMPG,
+            Dcmpl => opcode::DCMPL,
+            Dcmpg => opcode::DCMPG,
+            Ifeq(_) => opcode::IFEQ,
+            Ifne(_) => opcode::IFNE,
+            Iflt(_) => opcode::IFLT,
+            Ifge(_) => opcode::IFGE,
+            Ifgt(_) => opcode::IFGT,
+            Ifle(_) => opcode::IFLE,
+            IfIcmpeq(_) => opcode::IF_ICMPEQ,
+            IfIcmpne(_) => opcode::IF_ICMPNE,
+            IfIcmplt(_) => opcode::IF_ICMPLT,
+            IfIcmpge(_) => opcode::IF_ICMPGE,
+            IfIcmpgt(_) => opcode::IF_ICMPGT,
+            IfIcmple(_) => opcode::IF_ICMPLE,
+            IfAcmpeq(_) => opcode::IF_ACMPEQ,
+            IfAcmpne(_) => opcode::IF_ACMPNE,
+            Goto(_) => opcode::GOTO,
+            Jsr(_) => opcode::JSR,
+            Ret(_) => opcode::RET,
+            Tableswitch { .. } => opcode::TABLESWITCH,
+            Lookupswitch { .. } => opcode::LOOKUPSWITCH,
+            Ireturn => opcode::IRETURN,
+            Lreturn => opcode::LRETURN,
+            Freturn => opcode::FRETURN,
+            Dreturn => opcode::DRETURN,
+            Areturn => opcode::ARETURN,
+            Return => opcode::RETURN,
+            Getstatic(_) => opcode::GETSTATIC,
+            Putstatic(_) => opcode::PUTSTATIC,
+            Getfield(_) => opcode::GETFIELD,
+            Putfield(_) => opcode::PUTFIELD,
+            Invokevirtual(_) => opcode::INVOKEVIRTUAL,
+            Invokespecial(_) => opcode::INVOKESPECIAL,
+            Invokestatic(_) => opcode::INVOKESTATIC,
+            Invokeinterface { .. } => opcode::INVOKEINTERFACE,
+            Invokedynamic(_) => opcode::INVOKEDYNAMIC,
+            New(_) => opcode::NEW,
+            Newarray(_) => opcode::NEWARRAY,
+            Anewarray(_) => opcode::ANEWARRAY,
+            Arraylength => opcode::ARRAYLENGTH,
+            Athrow => opcode::ATHROW,
+            Checkcast(_) => opcode::CHECKCAST,
+            Instanceof(_) => opcode::INSTANCEOF,
+            Monitorenter => opcode::MONITORENTER,
+            Monitorexit => opcode::MONITOREXIT,
+            Multianewarray { .. } => opcode::MULTIANEWARRAY,
+            Ifnull(_) => opcode::IFNULL,
+            Ifnonnull(_) => opcode::IFNONNULL,
+            GotoW(_) => opcode::GOTO_W,
+            JsrW(_) => opcode::JSR_W,
+            Breakpoint => opcode::BREAKPOINT,
+            Impdep1 => opcode::IMPDEP1,
+            Impdep2 => opcode::IMPDEP2,
+        }
+    }
+
+    /// The mnemonic of this instruction, as it appears in the JVM specification.
+    pub fn mnemonic(&self) -> &'static str {
+        mnemonic(self.opcode()).unwrap()
+    }
+}
+
+/// An iterator over the instructions in the code array of a method, yielding the offset of each
+/// instruction along with the decoded instruction. Iteration stops at the end of the code array,
+/// or at the first instruction which has an unknown opcode or which extends beyond the end of the
+/// code array.
+pub struct InstructionIterator<'a> {
+    code: &'a [u8],
+    pc: usize,
+}
+
+impl<'a> InstructionIterator<'a> {
+    pub fn new(code: &'a [u8]) -> Self {
+        InstructionIterator { code: code, pc: 0 }
+    }
+
+    /// The offset of the next instruction to be decoded. Once iteration has stopped, this is less
+    /// than the length of the code array only if an instruction there could not be decoded.
+    pub fn offset(&self) -> usize {
+        self.pc
+    }
+}
+
+impl<'a> Iterator for InstructionIterator<'a> {
+    type Item = (u32, Instruction);
+
+    fn next(&mut self) -> Option<(u32, Instruction)> {
+        let pc = self.pc;
+        let mut reader = Reader { code: self.code, pc: pc };
+        let instruction = try_opt!(decode(&mut reader));
+        self.pc = reader.pc;
+        Some((pc as u32, instruction))
+    }
+}
+
+/// Reads big-endian operands from the code array, returning `None` if the code array is too short.
+struct Reader<'a> {
+    code: &'a [u8],
+    pc: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u1(&mut self) -> Option<u8> {
+        let b = self.code.get(self.pc).cloned();
+        self.pc += 1;
+        b
+    }
+
+    fn u2(&mut self) -> Option<u16> {
+        let hi = try_opt!(self.u1()) as u16;
+        let lo = try_opt!(self.u1()) as u16;
+        Some((hi << 8) | lo)
+    }
+
+    fn i4(&mut self) -> Option<i32> {
+        let hi = try_opt!(self.u2()) as u32;
+        let lo = try_opt!(self.u2()) as u32;
+        Some(((hi << 16) | lo) as i32)
+    }
+
+    /// Skips the padding which aligns the operands of a switch instruction to a multiple of four
+    /// bytes from the start of the code array.
+    fn align(&mut self) {
+        self.pc += (4 - self.pc % 4) % 4;
+    }
+}
+
+fn decode(r: &mut Reader) -> Option<Instruction> {
+    use self::Instruction::*;
+    let instruction = match try_opt!(r.u1()) {
+        opcode::NOP => Nop,
+        opcode::ACONST_NULL => AconstNull,
+        opcode::ICONST_M1 => IconstM1,
+        opcode::ICONST_0 => Iconst0,
+        opcode::ICONST_1 => Iconst1,
+        opcode::ICONST_2 => Iconst2,
+        opcode::ICONST_3 => Iconst3,
+        opcode::ICONST_4 => Iconst4,
+        opcode::ICONST_5 => Iconst5,
+        opcode::LCONST_0 => Lconst0,
+        opcode::LCONST_1 => Lconst1,
+        opcode::FCONST_0 => Fconst0,
+        opcode::FCONST_1 => Fconst1,
+        opcode::FCONST_2 => Fconst2,
+        opcode::DCONST_0 => Dconst0,
+        opcode::DCONST_1 => Dconst1,
+        opcode::ILOAD_0 => Iload0,
+        opcode::ILOAD_1 => Iload1,
+        opcode::ILOAD_2 => Iload2,
+        opcode::ILOAD_3 => Iload3,
+        opcode::LLOAD_0 => Lload0,
+        opcode::LLOAD_1 => Lload1,
+        opcode::LLOAD_2 => Lload2,
+        opcode::LLOAD_3 => Lload3,
+        opcode::FLOAD_0 => Fload0,
+        opcode::FLOAD_1 => Fload1,
+        opcode::FLOAD_2 => Fload2,
+        opcode::FLOAD_3 => Fload3,
+        opcode::DLOAD_0 => Dload0,
+        opcode::DLOAD_1 => Dload1,
+        opcode::DLOAD_2 => Dload2,
+        opcode::DLOAD_3 => Dload3,
+        opcode::ALOAD_0 => Aload0,
+        opcode::ALOAD_1 => Aload1,
+        opcode::ALOAD_2 => Aload2,
+        opcode::ALOAD_3 => Aload3,
+        opcode::IALOAD => Iaload,
+        opcode::LALOAD => Laload,
+        opcode::FALOAD => Faload,
+        opcode::DALOAD => Daload,
+        opcode::AALOAD => Aaload,
+        opcode::BALOAD => Baload,
+        opcode::CALOAD => Caload,
+        opcode::SALOAD => Saload,
+        opcode::ISTORE_0 => Istore0,
+        opcode::ISTORE_1 => Istore1,
+        opcode::ISTORE_2 => Istore2,
+        opcode::ISTORE_3 => Istore3,
+        opcode::LSTORE_0 => Lstore0,
+        opcode::LSTORE_1 => Lstore1,
+        opcode::LSTORE_2 => Lstore2,
+        opcode::LSTORE_3 => Lstore3,
+        opcode::FSTORE_0 => Fstore0,
+        opcode::FSTORE_1 => Fstore1,
+        opcode::FSTORE_2 => Fstore2,
+        opcode::FSTORE_3 => Fstore3,
+        opcode::DSTORE_0 => Dstore0,
+        opcode::DSTORE_1 => Dstore1,
+        opcode::DSTORE_2 => Dstore2,
+        opcode::DSTORE_3 => Dstore3,
+        opcode::ASTORE_0 => Astore0,
+        opcode::ASTORE_1 => Astore1,
+        opcode::ASTORE_2 => Astore2,
+        opcode::ASTORE_3 => Astore3,
+        opcode::IASTORE => Iastore,
+        opcode::LASTORE => Lastore,
+        opcode::FASTORE => Fastore,
+        opcode::DASTORE => Dastore,
+        opcode::AASTORE => Aastore,
+        opcode::BASTORE => Bastore,
+        opcode::CASTORE => Castore,
+        opcode::SASTORE => Sastore,
+        opcode::POP => Pop,
+        opcode::POP2 => Pop2,
+        opcode::DUP => Dup,
+        opcode::DUP_X1 => DupX1,
+        opcode::DUP_X2 => DupX2,
+        opcode::DUP2 => Dup2,
+        opcode::DUP2_X1 => Dup2X1,
+        opcode::DUP2_X2 => Dup2X2,
+        opcode::SWAP => Swap,
+        opcode::IADD => Iadd,
+        opcode::LADD => Ladd,
+        opcode::FADD => Fadd,
+        opcode::DADD => Dadd,
+        opcode::ISUB => Isub,
+        opcode::LSUB => Lsub,
+        opcode::FSUB => Fsub,
+        opcode::DSUB => Dsub,
+        opcode::IMUL => Imul,
+        opcode::LMUL => Lmul,
+        opcode::FMUL => Fmul,
+        opcode::DMUL => Dmul,
+        opcode::IDIV => Idiv,
+        opcode::LDIV => Ldiv,
+        opcode::FDIV => Fdiv,
+        opcode::DDIV => Ddiv,
+        opcode::IREM => Irem,
+        opcode::LREM => Lrem,
+        opcode::FREM => Frem,
+        opcode::DREM => Drem,
+        opcode::INEG => Ineg,
+        opcode::LNEG => Lneg,
+        opcode::FNEG => Fneg,
+        opcode::DNEG => Dneg,
+        opcode::ISHL => Ishl,
+        opcode::LSHL => Lshl,
+        opcode::ISHR => Ishr,
+        opcode::LSHR => Lshr,
+        opcode::IUSHR => Iushr,
+        opcode::LUSHR => Lushr,
+        opcode::IAND => Iand,
+        opcode::LAND => Land,
+        opcode::IOR => Ior,
+        opcode::LOR => Lor,
+        opcode::IXOR => Ixor,
+        opcode::LXOR => Lxor,
+        opcode::I2L => I2l,
+        opcode::I2F => I2f,
+        opcode::I2D => I2d,
+        opcode::L2I => L2i,
+        opcode::L2F => L2f,
+        opcode::L2D => L2d,
+        opcode::F2I => F2i,
+        opcode::F2L => F2l,
+        opcode::F2D => F2d,
+        opcode::D2I => D2i,
+        opcode::D2L => D2l,
+        opcode::D2F => D2f,
+        opcode::I2B => I2b,
+        opcode::I2C => I2c,
+        opcode::I2S => I2s,
+        opcode::LCMP => Lcmp,
+        opcode::FCMPL => Fcmpl,
+        opcode::FCMPG => Fcmpg,
+        opcode::DCMPL => Dcmpl,
+        opcode::DCMPG => Dcmpg,
+        opcode::IRETURN => Ireturn,
+        opcode::LRETURN => Lreturn,
+        opcode::FRETURN => Freturn,
+        opcode::DRETURN => Dreturn,
+        opcode::ARETURN => Areturn,
+        opcode::RETURN => Return,
+        opcode::ARRAYLENGTH => Arraylength,
+        opcode::ATHROW => Athrow,
+        opcode::MONITORENTER => Monitorenter,
+        opcode::MONITOREXIT => Monitorexit,
+        opcode::BREAKPOINT => Breakpoint,
+        opcode::IMPDEP1 => Impdep1,
+        opcode::IMPDEP2 => Impdep2,
+
+        opcode::BIPUSH => Bipush(try_opt!(r.u1()) as i8),
+        opcode::SIPUSH => Sipush(try_opt!(r.u2()) as i16),
+        opcode::LDC => Ldc(try_opt!(r.u1()) as u16),
+        opcode::LDC_W => LdcW(try_opt!(r.u2())),
+        opcode::LDC2_W => Ldc2W(try_opt!(r.u2())),
+
+        opcode::ILOAD => Iload(try_opt!(r.u1()) as u16),
+        opcode::LLOAD => Lload(try_opt!(r.u1()) as u16),
+        opcode::FLOAD => Fload(try_opt!(r.u1()) as u16),
+        opcode::DLOAD => Dload(try_opt!(r.u1()) as u16),
+        opcode::ALOAD => Aload(try_opt!(r.u1()) as u16),
+        opcode::ISTORE => Istore(try_opt!(r.u1()) as u16),
+        opcode::LSTORE => Lstore(try_opt!(r.u1()) as u16),
+        opcode::FSTORE => Fstore(try_opt!(r.u1()) as u16),
+        opcode::DSTORE => Dstore(try_opt!(r.u1()) as u16),
+        opcode::ASTORE => Astore(try_opt!(r.u1()) as u16),
+        opcode::RET => Ret(try_opt!(r.u1()) as u16),
+        opcode::IINC => {
+            let index = try_opt!(r.u1()) as u16;
+            let constant = try_opt!(r.u1()) as i8 as i16;
+            Iinc { index: index, constant: constant }
+        },
+
+        opcode::IFEQ => Ifeq(try_opt!(r.u2()) as i16),
+        opcode::IFNE => Ifne(try_opt!(r.u2()) as i16),
+        opcode::IFLT => Iflt(try_opt!(r.u2()) as i16),
+        opcode::IFGE => Ifge(try_opt!(r.u2()) as i16),
+        opcode::IFGT => Ifgt(try_opt!(r.u2()) as i16),
+        opcode::IFLE => Ifle(try_opt!(r.u2()) as i16),
+        opcode::IF_ICMPEQ => IfIcmpeq(try_opt!(r.u2()) as i16),
+        opcode::IF_ICMPNE => IfIcmpne(try_opt!(r.u2()) as i16),
+        opcode::IF_ICMPLT => IfIcmplt(try_opt!(r.u2()) as i16),
+        opcode::IF_ICMPGE => IfIcmpge(try_opt!(r.u2()) as i16),
+        opcode::IF_ICMPGT => IfIcmpgt(try_opt!(r.u2()) as i16),
+        opcode::IF_ICMPLE => IfIcmple(try_opt!(r.u2()) as i16),
+        opcode::IF_ACMPEQ => IfAcmpeq(try_opt!(r.u2()) as i16),
+        opcode::IF_ACMPNE => IfAcmpne(try_opt!(r.u2()) as i16),
+        opcode::GOTO => Goto(try_opt!(r.u2()) as i16),
+        opcode::JSR => Jsr(try_opt!(r.u2()) as i16),
+        opcode::IFNULL => Ifnull(try_opt!(r.u2()) as i16),
+        opcode::IFNONNULL => Ifnonnull(try_opt!(r.u2()) as i16),
+        opcode::GOTO_W => GotoW(try_opt!(r.i4())),
+        opcode::JSR_W => JsrW(try_opt!(r.i4())),
+
+        opcode::TABLESWITCH => {
+            r.align();
+            let default = try_opt!(r.i4());
+            let low = try_opt!(r.i4());
+            let high = try_opt!(r.i4());
+            if low > high {
+                return None;
+            }
+            let mut offsets = vec![];
+            for _ in low as i64..high as i64 + 1 {
+                offsets.push(try_opt!(r.i4()));
+            }
+            Tableswitch { default: default, low: low, high: high, offsets: offsets }
+        },
+        opcode::LOOKUPSWITCH => {
+            r.align();
+            let default = try_opt!(r.i4());
+            let npairs = try_opt!(r.i4());
+            let mut pairs = vec![];
+            for _ in 0..npairs {
+                let key = try_opt!(r.i4());
+                pairs.push((key, try_opt!(r.i4())));
+            }
+            Lookupswitch { default: default, pairs: pairs }
+        },
+
+        opcode::GETSTATIC => Getstatic(try_opt!(r.u2())),
+        opcode::PUTSTATIC => Putstatic(try_opt!(r.u2())),
+        opcode::GETFIELD => Getfield(try_opt!(r.u2())),
+        opcode::PUTFIELD => Putfield(try_opt!(r.u2())),
+        opcode::INVOKEVIRTUAL => Invokevirtual(try_opt!(r.u2())),
+        opcode::INVOKESPECIAL => Invokespecial(try_opt!(r.u2())),
+        opcode::INVOKESTATIC => Invokestatic(try_opt!(r.u2())),
+        opcode::INVOKEINTERFACE => {
+            let index = try_opt!(r.u2());
+            let count = try_opt!(r.u1());
+            try_opt!(r.u1());
+            Invokeinterface { index: index, count: count }
+        },
+        opcode::INVOKEDYNAMIC => {
+            let index = try_opt!(r.u2());
+            try_opt!(r.u2());
+            Invokedynamic(index)
+        },
+
+        opcode::NEW => New(try_opt!(r.u2())),
+        opcode::NEWARRAY => Newarray(try_opt!(r.u1())),
+        opcode::ANEWARRAY => Anewarray(try_opt!(r.u2())),
+        opcode::CHECKCAST => Checkcast(try_opt!(r.u2())),
+        opcode::INSTANCEOF => Instanceof(try_opt!(r.u2())),
+        opcode::MULTIANEWARRAY => {
+            let index = try_opt!(r.u2());
+            let dimensions = try_opt!(r.u1());
+            Multianewarray { index: index, dimensions: dimensions }
+        },
+
+        opcode::WIDE => {
+            let modified = try_opt!(r.u1());
+            let index = try_opt!(r.u2());
+            match modified {
+                opcode::ILOAD => Iload(index),
+                opcode::LLOAD => Lload(index),
+                opcode::FLOAD => Fload(index),
+                opcode::DLOAD => Dload(index),
+                opcode::ALOAD => Aload(index),
+                opcode::ISTORE => Istore(index),
+                opcode::LSTORE => Lstore(index),
+                opcode::FSTORE => Fstore(index),
+                opcode::DSTORE => Dstore(index),
+                opcode::ASTORE => Astore(index),
+                opcode::RET => Ret(index),
+                opcode::IINC => Iinc { index: index, constant: try_opt!(r.u2()) as i16 },
+                _ => return None,
+            }
+        },
+
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use model::class_file::AttributeInfo;
+    use parser::class_file::parse_class_file;
+
+    #[test]
+    fn test_hello_world() {
+        let class = parse_class_file(include_bytes!("../../data/HelloWorld.class")).unwrap();
+        let main = class.methods.iter().find(|method| {
+            class.utf8_at(method.name_index).unwrap() == "main"
+        }).unwrap();
+        let code = main.attributes.iter().filter_map(|attr| match *attr {
+            AttributeInfo::Code { ref code, .. } => Some(code),
+            _ => None,
+        }).next().unwrap();
+        let instructions = InstructionIterator::new(code).collect::<Vec<_>>();
+        assert_eq!(instructions, vec![
+            (0, Instruction::Getstatic(2)),
+            (3, Instruction::Ldc(3)),
+            (5, Instruction::Invokevirtual(4)),
+            (8, Instruction::Return),
+        ]);
+        let mnemonics = instructions.iter().map(|&(_, ref instruction)| {
+            instruction.mnemonic()
+        }).collect::<Vec<_>>();
+        assert_eq!(mnemonics, ["getstatic", "ldc", "invokevirtual", "return"]);
+    }
+
+    #[test]
+    fn test_switches_and_wide() {
+        let code = [
+            opcode::NOP,
+            opcode::TABLESWITCH, 0, 0,
+            0, 0, 0, 30,
+            0, 0, 0, 1,
+            0, 0, 0, 2,
+            0, 0, 0, 20,
+            0, 0, 0, 25,
+            opcode::LOOKUPSWITCH, 0, 0, 0,
+            0, 0, 0, 10,
+            0, 0, 0, 1,
+            0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xe8,
+            opcode::WIDE, opcode::IINC, 0x01, 0x00, 0xff, 0xff,
+            opcode::WIDE, opcode::ALOAD, 0x01, 0x02,
+            opcode::IINC, 0x01, 0xff,
+            opcode::RETURN,
+        ];
+        assert_eq!(InstructionIterator::new(&code).collect::<Vec<_>>(), vec![
+            (0, Instruction::Nop),
+            (1, Instruction::Tableswitch { default: 30, low: 1, high: 2, offsets: vec![20, 25] }),
+            (24, Instruction::Lookupswitch { default: 10, pairs: vec![(-1, -24)] }),
+            (44, Instruction::Iinc { index: 256, constant: -1 }),
+            (50, Instruction::Aload(258)),
+            (54, Instruction::Iinc { index: 1, constant: -1 }),
+            (57, Instruction::Return),
+        ]);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let code = [opcode::ICONST_0, opcode::SIPUSH, 0];
+        assert_eq!(InstructionIterator::new(&code).collect::<Vec<_>>(),
+                   vec![(0, Instruction::Iconst0)]);
+        let code = [opcode::ICONST_1, 0xee, opcode::RETURN];
+        assert_eq!(InstructionIterator::new(&code).collect::<Vec<_>>(),
+                   vec![(0, Instruction::Iconst1)]);
+    }
+
+    #[test]
+    fn test_opcodes() {
+        for op in 0..256 {
+            let op = op as u8;
+            if op == opcode::WIDE || mnemonic(op).is_none() {
+                continue;
+            }
+            // long enough for the operands of any instruction, including a tableswitch whose
+            // table covers the single key 0
+            let mut code = vec![op];
+            code.extend_from_slice(&[0; 20]);
+            let (_, instruction) = InstructionIterator::new(&code).next().unwrap();
+            assert_eq!(instruction.opcode(), op);
+            assert_eq!(Some(instruction.mnemonic()), mnemonic(op));
+        }
+    }
+}
@@ -1,16 +1,20 @@
 //! Internal JVM representations of classes and methods.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::time::Instant;
 
 use model::class_file::{access_flags, ClassFile, constant_pool_index, MethodInfo};
 use model::class_file::attribute::{AttributeInfo, ExceptionTableEntry};
-use util::one_indexed_vec::OneIndexedVec;
 use vm::{native, sig, symref};
+use vm::native::NativeRegistry;
 use vm::class_loader::ClassLoader;
 use vm::constant_pool::RuntimeConstantPool;
-use vm::frame::Frame;
+use vm::context::Context;
+use vm::exceptions::{self, ExceptionInfo};
+use vm::frame::{self, Exception, Frame, FrameDebugInfo, MethodResult};
+use vm::heap::HeapRef;
 use vm::value::Value;
 
 /// A JVM representation of a class that has been loaded.
@@ -23,6 +27,9 @@ pub struct Class {
     pub access_flags: u16,
     /// The superclass extended by the class. If the class is `java/lang/Object`, this is `None`.
     pub superclass: Option<Rc<Class>>,
+    /// The interfaces directly implemented by the class (or extended by the interface), in the
+    /// order in which they are declared. Array classes have none.
+    pub interfaces: Vec<Rc<Class>>,
     /// The runtime constant pool of the current class, created from the constant pool defined in
     /// the `.class` file that has been loaded.
     constant_pool: RuntimeConstantPool,
@@ -50,17 +57,27 @@ pub struct Class {
     /// field contains a `Some` with a `HashMap` value, which must contain the current values for
     /// each `static` field of this class.
     field_values: RefCell<Option<HashMap<sig::Field, Value>>>,
+    /// Whether initialization of this class has completed. The static fields of the class are
+    /// set before its `<clinit>` method is run, so a class whose `field_values` are set but which
+    /// is not yet initialized is currently being initialized.
+    pub initialized: Cell<bool>,
+    /// The name of the source file from which the class was compiled, if its class file has a
+    /// `SourceFile` attribute.
+    pub source_file: Option<Rc<str>>,
 }
 
 impl Class {
-    pub fn new(symref: symref::Class, superclass: Option<Rc<Class>>,
-               constant_pool: RuntimeConstantPool, class_file: ClassFile) -> Self {
+    /// Create a class from its parsed class file, binding its native methods to their
+    /// implementations in `natives`.
+    pub fn new(symref: symref::Class, superclass: Option<Rc<Class>>, interfaces: Vec<Rc<Class>>,
+               constant_pool: RuntimeConstantPool, class_file: ClassFile,
+               natives: &NativeRegistry) -> Self {
         let mut fields = HashMap::new();
         let mut field_constants = HashMap::new();
         for field_info in class_file.fields {
             let name = constant_pool.lookup_raw_string(field_info.name_index);
             let ty = sig::Type::new(&constant_pool.lookup_raw_string(field_info.descriptor_index));
-            let sig = sig::Field { name: name, ty: ty };
+            let sig = sig::Field { name: String::from(&*name), ty: ty };
             if field_info.access_flags & access_flags::field_access_flags::ACC_STATIC != 0 {
                 for attribute in field_info.attributes {
                     if let AttributeInfo::ConstantValue { constant_value_index } = attribute {
@@ -77,41 +94,52 @@ impl Class {
             let descriptor = constant_pool.lookup_raw_string(method_info.descriptor_index);
             let sig = sig::Method::new(&name, &descriptor);
             let method_symref = symref::Method { class: symref.clone(), sig: sig.clone() };
-            methods.insert(sig, Method::new(method_symref, method_info));
+            methods.insert(sig, Method::new(method_symref, method_info, &constant_pool, natives));
         }
 
+        let source_file = class_file.attributes.iter().filter_map(|attribute| match *attribute {
+            AttributeInfo::SourceFile { sourcefile_index } =>
+                Some(constant_pool.lookup_raw_string(sourcefile_index)),
+            _ => None,
+        }).next();
+
         Class {
             symref: symref,
             access_flags: class_file.access_flags,
             superclass: superclass,
+            interfaces: interfaces,
             constant_pool: constant_pool,
             fields: fields,
             field_constants: field_constants,
             methods: methods,
             field_values: RefCell::new(None),
+            initialized: Cell::new(false),
+            source_file: source_file,
         }
     }
 
-    /// Create a new array class for a given element type.
-    pub fn new_array(object_class: Rc<Class>, component_access_flags: u16,
-                     component_type: sig::Type) -> Self {
+    /// Create a new array class for a given element type, which implements the given interfaces.
+    pub fn new_array(object_class: Rc<Class>, interfaces: Vec<Rc<Class>>,
+                     component_access_flags: u16, component_type: sig::Type) -> Self {
         let access_flags = (component_access_flags & 0x0001) | 0x1030;
         let length_field = sig::Field {
             name: String::from("length"),
             ty: sig::Type::Int,
         };
-        let empty_constant_pool = OneIndexedVec::from(vec![]);
         let mut fields = HashMap::new();
         fields.insert(length_field, 0x1011);
         Class {
             symref: symref::Class { sig: sig::Class::Array(Box::new(component_type)) },
             access_flags: access_flags,
             superclass: Some(object_class.clone()),
-            constant_pool: RuntimeConstantPool::new(&empty_constant_pool),
+            interfaces: interfaces,
+            constant_pool: RuntimeConstantPool::empty(),
             fields: fields,
             field_constants: HashMap::new(),
             methods: HashMap::new(),
             field_values: RefCell::new(None),
+            initialized: Cell::new(false),
+            source_file: None,
         }
     }
 
@@ -127,12 +155,47 @@ impl Class {
         &self.constant_pool
     }
 
-    /// Find the method in the current class referred to by a given symbolic reference. If the
-    /// method is not found, panics with a `NoSuchMethodError`.
-    pub fn resolve_method(&self, method_symref: &symref::Method) -> &Method {
+    /// Returns the heap references held by the static fields of the class. A class which has not
+    /// begun initialization has no static field values yet.
+    pub fn static_references(&self) -> Vec<HeapRef> {
+        match *self.field_values.borrow() {
+            Some(ref field_values) => {
+                field_values.values().filter_map(Value::as_heap_ref).collect()
+            },
+            None => vec![],
+        }
+    }
+
+    /// Resolves a method of this class by its signature (§5.4.3.3). The method is looked up in
+    /// this class and its superclasses, and otherwise among the methods of its superinterfaces,
+    /// preferring the default method of a maximally-specific superinterface if there is exactly
+    /// one. Returns `None` if no method is found, which should result in a `NoSuchMethodError`.
+    pub fn resolve_method(&self, method_sig: &sig::Method) -> Option<&Method> {
         // TODO access control
         // TODO check if this is an interface
-        self.find_method(&method_symref.sig).expect("NoSuchMethodError")
+        self.find_method(method_sig).or_else(|| {
+            let methods = self.maximally_specific_superinterface_methods(method_sig);
+            unique_default_method(&methods).or_else(|| methods.first().cloned())
+        })
+    }
+
+    /// Resolves a static method of this class by its signature. Static methods of interfaces are
+    /// not inherited, so only this class and its superclasses are searched. Returns `None` if no
+    /// method is found, or if the method found is not static.
+    pub fn resolve_static_method(&self, method_sig: &sig::Method) -> Option<&Method> {
+        self.find_method(method_sig).and_then(|method| {
+            if method.access_flags & access_flags::method_access_flags::ACC_STATIC != 0 {
+                Some(method)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the methods declared by this class with the given name, whatever their
+    /// descriptors.
+    pub fn methods_named(&self, name: &str) -> Vec<&Method> {
+        self.methods.values().filter(|method| method.symref.sig.name == name).collect()
     }
 
     /// Implements dynamic lookup of a method's signature in the current class. If no method with
@@ -143,6 +206,86 @@ impl Class {
         })
     }
 
+    /// Resolves a method of this interface by its signature (§5.4.3.4), searching this
+    /// interface, `java/lang/Object`, and then its superinterfaces. Returns `None` if no method
+    /// is found, which should result in a `NoSuchMethodError`.
+    pub fn resolve_interface_method(&self, method_sig: &sig::Method) -> Option<&Method> {
+        // TODO access control
+        self.find_method(method_sig)
+            .or_else(|| self.find_superinterface_method(method_sig))
+    }
+
+    /// Searches the superinterfaces of this class or interface, but not those of its
+    /// superclasses, for a method with the given signature.
+    fn find_superinterface_method(&self, method_sig: &sig::Method) -> Option<&Method> {
+        self.interfaces.iter().filter_map(|interface| {
+            interface.methods.get(method_sig)
+                .or_else(|| interface.find_superinterface_method(method_sig))
+        }).next()
+    }
+
+    /// Implements the selection of a method for the `invokeinterface` instruction, where this is
+    /// the class of the object on which the method is invoked (§6.5). An instance method declared
+    /// by this class or one of its superclasses is selected first. Otherwise, the method is a
+    /// default method provided by exactly one maximally-specific superinterface. Returns `None`
+    /// if no method is selected, which should result in an `AbstractMethodError`.
+    pub fn lookup_interface_method(&self, method_sig: &sig::Method) -> Option<&Method> {
+        self.find_instance_method(method_sig).or_else(|| {
+            unique_default_method(&self.maximally_specific_superinterface_methods(method_sig))
+        })
+    }
+
+    /// Returns the non-static, non-private methods with the given signature declared by the
+    /// maximally-specific superinterfaces of this class or interface and its superclasses: those
+    /// interfaces which are not a superinterface of another interface declaring such a method.
+    fn maximally_specific_superinterface_methods(&self, method_sig: &sig::Method) -> Vec<&Method> {
+        let mut candidates = vec![];
+        self.collect_interface_methods(method_sig, &mut candidates);
+        candidates.iter().filter(|&&(interface, _)| {
+            !candidates.iter().any(|&(other, _)| {
+                other.symref.sig != interface.symref.sig && other.implements(interface)
+            })
+        }).map(|&(_, method)| method).collect()
+    }
+
+    /// Searches this class and its superclasses for a non-static method with the given signature.
+    fn find_instance_method(&self, method_sig: &sig::Method) -> Option<&Method> {
+        self.methods.get(method_sig).and_then(|method| {
+            if method.access_flags & access_flags::method_access_flags::ACC_STATIC == 0 {
+                Some(method)
+            } else {
+                None
+            }
+        }).or_else(|| {
+            self.superclass.as_ref().and_then(|superclass| {
+                superclass.find_instance_method(method_sig)
+            })
+        })
+    }
+
+    /// Collects the non-static, non-private methods with the given signature declared by all of
+    /// the superinterfaces of this class or interface and its superclasses, together with the
+    /// interfaces declaring them. Each interface appears at most once.
+    fn collect_interface_methods<'a>(&'a self, method_sig: &sig::Method,
+                                     candidates: &mut Vec<(&'a Class, &'a Method)>) {
+        for interface in &self.interfaces {
+            let seen = candidates.iter().any(|&(class, _)| {
+                class.symref.sig == interface.symref.sig
+            });
+            if let (false, Some(method)) = (seen, interface.methods.get(method_sig)) {
+                let excluded = access_flags::method_access_flags::ACC_STATIC
+                    | access_flags::method_access_flags::ACC_PRIVATE;
+                if method.access_flags & excluded == 0 {
+                    candidates.push((interface, method));
+                }
+            }
+            interface.collect_interface_methods(method_sig, candidates);
+        }
+        if let Some(ref superclass) = self.superclass {
+            superclass.collect_interface_methods(method_sig, candidates);
+        }
+    }
+
     /// Implements dynamic dispatch of a resolved method according to the lookup procedure
     /// specified for the `invokevirtual` instruction. Method resolution depends on whether the
     /// method in question overrides a superclass method. (See spec for more information.)
@@ -179,16 +322,76 @@ impl Class {
         }
     }
 
+    /// Returns true if this class is an interface.
+    pub fn is_interface(&self) -> bool {
+        self.access_flags & access_flags::class_access_flags::ACC_INTERFACE != 0
+    }
+
+    /// Returns true if this class or interface is the given interface, or implements it directly
+    /// or through its superclasses and superinterfaces.
+    pub fn implements(&self, interface: &Class) -> bool {
+        self.symref.sig == interface.symref.sig
+            || self.interfaces.iter().any(|superinterface| superinterface.implements(interface))
+            || self.superclass.as_ref().map_or(false, |superclass| superclass.implements(interface))
+    }
+
+    /// Returns true if an object of this class is an instance of the class `other`, according to
+    /// the rules given for the `checkcast` and `instanceof` instructions (§6.5). The class loader
+    /// is used to look up the component classes of reference array types.
+    pub fn is_instance_of(&self, other: &Class, class_loader: &mut dyn ClassLoader) -> bool {
+        match (&self.symref.sig, &other.symref.sig) {
+            (&sig::Class::Scalar(_), &sig::Class::Scalar(_)) => {
+                if other.is_interface() {
+                    self.implements(other)
+                } else {
+                    self.is_descendant(other)
+                }
+            },
+
+            (&sig::Class::Scalar(_), &sig::Class::Array(_)) => false,
+
+            (&sig::Class::Array(_), &sig::Class::Scalar(ref name)) => {
+                if other.is_interface() {
+                    name == "java/lang/Cloneable" || name == "java/io/Serializable"
+                } else {
+                    name == "java/lang/Object"
+                }
+            },
+
+            (&sig::Class::Array(ref component_ty), &sig::Class::Array(ref other_component_ty)) => {
+                match (&**component_ty, &**other_component_ty) {
+                    (&sig::Type::Reference(ref component_sig),
+                     &sig::Type::Reference(ref other_component_sig)) => {
+                        // both component classes were loaded along with their array classes
+                        let component = class_loader.load_class(component_sig).unwrap();
+                        let other_component = class_loader.load_class(other_component_sig).unwrap();
+                        component.is_instance_of(&other_component, class_loader)
+                    },
+                    (component_ty, other_component_ty) => component_ty == other_component_ty,
+                }
+            },
+        }
+    }
+
     /// Initialize the class by executing its class or interface initialization method.  Prior to
     /// initialization, a class or interface must be linked, that is, verified, prepared, and
     /// optionally resolved.
-    pub fn initialize(&self, class_loader: &mut ClassLoader) {
+    ///
+    /// The superclass of the class is initialized first. If the class is already being
+    /// initialized, the request is recursive, and this method returns immediately (§5.5).
+    pub fn initialize(self: &Rc<Self>, class_loader: &mut dyn ClassLoader,
+                      context: &mut Context) {
+        if self.initialized.get() {
+            return;
+        }
+
         // we don't want to have the RefCell borrowed during the initializer
         // therefore, we borrow it in an inner scope and run the initializer later
         let run_initializer = {
             let mut field_values = self.field_values.borrow_mut();
             match *field_values {
                 None => {
+                    context.add_class(self.clone());
                     let mut map = HashMap::new();
 
                     // initialize all static fields to their default values
@@ -201,7 +404,9 @@ impl Class {
 
                     // initialize fields with a ConstantValue attribute to those constant values
                     for (sig, index) in &self.field_constants {
-                        let value = self.constant_pool.resolve_literal(*index, class_loader).unwrap();
+                        let value = self.constant_pool
+                            .resolve_literal(*index, class_loader, context)
+                            .unwrap();
                         map.insert(sig.clone(), value);
                     }
 
@@ -214,6 +419,10 @@ impl Class {
         };
 
         if run_initializer {
+            if let Some(ref superclass) = self.superclass {
+                superclass.initialize(class_loader, context);
+            }
+
             let clinit_sig = sig::Method {
                 name: String::from("<clinit>"),
                 params: vec![],
@@ -222,46 +431,97 @@ impl Class {
             match self.methods.get(&clinit_sig) {
                 None => (),
                 Some(ref method) => {
-                    let result = method.invoke(&self, class_loader, vec![]);
+                    let result = method.invoke(&self, class_loader, context, vec![]);
                     match result {
-                        None => (),
-                        Some(_) => panic!("<clinit> returned a value!"),
+                        Ok(None) => (),
+                        Ok(Some(_)) => panic!("<clinit> returned a value!"),
+                        Err(_) => panic!("ExceptionInInitializerError"),
                     }
                 },
             };
+            self.initialized.set(true);
+            class_loader.class_initialized(self);
         }
     }
 
-    /// Resolves a symbolic reference to a field and reads a value from that field.
-    pub fn resolve_and_get_field(&self, symref: &symref::Field, class_loader: &mut ClassLoader)
-            -> Value {
-        self.initialize(class_loader);
-        // TODO we're ignoring the superinterfaces
-        // TODO: also not checking for static
-        let field_values_opt = self.field_values.borrow();
-        let field_values = field_values_opt.as_ref().unwrap();
-        let value_opt = field_values.get(&symref.sig).map(|v| v.clone());
-        value_opt.unwrap_or_else(move || {
-            let superclass = self.superclass.as_ref().expect("NoSuchFieldError");
-            superclass.resolve_and_get_field(symref, class_loader)
-        })
+    /// Resolves a field by its signature (§5.4.3.2), returning the class or interface which
+    /// declares it. The field is looked up in this class, then in its superinterfaces, and then
+    /// in its superclass, recursively. Returns a `NoSuchFieldError` if no field is found.
+    pub fn resolve_field(self: &Rc<Self>, field_sig: &sig::Field)
+                         -> Result<Rc<Class>, ExceptionInfo> {
+        self.find_field(field_sig).ok_or_else(|| exceptions::create_no_such_field(field_sig))
+    }
+
+    /// Searches this class, its superinterfaces and its superclasses for the class or interface
+    /// declaring a field, as `resolve_field` does.
+    fn find_field(self: &Rc<Self>, field_sig: &sig::Field) -> Option<Rc<Class>> {
+        if self.fields.contains_key(field_sig) {
+            return Some(self.clone());
+        }
+        self.interfaces.iter().filter_map(|interface| interface.find_field(field_sig)).next()
+            .or_else(|| {
+                self.superclass.as_ref().and_then(|superclass| superclass.find_field(field_sig))
+            })
     }
 
-    /// Resolves a symbolic reference to a field and writes a new value to that field.
-    pub fn resolve_and_put_field(&self, symref: &symref::Field, new_value: Value,
-                                 class_loader: &mut ClassLoader) {
-        self.initialize(class_loader);
-        // TODO we're ignoring superinterfaces and not checking for static
-        let mut field_values_opt = self.field_values.borrow_mut();
-        let mut field_values = field_values_opt.as_mut().unwrap();
-        if field_values.contains_key(&symref.sig) {
-            field_values.insert(symref.sig.clone(), new_value);
+    /// Resolves a field by its signature, as `resolve_field` does, for access to the field of an
+    /// instance of this class. Returns an `IncompatibleClassChangeError` if the field is static.
+    pub fn resolve_instance_field(self: &Rc<Self>, field_sig: &sig::Field)
+                                  -> Result<(), ExceptionInfo> {
+        let class = try!(self.resolve_field(field_sig));
+        if class.is_static_field(field_sig) {
+            Err(exceptions::create_incompatible_class_change(format!(
+                "Expected non-static field {}.{}", class.symref.sig.name().replace('/', "."),
+                field_sig.name)))
         } else {
-            let superclass = self.superclass.as_ref().expect("NoSuchFieldError");
-            superclass.resolve_and_put_field(symref, new_value, class_loader);
+            Ok(())
         }
     }
 
+    /// Resolves a field by its signature, as `resolve_field` does, for access to a static field.
+    /// The class or interface declaring the field is initialized, and returned. Returns an
+    /// `IncompatibleClassChangeError` if the field is not static.
+    fn resolve_static_field(self: &Rc<Self>, field_sig: &sig::Field,
+                            class_loader: &mut dyn ClassLoader, context: &mut Context)
+                            -> Result<Rc<Class>, ExceptionInfo> {
+        let class = try!(self.resolve_field(field_sig));
+        if !class.is_static_field(field_sig) {
+            return Err(exceptions::create_incompatible_class_change(format!(
+                "Expected static field {}.{}", class.symref.sig.name().replace('/', "."),
+                field_sig.name)));
+        }
+        class.initialize(class_loader, context);
+        Ok(class)
+    }
+
+    /// Returns true if this class declares a static field with the given signature.
+    fn is_static_field(&self, field_sig: &sig::Field) -> bool {
+        self.fields.get(field_sig).map_or(false, |&access_flags| {
+            access_flags & access_flags::field_access_flags::ACC_STATIC != 0
+        })
+    }
+
+    /// Resolves a symbolic reference to a static field and reads a value from that field. The
+    /// class or interface declaring the field is initialized first.
+    pub fn resolve_and_get_field(self: &Rc<Self>, symref: &symref::Field,
+                                 class_loader: &mut dyn ClassLoader, context: &mut Context)
+                                 -> Result<Value, ExceptionInfo> {
+        let class = try!(self.resolve_static_field(&symref.sig, class_loader, context));
+        let field_values = class.field_values.borrow();
+        Ok(field_values.as_ref().unwrap()[&symref.sig].clone())
+    }
+
+    /// Resolves a symbolic reference to a static field and writes a new value to that field. The
+    /// class or interface declaring the field is initialized first.
+    pub fn resolve_and_put_field(self: &Rc<Self>, symref: &symref::Field, new_value: Value,
+                                 class_loader: &mut dyn ClassLoader, context: &mut Context)
+                                 -> Result<(), ExceptionInfo> {
+        let class = try!(self.resolve_static_field(&symref.sig, class_loader, context));
+        let mut field_values = class.field_values.borrow_mut();
+        field_values.as_mut().unwrap().insert(symref.sig.clone(), new_value);
+        Ok(())
+    }
+
     /// Returns a set of the signatures of the fields of an instance of this class.
     pub fn collect_instance_fields(&self) -> HashSet<sig::Field> {
         // TODO: fix semantics wrt private fields (which aren't inherited, but still exist in a
@@ -278,6 +538,17 @@ impl Class {
     }
 }
 
+/// Returns the only method among `methods` which is not `abstract`, if there is exactly one.
+fn unique_default_method<'a>(methods: &[&'a Method]) -> Option<&'a Method> {
+    let mut defaults = methods.iter().filter(|method| {
+        method.access_flags & access_flags::method_access_flags::ACC_ABSTRACT == 0
+    });
+    match (defaults.next(), defaults.next()) {
+        (Some(&method), None) => Some(method),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 /// A JVM representation of a method in a loaded class.
 pub struct Method {
@@ -290,30 +561,47 @@ pub struct Method {
 }
 
 impl Method {
-    pub fn new(symref: symref::Method, method_info: MethodInfo) -> Self {
+    /// Create a method from its `MethodInfo`. A `native` method, or any other method without a
+    /// `Code` attribute which is not `abstract`, is bound to its implementation in `natives`. A
+    /// constructor registered in `natives` is bound to it in place of its bytecode, since Java
+    /// cannot declare a constructor `native`. The names of the local variables of the method are
+    /// looked up in `constant_pool`.
+    pub fn new(symref: symref::Method, method_info: MethodInfo,
+               constant_pool: &RuntimeConstantPool, natives: &NativeRegistry) -> Self {
+        let native_constructor = if symref.sig.name == "<init>" {
+            natives.bind(&symref)
+        } else {
+            None
+        };
         let method_code = {
-            if method_info.access_flags & access_flags::method_access_flags::ACC_NATIVE != 0 {
-                match native::bind(&symref) {
-                    None => MethodCode::NativeNotFound,
-                    Some(native_method) => MethodCode::Native(native_method),
-                }
-            } else if method_info.access_flags & access_flags::method_access_flags::ACC_ABSTRACT != 0 {
+            if method_info.access_flags & access_flags::method_access_flags::ACC_ABSTRACT != 0 {
                 MethodCode::Abstract
+            } else if let Some(native_method) = native_constructor {
+                MethodCode::Native(native_method)
             } else {
                 method_info.attributes.into_iter().fold(None, |method_code, attribute_info| {
                     method_code.or(
                         match attribute_info {
-                            AttributeInfo::Code { max_locals, code, exception_table, .. } => {
+                            AttributeInfo::Code {
+                                max_stack, max_locals, code, exception_table, attributes
+                            } => {
                                 Some(MethodCode::Concrete {
+                                    max_stack: max_stack,
                                     max_locals: max_locals,
                                     code: code,
                                     exception_table: exception_table,
+                                    debug_info: FrameDebugInfo::new(&attributes, constant_pool),
                                 })
                             },
                             _ => None,
                         }
                     )
-                }).unwrap()
+                }).unwrap_or_else(|| {
+                    match natives.bind(&symref) {
+                        None => MethodCode::NativeNotFound,
+                        Some(native_method) => MethodCode::Native(native_method),
+                    }
+                })
             }
         };
         Method {
@@ -323,50 +611,85 @@ impl Method {
         }
     }
 
-    pub fn invoke(&self, class: &Class, class_loader: &mut ClassLoader,
-                  args: Vec<Value>) -> Option<Value> {
-        println!("Starting to invoke {:?}", self);
+    /// Returns the debugging information about the local variables of the method, if it has code
+    /// whose class file records them.
+    pub fn debug_info(&self) -> Option<&FrameDebugInfo> {
+        match self.code {
+            MethodCode::Concrete { ref debug_info, .. } => debug_info.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn invoke(&self, class: &Class, class_loader: &mut dyn ClassLoader, context: &mut Context,
+                  args: Vec<Value>) -> MethodResult {
+        if !context.enter_call() {
+            return Err(stack_overflow_error(class_loader, context));
+        }
+        debug!("Starting to invoke {:?}", self);
+        let start = context.profiler().map(|_| Instant::now());
         let result = match self.code {
-            MethodCode::Concrete { max_locals, ref code, .. } => {
-                let mut locals = Vec::with_capacity(max_locals as usize);
-                for value in args {
-                    let realign = match value {
-                        Value::Long(_) | Value::Double(_) => true,
-                        _ => false,
-                    };
-                    locals.push(Some(value));
-                    if realign {
-                        locals.push(None);
-                    }
-                }
-                while locals.len() < max_locals as usize {
-                    locals.push(None)
-                }
-                let frame = Frame::new(class, code, locals);
-                frame.run(class_loader)
+            MethodCode::Concrete {
+                max_stack, max_locals, ref code, ref exception_table, ..
+            } => {
+                let locals = frame::local_variables(max_locals, args);
+                let mut frame = Frame::new(class, code, exception_table, max_stack, locals);
+                frame.set_method(self);
+                frame.run(class_loader, context)
             },
-            MethodCode::Abstract => panic!("AbstractMethodError"),
-            MethodCode::Native(ref native_method) => native_method.invoke(args),
-            MethodCode::NativeNotFound => panic!("UnsatisfiedLinkError"),
+            MethodCode::Abstract =>
+                Err(exceptions::create_abstract_method(&self.symref)
+                        .instantiate(class_loader, context)),
+            MethodCode::Native(ref native_method) =>
+                native_method.invoke(class_loader, context, args).map_err(|exception| {
+                    exception.instantiate(class_loader, context)
+                }),
+            MethodCode::NativeNotFound =>
+                Err(exceptions::create_unsatisfied_link(&self.symref)
+                        .instantiate(class_loader, context)),
         };
-        println!("Finished invoking {:?}", self);
+        debug!("Finished invoking {:?}", self);
+        if let (Some(start), Some(profiler)) = (start, context.profiler()) {
+            profiler.borrow_mut().record(&self.symref, start.elapsed());
+        }
+        context.exit_call();
         result
     }
 }
 
+/// The number of invocations beyond the maximum call depth which are allowed while constructing a
+/// `StackOverflowError`, so that its constructors can run.
+const STACK_OVERFLOW_RESERVE: usize = 16;
+
+/// Create a `StackOverflowError` to throw when invoking a method would exceed the maximum call
+/// depth.
+fn stack_overflow_error(class_loader: &mut dyn ClassLoader, context: &mut Context) -> Exception {
+    let max_call_depth = context.max_call_depth();
+    context.set_max_call_depth(max_call_depth + STACK_OVERFLOW_RESERVE);
+    let error = ExceptionInfo::new("java/lang/StackOverflowError", None)
+        .instantiate(class_loader, context);
+    context.set_max_call_depth(max_call_depth);
+    error
+}
+
 /// A representation of the code associated with a method, or more generally, the action that
 /// should be taken when a method is invoked.
 #[derive(Debug)]
 enum MethodCode {
     /// The code for a non-`abstract`, non-`native` Java method. Such contains executable bytecode
     /// which may be used to create a new JVM stack frame.
-    Concrete { max_locals: u16, code: Vec<u8>, exception_table: Vec<ExceptionTableEntry>, },
-    /// to invoke an `abstract` method fails with `AbstractMethodError`.
+    Concrete {
+        max_stack: u16,
+        max_locals: u16,
+        code: Vec<u8>,
+        exception_table: Vec<ExceptionTableEntry>,
+        debug_info: Option<FrameDebugInfo>,
+    },
+    /// An `abstract` method, which has no code. Invoking it throws `AbstractMethodError`.
     Abstract,
     /// The code for a `native` Java method for which the class loader has located a corresponding
     /// Rust function pointer.
     Native(native::NativeMethod),
     /// The code for a `native` Java method for which the class loader failed to locate a Rust
-    /// function pointer.
+    /// function pointer. Invoking it throws `UnsatisfiedLinkError`.
     NativeNotFound,
 }
@@ -1,21 +1,31 @@
 use std::{error, fmt};
-use std::fs::File;
-use std::io::{self, Read};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
-use nom;
-
-use model::class_file::ClassFile;
+use intern::Interner;
+use model::class_file::{AttributeInfo, ClassFile, validate};
+use model::class_file::class_access_flags;
 use parser::class_file;
+use util::modified_utf8::ModifiedUtf8Error;
 use vm::{sig, symref};
 use vm::class;
-use vm::constant_pool::{RuntimeConstantPool, RuntimeConstantPoolEntry};
+use vm::class_path::ClassPath;
+use vm::constant_pool::{self, RuntimeConstantPool, RuntimeConstantPoolEntry};
+use vm::native::NativeRegistry;
+
+/// The oldest class file major version which can be loaded, that of JDK 1.0.2.
+pub const MIN_MAJOR_VERSION: u16 = 45;
+/// The newest class file major version which can be loaded by default, that of Java SE 11.
+pub const DEFAULT_MAX_MAJOR_VERSION: u16 = 55;
+/// The first class file major version in which a minor version of `0xffff` marks a class file
+/// which depends on the preview features of Java SE 12 and later (§4.1).
+const FIRST_PREVIEW_MAJOR_VERSION: u16 = 56;
 
 #[derive(Debug)]
 pub enum Error {
-    /// If no "purported representation" of the class is found. §5.3.1.
-    ClassNotFound { name: String, error: io::Error },
+    /// If no "purported representation" of the class is found on the class path. §5.3.1.
+    ClassNotFound { name: String },
     /// The "purported representation" does not follow the class file format. §5.3.5.
     ClassFormat,
     /// The "purported representation" is not of a supported version. §5.3.5.
@@ -29,13 +39,17 @@ pub enum Error {
     IncompatibleClassChange(String),
     /// The class is its own superclass or superinterface. §5.3.5.
     ClassCircularity,
+    /// The class, or one of its fields or methods, has an illegal combination of access flags.
+    /// §4.1, §4.5, §4.6.
+    AccessFlagViolation(String),
+    /// The class being defined has already been loaded by this class loader. §5.3.5.
+    DuplicateClassDefinition { name: String },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::ClassNotFound { ref name, ref error } => write!(f, "ClassNotFound: {}. {:?}",
-                                                                   name, error),
+            Error::ClassNotFound { ref name } => write!(f, "ClassNotFound: {}", name),
             Error::ClassFormat => write!(f, "ClassFormat"),
             Error::UnsupportedVersion { major, minor } =>
                 write!(f, "UnsupportedVersion {}.{}", major, minor),
@@ -45,14 +59,33 @@ impl fmt::Display for Error {
             Error::IncompatibleClassChange(ref class) =>
                 write!(f, "IncompatibleClassChange with {}", class),
             Error::ClassCircularity => write!(f, "ClassCircularity"),
+            Error::AccessFlagViolation(ref problem) =>
+                write!(f, "AccessFlagViolation: {}", problem),
+            Error::DuplicateClassDefinition { ref name } =>
+                write!(f, "DuplicateClassDefinition: {}", name),
         }
     }
 }
 
+/// A class file whose constant pool contains a string which is not valid modified UTF-8 does not
+/// follow the class file format.
+impl From<ModifiedUtf8Error> for Error {
+    fn from(_: ModifiedUtf8Error) -> Self {
+        Error::ClassFormat
+    }
+}
+
+/// Neither does a class file whose constant pool cannot be turned into a runtime constant pool.
+impl From<constant_pool::Error> for Error {
+    fn from(_: constant_pool::Error) -> Self {
+        Error::ClassFormat
+    }
+}
+
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
-            Error::ClassNotFound { .. } => "class representation not found due to I/O error",
+            Error::ClassNotFound { .. } => "class representation not found on the class path",
             Error::ClassFormat => "invalid class format",
             Error::UnsupportedVersion { .. } => "unsupported version",
             Error::NoClassDefFound { .. } => "class representation is not of the requested class",
@@ -61,43 +94,239 @@ impl error::Error for Error {
             Error::IncompatibleClassChange(_) =>
                 "declared superclass (superinterface) is actually an interface (class)",
             Error::ClassCircularity => "the class is its own superclass or superinterface",
+            Error::AccessFlagViolation(_) => "illegal combination of access flags",
+            Error::DuplicateClassDefinition { .. } => "the class has already been loaded",
         }
     }
 
     fn cause(&self) -> Option<&error::Error> {
-        match *self {
-            Error::ClassNotFound { ref error, .. } => Some(error),
-            _ => None,
-        }
+        None
     }
 }
 
-#[derive(Debug)]
-/// A class loader suitable for loading classes into the JVM.
-pub struct ClassLoader {
+/// A strategy for loading classes into the JVM. Every class and interface is loaded by some class
+/// loader, which is used in turn to load the classes it refers to.
+pub trait ClassLoader {
+    /// Attempts to create, load, and prepare the specified class, or returns the class if it has
+    /// already been loaded by this class loader.
+    fn load_class(&mut self, sig: &sig::Class) -> Result<Rc<class::Class>, Error>;
+
+    /// Load a class based on a symbolic reference.
+    fn resolve_class(&mut self, symref: &symref::Class) -> Result<Rc<class::Class>, Error> {
+        // TODO check access modifiers
+        self.load_class(&symref.sig)
+    }
+
+    /// Called once a class loaded by this class loader has been initialized.
+    fn class_initialized(&mut self, _class: &class::Class) {}
+}
+
+/// An observer of the classes loaded by a class loader, notified as each class is loaded, linked
+/// and initialized, or fails to load. Classes are named by their binary names.
+pub trait ClassLoaderListener {
+    /// Called when the class file of a class has been found and parsed, before its superclass
+    /// and superinterfaces are loaded.
+    fn on_class_loaded(&mut self, name: &str);
+
+    /// Called when a class has been linked to its superclass and superinterfaces, and is ready to
+    /// be used.
+    fn on_class_linked(&mut self, name: &str);
+
+    /// Called when a class has been initialized, after its superclass.
+    fn on_class_initialized(&mut self, name: &str);
+
+    /// Called when a class fails to load. A class which fails to load because its superclass
+    /// does is reported after the superclass.
+    fn on_load_error(&mut self, name: &str, err: &Error);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An event observed by a `TracingClassLoaderListener`.
+pub enum Event {
+    Loaded,
+    Linked,
+    Initialized,
+    /// The class failed to load, with the given error message.
+    LoadError(String),
+}
+
+#[derive(Debug, Clone, Default)]
+/// A `ClassLoaderListener` which records every event along with the name of its class. Clones of
+/// the listener share the same record, so one clone can be given to a class loader while another
+/// is kept to inspect the events.
+pub struct TracingClassLoaderListener {
+    events: Rc<RefCell<Vec<(String, Event)>>>,
+}
+
+impl TracingClassLoaderListener {
+    pub fn new() -> Self {
+        TracingClassLoaderListener::default()
+    }
+
+    /// The events recorded so far, in the order in which they happened.
+    pub fn events(&self) -> Vec<(String, Event)> {
+        self.events.borrow().clone()
+    }
+
+    fn record(&mut self, name: &str, event: Event) {
+        self.events.borrow_mut().push((String::from(name), event));
+    }
+}
+
+impl ClassLoaderListener for TracingClassLoaderListener {
+    fn on_class_loaded(&mut self, name: &str) {
+        self.record(name, Event::Loaded);
+    }
+
+    fn on_class_linked(&mut self, name: &str) {
+        self.record(name, Event::Linked);
+    }
+
+    fn on_class_initialized(&mut self, name: &str) {
+        self.record(name, Event::Initialized);
+    }
+
+    fn on_load_error(&mut self, name: &str, err: &Error) {
+        self.record(name, Event::LoadError(err.to_string()));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What `BootstrapClassLoader::define_class` does when asked to define a class which has already
+/// been loaded.
+pub enum RedefinitionPolicy {
+    /// Fail with `Error::DuplicateClassDefinition`, as the JVM does (§5.3.5).
+    Reject,
+    /// Return the class which was already loaded, ignoring the new class file.
+    UseExisting,
+}
+
+/// The bootstrap class loader, which loads class files found on its class path.
+pub struct BootstrapClassLoader {
+    /// The locations searched for the class files of classes loaded by this class loader.
+    class_path: ClassPath,
     /// The classes that have already been resolved by this class loader.
     classes: HashMap<sig::Class, Rc<class::Class>>,
     /// The signatures of classes that have not yet been resolved by this class loader.
     pending: HashSet<sig::Class>,
+    /// The strings of the constant pools of the classes loaded by this class loader.
+    interner: Interner,
+    /// The implementations of the native methods of the classes loaded by this class loader.
+    natives: NativeRegistry,
+    /// The newest class file major version which this class loader accepts.
+    max_major_version: u16,
+    /// The listener notified of the classes loaded by this class loader, if there is one.
+    listener: Option<Box<dyn ClassLoaderListener>>,
+    /// What `define_class` does with a class which has already been loaded.
+    redefinition_policy: RedefinitionPolicy,
+}
+
+impl fmt::Debug for BootstrapClassLoader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BootstrapClassLoader")
+            .field("class_path", &self.class_path)
+            .field("classes", &self.classes)
+            .field("pending", &self.pending)
+            .field("interner", &self.interner)
+            .field("natives", &self.natives)
+            .field("max_major_version", &self.max_major_version)
+            .field("has_listener", &self.listener.is_some())
+            .field("redefinition_policy", &self.redefinition_policy)
+            .finish()
+    }
 }
 
-impl ClassLoader {
-    pub fn new() -> ClassLoader {
-        ClassLoader {
+impl BootstrapClassLoader {
+    pub fn new(class_path: ClassPath) -> BootstrapClassLoader {
+        Self::with_natives(class_path, NativeRegistry::new())
+    }
+
+    /// Create a class loader which binds native methods to their implementations in `natives`.
+    pub fn with_natives(class_path: ClassPath, natives: NativeRegistry) -> BootstrapClassLoader {
+        BootstrapClassLoader {
+            class_path: class_path,
             classes: HashMap::new(),
             pending: HashSet::new(),
+            interner: Interner::new(),
+            natives: natives,
+            max_major_version: DEFAULT_MAX_MAJOR_VERSION,
+            listener: None,
+            redefinition_policy: RedefinitionPolicy::Reject,
         }
     }
 
-    /// Given a class name, read the bytes from the corresponding class file.
-    fn find_class_bytes(&mut self, name: &str) -> Result<Vec<u8>, io::Error> {
-        // isn't this so convenient!
-        // FIXME: Set up classpath for find_class_bytes
-        let file_name = String::from("rt/") + name + ".class";
-        File::open(file_name).and_then(|mut file| {
-            let mut res = vec![];
-            file.read_to_end(&mut res).map(|_| res)
-        })
+    /// Handle a class passed to `define_class` which has already been loaded according to
+    /// `policy`, rather than by failing with `Error::DuplicateClassDefinition`.
+    pub fn with_redefinition_policy(mut self, policy: RedefinitionPolicy) -> Self {
+        self.redefinition_policy = policy;
+        self
+    }
+
+    /// Notify `listener` of each class loaded, linked or initialized by this class loader, and
+    /// of each class which fails to load.
+    pub fn with_listener(mut self, listener: Box<dyn ClassLoaderListener>) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Accept class files with major versions up to and including `major`, rather than
+    /// `DEFAULT_MAX_MAJOR_VERSION`.
+    pub fn with_max_version(mut self, major: u16) -> Self {
+        self.max_major_version = major;
+        self
+    }
+
+    /// Checks that a class file of the given version can be loaded. Class files with a major
+    /// version of 56 or above must have a minor version of either 0, or 0xffff if they use
+    /// preview features.
+    fn check_version(&self, major: u16, minor: u16) -> Result<(), Error> {
+        let supported_major = major >= MIN_MAJOR_VERSION && major <= self.max_major_version;
+        let supported_minor = major < FIRST_PREVIEW_MAJOR_VERSION || minor == 0 || minor == 0xffff;
+        if supported_major && supported_minor {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedVersion { major: major, minor: minor })
+        }
+    }
+
+    /// Checks that the access flags of a class, and of its fields and methods, are consistent with
+    /// one another. That a class, `public` or not, has the name which was requested is checked
+    /// once the class is derived.
+    fn check_access_flags(class_file: &ClassFile) -> Result<(), Error> {
+        let violation = |problem: String| Err(Error::AccessFlagViolation(problem));
+        let name = class_file.class_name_at(class_file.this_class).unwrap_or_default();
+        let flags = class_file.access_flags;
+        let is_interface = flags & class_access_flags::ACC_INTERFACE != 0;
+        if flags & class_access_flags::ACC_ABSTRACT != 0
+                && flags & class_access_flags::ACC_FINAL != 0 {
+            return violation(format!("class {} is both abstract and final", name));
+        }
+        if is_interface && flags & class_access_flags::ACC_ABSTRACT == 0 {
+            return violation(format!("interface {} is not abstract", name));
+        }
+        if !is_interface {
+            return Ok(());
+        }
+
+        for field in &class_file.fields {
+            let is_constant = field.attributes.iter().any(|attr| match *attr {
+                AttributeInfo::ConstantValue { .. } => true,
+                _ => false,
+            });
+            if field.is_static() && !field.is_final() && !is_constant {
+                let field_name = class_file.utf8_at(field.name_index).unwrap_or_default();
+                return violation(format!("static field {}.{} of an interface is not final",
+                                         name, field_name));
+            }
+        }
+        for method in &class_file.methods {
+            if method.is_native() || method.is_synchronized() {
+                let method_name = class_file.utf8_at(method.name_index).unwrap_or_default();
+                return violation(format!("method {}.{} of an interface is native or synchronized",
+                                         name, method_name));
+            }
+        }
+        Ok(())
     }
 
     /// Get the symbolic reference to a class from a runtime constant pool index.
@@ -109,12 +338,6 @@ impl ClassLoader {
         }
     }
 
-    /// Load a class based on a symbolic reference.
-    pub fn resolve_class(&mut self, symref: &symref::Class) -> Result<Rc<class::Class>, Error> {
-        // TODO check access modifiers
-        self.load_class(&symref.sig)
-    }
-
     /// Derives the super class (if it exists) of the specified class.
     fn derive_super_class(&mut self, rcp: &RuntimeConstantPool, class_file: &ClassFile)
             -> Result<Option<Rc<class::Class>>, Error> {
@@ -130,39 +353,35 @@ impl ClassLoader {
     fn derive_class(&mut self, original_name: &str, sig: &sig::Class, class_bytes: &[u8])
                     -> Result<Rc<class::Class>, Error> {
         // TODO we discard the parse errors, but it's so hard to fix that...
-        let parsed_class = try!(
-            match class_file::parse_class_file(&class_bytes) {
-                nom::IResult::Done(_, parsed_class) => Ok(parsed_class),
-                nom::IResult::Incomplete(_) => Err(Error::ClassFormat),
-                nom::IResult::Error(_) => Err(Error::ClassFormat),
-            }
-        );
-        try!(
-            if parsed_class.major_version != 50 || parsed_class.minor_version != 0 {
-                Err(Error::UnsupportedVersion {
-                    major: parsed_class.major_version,
-                    minor: parsed_class.minor_version,
-                })
-            } else {
-                Ok(())
-            }
-        );
-        let rcp = RuntimeConstantPool::new(&parsed_class.constant_pool);
+        let parsed_class = try!(class_file::parse_class_file(&class_bytes)
+                                .map_err(|_| Error::ClassFormat));
+        try!(validate(&parsed_class).map_err(|_| Error::ClassFormat));
+        try!(self.check_version(parsed_class.major_version, parsed_class.minor_version));
+        try!(Self::check_access_flags(&parsed_class));
+        let rcp = try!(RuntimeConstantPool::new(&parsed_class.constant_pool, &mut self.interner));
         let sig_matches = {
             let this_symref = try!(Self::get_class_ref(&rcp, parsed_class.this_class));
             *sig == this_symref.sig
         };
         if sig_matches {
+            if let Some(ref mut listener) = self.listener {
+                listener.on_class_loaded(original_name);
+            }
             let super_class = try!(self.derive_super_class(&rcp, &parsed_class));
             // TODO: Check that the entry is actually an interface
+            let mut interfaces = vec![];
             for interface in &parsed_class.interfaces {
                 let iface_symref = try!(Self::get_class_ref(&rcp, *interface));
-                try!(self.resolve_class(&iface_symref));
+                interfaces.push(try!(self.resolve_class(&iface_symref)));
             }
             let symref = symref::Class { sig: sig.clone() };
-            let class = class::Class::new(symref, super_class, rcp, parsed_class);
+            let class = class::Class::new(symref, super_class, interfaces, rcp, parsed_class,
+                                         &self.natives);
             let rc = Rc::new(class);
             self.classes.insert(sig.clone(), rc.clone());
+            if let Some(ref mut listener) = self.listener {
+                listener.on_class_linked(original_name);
+            }
             Ok(rc)
         } else {
             Err(Error::NoClassDefFound { name: String::from(original_name) })
@@ -184,33 +403,14 @@ impl ClassLoader {
         self.derive_class(name, sig, class_bytes)
     }
 
-    /// Attempts to create, load, and prepare the specified class using the bootstrap class loader
-    /// implementation. The bootstrap class loader searches the current directory for a class file
-    /// with the correct fully-qualified name. If none is found, the bootstrap class loader then
-    /// attempts to load the class from the standard library JAR.
-    ///
-    /// This implementation lazily resolves symbolic references, so no resolution of references
-    /// within the loaded class is performed by this function.
-    ///
-    /// This implementation does not attempt to perform bytecode verification; we assume that any
-    /// class files we attempt to load are valid.
-    pub fn load_class(&mut self, sig: &sig::Class) -> Result<Rc<class::Class>, Error> {
-        if self.pending.contains(&sig) {
-            // we're already resolving this name
-            return Err(Error::ClassCircularity)
-        } else if let Some(class) = self.classes.get(&sig) {
-            // the class is already resolved
-            return Ok(class.clone())
-        }
-
-        // this can't just be an else block thanks to the borrow checker
-        self.pending.insert(sig.clone());
-        let res = match *sig {
+    /// Creates the specified class, which has not been loaded before, either from its class file
+    /// on the class path or, for an array class, from its component type.
+    fn load_new_class(&mut self, sig: &sig::Class) -> Result<Rc<class::Class>, Error> {
+        match *sig {
             sig::Class::Scalar(ref name) => {
-                let class_bytes = try!(self.find_class_bytes(name)
-                                       .map_err(|e| Error::ClassNotFound {
+                let class_bytes = try!(self.class_path.find_class(name)
+                                       .ok_or_else(|| Error::ClassNotFound {
                                            name: name.clone(),
-                                           error: e,
                                        }));
                 // TODO: Catch errors in recursive loading and wrap them in a NoClassDefFoundError
                 // as specified in §5.3
@@ -232,15 +432,415 @@ impl ClassLoader {
                 let object_name = String::from("java/lang/Object");
                 let object_sig = sig::Class::Scalar(object_name);
                 let object_class = try!(self.load_class(&object_sig));
-                let class = class::Class::new_array(object_class, component_access_flags,
+                // every array type implements these interfaces (§4.10.1.2)
+                let interfaces = vec![
+                    try!(self.load_class(&sig::Class::new("java/lang/Cloneable"))),
+                    try!(self.load_class(&sig::Class::new("java/io/Serializable"))),
+                ];
+                let class = class::Class::new_array(object_class, interfaces,
+                                                 component_access_flags,
                                                  *component_type.clone());
                 let rc = Rc::new(class);
                 self.classes.insert(sig.clone(), rc.clone());
+                if let Some(ref mut listener) = self.listener {
+                    let name = sig.name();
+                    listener.on_class_loaded(&name);
+                    listener.on_class_linked(&name);
+                }
                 Ok(rc)
             },
-        };
-        self.pending.remove(&sig);
-        res
+        }
+    }
+
+    /// Defines the class with the given binary name from the bytes of its class file, rather than
+    /// searching the class path for it. This allows classes generated at runtime to be loaded.
+    /// The class file must define the named class, or this fails with `NoClassDefFound`. The
+    /// superclass and superinterfaces of the class are loaded as usual.
+    pub fn define_class(&mut self, name: &str, class_bytes: &[u8])
+                        -> Result<Rc<class::Class>, Error> {
+        let sig = sig::Class::Scalar(String::from(name));
+        if let Some(class) = self.classes.get(&sig) {
+            return match self.redefinition_policy {
+                RedefinitionPolicy::Reject =>
+                    Err(Error::DuplicateClassDefinition { name: String::from(name) }),
+                RedefinitionPolicy::UseExisting => Ok(class.clone()),
+            };
+        }
+        if self.pending.contains(&sig) {
+            return Err(Error::ClassCircularity);
+        }
+
+        let guard = LoadGuard::new(self, &sig);
+        let result = guard.class_loader.load_class_bytes(name, &sig, class_bytes);
+        if let Err(ref err) = result {
+            if let Some(ref mut listener) = guard.class_loader.listener {
+                listener.on_load_error(name, err);
+            }
+        }
+        result
+    }
+}
+
+impl ClassLoader for BootstrapClassLoader {
+    /// Attempts to create, load, and prepare the specified class using the bootstrap class loader
+    /// implementation. The bootstrap class loader searches each entry of its class path in turn for
+    /// a class file with the correct fully-qualified name.
+    ///
+    /// This implementation lazily resolves symbolic references, so no resolution of references
+    /// within the loaded class is performed by this function.
+    ///
+    /// This implementation does not attempt to perform bytecode verification; we assume that any
+    /// class files we attempt to load are valid.
+    fn load_class(&mut self, sig: &sig::Class) -> Result<Rc<class::Class>, Error> {
+        if self.pending.contains(&sig) {
+            // we're already resolving this name
+            return Err(Error::ClassCircularity)
+        } else if let Some(class) = self.classes.get(&sig) {
+            // the class is already resolved
+            return Ok(class.clone())
+        }
+
+        let guard = LoadGuard::new(self, sig);
+        let result = guard.class_loader.load_new_class(sig);
+        if let Err(ref err) = result {
+            if let Some(ref mut listener) = guard.class_loader.listener {
+                listener.on_load_error(&sig.name(), err);
+            }
+        }
+        result
+    }
+
+    fn class_initialized(&mut self, class: &class::Class) {
+        if let Some(ref mut listener) = self.listener {
+            listener.on_class_initialized(&class.symref.sig.name());
+        }
     }
 }
 
+/// Marks a class as pending in its class loader for as long as the guard is alive, so that an
+/// attempt to load the class again while it is being loaded fails with `ClassCircularity`. The
+/// class is no longer pending once the guard is dropped, even if loading it failed.
+struct LoadGuard<'a> {
+    class_loader: &'a mut BootstrapClassLoader,
+    sig: sig::Class,
+}
+
+impl<'a> LoadGuard<'a> {
+    fn new(class_loader: &'a mut BootstrapClassLoader, sig: &sig::Class) -> Self {
+        class_loader.pending.insert(sig.clone());
+        LoadGuard { class_loader: class_loader, sig: sig.clone() }
+    }
+}
+
+impl<'a> Drop for LoadGuard<'a> {
+    fn drop(&mut self) {
+        self.class_loader.pending.remove(&self.sig);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::{env, fs, process};
+    use std::num::Wrapping;
+    use std::path::PathBuf;
+
+    use builder::class_file::ClassFileBuilder;
+    use model::class_file::{field_access_flags, method_access_flags};
+    use model::class_file::constant_pool::ConstantPoolInfo;
+    use vm::bytecode::opcode;
+    use vm::class_path::ClassPathEntry;
+    use vm::context::Context;
+    use vm::value::Value;
+    use writer::class_file::write_class_file;
+
+    /// A class loader which only knows about classes which have already been created.
+    struct TestClassLoader {
+        classes: HashMap<sig::Class, Rc<class::Class>>,
+        interner: Interner,
+    }
+
+    impl TestClassLoader {
+        fn new() -> Self {
+            TestClassLoader { classes: HashMap::new(), interner: Interner::new() }
+        }
+
+        /// Create a class from the bytes of its class file and add it to this class loader.
+        fn add_class(&mut self, name: &str, superclass: Option<Rc<class::Class>>,
+                     class_bytes: &[u8]) -> Rc<class::Class> {
+            let class_file = class_file::parse_class_file(class_bytes).unwrap();
+            let constant_pool =
+                RuntimeConstantPool::new(&class_file.constant_pool, &mut self.interner).unwrap();
+            let symref = symref::Class { sig: sig::Class::new(name) };
+            let class = class::Class::new(symref, superclass, vec![], constant_pool, class_file,
+                                         &NativeRegistry::new());
+            let rc = Rc::new(class);
+            self.classes.insert(sig::Class::new(name), rc.clone());
+            rc
+        }
+    }
+
+    impl ClassLoader for TestClassLoader {
+        fn load_class(&mut self, sig: &sig::Class) -> Result<Rc<class::Class>, Error> {
+            self.classes.get(sig).cloned().ok_or_else(|| {
+                Error::ClassNotFound { name: format!("{:?}", sig) }
+            })
+        }
+    }
+
+    #[test]
+    fn test_custom_class_loader() {
+        let mut class_loader = TestClassLoader::new();
+        let object = class_loader.add_class("java/lang/Object", None,
+                                            include_bytes!("../../data/vm/java/lang/Object.class"));
+        let invoke = class_loader.add_class("Invoke", Some(object),
+                                            include_bytes!("../../data/vm/Invoke.class"));
+        class_loader.add_class("InvokeSub", Some(invoke.clone()),
+                               include_bytes!("../../data/vm/InvokeSub.class"));
+
+        let method = invoke.find_method(&sig::Method::new("callVirtual", "()I")).unwrap();
+        match method.invoke(&invoke, &mut class_loader, &mut Context::new(), vec![]) {
+            Ok(Some(Value::Int(Wrapping(12)))) => (),
+            result => panic!("expected 12, but was {:?}", result),
+        }
+        assert!(class_loader.load_class(&sig::Class::new("java/lang/String")).is_err());
+    }
+
+    /// Load `java/lang/Object` from a copy of its class file with the given version.
+    fn load_object_version(class_loader: &mut BootstrapClassLoader, major: u16, minor: u16)
+                           -> Result<Rc<class::Class>, Error> {
+        let mut class_bytes = include_bytes!("../../data/vm/java/lang/Object.class").to_vec();
+        class_bytes[4] = (minor >> 8) as u8;
+        class_bytes[5] = minor as u8;
+        class_bytes[6] = (major >> 8) as u8;
+        class_bytes[7] = major as u8;
+        class_loader.define_class("java/lang/Object", &class_bytes)
+    }
+
+    fn assert_unsupported(result: Result<Rc<class::Class>, Error>, major: u16, minor: u16) {
+        match result {
+            Err(Error::UnsupportedVersion { major: m, minor: n }) if m == major && n == minor => (),
+            result => panic!("expected UnsupportedVersion {}.{}, but was {:?}",
+                             major, minor, result),
+        }
+    }
+
+    #[test]
+    fn test_version_bounds() {
+        let new_class_loader = || BootstrapClassLoader::new(ClassPath::new(vec![]));
+        assert!(load_object_version(&mut new_class_loader(), 45, 3).is_ok());
+        assert!(load_object_version(&mut new_class_loader(), 55, 0).is_ok());
+        assert_unsupported(load_object_version(&mut new_class_loader(), 44, 0), 44, 0);
+        assert_unsupported(load_object_version(&mut new_class_loader(), 56, 0), 56, 0);
+        assert_unsupported(load_object_version(&mut new_class_loader(), 0xcafe, 0), 0xcafe, 0);
+    }
+
+    #[test]
+    fn test_with_max_version() {
+        let new_class_loader = || {
+            BootstrapClassLoader::new(ClassPath::new(vec![])).with_max_version(61)
+        };
+        assert!(load_object_version(&mut new_class_loader(), 61, 0).is_ok());
+        assert!(load_object_version(&mut new_class_loader(), 58, 0xffff).is_ok());
+        assert_unsupported(load_object_version(&mut new_class_loader(), 58, 1), 58, 1);
+        assert_unsupported(load_object_version(&mut new_class_loader(), 62, 0), 62, 0);
+    }
+
+    /// Create a temporary directory holding an empty class with the given name and superclass for
+    /// each pair in `classes`, and a class loader whose class path is that directory.
+    fn class_loader_with(test_name: &str, classes: &[(&str, &str)])
+                         -> (BootstrapClassLoader, PathBuf) {
+        let mut directory = env::temp_dir();
+        directory.push(format!("rust-jvm-{}-{}", test_name, process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for &(name, super_name) in classes {
+            let class = ClassFileBuilder::new(name, super_name).build();
+            fs::write(directory.join(format!("{}.class", name)), write_class_file(&class))
+                .unwrap();
+        }
+        let class_path = ClassPath::new(vec![ClassPathEntry::Directory(directory.clone())]);
+        (BootstrapClassLoader::new(class_path), directory)
+    }
+
+    fn assert_circularity(result: Result<Rc<class::Class>, Error>) {
+        match result {
+            Err(Error::ClassCircularity) => (),
+            result => panic!("expected ClassCircularity, but was {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_class_circularity() {
+        let (mut class_loader, directory) =
+            class_loader_with("class-circularity", &[("A", "B"), ("B", "A")]);
+        assert_circularity(class_loader.load_class(&sig::Class::new("A")));
+        assert!(class_loader.pending.is_empty());
+        assert_circularity(class_loader.load_class(&sig::Class::new("B")));
+        fs::remove_dir_all(directory).unwrap();
+
+        let (mut class_loader, directory) =
+            class_loader_with("long-class-circularity", &[("A", "B"), ("B", "C"), ("C", "A")]);
+        assert_circularity(class_loader.load_class(&sig::Class::new("B")));
+        assert!(class_loader.pending.is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_pending_cleared_on_error() {
+        let (mut class_loader, directory) = class_loader_with("pending-cleared", &[]);
+        for _ in 0..2 {
+            match class_loader.load_class(&sig::Class::new("Missing")) {
+                Err(Error::ClassNotFound { ref name }) if name == "Missing" => (),
+                result => panic!("expected ClassNotFound, but was {:?}", result),
+            }
+        }
+        assert!(class_loader.pending.is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_listener() {
+        let (class_loader, directory) =
+            class_loader_with("listener", &[("A", "B"), ("B", "java/lang/Object"), ("C", "D")]);
+        let listener = TracingClassLoaderListener::new();
+        let mut class_loader = class_loader.with_listener(Box::new(listener.clone()));
+        class_loader.define_class("java/lang/Object",
+                                           include_bytes!("../../data/vm/java/lang/Object.class"))
+            .unwrap();
+        let class = class_loader.load_class(&sig::Class::new("A")).unwrap();
+        class.initialize(&mut class_loader, &mut Context::new());
+        assert!(class_loader.load_class(&sig::Class::new("C")).is_err());
+        fs::remove_dir_all(directory).unwrap();
+
+        let event = |name: &str, event: Event| (String::from(name), event);
+        assert_eq!(listener.events(), vec![
+            event("java/lang/Object", Event::Loaded),
+            event("java/lang/Object", Event::Linked),
+            event("A", Event::Loaded),
+            event("B", Event::Loaded),
+            event("B", Event::Linked),
+            event("A", Event::Linked),
+            event("java/lang/Object", Event::Initialized),
+            event("B", Event::Initialized),
+            event("A", Event::Initialized),
+            event("C", Event::Loaded),
+            event("D", Event::LoadError(String::from("ClassNotFound: D"))),
+            event("C", Event::LoadError(String::from("ClassNotFound: D"))),
+        ]);
+    }
+
+    /// Build a class `Generated` with a static method `answer` returning 42.
+    fn generated_class() -> Vec<u8> {
+        let class = ClassFileBuilder::new("Generated", "java/lang/Object")
+            .add_method("answer", "()I", method_access_flags::ACC_STATIC,
+                        vec![opcode::BIPUSH, 42, opcode::IRETURN])
+            .build();
+        write_class_file(&class)
+    }
+
+    fn class_loader_with_object() -> BootstrapClassLoader {
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        class_loader.define_class("java/lang/Object",
+                                  include_bytes!("../../data/vm/java/lang/Object.class"))
+            .unwrap();
+        class_loader
+    }
+
+    #[test]
+    fn test_define_class() {
+        let mut class_loader = class_loader_with_object();
+        let class = class_loader.define_class("Generated", &generated_class()).unwrap();
+        let method = class.find_method(&sig::Method::new("answer", "()I")).unwrap();
+        match method.invoke(&class, &mut class_loader, &mut Context::new(), vec![]) {
+            Ok(Some(Value::Int(Wrapping(42)))) => (),
+            result => panic!("expected 42, but was {:?}", result),
+        }
+        let loaded = class_loader.load_class(&sig::Class::new("Generated")).unwrap();
+        assert!(Rc::ptr_eq(&class, &loaded));
+
+        match class_loader.define_class("Generated", &generated_class()) {
+            Err(Error::DuplicateClassDefinition { ref name }) if name == "Generated" => (),
+            result => panic!("expected DuplicateClassDefinition, but was {:?}", result),
+        }
+        match class_loader.define_class("pkg/Generated", &generated_class()) {
+            Err(Error::NoClassDefFound { ref name }) if name == "pkg/Generated" => (),
+            result => panic!("expected NoClassDefFound, but was {:?}", result),
+        }
+        assert!(class_loader.pending.is_empty());
+    }
+
+    #[test]
+    fn test_define_invalid_class() {
+        let mut class = ClassFileBuilder::new("Invalid", "java/lang/Object").build();
+        // make the names of the classes refer to a class entry instead of a string
+        for info in class.constant_pool.iter_mut() {
+            if let ConstantPoolInfo::Class { ref mut name_index } = *info {
+                *name_index = class.this_class;
+            }
+        }
+        let mut class_loader = class_loader_with_object();
+        match class_loader.define_class("Invalid", &write_class_file(&class)) {
+            Err(Error::ClassFormat) => (),
+            result => panic!("expected ClassFormat, but was {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_define_class_use_existing() {
+        let mut class_loader = class_loader_with_object()
+            .with_redefinition_policy(RedefinitionPolicy::UseExisting);
+        let class = class_loader.define_class("Generated", &generated_class()).unwrap();
+        let redefined = class_loader.define_class("Generated", &[]).unwrap();
+        assert!(Rc::ptr_eq(&class, &redefined));
+    }
+
+    fn assert_access_flag_violation(class: &ClassFileBuilder, name: &str, problem: &str) {
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        match class_loader.define_class(name, &write_class_file(&class.build())) {
+            Err(Error::AccessFlagViolation(ref message)) if message == problem => (),
+            result => panic!("expected AccessFlagViolation({}), but was {:?}", problem, result),
+        }
+    }
+
+    #[test]
+    fn test_define_public_class_with_wrong_name() {
+        // a class with the same simple name in another package is not the requested class
+        let class = ClassFileBuilder::new("other/Shape", "java/lang/Object").build();
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        match class_loader.define_class("pkg/Shape", &write_class_file(&class)) {
+            Err(Error::NoClassDefFound { ref name }) if name == "pkg/Shape" => (),
+            result => panic!("expected NoClassDefFound, but was {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_access_flag_violations() {
+        let interface_flags = class_access_flags::ACC_INTERFACE | class_access_flags::ACC_ABSTRACT;
+        assert_access_flag_violation(
+            ClassFileBuilder::new("Shape", "java/lang/Object")
+                .set_access_flags(class_access_flags::ACC_ABSTRACT | class_access_flags::ACC_FINAL),
+            "Shape", "class Shape is both abstract and final");
+        assert_access_flag_violation(
+            ClassFileBuilder::new("Shape", "java/lang/Object")
+                .set_access_flags(class_access_flags::ACC_INTERFACE),
+            "Shape", "interface Shape is not abstract");
+        assert_access_flag_violation(
+            ClassFileBuilder::new("Shape", "java/lang/Object")
+                .set_access_flags(interface_flags)
+                .add_field("SIDES", "I", field_access_flags::ACC_STATIC),
+            "Shape", "static field Shape.SIDES of an interface is not final");
+        assert_access_flag_violation(
+            ClassFileBuilder::new("Shape", "java/lang/Object")
+                .set_access_flags(interface_flags)
+                .add_method("draw", "()V", method_access_flags::ACC_NATIVE, vec![]),
+            "Shape", "method Shape.draw of an interface is native or synchronized");
+        assert_access_flag_violation(
+            ClassFileBuilder::new("Shape", "java/lang/Object")
+                .set_access_flags(interface_flags)
+                .add_method("draw", "()V", method_access_flags::ACC_SYNCHRONIZED,
+                            vec![opcode::RETURN]),
+            "Shape", "method Shape.draw of an interface is native or synchronized");
+    }
+}
@@ -0,0 +1,214 @@
+//! The locations which are searched for class files.
+//!
+//! A class path is an ordered list of entries, each of which is either a directory or a JAR file.
+//! A class with the binary name `java/lang/String` is found in a directory entry at the relative
+//! path `java/lang/String.class`. When a class is present in more than one entry, the first entry
+//! in the class path takes precedence.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+#[derive(Debug, Clone)]
+/// A single location in which to search for class files.
+pub enum ClassPathEntry {
+    /// A directory containing class files in subdirectories corresponding to their packages.
+    Directory(PathBuf),
+    /// A JAR file containing class files at paths corresponding to their packages.
+    JarFile(PathBuf),
+}
+
+/// Read the bytes of the class file for the class with the given binary name from a directory, if
+/// the directory contains one.
+fn find_class_in_directory(directory: &Path, class_name: &str) -> Option<Vec<u8>> {
+    let mut path = directory.to_path_buf();
+    for component in class_name.split('/') {
+        path.push(component);
+    }
+    path.set_extension("class");
+    File::open(path).and_then(|mut file| {
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).map(|_| bytes)
+    }).ok()
+}
+
+#[derive(Debug)]
+/// An ordered list of locations in which to search for class files.
+pub struct ClassPath {
+    entries: Vec<ClassPathEntry>,
+    /// The JAR files of the class path which have been opened so far. Opening a JAR file requires
+    /// reading its central directory, so each one is opened at most once and kept here.
+    jar_files: RefCell<HashMap<PathBuf, ZipArchive<File>>>,
+}
+
+impl ClassPath {
+    pub fn new(entries: Vec<ClassPathEntry>) -> Self {
+        ClassPath {
+            entries: entries,
+            jar_files: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Search the entries of the class path in order for the class file of the class with the
+    /// given binary name (such as `java/lang/String`), and return the bytes of the first one found.
+    pub fn find_class(&self, class_name: &str) -> Option<Vec<u8>> {
+        self.entries.iter().filter_map(|entry| {
+            match *entry {
+                ClassPathEntry::Directory(ref directory) =>
+                    find_class_in_directory(directory, class_name),
+                ClassPathEntry::JarFile(ref jar_path) =>
+                    self.find_class_in_jar(jar_path, class_name),
+            }
+        }).next()
+    }
+
+    /// Read the bytes of the class file for the class with the given binary name from a JAR file,
+    /// if the JAR file contains one. A JAR file which cannot be opened contains no classes.
+    fn find_class_in_jar(&self, jar_path: &Path, class_name: &str) -> Option<Vec<u8>> {
+        let mut jar_files = self.jar_files.borrow_mut();
+        let archive = match jar_files.entry(jar_path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                match File::open(jar_path).ok().and_then(|file| ZipArchive::new(file).ok()) {
+                    Some(archive) => entry.insert(archive),
+                    None => return None,
+                }
+            },
+        };
+        let entry_name = String::from(class_name) + ".class";
+        let mut file = match archive.by_name(&entry_name) {
+            Ok(file) => file,
+            Err(_) => return None,
+        };
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).map(|_| bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::process;
+
+    use zip::{CompressionMethod, ZipWriter};
+    use zip::write::FileOptions;
+
+    use vm::class_loader::{BootstrapClassLoader, ClassLoader};
+    use vm::sig;
+
+    /// Create a new empty temporary directory for the test with the given name.
+    fn temp_dir(test_name: &str) -> PathBuf {
+        let mut path = env::temp_dir();
+        path.push(format!("rust-jvm-{}-{}", test_name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    /// Write a file at a path relative to the given directory, creating any missing parents.
+    fn write_file(directory: &Path, relative_path: &str, bytes: &[u8]) {
+        let path = directory.join(relative_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap().write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn test_find_class() {
+        let directory = temp_dir("find-class");
+        write_file(&directory, "java/lang/Object.class", b"Object");
+        write_file(&directory, "Main.class", b"Main");
+        let class_path = ClassPath::new(vec![ClassPathEntry::Directory(directory.clone())]);
+        assert_eq!(class_path.find_class("java/lang/Object"), Some(b"Object".to_vec()));
+        assert_eq!(class_path.find_class("Main"), Some(b"Main".to_vec()));
+        assert_eq!(class_path.find_class("java/lang/String"), None);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_find_class_in_order() {
+        let first = temp_dir("find-class-first");
+        let second = temp_dir("find-class-second");
+        write_file(&first, "A.class", b"first A");
+        write_file(&second, "A.class", b"second A");
+        write_file(&second, "B.class", b"second B");
+        let class_path = ClassPath::new(vec![ClassPathEntry::Directory(first.clone()),
+                                             ClassPathEntry::Directory(second.clone())]);
+        assert_eq!(class_path.find_class("A"), Some(b"first A".to_vec()));
+        assert_eq!(class_path.find_class("B"), Some(b"second B".to_vec()));
+        fs::remove_dir_all(first).unwrap();
+        fs::remove_dir_all(second).unwrap();
+    }
+
+    #[test]
+    fn test_load_class_from_class_path() {
+        let directory = temp_dir("load-class");
+        write_file(&directory, "java/lang/Object.class",
+                   include_bytes!("../../data/vm/java/lang/Object.class"));
+        let class_path = ClassPath::new(vec![ClassPathEntry::Directory(directory.clone())]);
+        let mut class_loader = BootstrapClassLoader::new(class_path);
+        let object_sig = sig::Class::new("java/lang/Object");
+        let class = class_loader.load_class(&object_sig).unwrap();
+        assert_eq!(class.symref.sig, object_sig);
+        assert!(class_loader.load_class(&sig::Class::new("java/lang/String")).is_err());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Write a JAR file at the given path containing the given files.
+    fn write_jar(path: &Path, files: &[(&str, &[u8])]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        for &(name, bytes) in files {
+            let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+            writer.start_file(name, options).unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_find_class_in_jar() {
+        let directory = temp_dir("find-class-in-jar");
+        let jar_path = directory.join("classes.jar");
+        write_jar(&jar_path, &[("java/lang/Object.class", b"Object"), ("Main.class", b"Main")]);
+        let class_path = ClassPath::new(vec![ClassPathEntry::JarFile(jar_path.clone())]);
+        assert_eq!(class_path.find_class("java/lang/Object"), Some(b"Object".to_vec()));
+        assert_eq!(class_path.find_class("Main"), Some(b"Main".to_vec()));
+        assert_eq!(class_path.find_class("java/lang/String"), None);
+        assert_eq!(class_path.jar_files.borrow().len(), 1);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_find_class_in_missing_jar() {
+        let directory = temp_dir("find-class-in-missing-jar");
+        write_file(&directory, "Main.class", b"Main");
+        let class_path = ClassPath::new(vec![
+            ClassPathEntry::JarFile(directory.join("missing.jar")),
+            ClassPathEntry::Directory(directory.clone()),
+        ]);
+        assert_eq!(class_path.find_class("Main"), Some(b"Main".to_vec()));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_load_class_from_jar() {
+        let directory = temp_dir("load-class-from-jar");
+        let jar_path = directory.join("rt.jar");
+        write_jar(&jar_path, &[("java/lang/Object.class",
+                                include_bytes!("../../data/vm/java/lang/Object.class"))]);
+        let class_path = ClassPath::new(vec![ClassPathEntry::JarFile(jar_path)]);
+        let mut class_loader = BootstrapClassLoader::new(class_path);
+        let object_sig = sig::Class::new("java/lang/Object");
+        let class = class_loader.load_class(&object_sig).unwrap();
+        assert_eq!(class.symref.sig, object_sig);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -21,19 +21,62 @@
 //! implemented, other constant pool entries as well) onto the stack for manipulation by the
 //! program.
 
+use std::{error, fmt};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::num::Wrapping;
 use std::ops::Index;
 use std::rc::Rc;
 
-use model::class_file::constant_pool::{ConstantPool, ConstantPoolInfo};
+use intern::Interner;
+use model::class_file::constant_pool::{ConstantPool, ConstantPoolInfo, Tag};
+use util::modified_utf8::{self, ModifiedUtf8Error};
 use util::one_indexed_vec::OneIndexedVec;
-use vm::{sig, symref};
+use vm::{java_lang, sig, symref};
+use vm::class::Class;
 use vm::class_loader::{self, ClassLoader};
-use vm::value::{Array, Scalar, Value};
+use vm::context::Context;
+use vm::exceptions::{self, ExceptionInfo};
+use vm::heap::HeapRef;
+use vm::value::Value;
 
 pub use model::class_file::constant_pool::constant_pool_index;
 
+/// A reason why a runtime constant pool cannot be created from the constant pool of a class file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// One of the strings of the constant pool is not valid modified UTF-8.
+    InvalidString(ModifiedUtf8Error),
+    /// The entry at `index`, which another entry refers to, does not exist or is not of the
+    /// `expected` type.
+    UnexpectedEntry { index: constant_pool_index, expected: Tag },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidString(ref error) => write!(f, "invalid string: {}", error),
+            Error::UnexpectedEntry { index, ref expected } =>
+                write!(f, "expected {:?} at constant pool index {}", expected, index),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::InvalidString(_) => "invalid modified UTF-8 string",
+            Error::UnexpectedEntry { .. } => "unexpected constant pool entry",
+        }
+    }
+}
+
+impl From<ModifiedUtf8Error> for Error {
+    fn from(error: ModifiedUtf8Error) -> Self {
+        Error::InvalidString(error)
+    }
+}
+
 #[derive(Debug)]
 /// An constant value in the runtime constant pool.
 pub enum RuntimeConstantPoolEntry {
@@ -41,69 +84,91 @@ pub enum RuntimeConstantPoolEntry {
     ClassRef(symref::Class),
     /// A symbolic reference to a method.
     MethodRef(symref::Method),
+    /// A symbolic reference to an interface method.
+    InterfaceMethodRef(symref::Method),
     /// A symbolic reference to an object field.
     FieldRef(symref::Field),
     /// A literal value that has undergone resolution.
     ResolvedLiteral(Value),
-    /// An unresolved reference to a modified UTF-8 string in the constant pool.
-    UnresolvedString(constant_pool_index),
-    /// A resolved modified UTF-8 string value.
-    StringValue(ModifiedUtf8String),
+    /// An unresolved `String` literal, holding the modified UTF-8 contents of the literal.
+    UnresolvedString(ModifiedUtf8String),
+    /// A decoded modified UTF-8 string value, shared with every other class loaded using the
+    /// same `Interner`.
+    StringValue(Rc<str>),
 }
 
 #[derive(Debug)]
 /// A runtime constant pool. This just consists of a `OneIndexedVec` of constant pool entries.
 pub struct RuntimeConstantPool {
     entries: OneIndexedVec<Option<RuntimeConstantPoolEntry>>,
+    /// The `String` objects created for the `String` literals resolved so far, by index.
+    string_literals: RefCell<HashMap<constant_pool_index, HeapRef>>,
 }
 
 impl Index<constant_pool_index> for RuntimeConstantPool {
     type Output = Option<RuntimeConstantPoolEntry>;
 
+    /// Returns the entry at the given index, or `None` if the index is out of bounds or there is
+    /// no runtime entry for the corresponding entry of the class file's constant pool.
     fn index(&self, index: constant_pool_index) -> &Self::Output {
-        &self.entries[index as usize]
+        self.entries.get(index as usize).unwrap_or(&None)
     }
 }
 
 impl RuntimeConstantPool {
     /// Creates a new runtime constant pool from the `ConstantPool` returned by the class file
     /// parser. Most of this process involves constructing `sig` and `symref` structures
-    /// representing the symbolic references in the constant pool.
-    pub fn new(constant_pool: &ConstantPool) -> Self {
+    /// representing the symbolic references in the constant pool. The strings of the constant
+    /// pool are interned using `interner`. Fails if any of the strings of the constant pool is not
+    /// valid modified UTF-8, or if an entry refers to another entry of the wrong type.
+    pub fn new(constant_pool: &ConstantPool, interner: &mut Interner) -> Result<Self, Error> {
         let mut entries = vec![];
         for info in constant_pool {
             let entry = match *info {
-                ConstantPoolInfo::Class { .. } => {
-                    let class_symref = Self::force_class_ref(&constant_pool, &info);
+                ConstantPoolInfo::Class { name_index } => {
+                    let name = try!(Self::force_string(constant_pool, name_index));
+                    let name = try!(name.to_string(interner));
+                    let class_symref = symref::Class { sig: sig::Class::new(&name) };
                     Some(RuntimeConstantPoolEntry::ClassRef(class_symref))
                 },
 
                 ConstantPoolInfo::FieldRef { class_index, name_and_type_index } => {
                     let class_symref =
-                        Self::force_class_ref(&constant_pool,
-                                              &constant_pool[class_index as usize]);
+                        try!(Self::force_class_ref(constant_pool, class_index, interner));
                     let (name, descriptor) =
-                        Self::force_name_and_type(&constant_pool,
-                                                  &constant_pool[name_and_type_index as usize]);
+                        try!(Self::force_name_and_type(constant_pool, name_and_type_index,
+                                                       interner));
                     let ty = sig::Type::new(&descriptor);
-                    let sig = sig::Field { name: name, ty: ty };
+                    let sig = sig::Field { name: String::from(&*name), ty: ty };
                     let field_symref = symref::Field { class: class_symref, sig: sig };
                     Some(RuntimeConstantPoolEntry::FieldRef(field_symref))
                 },
 
                 ConstantPoolInfo::MethodRef { class_index, name_and_type_index } => {
                     let class_symref =
-                        Self::force_class_ref(&constant_pool, &constant_pool[class_index as usize]);
+                        try!(Self::force_class_ref(constant_pool, class_index, interner));
                     let (name, descriptor) =
-                        Self::force_name_and_type(&constant_pool,
-                                                  &constant_pool[name_and_type_index as usize]);
+                        try!(Self::force_name_and_type(constant_pool, name_and_type_index,
+                                                       interner));
                     let sig = sig::Method::new(&name, &descriptor);
                     let method_symref = symref::Method { class: class_symref, sig: sig };
                     Some(RuntimeConstantPoolEntry::MethodRef(method_symref))
                 },
 
+                ConstantPoolInfo::InterfaceMethodRef { class_index, name_and_type_index } => {
+                    let class_symref =
+                        try!(Self::force_class_ref(constant_pool, class_index, interner));
+                    let (name, descriptor) =
+                        try!(Self::force_name_and_type(constant_pool, name_and_type_index,
+                                                       interner));
+                    let sig = sig::Method::new(&name, &descriptor);
+                    let method_symref = symref::Method { class: class_symref, sig: sig };
+                    Some(RuntimeConstantPoolEntry::InterfaceMethodRef(method_symref))
+                },
+
                 ConstantPoolInfo::String { string_index } => {
-                    Some(RuntimeConstantPoolEntry::UnresolvedString(string_index))
+                    let modified_utf8 = try!(Self::force_string(constant_pool, string_index));
+                    Some(RuntimeConstantPoolEntry::UnresolvedString(modified_utf8))
                 },
 
                 ConstantPoolInfo::Integer { bytes } => {
@@ -112,27 +177,27 @@ impl RuntimeConstantPool {
                 },
 
                 ConstantPoolInfo::Float { bytes } => {
-                    let value = Value::Float(bytes as f32);
+                    let value = Value::Float(f32::from_bits(bytes));
                     Some(RuntimeConstantPoolEntry::ResolvedLiteral(value))
                 },
 
                 ConstantPoolInfo::Long { high_bytes, low_bytes } => {
-                    let bits = ((high_bytes as i64) << 32) & (low_bytes as i64);
+                    let bits = ((high_bytes as i64) << 32) | (low_bytes as i64);
                     let value = Value::Long(Wrapping(bits));
                     Some(RuntimeConstantPoolEntry::ResolvedLiteral(value))
                 },
 
                 ConstantPoolInfo::Double { high_bytes, low_bytes } => {
-                    let bits = ((high_bytes as u64) << 32) & (low_bytes as u64);
-                    let value = Value::Double(bits as f64);
+                    let bits = ((high_bytes as u64) << 32) | (low_bytes as u64);
+                    let value = Value::Double(f64::from_bits(bits));
                     Some(RuntimeConstantPoolEntry::ResolvedLiteral(value))
                 },
 
                 ConstantPoolInfo::NameAndType { .. } => None,
 
                 ConstantPoolInfo::Utf8 { ref bytes } => {
-                    let modified_utf8 = ModifiedUtf8String::new(bytes.to_vec());
-                    Some(RuntimeConstantPoolEntry::StringValue(modified_utf8))
+                    let string = try!(modified_utf8::decode(bytes));
+                    Some(RuntimeConstantPoolEntry::StringValue(interner.intern(&string)))
                 },
 
                 ConstantPoolInfo::Unusable => None,
@@ -141,129 +206,244 @@ impl RuntimeConstantPool {
             };
             entries.push(entry);
         }
-        RuntimeConstantPool { entries: OneIndexedVec::from(entries) }
+        Ok(RuntimeConstantPool {
+            entries: OneIndexedVec::from(entries),
+            string_literals: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Creates a runtime constant pool with no entries, for classes which are not loaded from a
+    /// class file, such as array classes.
+    pub fn empty() -> Self {
+        RuntimeConstantPool {
+            entries: OneIndexedVec::from(vec![]),
+            string_literals: RefCell::new(HashMap::new()),
+        }
     }
 
-    /// Constructs a `symref::Class` from a `ConstantPoolInfo::Class`, panicking if `info` is of a
-    /// different variant of `ConstantPoolInfo`.
+    /// Constructs a `symref::Class` from the `ConstantPoolInfo::Class` at `index`, failing if
+    /// there is no such entry or it is of a different variant of `ConstantPoolInfo`.
     ///
-    /// This should only be called where the specification requires that `info` be of the correct
-    /// variant.
-    fn force_class_ref(constant_pool: &ConstantPool, info: &ConstantPoolInfo) -> symref::Class {
-        match *info {
-            ConstantPoolInfo::Class { name_index } => {
-                let name = Self::force_string(&constant_pool[name_index as usize]).to_string();
-                symref::Class { sig: sig::Class::new(&name) }
+    /// This should only be called where the specification requires that the entry be of the
+    /// correct variant.
+    fn force_class_ref(constant_pool: &ConstantPool, index: constant_pool_index,
+                       interner: &mut Interner) -> Result<symref::Class, Error> {
+        match constant_pool.get(index as usize) {
+            Some(&ConstantPoolInfo::Class { name_index }) => {
+                let name = try!(Self::force_string(constant_pool, name_index));
+                let name = try!(name.to_string(interner));
+                Ok(symref::Class { sig: sig::Class::new(&name) })
             },
-            _ => panic!("expected ConstantPoolInfo::Class"),
+            _ => Err(Error::UnexpectedEntry { index: index, expected: Tag::Class }),
         }
     }
 
-    /// Constructs a tuple of name and descriptor (type) strings from a
-    /// `ConstantPoolInfo::NameAndType`, panicking if `info` is of a different variant of
-    /// `ConstantPoolInfo`. The names of classes are binary names (§4.2.1) while the names of
-    /// fields and methods are unqualified names (§4.2.2). Descriptor formats vary depending on the
-    /// type of descriptor being referenced (§4.3).
+    /// Returns the interned name and descriptor (type) strings of the
+    /// `ConstantPoolInfo::NameAndType` at `index`, failing if there is no such entry or it is of a
+    /// different variant of `ConstantPoolInfo`. The names of classes are binary names (§4.2.1)
+    /// while the names of fields and methods are unqualified names (§4.2.2). Descriptor formats
+    /// vary depending on the type of descriptor being referenced (§4.3).
     ///
-    /// This should only be called where the specification requires that `info` be of the correct
-    /// variant.
-    fn force_name_and_type(constant_pool: &ConstantPool, info: &ConstantPoolInfo)
-            -> (String, String) {
-        match *info {
-            ConstantPoolInfo::NameAndType { name_index, descriptor_index } => {
-                let ref name_info = constant_pool[name_index as usize];
-                let ref descriptor_info = constant_pool[descriptor_index as usize];
-                let name_string = Self::force_string(name_info).to_string();
-                let descriptor_string = Self::force_string(descriptor_info).to_string();
-                (name_string, descriptor_string)
+    /// This should only be called where the specification requires that the entry be of the
+    /// correct variant.
+    fn force_name_and_type(constant_pool: &ConstantPool, index: constant_pool_index,
+                           interner: &mut Interner) -> Result<(Rc<str>, Rc<str>), Error> {
+        match constant_pool.get(index as usize) {
+            Some(&ConstantPoolInfo::NameAndType { name_index, descriptor_index }) => {
+                let name = try!(Self::force_interned_string(constant_pool, name_index, interner));
+                let descriptor =
+                    try!(Self::force_interned_string(constant_pool, descriptor_index, interner));
+                Ok((name, descriptor))
             },
-            _ => panic!("expected ConstantPoolInfo::NameAndType"),
+            _ => Err(Error::UnexpectedEntry { index: index, expected: Tag::NameAndType }),
         }
     }
 
-    /// Constructs a `ModifiedUtf8String` from a `ConstantPoolInfo::Utf8`, panicking in `info` is
-    /// of a different variant of `ConstantPoolInfo`.
+    /// Decodes the string in the `ConstantPoolInfo::Utf8` at `index` and interns it using
+    /// `interner`, failing if there is no such entry or it is of a different variant of
+    /// `ConstantPoolInfo`.
     ///
-    /// This should only be called where the specification requires that `info` be of the correct
-    /// variant.
-    fn force_string(info: &ConstantPoolInfo) -> ModifiedUtf8String {
-        match *info {
-            ConstantPoolInfo::Utf8 { ref bytes } => {
-                ModifiedUtf8String::new(bytes.to_vec())
-            },
-            _ => panic!("expected ConstantPoolInfo::Utf8"),
+    /// This should only be called where the specification requires that the entry be of the
+    /// correct variant.
+    fn force_interned_string(constant_pool: &ConstantPool, index: constant_pool_index,
+                             interner: &mut Interner) -> Result<Rc<str>, Error> {
+        match constant_pool.get(index as usize) {
+            Some(&ConstantPoolInfo::Utf8 { ref bytes }) =>
+                Ok(interner.intern(&try!(modified_utf8::decode(bytes)))),
+            _ => Err(Error::UnexpectedEntry { index: index, expected: Tag::Utf8 }),
+        }
+    }
+
+    /// Constructs a `ModifiedUtf8String` from the `ConstantPoolInfo::Utf8` at `index`, failing if
+    /// there is no such entry or it is of a different variant of `ConstantPoolInfo`.
+    ///
+    /// This should only be called where the specification requires that the entry be of the
+    /// correct variant.
+    fn force_string(constant_pool: &ConstantPool, index: constant_pool_index)
+            -> Result<ModifiedUtf8String, Error> {
+        match constant_pool.get(index as usize) {
+            Some(&ConstantPoolInfo::Utf8 { ref bytes }) =>
+                Ok(ModifiedUtf8String::new(bytes.to_vec())),
+            _ => Err(Error::UnexpectedEntry { index: index, expected: Tag::Utf8 }),
         }
     }
 
-    /// Returns the `String` at the runtime constant pool entry at `index`, panicking if that entry
+    /// Returns the string at the runtime constant pool entry at `index`, panicking if that entry
     /// is not a `RuntimeConstantPoolEntry::StringValue`. This is used during class creation,
     /// because the structures describing fields and methods later in the class file (after the
     /// constant pool) use constant pool indices to refer to their names.
-    pub fn lookup_raw_string(&self, index: constant_pool_index) -> String {
-        match self.entries[index as usize] {
-            Some(RuntimeConstantPoolEntry::StringValue(ref modified_utf8)) =>
-                modified_utf8.to_string(),
+    pub fn lookup_raw_string(&self, index: constant_pool_index) -> Rc<str> {
+        match self[index] {
+            Some(RuntimeConstantPoolEntry::StringValue(ref string)) => string.clone(),
             _ => panic!("expected RuntimeConstantPoolInfo::StringValue"),
         }
     }
 
+    /// Resolves the class referred to by the `ClassRef` entry at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry at `index` is not a `RuntimeConstantPoolEntry::ClassRef`.
+    pub fn resolve_class(&self, index: constant_pool_index, class_loader: &mut dyn ClassLoader)
+                         -> Result<Rc<Class>, class_loader::Error> {
+        match self[index] {
+            Some(RuntimeConstantPoolEntry::ClassRef(ref symref)) =>
+                class_loader.resolve_class(symref),
+            _ => panic!("expected a class reference at constant pool index {}", index),
+        }
+    }
+
+    /// Resolves the class declaring the method referred to by the `MethodRef` entry at the given
+    /// index, returning the class along with the symbolic reference to the method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry at `index` is not a `RuntimeConstantPoolEntry::MethodRef`.
+    pub fn resolve_method(&self, index: constant_pool_index, class_loader: &mut dyn ClassLoader)
+                          -> Result<(Rc<Class>, &symref::Method), class_loader::Error> {
+        match self[index] {
+            Some(RuntimeConstantPoolEntry::MethodRef(ref symref)) =>
+                class_loader.resolve_class(&symref.class).map(|class| (class, symref)),
+            _ => panic!("expected a method reference at constant pool index {}", index),
+        }
+    }
+
+    /// Like `resolve_method`, but for the `InterfaceMethodRef` entry at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry at `index` is not a `RuntimeConstantPoolEntry::InterfaceMethodRef`.
+    pub fn resolve_interface_method(&self, index: constant_pool_index,
+                                    class_loader: &mut dyn ClassLoader)
+                                    -> Result<(Rc<Class>, &symref::Method), class_loader::Error> {
+        match self[index] {
+            Some(RuntimeConstantPoolEntry::InterfaceMethodRef(ref symref)) =>
+                class_loader.resolve_class(&symref.class).map(|class| (class, symref)),
+            _ => panic!("expected an interface method reference at constant pool index {}",
+                        index),
+        }
+    }
+
+    /// Resolves the class named by the `FieldRef` entry at the given index, returning the class
+    /// along with the symbolic reference to the field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry at `index` is not a `RuntimeConstantPoolEntry::FieldRef`.
+    pub fn resolve_field(&self, index: constant_pool_index, class_loader: &mut dyn ClassLoader)
+                         -> Result<(Rc<Class>, &symref::Field), class_loader::Error> {
+        match self[index] {
+            Some(RuntimeConstantPoolEntry::FieldRef(ref symref)) =>
+                class_loader.resolve_class(&symref.class).map(|class| (class, symref)),
+            _ => panic!("expected a field reference at constant pool index {}", index),
+        }
+    }
+
     /// Resolves a literal value in the constant pool into a `Value`. For `String` literals, this
     /// requires instantiating an instance of the `String` class, which we do by calling the
     /// `String(char[])` constructor using the content of the modified UTF-8 string in the constant
-    /// pool, parsed into UTF-16.
-    pub fn resolve_literal(&self, index: constant_pool_index, class_loader: &mut ClassLoader)
-            -> Result<Value, class_loader::Error> {
-        match self.entries[index as usize] {
+    /// pool, parsed into UTF-16. The `String` is allocated on the given heap. For class literals,
+    /// this resolves the class and returns its `java/lang/Class` mirror, without initializing it.
+    pub fn resolve_literal(&self, index: constant_pool_index, class_loader: &mut dyn ClassLoader,
+                           context: &mut Context) -> Result<Value, ExceptionInfo> {
+        match self[index] {
             Some(RuntimeConstantPoolEntry::ResolvedLiteral(ref value)) => Ok(value.clone()),
-            Some(RuntimeConstantPoolEntry::UnresolvedString(string_index)) => {
-                let array_sig = sig::Class::Array(Box::new(sig::Type::Char));
-                let array_symref = symref::Class { sig: array_sig.clone() };
-                let array_class = try!(class_loader.resolve_class(&array_symref));
-
-                let chars = {
-                    if let Some(RuntimeConstantPoolEntry::StringValue(ref modified_utf8)) =
-                            self.entries[string_index as usize] {
-                        modified_utf8.to_utf16()
-                    } else {
-                        panic!("expected RuntimeConstantPoolEntry::StringValue");
-                    }
-                };
-                let mut array = Array::new(array_class, chars.len() as i32);
-                let mut i = 0;
-                for c in chars {
-                    array.put(i, Value::Int(Wrapping(c as i32)));
-                    i += 1;
-                }
-                let array_rc = Rc::new(RefCell::new(array));
-
-                let string_sig = sig::Class::Scalar(String::from("java/lang/String"));
-                let string_symref = symref::Class { sig: string_sig };
-                let string_class = try!(class_loader.resolve_class(&string_symref));
-                let string = Scalar::new(string_class.clone());
-                let string_rc = Rc::new(RefCell::new(string));
-
-                let constructor_sig = sig::Method {
-                    name: String::from("<init>"),
-                    params: vec![sig::Type::Reference(array_sig.clone())],
-                    return_ty: None,
-                };
-                let constructor_symref = symref::Method {
-                    class: string_symref,
-                    sig: constructor_sig,
-                };
-                let constructor = string_class.resolve_method(&constructor_symref);
-                let args = vec![Value::ScalarReference(string_rc.clone()),
-                                Value::ArrayReference(array_rc)];
-                let result = constructor.invoke(string_class.as_ref(), class_loader, args);
-                match result {
-                    None => (),
-                    Some(_) => panic!("<init> returned a value!"),
-                }
-                Ok(Value::ScalarReference(string_rc))
+            Some(RuntimeConstantPoolEntry::UnresolvedString(_)) =>
+                self.resolve_string(index, class_loader, context),
+            Some(RuntimeConstantPoolEntry::ClassRef(ref symref)) => {
+                let class = try!(class_loader.resolve_class(symref));
+                let mirror = try!(java_lang::class_mirror(class_loader, context, &class));
+                Ok(Value::ScalarReference(mirror))
             },
             _ => panic!("expected literal constant pool entry"),
         }
     }
+
+    /// Returns the `String` objects created for the `String` literals resolved so far.
+    pub fn string_literals(&self) -> Vec<HeapRef> {
+        self.string_literals.borrow().values().cloned().collect()
+    }
+
+    /// Resolves the `String` literal at `index` into a reference to a `String` object. The object
+    /// is created the first time the literal is resolved, and the same object is returned every
+    /// time after, so that equal literals of the class are `==` (§5.1).
+    pub fn resolve_string(&self, index: constant_pool_index, class_loader: &mut dyn ClassLoader,
+                          context: &mut Context) -> Result<Value, ExceptionInfo> {
+        if let Some(&string) = self.string_literals.borrow().get(&index) {
+            return Ok(Value::ScalarReference(string));
+        }
+        let chars = match self[index] {
+            Some(RuntimeConstantPoolEntry::UnresolvedString(ref modified_utf8)) =>
+                try!(modified_utf8.to_utf16().map_err(class_loader::Error::from)),
+            _ => panic!("expected a string literal at constant pool index {}", index),
+        };
+        let string = try!(new_string(chars, class_loader, context));
+        if let Some(heap_ref) = string.as_heap_ref() {
+            self.string_literals.borrow_mut().insert(index, heap_ref);
+        }
+        Ok(string)
+    }
+}
+
+/// Create a new instance of `java/lang/String` on the heap with the given UTF-16 contents, by
+/// calling the `String(char[])` constructor. Returns the exception raised instead if the
+/// constructor cannot be found or throws.
+pub fn new_string(chars: Vec<u16>, class_loader: &mut dyn ClassLoader, context: &mut Context)
+                  -> Result<Value, ExceptionInfo> {
+    let array_sig = sig::Class::Array(Box::new(sig::Type::Char));
+    let array_symref = symref::Class { sig: array_sig.clone() };
+    let array_class = try!(class_loader.resolve_class(&array_symref));
+    let array = context.heap.alloc_array(array_class, chars.len() as i32);
+    let mut i = 0;
+    for c in chars {
+        context.heap.get_array_mut(array).put(i, Value::Int(Wrapping(c as i32)));
+        i += 1;
+    }
+
+    let string_sig = sig::Class::Scalar(String::from("java/lang/String"));
+    let string_symref = symref::Class { sig: string_sig };
+    let string_class = try!(class_loader.resolve_class(&string_symref));
+    let string = context.heap.alloc_scalar(string_class.clone());
+
+    let constructor_symref = symref::Method {
+        class: string_symref,
+        sig: sig::Method {
+            name: String::from("<init>"),
+            params: vec![sig::Type::Reference(array_sig.clone())],
+            return_ty: None,
+        },
+    };
+    let constructor = match string_class.resolve_method(&constructor_symref.sig) {
+        Some(constructor) => constructor,
+        None => return Err(exceptions::create_no_such_method(&constructor_symref)),
+    };
+    let args = vec![Value::ScalarReference(string), Value::ArrayReference(array)];
+    let result = constructor.invoke(string_class.as_ref(), class_loader, context, args);
+    match result {
+        Ok(None) => Ok(Value::ScalarReference(string)),
+        Ok(Some(_)) => panic!("<init> returned a value!"),
+        Err(exception) => Err(ExceptionInfo::thrown(&context.heap, exception)),
+    }
 }
 
 #[derive(Debug)]
@@ -278,109 +458,157 @@ impl ModifiedUtf8String {
         ModifiedUtf8String { bytes: bytes }
     }
 
-    /// Converts a modified UTF-8 string to a Rust `String`.
-    fn to_string(&self) -> String {
-        let mut utf8 = vec![];
-        let mut i = 0;
-        while i < self.bytes.len() {
-            match self.bytes[i] {
-                0x01 ... 0x7f => {
-                    utf8.push(self.bytes[i]);
-                    i += 1;
-                },
-                0xc0 ... 0xdf => {
-                    if self.bytes.len() < i + 2 {
-                        panic!("error decoding modified UTF-8: invalid sequence");
-                    } else if self.bytes[i] == 0xc0 && self.bytes[i + 1] == 0x80 {
-                        // this is the encoding of a null character
-                        utf8.push(0x00);
-                    } else {
-                        utf8.push(self.bytes[i]);
-                        utf8.push(self.bytes[i + 1]);
-                    }
-                    i += 2;
-                },
-                0xe0 ... 0xef => {
-                    if self.bytes.len() < i + 3 {
-                        panic!("error decoding modified UTF-8: invalid sequence");
-                    } else if self.bytes[i] == 0xed && self.bytes[i + 1] >= 0xa0
-                            && self.bytes[i + 1] <= 0xaf {
-                        // this sequence encodes a high surrogate
-                        // check that the following sequence encodes a low surrogate
-                        if self.bytes.len() < i + 6 || self.bytes[i + 3] != 0xed
-                                || self.bytes[i + 4] < 0xb0 || self.bytes[i + 4] > 0xbf {
-                            panic!("error decoding modified UTF-8: invalid surrogate pair");
-                        } else {
-                            // decode the surrogate pair into a code point
-                            let code_point = (((self.bytes[i + 1] & 0x0f) as u32) << 16)
-                                & (((self.bytes[i + 2] & 0x3f) as u32) << 10)
-                                & (((self.bytes[i + 4] & 0x0f) as u32) << 6)
-                                & ((self.bytes[i + 5] & 0x3f) as u32)
-                                + 0x10000;
-                            // encode the code point in UTF-8
-                            utf8.push(0xf0 & ((code_point & 0x001c0000 >> 18) as u8));
-                            utf8.push(0x80 & ((code_point & 0x0003f000 >> 12) as u8));
-                            utf8.push(0x80 & ((code_point & 0x00000fc0 >> 6) as u8));
-                            utf8.push(0x80 & ((code_point & 0x0000003f) as u8));
-                            // skip past the entire surrogate pair
-                            i += 6;
-                        }
-                    } else {
-                        utf8.push(self.bytes[i]);
-                        utf8.push(self.bytes[i + 1]);
-                        utf8.push(self.bytes[i + 2]);
-                        i += 3;
-                    }
-                },
-                0x80 ... 0xbf => panic!("error decoding modified UTF-8: invalid continuation byte"),
-                _ => panic!("error decoding modified UTF-8: illegal byte"),
-            }
-        }
-        String::from_utf8(utf8).expect("unexpected error decoding modified UTF-8")
+    /// Converts a modified UTF-8 string to a Rust string, and returns its interned copy from
+    /// `interner`. Characters outside the Basic Multilingual Plane are encoded as surrogate pairs,
+    /// each half of which must be present.
+    fn to_string(&self, interner: &mut Interner) -> Result<Rc<str>, ModifiedUtf8Error> {
+        let string = try!(modified_utf8::from_modified_utf8(&self.bytes));
+        Ok(interner.intern(&string))
     }
 
     /// Converts a modified UTF-8 string to a UTF-16 string. This function is provided as an
     /// optimization in creating Java `String` literals, which are in UTF-16 format. It does not
     /// validate surrogate pairs.
-    fn to_utf16(&self) -> Vec<u16> {
-        let mut utf16 = vec![];
-        let mut i = 0;
-        while i < self.bytes.len() {
-            match self.bytes[i] {
-                0x01 ... 0x7f => {
-                    utf16.push(self.bytes[i] as u16);
-                    i += 1;
-                },
-                0xc0 ... 0xdf => {
-                    if self.bytes.len() < i + 2 {
-                        panic!("error decoding modified UTF-8: invalid sequence");
-                    } else if self.bytes[i] == 0xc0 && self.bytes[i + 1] == 0x80 {
-                        // this is the encoding of a null character
-                        utf16.push(0x0000);
-                    } else {
-                        let code_point =
-                            (((self.bytes[i] & 0x1f) as u16) << 6)
-                               & ((self.bytes[i + 1] & 0x3f) as u16);
-                        utf16.push(code_point);
-                    }
-                    i += 2;
-                },
-                0xe0 ... 0xef => {
-                    if self.bytes.len() < i + 3 {
-                        panic!("error decoding modified UTF-8: invalid sequence");
-                    } else {
-                        let code_point =
-                            (((self.bytes[i] & 0x0f) as u16) << 12)
-                                & (((self.bytes[i + 1] & 0x3f) as u16) << 6)
-                                & ((self.bytes[i + 2] & 0x3f) as u16);
-                        utf16.push(code_point);
-                        i += 3;
-                    }
-                },
-                0x80 ... 0xbf => panic!("error decoding modified UTF-8: invalid continuation byte"),
-                _ => panic!("error decoding modified UTF-8: illegal byte"),
+    fn to_utf16(&self) -> Result<Vec<u16>, ModifiedUtf8Error> {
+        modified_utf8::to_utf16(&self.bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use parser::class_file::parse_class_file;
+    use util::modified_utf8::ModifiedUtf8ErrorKind;
+    use vm::class_loader::BootstrapClassLoader;
+    use vm::class_path::{ClassPath, ClassPathEntry};
+
+    #[test]
+    fn test_modified_utf8_string_interned() {
+        let mut interner = Interner::new();
+        let first = ModifiedUtf8String::new(b"java/lang/Object".to_vec());
+        let second = ModifiedUtf8String::new(b"java/lang/Object".to_vec());
+        let first = first.to_string(&mut interner).unwrap();
+        assert_eq!(&*first, "java/lang/Object");
+        assert!(Rc::ptr_eq(&first, &second.to_string(&mut interner).unwrap()));
+    }
+
+    #[test]
+    fn test_invalid_string_constant() {
+        let class_file = parse_class_file(include_bytes!("../../data/HelloWorld.class")).unwrap();
+        let mut constant_pool = class_file.constant_pool.clone();
+        // #18 = Utf8 "Hello, World"
+        match constant_pool.get_mut(18) {
+            Some(&mut ConstantPoolInfo::Utf8 { ref mut bytes }) => bytes.push(0xe0),
+            _ => panic!("expected a Utf8 entry at #18"),
+        }
+        let error = RuntimeConstantPool::new(&constant_pool, &mut Interner::new()).unwrap_err();
+        assert_eq!(error, Error::InvalidString(ModifiedUtf8Error {
+            kind: ModifiedUtf8ErrorKind::InvalidSequence,
+            offset: 12,
+            byte: 0xe0,
+        }));
+    }
+
+    #[test]
+    fn test_unexpected_entry() {
+        let class_file = parse_class_file(include_bytes!("../../data/HelloWorld.class")).unwrap();
+        let mut constant_pool = class_file.constant_pool.clone();
+        // #6 = Class java/lang/Object, which #1 = Methodref java/lang/Object."<init>":()V
+        // refers to
+        *constant_pool.get_mut(6).unwrap() = ConstantPoolInfo::Integer { bytes: 0 };
+        let error = RuntimeConstantPool::new(&constant_pool, &mut Interner::new()).unwrap_err();
+        assert_eq!(error, Error::UnexpectedEntry { index: 6, expected: Tag::Class });
+    }
+
+    #[test]
+    fn test_intern_strings() {
+        let class_file = parse_class_file(include_bytes!("../../data/String.class")).unwrap();
+        let mut interner = Interner::new();
+        let first = RuntimeConstantPool::new(&class_file.constant_pool, &mut interner).unwrap();
+        let num_strings = interner.len();
+        assert!(num_strings > 0);
+
+        // loading the same strings again allocates no new copies of them
+        let second = RuntimeConstantPool::new(&class_file.constant_pool, &mut interner).unwrap();
+        assert_eq!(interner.len(), num_strings);
+        for (index, info) in class_file.constant_pool.iter().enumerate() {
+            if let ConstantPoolInfo::Utf8 { .. } = *info {
+                let index = (index + 1) as constant_pool_index;
+                assert!(Rc::ptr_eq(&first.lookup_raw_string(index),
+                                   &second.lookup_raw_string(index)));
             }
         }
-        utf16
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        let class_file = parse_class_file(include_bytes!("../../data/String.class")).unwrap();
+        let constant_pool = RuntimeConstantPool::new(&class_file.constant_pool,
+                                                     &mut Interner::new()).unwrap();
+        let len = class_file.constant_pool.len() as constant_pool_index;
+        assert!(constant_pool[0].is_none());
+        assert!(constant_pool[len].is_some());
+        assert!(constant_pool[len + 1].is_none());
+    }
+
+    fn hello_world_constant_pool() -> RuntimeConstantPool {
+        let class_file = parse_class_file(include_bytes!("../../data/HelloWorld.class")).unwrap();
+        RuntimeConstantPool::new(&class_file.constant_pool, &mut Interner::new()).unwrap()
+    }
+
+    fn test_class_loader() -> BootstrapClassLoader {
+        let data_dir = ClassPathEntry::Directory(PathBuf::from("data/vm"));
+        BootstrapClassLoader::new(ClassPath::new(vec![data_dir]))
+    }
+
+    #[test]
+    fn test_resolve_symbolic_references() {
+        let constant_pool = hello_world_constant_pool();
+        let mut class_loader = test_class_loader();
+        // #6 = Class java/lang/Object
+        let object = constant_pool.resolve_class(6, &mut class_loader).unwrap();
+        assert_eq!(object.symref.sig, sig::Class::new("java/lang/Object"));
+        // #1 = Methodref java/lang/Object."<init>":()V
+        let (class, method) = constant_pool.resolve_method(1, &mut class_loader).unwrap();
+        assert!(Rc::ptr_eq(&class, &object));
+        assert_eq!(method.sig, sig::Method::new("<init>", "()V"));
+        // #2 = Fieldref java/lang/System.out:Ljava/io/PrintStream;
+        let (class, field) = constant_pool.resolve_field(2, &mut class_loader).unwrap();
+        assert_eq!(class.symref.sig, sig::Class::new("java/lang/System"));
+        assert_eq!(field.sig.name, "out");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a class reference at constant pool index 1")]
+    fn test_resolve_class_wrong_entry() {
+        let constant_pool = hello_world_constant_pool();
+        let _ = constant_pool.resolve_class(1, &mut test_class_loader());
+    }
+
+    #[test]
+    fn test_resolve_string() {
+        let constant_pool = hello_world_constant_pool();
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        // #3 = String "Hello, World"
+        let first = constant_pool.resolve_literal(3, &mut class_loader, &mut context).unwrap();
+        let live_count = context.heap.live_count();
+        let second = constant_pool.resolve_string(3, &mut class_loader, &mut context).unwrap();
+        assert_eq!(first.as_heap_ref(), second.as_heap_ref());
+        assert!(first.as_heap_ref().is_some());
+        assert_eq!(context.heap.live_count(), live_count);
+    }
+
+    #[test]
+    fn test_new_string_stack_overflow() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        context.set_max_call_depth(0);
+        let error = new_string(vec![], &mut class_loader, &mut context).unwrap_err();
+        assert_eq!(error.class_name, "java/lang/StackOverflowError");
+        assert!(error.exception.is_some());
     }
 }
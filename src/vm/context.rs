@@ -0,0 +1,249 @@
+//! The state of the virtual machine which every frame shares: the heap, and the state of the
+//! program which is not held in its objects.
+//!
+//! The heap only allocates and collects objects. Everything else which the interpreter must keep
+//! between instructions and across invocations, such as the `java/lang/Class` objects created for
+//! classes and the profile of the calls made, is kept here, alongside the heap.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use vm::class::Class;
+use vm::debug::Debugger;
+use vm::heap::{Heap, HeapRef};
+use vm::profiler::Profiler;
+use vm::sig;
+use vm::stack_trace::{StackTrace, StackTraceElement};
+
+#[derive(Debug)]
+/// The heap of the virtual machine, together with the state of the program which is not held in
+/// its objects.
+pub struct Context {
+    /// The heap on which every object created by the program is allocated.
+    pub heap: Heap,
+    /// The `java/lang/Class` objects which represent the classes loaded by the virtual machine.
+    /// These objects are never freed.
+    class_mirrors: HashMap<sig::Class, HeapRef>,
+    /// The `java/lang/Integer` objects which `Integer.valueOf` returns for small values, so that
+    /// boxing the same small value twice gives the same object. These objects are never freed.
+    boxed_integers: HashMap<i32, HeapRef>,
+    /// The stack traces of the exceptions which have been thrown, recorded as they propagate.
+    stack_traces: HashMap<HeapRef, StackTrace>,
+    /// The profiler recording each method invocation, if the program is being profiled.
+    profiler: Option<Rc<RefCell<Profiler>>>,
+    /// The debugger observing the program.
+    debugger: Debugger,
+    /// The number of method invocations currently in progress.
+    call_depth: usize,
+    /// The greatest number of method invocations which may be in progress at once, beyond which
+    /// invoking a method throws a `StackOverflowError`.
+    max_call_depth: usize,
+    /// The number of times the monitor of each object has been entered and not yet exited.
+    /// Objects whose monitors are not held have no entry.
+    monitors: HashMap<HeapRef, usize>,
+    /// The classes which have been initialized. The values of their static fields and the
+    /// `String` objects created for the literals in their constant pools are never freed.
+    classes: Vec<Rc<Class>>,
+}
+
+/// The default maximum call depth. Each invocation also recurses in the interpreter itself, using
+/// about 40KiB of native stack in an unoptimized build and 6KiB in an optimized one, so programs
+/// which may recurse this deeply should be run on a thread with a stack of `STACK_SIZE` bytes.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 500;
+
+/// The size of the native stack needed to invoke methods to the default maximum call depth, plus
+/// those needed to construct the `StackOverflowError`, in an unoptimized build.
+pub const STACK_SIZE: usize = 32 * 1024 * 1024;
+
+impl Context {
+    pub fn new() -> Self {
+        Context {
+            heap: Heap::new(),
+            class_mirrors: HashMap::new(),
+            boxed_integers: HashMap::new(),
+            stack_traces: HashMap::new(),
+            profiler: None,
+            debugger: Debugger::new(),
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            monitors: HashMap::new(),
+            classes: vec![],
+        }
+    }
+
+    /// Returns the `java/lang/Class` object representing the class with the given signature, if
+    /// one has been created.
+    pub fn class_mirror(&self, class_sig: &sig::Class) -> Option<HeapRef> {
+        self.class_mirrors.get(class_sig).cloned()
+    }
+
+    /// Record the `java/lang/Class` object representing the class with the given signature.
+    pub fn set_class_mirror(&mut self, class_sig: sig::Class, mirror: HeapRef) {
+        self.class_mirrors.insert(class_sig, mirror);
+    }
+
+    /// Returns the cached `java/lang/Integer` object holding the given value, if there is one.
+    pub fn boxed_integer(&self, value: i32) -> Option<HeapRef> {
+        self.boxed_integers.get(&value).cloned()
+    }
+
+    /// Cache the `java/lang/Integer` object holding the given value.
+    pub fn set_boxed_integer(&mut self, value: i32, integer: HeapRef) {
+        self.boxed_integers.insert(value, integer);
+    }
+
+    /// Record that an exception propagated out of the frame described by `element`.
+    pub fn push_stack_trace_element(&mut self, exception: HeapRef, element: StackTraceElement) {
+        self.stack_traces.entry(exception).or_default().elements.push(element);
+    }
+
+    /// Returns the frames which an exception has propagated out of so far, if any.
+    pub fn stack_trace(&self, exception: HeapRef) -> Option<&StackTrace> {
+        self.stack_traces.get(&exception)
+    }
+
+    pub fn profiler(&self) -> Option<&Rc<RefCell<Profiler>>> {
+        self.profiler.as_ref()
+    }
+
+    pub fn set_profiler(&mut self, profiler: Rc<RefCell<Profiler>>) {
+        self.profiler = Some(profiler);
+    }
+
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Record the start of a method invocation. Returns false, without recording it, if there
+    /// are already as many invocations in progress as the maximum call depth allows.
+    pub fn enter_call(&mut self) -> bool {
+        if self.call_depth >= self.max_call_depth {
+            false
+        } else {
+            self.call_depth += 1;
+            true
+        }
+    }
+
+    /// Record the end of a method invocation started by `enter_call`.
+    pub fn exit_call(&mut self) {
+        self.call_depth -= 1;
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Enter the monitor of an object. There is only a single thread of execution, so a monitor
+    /// is never contended, and entering it only counts one more entry.
+    pub fn enter_monitor(&mut self, heap_ref: HeapRef) {
+        *self.monitors.entry(heap_ref).or_insert(0) += 1;
+    }
+
+    /// Exit the monitor of an object, counting one fewer entry. Returns false if the monitor was
+    /// not held, in which case an `IllegalMonitorStateException` should be thrown.
+    pub fn exit_monitor(&mut self, heap_ref: HeapRef) -> bool {
+        match self.monitor_entry_count(heap_ref) {
+            0 => return false,
+            1 => {
+                self.monitors.remove(&heap_ref);
+            },
+            count => {
+                self.monitors.insert(heap_ref, count - 1);
+            },
+        }
+        true
+    }
+
+    /// Returns the number of times the monitor of an object has been entered and not yet exited.
+    pub fn monitor_entry_count(&self, heap_ref: HeapRef) -> usize {
+        self.monitors.get(&heap_ref).cloned().unwrap_or(0)
+    }
+
+    /// Record that a class is being initialized, so that the objects it refers to are kept alive.
+    pub fn add_class(&mut self, class: Rc<Class>) {
+        self.classes.push(class);
+    }
+
+    /// Free every object on the heap which is not reachable from one of the given roots, from a
+    /// class mirror or cached `Integer`, or from a static field or `String` literal of an
+    /// initialized class, and forget the state kept for the objects freed.
+    pub fn collect(&mut self, roots: &[HeapRef]) {
+        let mut roots = roots.to_vec();
+        roots.extend(self.class_mirrors.values());
+        roots.extend(self.boxed_integers.values());
+        for class in &self.classes {
+            roots.extend(class.static_references());
+            roots.extend(class.get_constant_pool().string_literals());
+        }
+        self.heap.collect(&roots);
+        let heap = &self.heap;
+        self.stack_traces.retain(|&exception, _| heap.is_live(exception));
+        self.monitors.retain(|&object, _| heap.is_live(object));
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use vm::heap::test::new_class;
+
+    #[test]
+    fn test_monitors() {
+        let mut context = Context::new();
+        let object = context.heap.alloc_scalar(new_class("java/lang/Object"));
+        assert!(!context.exit_monitor(object));
+        context.enter_monitor(object);
+        context.enter_monitor(object);
+        assert_eq!(context.monitor_entry_count(object), 2);
+        assert!(context.exit_monitor(object));
+        assert!(context.exit_monitor(object));
+        assert_eq!(context.monitor_entry_count(object), 0);
+        assert!(!context.exit_monitor(object));
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut context = Context::new();
+        let class = new_class("java/lang/Throwable");
+        let mirror = context.heap.alloc_scalar(class.clone());
+        context.set_class_mirror(sig::Class::new("java/lang/Throwable"), mirror);
+        let integer = context.heap.alloc_scalar(class.clone());
+        context.set_boxed_integer(7, integer);
+        let garbage = context.heap.alloc_scalar(class.clone());
+        let live = context.heap.alloc_scalar(class);
+        for &exception in &[garbage, live] {
+            context.push_stack_trace_element(exception, StackTraceElement {
+                class_name: String::from("Main"),
+                method_name: String::from("main"),
+                source_file: None,
+                line_number: None,
+            });
+        }
+        context.enter_monitor(garbage);
+        context.enter_monitor(live);
+        context.collect(&[live]);
+        assert_eq!(context.heap.live_count(), 3);
+        assert!(context.heap.is_live(mirror) && context.heap.is_live(integer));
+        assert!(context.stack_trace(garbage).is_none());
+        assert_eq!(context.stack_trace(live).unwrap().elements.len(), 1);
+        assert_eq!(context.monitors.len(), 1);
+        assert_eq!(context.monitor_entry_count(live), 1);
+    }
+}
@@ -0,0 +1,117 @@
+//! Hooks through which a debugger observes the program as the interpreter executes it.
+//!
+//! A `DebugAgent` is told about each instruction as it is about to execute while the interpreter
+//! is single-stepping, about each instruction at which a breakpoint is set, and about each
+//! exception as it is thrown. Classes are named by their binary names, and methods by their
+//! names alone.
+
+use std::fmt;
+
+use vm::sig;
+
+/// An observer of the execution of a program, such as the front end of a debugger.
+pub trait DebugAgent {
+    /// Called before each instruction is executed while single-stepping, with the source line
+    /// from which the instruction was compiled, if the class file records it.
+    fn on_step(&mut self, class: &str, method: &str, pc: u32, line: Option<u16>);
+
+    /// Called before an instruction at which a breakpoint is set is executed.
+    fn on_breakpoint(&mut self, class: &str, method: &str, pc: u32);
+
+    /// Called when an instruction throws an exception, before a handler for it is searched for.
+    /// An exception propagating out of an invoked method is reported only where it was thrown.
+    fn on_exception(&mut self, exception_class: &str, pc: u32);
+}
+
+/// A `DebugAgent` which prints every event to the standard output.
+pub struct PrintDebugAgent;
+
+impl DebugAgent for PrintDebugAgent {
+    fn on_step(&mut self, class: &str, method: &str, pc: u32, line: Option<u16>) {
+        match line {
+            Some(line) => println!("step: {}.{} pc {} (line {})", class.replace('/', "."), method,
+                                   pc, line),
+            None => println!("step: {}.{} pc {}", class.replace('/', "."), method, pc),
+        }
+    }
+
+    fn on_breakpoint(&mut self, class: &str, method: &str, pc: u32) {
+        println!("breakpoint: {}.{} pc {}", class.replace('/', "."), method, pc);
+    }
+
+    fn on_exception(&mut self, exception_class: &str, pc: u32) {
+        println!("exception: {} at pc {}", exception_class.replace('/', "."), pc);
+    }
+}
+
+/// An instruction at which execution is reported to the debug agent.
+struct Breakpoint {
+    class: sig::Class,
+    method: sig::Method,
+    pc: u16,
+}
+
+#[derive(Default)]
+/// The state of the debugger: the agent to notify, whether the interpreter is single-stepping,
+/// and the breakpoints which have been set.
+pub struct Debugger {
+    agent: Option<Box<dyn DebugAgent>>,
+    stepping: bool,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Debugger {{ attached: {}, stepping: {}, breakpoints: {} }}",
+               self.agent.is_some(), self.stepping, self.breakpoints.len())
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger::default()
+    }
+
+    /// Returns true if a debug agent has been attached.
+    pub fn is_attached(&self) -> bool {
+        self.agent.is_some()
+    }
+
+    pub fn set_agent(&mut self, agent: Box<dyn DebugAgent>) {
+        self.agent = Some(agent);
+    }
+
+    pub fn set_stepping(&mut self, stepping: bool) {
+        self.stepping = stepping;
+    }
+
+    /// Set a breakpoint at the instruction at `pc` in a method.
+    pub fn add_breakpoint(&mut self, class: sig::Class, method: sig::Method, pc: u16) {
+        self.breakpoints.push(Breakpoint { class: class, method: method, pc: pc });
+    }
+
+    /// Report the instruction at `pc` of a method, which is about to be executed.
+    pub fn before_instruction(&mut self, class: &sig::Class, method: &sig::Method, pc: u16,
+                              line: Option<u16>) {
+        let stepping = self.stepping;
+        let at_breakpoint = self.breakpoints.iter().any(|breakpoint| {
+            breakpoint.pc == pc && breakpoint.class == *class && breakpoint.method == *method
+        });
+        if let Some(ref mut agent) = self.agent {
+            if at_breakpoint {
+                agent.on_breakpoint(&class.name(), &method.name, pc as u32);
+            }
+            if stepping {
+                agent.on_step(&class.name(), &method.name, pc as u32, line);
+            }
+        }
+    }
+
+    /// Report an exception of the class with the given binary name thrown by the instruction at
+    /// `pc`.
+    pub fn exception_thrown(&mut self, exception_class: &str, pc: u16) {
+        if let Some(ref mut agent) = self.agent {
+            agent.on_exception(exception_class, pc as u32);
+        }
+    }
+}
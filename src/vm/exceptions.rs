@@ -0,0 +1,266 @@
+//! Construction of the Java exceptions thrown by the virtual machine itself, such as those thrown
+//! by instructions which access an array out of bounds.
+
+use std::panic;
+
+use vm::{sig, symref};
+use vm::class_loader::{self, ClassLoader};
+use vm::constant_pool;
+use vm::context::Context;
+use vm::frame::Exception;
+use vm::heap::Heap;
+use vm::java_lang;
+use vm::value::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A description of an exception for the virtual machine to throw: the binary name of its class,
+/// and its detail message, if it has one.
+pub struct ExceptionInfo {
+    pub class_name: String,
+    pub message: Option<String>,
+    /// The exception itself, if it has already been created on the heap, as when it was thrown by
+    /// Java code which the virtual machine invoked.
+    pub exception: Option<Exception>,
+}
+
+impl ExceptionInfo {
+    pub fn new(class_name: &str, message: Option<String>) -> Self {
+        ExceptionInfo {
+            class_name: String::from(class_name),
+            message: message,
+            exception: None,
+        }
+    }
+
+    /// Describe an exception which has already been thrown by Java code, so that Rust code can
+    /// propagate it as an error.
+    pub fn thrown(heap: &Heap, exception: Exception) -> Self {
+        ExceptionInfo {
+            class_name: heap.get_scalar(exception).get_class().symref.sig.name(),
+            message: java_lang::throwable_message(heap, exception),
+            exception: Some(exception),
+        }
+    }
+
+    /// Returns the described exception, ready to be thrown, creating an instance of it on the
+    /// heap unless one already exists. If the instance cannot be created, the exception raised
+    /// while trying is returned instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if creating the instance fails by raising another exception of the same class, as
+    /// when the class of a `NoClassDefFoundError` cannot itself be loaded.
+    pub fn instantiate(&self, class_loader: &mut dyn ClassLoader, context: &mut Context)
+                       -> Exception {
+        if let Some(exception) = self.exception {
+            return exception;
+        }
+        let message = self.message.as_ref().map(|message| &**message);
+        match create_exception(class_loader, context, &self.class_name, message) {
+            Ok(exception) => exception,
+            Err(ref error) if error.exception.is_none() && error.class_name == self.class_name =>
+                panic!("could not create {}: {:?}", self.class_name, error.message),
+            Err(error) => error.instantiate(class_loader, context),
+        }
+    }
+}
+
+/// A class which the virtual machine needs could not be loaded, which is reported to the program
+/// as a `NoClassDefFoundError`.
+impl From<class_loader::Error> for ExceptionInfo {
+    fn from(error: class_loader::Error) -> Self {
+        ExceptionInfo::new("java/lang/NoClassDefFoundError", Some(error.to_string()))
+    }
+}
+
+/// Throw the described exception from Rust code which cannot return it as an error. The exception
+/// unwinds the native stack to the nearest enclosing `catch_thrown`, such as the one around every
+/// native method, which returns it.
+pub fn throw(info: ExceptionInfo) -> ! {
+    panic::resume_unwind(Box::new(info))
+}
+
+/// Run `f`, returning the exception described by any call to `throw` within it as an error. Other
+/// panics continue to unwind.
+pub fn catch_thrown<T, F>(f: F) -> Result<T, ExceptionInfo>
+    where F: FnOnce() -> Result<T, ExceptionInfo> {
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => match payload.downcast::<ExceptionInfo>() {
+            Ok(info) => Err(*info),
+            Err(payload) => panic::resume_unwind(payload),
+        },
+    }
+}
+
+/// A `NullPointerException`, thrown when an instruction requires an object but is given `null`.
+pub fn create_npe() -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/NullPointerException", None)
+}
+
+/// An `ArrayIndexOutOfBoundsException`, thrown when an array of the given length is accessed at
+/// an index beyond its bounds.
+pub fn create_aiobe(index: i32, length: i32) -> ExceptionInfo {
+    let message = format!("Index {} out of bounds for length {}", index, length);
+    ExceptionInfo::new("java/lang/ArrayIndexOutOfBoundsException", Some(message))
+}
+
+/// An `ArrayIndexOutOfBoundsException` with the given detail message, for an access to a range
+/// of components, such as a copy by `System.arraycopy`, which is not within the array.
+pub fn create_array_range_out_of_bounds(message: String) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/ArrayIndexOutOfBoundsException", Some(message))
+}
+
+/// A `NegativeArraySizeException`, thrown when an array is created with a negative length.
+pub fn create_negative_array_size(length: i32) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/NegativeArraySizeException", Some(length.to_string()))
+}
+
+/// An `ArrayStoreException`, thrown when a value cannot be stored into an array.
+pub fn create_array_store(message: String) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/ArrayStoreException", Some(message))
+}
+
+/// A `StringIndexOutOfBoundsException`, thrown when a string is accessed at an index beyond its
+/// bounds.
+pub fn create_sioobe(index: i32) -> ExceptionInfo {
+    let message = format!("String index out of range: {}", index);
+    ExceptionInfo::new("java/lang/StringIndexOutOfBoundsException", Some(message))
+}
+
+/// A `CloneNotSupportedException`, thrown when an object whose class does not implement
+/// `Cloneable` is cloned. The class is given by its binary name.
+pub fn create_clone_not_supported(class_name: &str) -> ExceptionInfo {
+    let message = class_name.replace('/', ".");
+    ExceptionInfo::new("java/lang/CloneNotSupportedException", Some(message))
+}
+
+/// A `ClassCastException`, thrown when an object of the class `from` is cast to the class `to`,
+/// which it is not an instance of. Both classes are given by their binary names.
+pub fn create_class_cast(from: &str, to: &str) -> ExceptionInfo {
+    let message = format!("class {} cannot be cast to class {}", from.replace('/', "."),
+                          to.replace('/', "."));
+    ExceptionInfo::new("java/lang/ClassCastException", Some(message))
+}
+
+/// An `ArithmeticException`, thrown by integer division by zero.
+pub fn create_arithmetic(message: &str) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/ArithmeticException", Some(String::from(message)))
+}
+
+/// A `NumberFormatException`, thrown when a string cannot be parsed as a number.
+pub fn create_number_format(message: String) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/NumberFormatException", Some(message))
+}
+
+/// An `AbstractMethodError`, thrown when an `abstract` method is invoked. The method is given by
+/// its symbolic reference.
+pub fn create_abstract_method(method: &symref::Method) -> ExceptionInfo {
+    let message = format!("{}.{}{}", method.class.sig.name().replace('/', "."), method.sig.name,
+                          method.sig.descriptor());
+    ExceptionInfo::new("java/lang/AbstractMethodError", Some(message))
+}
+
+/// An `UnsatisfiedLinkError`, thrown when a `native` method is invoked which has no native
+/// implementation. The method is given by its symbolic reference.
+pub fn create_unsatisfied_link(method: &symref::Method) -> ExceptionInfo {
+    let message = format!("{}.{}{}", method.class.sig.name().replace('/', "."), method.sig.name,
+                          method.sig.descriptor());
+    ExceptionInfo::new("java/lang/UnsatisfiedLinkError", Some(message))
+}
+
+/// An `IncompatibleClassChangeError`, thrown when a class has changed incompatibly with the code
+/// using it, such as when the receiver of `invokeinterface` does not implement the interface.
+pub fn create_incompatible_class_change(message: String) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/IncompatibleClassChangeError", Some(message))
+}
+
+/// A `NoSuchFieldError`, thrown when a field cannot be resolved. The message is the name of the
+/// field.
+pub fn create_no_such_field(field: &sig::Field) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/NoSuchFieldError", Some(field.name.clone()))
+}
+
+/// A `NoSuchMethodError`, thrown when a method which the virtual machine needs to invoke does not
+/// exist. The message names the method by its symbolic reference.
+pub fn create_no_such_method(method: &symref::Method) -> ExceptionInfo {
+    let message = format!("{}.{}{}", method.class.sig.name().replace('/', "."), method.sig.name,
+                          method.sig.descriptor());
+    ExceptionInfo::new("java/lang/NoSuchMethodError", Some(message))
+}
+
+/// A `VerifyError`, thrown when the bytecode of a method is found to be malformed.
+pub fn create_verify(message: String) -> ExceptionInfo {
+    ExceptionInfo::new("java/lang/VerifyError", Some(message))
+}
+
+/// Create an instance of the named exception class with the given detail message, by calling its
+/// `(String)` constructor, or its no-argument constructor if there is no message. The exception
+/// class is initialized first if necessary. Returns the exception raised instead if the class
+/// cannot be loaded or has no such constructor, or if the constructor throws.
+pub fn create_exception(class_loader: &mut dyn ClassLoader, context: &mut Context, class_name: &str,
+                        message: Option<&str>) -> Result<Exception, ExceptionInfo> {
+    let class_symref = symref::Class { sig: sig::Class::new(class_name) };
+    let class = try!(class_loader.resolve_class(&class_symref));
+    class.initialize(class_loader, context);
+    let exception = context.heap.alloc_scalar(class.clone());
+    let mut args = vec![Value::ScalarReference(exception)];
+    let descriptor = match message {
+        Some(message) => {
+            let chars = message.encode_utf16().collect();
+            args.push(try!(constant_pool::new_string(chars, class_loader, context)));
+            "(Ljava/lang/String;)V"
+        },
+        None => "()V",
+    };
+
+    let constructor_symref = symref::Method {
+        class: class_symref,
+        sig: sig::Method::new("<init>", descriptor),
+    };
+    // constructors are not inherited, so the class must declare the constructor itself
+    let constructor = match class.find_method(&constructor_symref.sig) {
+        Some(constructor) if constructor.symref.class == constructor_symref.class => constructor,
+        _ => return Err(create_no_such_method(&constructor_symref)),
+    };
+    match constructor.invoke(class.as_ref(), class_loader, context, args) {
+        Ok(None) => Ok(exception),
+        Ok(Some(_)) => panic!("<init> returned a value!"),
+        Err(thrown) => Err(ExceptionInfo::thrown(&context.heap, thrown)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exception_info() {
+        assert_eq!(create_npe(), ExceptionInfo {
+            class_name: String::from("java/lang/NullPointerException"),
+            message: None,
+            exception: None,
+        });
+        let aiobe = create_aiobe(5, 3);
+        assert_eq!(aiobe.class_name, "java/lang/ArrayIndexOutOfBoundsException");
+        assert_eq!(aiobe.message.unwrap(), "Index 5 out of bounds for length 3");
+        let class_cast = create_class_cast("java/lang/Object", "java/lang/String");
+        assert_eq!(class_cast.message.unwrap(),
+                   "class java.lang.Object cannot be cast to class java.lang.String");
+        assert_eq!(create_arithmetic("/ by zero").message.unwrap(), "/ by zero");
+    }
+
+    #[test]
+    fn test_throw() {
+        let result: Result<(), _> = catch_thrown(|| throw(create_aiobe(-1, 0)));
+        assert_eq!(result, Err(create_aiobe(-1, 0)));
+        assert_eq!(catch_thrown(|| Err::<(), _>(create_npe())), Err(create_npe()));
+        assert_eq!(catch_thrown(|| Ok::<_, ExceptionInfo>(7)), Ok(7));
+    }
+
+    #[test]
+    #[should_panic(expected = "not an exception")]
+    fn test_catch_thrown_other_panic() {
+        let _ = catch_thrown(|| -> Result<(), ExceptionInfo> { panic!("not an exception") });
+    }
+}
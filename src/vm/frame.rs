@@ -10,20 +10,103 @@
 //! The operand stack is the central focus of the Java machine bytecode, and is directly
 //! manipulated by the bytecode instructions (in lieu of registers).
 
-use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::{error, fmt};
 use std::num::Wrapping;
 use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Sub};
 use std::rc::Rc;
 
-use model::class_file::access_flags::class_access_flags;
+use model::class_file::access_flags::{class_access_flags, method_access_flags};
+use model::class_file::attribute::{AttributeInfo, ExceptionTableEntry};
 
 use vm::{sig, symref};
 use vm::bytecode::opcode;
-use vm::class::Class;
+use vm::class::{Class, Method};
 use vm::class_loader::ClassLoader;
-use vm::constant_pool::RuntimeConstantPoolEntry;
+use vm::constant_pool::{RuntimeConstantPool, RuntimeConstantPoolEntry};
+use vm::context::Context;
+use vm::debug::Debugger;
+use vm::exceptions::{self, ExceptionInfo};
+use vm::heap::{Heap, HeapRef};
 use vm::sig::Type;
-use vm::value::{Array, Scalar, Value};
+use vm::source_map::SourceMap;
+use vm::stack::OperandStack;
+use vm::stack_trace::StackTraceElement;
+use vm::value::Value;
+
+/// Returns true if both values are references to the same object, or are both null.
+fn same_reference(v1: &Value, v2: &Value) -> bool {
+    match (v1, v2) {
+        (&Value::ScalarReference(x), &Value::ScalarReference(y)) => x == y,
+        (&Value::ArrayReference(x), &Value::ArrayReference(y)) => x == y,
+        (&Value::NullReference, &Value::NullReference) => true,
+        _ => false,
+    }
+}
+
+/// Returns the class of the object referred to by a value, or `None` if the value is `null`.
+fn class_of(value: &Value, heap: &Heap) -> Option<Rc<Class>> {
+    match *value {
+        Value::ScalarReference(object) => Some(heap.get_scalar(object).get_class()),
+        Value::ArrayReference(array) => Some(heap.get_array(array).get_class()),
+        Value::NullReference => None,
+        ref v => panic!("Expected a reference, but was {:?}", v),
+    }
+}
+
+/// An `AbstractMethodError` for a method which the class of the receiver neither declares nor
+/// inherits an implementation of.
+fn abstract_method_error(object_class: &Class, symref: &symref::Method) -> ExceptionInfo {
+    exceptions::create_abstract_method(&symref::Method {
+        class: object_class.symref.clone(),
+        sig: symref.sig.clone(),
+    })
+}
+
+/// Returns the heap reference held by a value, or a new `NullPointerException` to be thrown if the
+/// value is `null`.
+fn check_not_null(value: &Value, class_loader: &mut dyn ClassLoader, context: &mut Context)
+                  -> Result<HeapRef, Exception> {
+    match *value {
+        Value::ScalarReference(heap_ref) | Value::ArrayReference(heap_ref) => Ok(heap_ref),
+        Value::NullReference => Err(exceptions::create_npe().instantiate(class_loader, context)),
+        ref v => panic!("Expected a reference, but was {:?}", v),
+    }
+}
+
+/// Create a multidimensional array of the given array class. The first dimension of the array
+/// has the first of `lengths`, and each of its components is in turn created with the rest of
+/// `lengths`. Dimensions beyond those given by `lengths` are left as `null`.
+fn new_multi_array(class: Rc<Class>, lengths: &[i32], class_loader: &mut dyn ClassLoader,
+                   context: &mut Context) -> Value {
+    let component_class = match class.symref.sig {
+        sig::Class::Array(ref component_ty) if lengths.len() > 1 => match **component_ty {
+            Type::Reference(ref component_sig) => {
+                let component_symref = symref::Class { sig: component_sig.clone() };
+                Some(class_loader.resolve_class(&component_symref).unwrap())
+            },
+            _ => panic!("multianewarray has more dimensions than its array class"),
+        },
+        _ => None,
+    };
+    let array = context.heap.alloc_array(class, lengths[0]);
+    if let Some(component_class) = component_class {
+        for i in 0..lengths[0] {
+            let component = new_multi_array(component_class.clone(), &lengths[1..], class_loader,
+                                            context);
+            context.heap.get_array_mut(array).put(i, component);
+        }
+    }
+    Value::ArrayReference(array)
+}
+
+/// A Java exception (an instance of `java/lang/Throwable`) which has been thrown and is propagating
+/// up the call stack.
+pub type Exception = HeapRef;
+
+/// The outcome of invoking a method. A method either completes normally, returning a value unless
+/// it is `void`, or completes abruptly by throwing an exception which it does not catch.
+pub type MethodResult = Result<Option<Value>, Exception>;
 
 /// A frame is used to store data and partial results, as well as to perform dynamic linking,
 /// return values for methods, and dispatch exceptions.
@@ -33,6 +116,8 @@ pub struct Frame<'a> {
     current_class: &'a Class,
     /// The bytecode currently executing in this frame.
     code: &'a [u8],
+    /// The exception handlers of the current method, in the order in which they are searched.
+    exception_table: &'a [ExceptionTableEntry],
     /// The current program counter.
     pc: u16,
     /// The local variables of the current method.
@@ -40,19 +125,273 @@ pub struct Frame<'a> {
     /// `None` value in the subsequent index.
     local_variables: Vec<Option<Value>>,
     /// The operand stack manipulated by the instructions of the current method.
-    operand_stack: Vec<Value>,
+    operand_stack: OperandStack,
+    /// The method executing in this frame, if the frame is executing the code of a method.
+    method: Option<&'a Method>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry of a `LocalVariableTable` or `LocalVariableTypeTable` attribute: a local variable
+/// which has a name and type while the program counter is in `[start_pc, start_pc + length)`.
+pub struct LocalVariableDebugInfo {
+    pub start_pc: u16,
+    pub length: u16,
+    /// The index of the local variable in the frame.
+    pub index: u16,
+    pub name: Rc<str>,
+    /// A field descriptor for `LocalVariableTable` entries, or a generic field signature for
+    /// `LocalVariableTypeTable` entries.
+    pub ty: Rc<str>,
+}
+
+impl LocalVariableDebugInfo {
+    fn is_visible(&self, index: u16, pc: u16) -> bool {
+        self.index == index && pc >= self.start_pc
+            && (pc as u32) < self.start_pc as u32 + self.length as u32
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The debugging information about a method, from the `LineNumberTable`, `LocalVariableTable` and
+/// `LocalVariableTypeTable` attributes of its `Code` attribute (§4.7.12, §4.7.13, §4.7.14).
+pub struct FrameDebugInfo {
+    /// The lines of the source file from which the code of the method was compiled.
+    pub source_map: Option<SourceMap>,
+    /// The local variables, with their types given by field descriptors.
+    pub local_variables: Vec<LocalVariableDebugInfo>,
+    /// The local variables whose types use type variables or parameterized types, with their
+    /// types given by generic signatures.
+    pub local_variable_types: Vec<LocalVariableDebugInfo>,
+}
+
+impl FrameDebugInfo {
+    /// Collect the debugging information in the attributes of a `Code` attribute, or return
+    /// `None` if it has none of the `LineNumberTable`, `LocalVariableTable` and
+    /// `LocalVariableTypeTable` attributes.
+    pub fn new(code_attributes: &[AttributeInfo], constant_pool: &RuntimeConstantPool)
+               -> Option<Self> {
+        let mut debug_info = FrameDebugInfo::default();
+        let mut line_number_table = vec![];
+        let mut found = false;
+        for attribute in code_attributes {
+            match *attribute {
+                AttributeInfo::LineNumberTable { line_number_table: ref table } => {
+                    found = true;
+                    line_number_table.extend(table.iter().cloned());
+                },
+                AttributeInfo::LocalVariableTable { ref local_variable_table } => {
+                    found = true;
+                    debug_info.local_variables.extend(local_variable_table.iter().map(|info| {
+                        LocalVariableDebugInfo {
+                            start_pc: info.start_pc,
+                            length: info.length,
+                            index: info.index,
+                            name: constant_pool.lookup_raw_string(info.name_index),
+                            ty: constant_pool.lookup_raw_string(info.descriptor_index),
+                        }
+                    }));
+                },
+                AttributeInfo::LocalVariableTypeTable { ref local_variable_type_table } => {
+                    found = true;
+                    debug_info.local_variable_types.extend(
+                        local_variable_type_table.iter().map(|info| LocalVariableDebugInfo {
+                            start_pc: info.start_pc,
+                            length: info.length,
+                            index: info.index,
+                            name: constant_pool.lookup_raw_string(info.name_index),
+                            ty: constant_pool.lookup_raw_string(info.signature_index),
+                        }));
+                },
+                _ => (),
+            }
+        }
+        if !line_number_table.is_empty() {
+            debug_info.source_map = Some(SourceMap::new(&line_number_table));
+        }
+        if found { Some(debug_info) } else { None }
+    }
+
+    /// Returns the line of the source file which compiled to the instruction at `pc`.
+    pub fn line_number(&self, pc: u16) -> Option<u16> {
+        self.source_map.as_ref().and_then(|source_map| source_map.line_at(pc as u32))
+    }
+
+    /// Returns the name of the local variable at `index` when the program counter is `pc`.
+    pub fn local_name(&self, index: u16, pc: u16) -> Option<&str> {
+        find_local_variable(&self.local_variables, index, pc)
+            .or_else(|| find_local_variable(&self.local_variable_types, index, pc))
+            .map(|local| &*local.name)
+    }
+
+    /// Returns the field descriptor of the type of the local variable at `index` when the
+    /// program counter is `pc`.
+    pub fn local_type(&self, index: u16, pc: u16) -> Option<&str> {
+        find_local_variable(&self.local_variables, index, pc).map(|local| &*local.ty)
+    }
+
+    /// Returns the generic signature of the type of the local variable at `index` when the
+    /// program counter is `pc`, if its type uses type variables or parameterized types.
+    pub fn local_signature(&self, index: u16, pc: u16) -> Option<&str> {
+        find_local_variable(&self.local_variable_types, index, pc).map(|local| &*local.ty)
+    }
+}
+
+fn find_local_variable(locals: &[LocalVariableDebugInfo], index: u16, pc: u16)
+                       -> Option<&LocalVariableDebugInfo> {
+    locals.iter().find(|local| local.is_visible(index, pc))
+}
+
+/// Lay out the arguments of a method invocation in the local variables of its frame, followed by
+/// enough unset local variables to make up `max_locals`. Arguments of type `long` and `double`
+/// take up two local variables.
+pub fn local_variables(max_locals: u16, args: Vec<Value>) -> Vec<Option<Value>> {
+    let mut locals = Vec::with_capacity(max_locals as usize);
+    for value in args {
+        let is_category_2 = value.is_category_2();
+        locals.push(Some(value));
+        if is_category_2 {
+            locals.push(None);
+        }
+    }
+    if locals.len() > max_locals as usize {
+        panic!("method arguments do not fit in {} local variables", max_locals);
+    }
+    while locals.len() < max_locals as usize {
+        locals.push(None)
+    }
+    locals
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An error in accessing a local variable of a frame.
+pub enum LocalVariableError {
+    /// The index is beyond the local variables of the frame.
+    OutOfBounds { index: usize, max_locals: usize },
+    /// The local variable has not been assigned a value, or holds the second half of a `long` or
+    /// `double` value.
+    Unset { index: usize },
+}
+
+impl fmt::Display for LocalVariableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LocalVariableError::OutOfBounds { index, max_locals } =>
+                write!(f, "local variable {} is out of bounds for {} local variables", index,
+                       max_locals),
+            LocalVariableError::Unset { index } =>
+                write!(f, "local variable {} has not been set", index),
+        }
+    }
 }
 
+impl error::Error for LocalVariableError {}
+
 impl<'a> Frame<'a> {
+    /// Create a frame for executing `code`, whose operand stack may hold up to `max_stack` words.
     pub fn new(current_class: &'a Class, code: &'a [u8],
+               exception_table: &'a [ExceptionTableEntry], max_stack: u16,
                local_variables: Vec<Option<Value>>) -> Self {
         Frame {
             current_class: current_class,
             code: code,
+            exception_table: exception_table,
             pc: 0,
             local_variables: local_variables,
-            operand_stack: vec![],
+            operand_stack: OperandStack::new(max_stack as usize),
+            method: None,
+        }
+    }
+
+    /// Set the method whose code is executing in this frame.
+    pub fn set_method(&mut self, method: &'a Method) {
+        self.method = Some(method);
+    }
+
+    /// Returns the debugging information of the method executing in this frame, if its class file
+    /// records any.
+    pub fn debug_info(&self) -> Option<&'a FrameDebugInfo> {
+        self.method.and_then(|method| method.debug_info())
+    }
+
+    /// Describe this frame for the stack trace of an exception which propagates out of it while
+    /// executing the instruction at `pc`. Returns `None` if the frame is not executing the code
+    /// of a method.
+    fn stack_trace_element(&self, pc: u16) -> Option<StackTraceElement> {
+        self.method.map(|method| StackTraceElement {
+            class_name: self.current_class.symref.sig.name(),
+            method_name: method.symref.sig.name.clone(),
+            source_file: self.current_class.source_file.as_ref().map(|name| String::from(&**name)),
+            line_number: self.debug_info().and_then(|debug_info| debug_info.line_number(pc)),
+        })
+    }
+
+    /// Report the instruction at `pc`, which is about to be executed, to the debugger.
+    fn notify_debugger(&self, pc: u16, debugger: &mut Debugger) {
+        if let Some(method) = self.method {
+            let line = self.debug_info().and_then(|debug_info| debug_info.line_number(pc));
+            debugger.before_instruction(&self.current_class.symref.sig, &method.symref.sig, pc,
+                                        line);
+        }
+    }
+
+    /// Returns the name of the local variable at `index` when the program counter is `pc`, if the
+    /// class file records it.
+    pub fn local_name(&self, index: u16, pc: u16) -> Option<&'a str> {
+        self.debug_info().and_then(|debug_info| debug_info.local_name(index, pc))
+    }
+
+    /// Returns the field descriptor of the type of the local variable at `index` when the program
+    /// counter is `pc`, if the class file records it.
+    pub fn local_type(&self, index: u16, pc: u16) -> Option<&'a str> {
+        self.debug_info().and_then(|debug_info| debug_info.local_type(index, pc))
+    }
+
+    /// Push a value onto the operand stack. Panics if this would exceed `max_stack`.
+    pub fn push(&mut self, value: Value) {
+        self.operand_stack.push(value);
+    }
+
+    /// Pop a value from the operand stack. Panics if the stack is empty.
+    pub fn pop(&mut self) -> Value {
+        self.operand_stack.pop().expect("operand stack underflow")
+    }
+
+    /// Returns the value of a local variable.
+    pub fn load(&self, index: usize) -> Result<Value, LocalVariableError> {
+        match self.local_variables.get(index) {
+            Some(&Some(ref value)) => Ok(value.clone()),
+            Some(&None) => Err(LocalVariableError::Unset { index: index }),
+            None => Err(LocalVariableError::OutOfBounds {
+                index: index,
+                max_locals: self.local_variables.len(),
+            }),
+        }
+    }
+
+    /// Set the value of a local variable. A `long` or `double` value also takes up the local
+    /// variable after it, and storing over either half of such a value unsets the other half.
+    pub fn store(&mut self, index: usize, value: Value) -> Result<(), LocalVariableError> {
+        let end_index = index + value.size_in_words();
+        if end_index > self.local_variables.len() {
+            return Err(LocalVariableError::OutOfBounds {
+                index: end_index - 1,
+                max_locals: self.local_variables.len(),
+            });
+        }
+        if index > 0 {
+            let overwrites_category_2 = match self.local_variables[index - 1] {
+                Some(ref prev_value) => prev_value.is_category_2(),
+                None => false,
+            };
+            if overwrites_category_2 {
+                self.local_variables[index - 1] = None;
+            }
+        }
+        if end_index == index + 2 {
+            self.local_variables[index + 1] = None;
         }
+        self.local_variables[index] = Some(value);
+        Ok(())
     }
 
     /// Read a byte (`u8`) value and advance the program counter.
@@ -67,16 +406,45 @@ impl<'a> Frame<'a> {
         ((self.read_next_byte() as u16) << 8) | (self.read_next_byte() as u16)
     }
 
-    /// Remove `count` items from the operand stack.
-    fn pop_multi(&mut self, count: usize) -> Vec<Value> {
-        let start_index = self.operand_stack.len() - count;
-        self.operand_stack.drain(start_index..).collect()
+    /// Read an int (`u32`) value and advance the program counter by 4.
+    fn read_next_int(&mut self) -> u32 {
+        ((self.read_next_short() as u32) << 16) | (self.read_next_short() as u32)
+    }
+
+    /// Skip the 0-3 bytes of padding following a `tableswitch` or `lookupswitch` opcode, which
+    /// align its operands to a multiple of 4 bytes from the start of the code.
+    fn skip_switch_padding(&mut self) {
+        self.pc += (4 - self.pc % 4) % 4;
+    }
+
+    /// Resolve the class referred to by the constant pool entry at `index` of the current class.
+    fn resolve_class_ref(&self, index: u16, class_loader: &mut dyn ClassLoader) -> Rc<Class> {
+        self.current_class.get_constant_pool().resolve_class(index, class_loader).unwrap()
+    }
+
+    /// Find the handler for an exception thrown by the instruction at `pc`, returning the address
+    /// of the handler, or `None` if the exception is not caught by the current method. Handlers
+    /// with a `catch_type` of zero catch every exception.
+    fn find_handler(&self, pc: u16, exception: Exception, class_loader: &mut dyn ClassLoader,
+                    heap: &Heap) -> Option<u16> {
+        let exception_class = heap.get_scalar(exception).get_class();
+        let constant_pool = self.current_class.get_constant_pool();
+        self.exception_table.iter().find(|entry| {
+            entry.start_pc <= pc && pc < entry.end_pc && (entry.catch_type == 0 || {
+                let catch_class =
+                    constant_pool.resolve_class(entry.catch_type, class_loader).unwrap();
+                exception_class.is_descendant(catch_class.as_ref())
+            })
+        }).map(|entry| entry.handler_pc)
     }
 
     /// Execute the method associated with this stack frame in the context of the currrent class
-    /// loader, and return a result if there is one. This method may create new stack frames as a
-    /// result of evaluating `invoke*` instructions.
-    pub fn run(mut self, class_loader: &mut ClassLoader) -> Option<Value> {
+    /// loader, and return a result if there is one. Objects are allocated on the given heap. This
+    /// method may create new stack frames as a result of evaluating `invoke*` instructions. If an
+    /// exception is thrown and not caught by this method, it is returned as an `Err` to be
+    /// rethrown in the calling frame.
+    pub fn run(mut self, class_loader: &mut dyn ClassLoader, context: &mut Context)
+               -> MethodResult {
         macro_rules! pop {
             () => (self.operand_stack.pop().unwrap());
             ($value_variant: path) => ({
@@ -88,20 +456,85 @@ impl<'a> Frame<'a> {
             });
         }
 
-        macro_rules! pop_not_null {
-            () => ({
-                match pop!() {
-                    Value::NullReference => panic!(
-                        "NullPointerException: expected {} but was null",
-                        stringify!($value_variant)),
-                    v => v,
+        macro_rules! push {
+            ($v: expr) => ({
+                let v = $v;     // satisfy the borrow checker
+                self.push(v);
+            });
+        }
+
+        // the address of the instruction currently executing
+        let mut instruction_pc;
+
+        // Transfer control to the handler for an exception thrown by the current instruction, or
+        // complete the method abruptly if there is none.
+        macro_rules! throw {
+            ($exception: expr) => ({
+                let exception = $exception;
+                // an exception which has not yet propagated out of a frame was thrown here
+                if context.debugger().is_attached()
+                        && context.stack_trace(exception).is_none() {
+                    let exception_class = context.heap.get_scalar(exception).get_class();
+                    let class_name = exception_class.symref.sig.name();
+                    context.debugger_mut().exception_thrown(&class_name, instruction_pc);
+                }
+                match self.find_handler(instruction_pc, exception, class_loader, &context.heap) {
+                    Some(handler_pc) => {
+                        self.operand_stack.clear();
+                        push!(Value::ScalarReference(exception));
+                        self.pc = handler_pc;
+                    },
+                    None => {
+                        if let Some(element) = self.stack_trace_element(instruction_pc) {
+                            context.push_stack_trace_element(exception, element);
+                        }
+                        return Err(exception);
+                    },
+                }
+            });
+        }
+
+        // Create the exception described by an `ExceptionInfo` and throw it.
+        macro_rules! throw_new {
+            ($info: expr) => ({
+                let info: ExceptionInfo = $info;
+                throw!(info.instantiate(class_loader, context));
+            });
+        }
+
+        // Evaluate to the heap reference held by a value, or throw a `NullPointerException` and
+        // move on to the next instruction if the value is null.
+        macro_rules! not_null {
+            ($value: expr) => ({
+                match check_not_null($value, class_loader, context) {
+                    Ok(heap_ref) => heap_ref,
+                    Err(exception) => {
+                        throw!(exception);
+                        continue;
+                    },
                 }
             });
+        }
+
+        // Evaluate to the value held by a result, or throw the exception described by its error
+        // and move on to the next instruction.
+        macro_rules! or_throw {
+            ($result: expr) => ({
+                match $result {
+                    Ok(value) => value,
+                    Err(error) => {
+                        throw_new!(ExceptionInfo::from(error));
+                        continue;
+                    },
+                }
+            });
+        }
+
+        macro_rules! pop_not_null {
             ($value_variant: path) => ({
-                match pop!() {
-                    Value::NullReference => panic!(
-                        "NullPointerException: expected {} but was null",
-                        stringify!($value_variant)),
+                let value = pop!();
+                not_null!(&value);
+                match value {
                     $value_variant(v) => v,
                     v => panic!("Expected to pop a value of type {}, but was {:?}",
                                 stringify!($value_variant), v),
@@ -109,14 +542,15 @@ impl<'a> Frame<'a> {
             });
         }
 
-        macro_rules! push {
-            ($v: expr) => ({
-                let v = $v;     // satisfy the borrow checker
-                self.operand_stack.push(v);
+        // Push the value returned by a method invocation, or rethrow its exception.
+        macro_rules! push_result {
+            ($result: expr) => ({
+                match $result {
+                    Ok(None) => (),
+                    Ok(Some(value)) => push!(value),
+                    Err(exception) => throw!(exception),
+                }
             });
-            ($($vs: expr),*) => ({
-                self.operand_stack.extend_from_slice(&[$($vs),*])
-            })
         }
 
         macro_rules! with {
@@ -126,50 +560,47 @@ impl<'a> Frame<'a> {
             })
         }
 
-        macro_rules! do_ipush {
-            ($value: ident) => (push!(Value::Int(Wrapping($value as i32))))
-        }
-
         macro_rules! do_ldc {
             ($index: ident) => ({
-                let value = self.current_class.get_constant_pool()
-                    .resolve_literal($index, class_loader).unwrap();
-                push!(value);
+                match self.current_class.get_constant_pool()
+                        .resolve_literal($index, class_loader, context) {
+                    Ok(value) => push!(value),
+                    Err(info) => throw_new!(info),
+                }
             });
         }
 
         macro_rules! do_load {
             ($index: expr) => ({
-                let value = self.local_variables[$index as usize].clone().unwrap();
-                push!(value);
+                match self.load($index as usize) {
+                    Ok(value) => push!(value),
+                    Err(error) => {
+                        let index = $index as u16;
+                        match (self.local_name(index, self.pc), self.local_type(index, self.pc)) {
+                            (Some(name), Some(ty)) => panic!("{} ({}: {})", error, name, ty),
+                            (Some(name), None) => panic!("{} ({})", error, name),
+                            _ => panic!("{}", error),
+                        }
+                    },
+                }
             })
         }
 
         macro_rules! do_store {
             ($index: expr) => ({
-                let value = self.operand_stack.pop().unwrap();
-                // invalidate the slot after this one if we're storing a category 2 operand
-                match value {
-                    Value::Int(_) | Value::Float(_) | Value::ScalarReference(_)
-                            | Value::ArrayReference(_) | Value::NullReference => (),
-                    Value::Long(_) | Value::Double(_) => {
-                        self.local_variables[($index + 1) as usize] = None;
-                    },
+                let value = self.pop();
+                if let Err(error) = self.store($index as usize, value) {
+                    panic!("{}", error);
                 }
-                // actually store the local variable
-                self.local_variables[$index as usize] = Some(value);
-                // invalidate the slot before this one if it was formerly storing a category 2
-                // operand
-                let prev_index = $index - 1;
-                if prev_index > 0 {
-                    match self.local_variables[prev_index as usize] {
-                        None | Some(Value::Int(_)) | Some(Value::Float(_))
-                                | Some(Value::ScalarReference(_)) | Some(Value::ArrayReference(_))
-                                | Some(Value::NullReference) => (),
-                        Some(Value::Long(_)) | Some(Value::Double(_)) => {
-                            self.local_variables[prev_index as usize] = None;
-                        },
-                    }
+            })
+        }
+
+        macro_rules! do_iinc {
+            ($index: expr, $c: expr) => ({
+                match self.local_variables[$index as usize] {
+                    Some(Value::Int(ref mut v)) => *v += Wrapping($c),
+                    Some(ref v) => panic!("IINC: Expected an int, but was {:?}", v),
+                    None => panic!("IINC: Not a local variable at index {}", $index),
                 }
             })
         }
@@ -182,6 +613,22 @@ impl<'a> Frame<'a> {
             });
         }
 
+        // Like `do_binop`, but for integer division and remainder, which throw an
+        // `ArithmeticException` if the divisor is zero.
+        macro_rules! do_int_division {
+            ($value_variant: path, $binop: expr) => ({
+                let v2 = pop!($value_variant);
+                let v1 = pop!($value_variant);
+                if v2 == Wrapping(0) {
+                    throw_new!(exceptions::create_arithmetic("/ by zero"));
+                } else {
+                    // the wrapping operations give the results required for `MIN / -1` and
+                    // `MIN % -1`, which overflow
+                    push!($value_variant($binop(v1, v2)));
+                }
+            });
+        }
+
         macro_rules! do_if_icmp {
             ($cmp_op: expr) => ({
                 let branch_offset = self.read_next_short() as i16;
@@ -207,6 +654,58 @@ impl<'a> Frame<'a> {
             });
         }
 
+        macro_rules! do_if_acmp {
+            ($pred: expr) => ({
+                let branch_offset = self.read_next_short() as i16;
+                let v2 = pop!();
+                let v1 = pop!();
+                if $pred(&v1, &v2) {
+                    // 3 byte long instruction; read* operations move the PC.
+                    let this_pc_start = self.pc - 3;
+                    self.pc = (this_pc_start as i32 + branch_offset as i32) as u16
+                }
+            });
+        }
+
+        // Pushes -1, 0 or 1 as the first of two values is less than, equal to or greater than the
+        // second. If either value is NaN the two are unordered, and `$nan` is pushed instead.
+        macro_rules! do_compare {
+            ($value_variant: path) => (do_compare!($value_variant, unreachable!()));
+            ($value_variant: path, $nan: expr) => ({
+                let v2 = pop!($value_variant);
+                let v1 = pop!($value_variant);
+                let result = match v1.partial_cmp(&v2) {
+                    Some(Ordering::Less) => -1,
+                    Some(Ordering::Equal) => 0,
+                    Some(Ordering::Greater) => 1,
+                    None => $nan,
+                };
+                push!(Value::Int(Wrapping(result)));
+            });
+        }
+
+        // Push the address of the next instruction and branch to a subroutine, at an offset from
+        // the start of the `jsr` or `jsr_w` instruction.
+        macro_rules! do_jsr {
+            ($branch_offset: expr, $instruction_length: expr) => ({
+                let branch_offset: i32 = $branch_offset;
+                push!(Value::ReturnAddress(self.pc as u32));
+                let this_pc_start = self.pc - $instruction_length;
+                self.pc = (this_pc_start as i32).wrapping_add(branch_offset) as u16;
+            });
+        }
+
+        // Return from a subroutine to the address held in a local variable.
+        macro_rules! do_ret {
+            ($index: expr) => ({
+                match self.local_variables[$index as usize] {
+                    Some(Value::ReturnAddress(address)) => self.pc = address as u16,
+                    ref v => panic!("expected a return address in local variable {}, but was {:?}",
+                                    $index, v),
+                }
+            });
+        }
+
         macro_rules! map_top {
             ($pat: pat, $result: expr) => ({
                 match pop!() {
@@ -218,6 +717,10 @@ impl<'a> Frame<'a> {
         }
 
         loop {
+            instruction_pc = self.pc;
+            if context.debugger().is_attached() {
+                self.notify_debugger(instruction_pc, context.debugger_mut());
+            }
             match self.read_next_byte() {
                 opcode::NOP => (),
                 opcode::ACONST_NULL => push!(Value::NullReference),
@@ -235,8 +738,9 @@ impl<'a> Frame<'a> {
                 opcode::FCONST_2 => push!(Value::Float(2.0)),
                 opcode::DCONST_0 => push!(Value::Double(0.0)),
                 opcode::DCONST_1 => push!(Value::Double(1.0)),
-                opcode::BIPUSH => with!(read_next_byte, do_ipush),
-                opcode::SIPUSH => with!(read_next_short, do_ipush),
+                // the immediate operands are sign-extended to an int
+                opcode::BIPUSH => push!(Value::Int(Wrapping(self.read_next_byte() as i8 as i32))),
+                opcode::SIPUSH => push!(Value::Int(Wrapping(self.read_next_short() as i16 as i32))),
                 opcode::LDC => with!(read_next_byte, do_ldc),
                 opcode::LDC_W | opcode::LDC2_W => with!(read_next_short, do_ldc),
 
@@ -258,8 +762,13 @@ impl<'a> Frame<'a> {
                 opcode::IALOAD | opcode::LALOAD | opcode::FALOAD | opcode::DALOAD
                         | opcode::AALOAD | opcode::BALOAD | opcode::CALOAD | opcode::SALOAD => {
                     let Wrapping(index) = pop!(Value::Int);
-                    let array_rc = pop_not_null!(Value::ArrayReference);
-                    push!(array_rc.borrow_mut().get(index));
+                    let array = pop_not_null!(Value::ArrayReference);
+                    let length = context.heap.get_array(array).len();
+                    if index < 0 || index >= length {
+                        throw_new!(exceptions::create_aiobe(index, length));
+                    } else {
+                        push!(context.heap.get_array(array).get(index));
+                    }
                 },
 
                 // same thing here
@@ -280,66 +789,29 @@ impl<'a> Frame<'a> {
                 opcode::IASTORE | opcode::LASTORE | opcode::FASTORE | opcode::DASTORE | opcode::AASTORE | opcode::BASTORE | opcode::CASTORE | opcode::SASTORE => {
                     let value = pop!();
                     let Wrapping(index) = pop!(Value::Int);
-                    let array_rc = pop_not_null!(Value::ArrayReference);
-                    array_rc.borrow_mut().put(index, value);
+                    let array = pop_not_null!(Value::ArrayReference);
+                    let length = context.heap.get_array(array).len();
+                    if index < 0 || index >= length {
+                        throw_new!(exceptions::create_aiobe(index, length));
+                    } else {
+                        context.heap.get_array_mut(array).put(index, value);
+                    }
                 },
 
+                // these operate on words of the stack, where long and double values are two words
                 opcode::POP => {
-                    pop!();
+                    self.operand_stack.pop_words(1);
                 },
                 opcode::POP2 => {
-                    match pop!() {
-                        Value::Long(_) | Value::Double(_) => (),
-                        _ => {
-                            pop!();
-                        }
-                    }
-                },
-                opcode::DUP => {
-                    let value = self.operand_stack.last().unwrap().clone();
-                    push!(value);
-                },
-                opcode::DUP_X1 => {
-                    let value1 = pop!();
-                    let value2 = pop!();
-
-                    push!(value1.clone(), value2, value1);
-                },
-                opcode::DUP_X2 => {
-                    let value1 = pop!();
-                    let value2 = pop!();
-                    match value2 {
-                        Value::Long(_) | Value::Double(_) => {
-                            push!(value1.clone(), value2, value1);
-                        },
-                        _ => {
-                            let value3 = pop!();
-                            push!(value1.clone(), value3, value2, value1);
-                        },
-                    }
-                },
-                opcode::DUP2 => {
-                    let value1 = pop!();
-                    match value1 {
-                        Value::Long(_) | Value::Double(_) => {
-                            push!(value1.clone(), value1);
-                        },
-                        _ => {
-                            let value2 = pop!();
-                            push!(value2.clone(), value1.clone(), value2, value1);
-                        },
-                    }
-                },
-                opcode::DUP2_X1 => unimplemented!(),
-                opcode::DUP2_X2 => unimplemented!(),
-
-                opcode::SWAP => {
-                    // both values need to be category 1
-                    let v1 = pop!();
-                    let v2 = pop!();
-                    push!(v1);
-                    push!(v2);
+                    self.operand_stack.pop_words(2);
                 },
+                opcode::DUP => self.operand_stack.dup(1, 0),
+                opcode::DUP_X1 => self.operand_stack.dup(1, 1),
+                opcode::DUP_X2 => self.operand_stack.dup(1, 2),
+                opcode::DUP2 => self.operand_stack.dup(2, 0),
+                opcode::DUP2_X1 => self.operand_stack.dup(2, 1),
+                opcode::DUP2_X2 => self.operand_stack.dup(2, 2),
+                opcode::SWAP => self.operand_stack.swap(),
 
                 opcode::IADD => do_binop!(Value::Int, Wrapping::<i32>::add),
                 opcode::LADD => do_binop!(Value::Long, Wrapping::<i64>::add),
@@ -353,19 +825,28 @@ impl<'a> Frame<'a> {
                 opcode::LMUL => do_binop!(Value::Long, Wrapping::<i64>::mul),
                 opcode::FMUL => do_binop!(Value::Float, f32::mul),
                 opcode::DMUL => do_binop!(Value::Double, f64::mul),
-                opcode::IDIV => do_binop!(Value::Int, Wrapping::<i32>::div),
-                opcode::LDIV => do_binop!(Value::Long, Wrapping::<i64>::div),
+                opcode::IDIV => do_int_division!(Value::Int, Wrapping::<i32>::div),
+                opcode::LDIV => do_int_division!(Value::Long, Wrapping::<i64>::div),
                 opcode::FDIV => do_binop!(Value::Float, f32::div),
                 opcode::DDIV => do_binop!(Value::Double, f64::div),
-                opcode::IREM => do_binop!(Value::Int, Wrapping::<i32>::rem),
-                opcode::LREM => do_binop!(Value::Long, Wrapping::<i64>::rem),
+                // the remainder takes the sign of the dividend, so that `(a / b) * b + a % b == a`,
+                // which is also how Rust's `%` behaves
+                opcode::IREM => do_int_division!(Value::Int, Wrapping::<i32>::rem),
+                opcode::LREM => do_int_division!(Value::Long, Wrapping::<i64>::rem),
                 opcode::FREM => do_binop!(Value::Float, f32::rem),
                 opcode::DREM => do_binop!(Value::Double, f64::rem),
                 // Issue #33037: Neg is missing for Wrapping
                 opcode::INEG => push!(Value::Int(!pop!(Value::Int) + Wrapping(1))),
                 opcode::LNEG => push!(Value::Long(!pop!(Value::Long) + Wrapping(1))),
-                opcode::FNEG => push!(Value::Float(-pop!(Value::Float))),
-                opcode::DNEG => push!(Value::Double(-pop!(Value::Double))),
+                // NaN has no sign, so negating it leaves it unchanged
+                opcode::FNEG => {
+                    let value = pop!(Value::Float);
+                    push!(Value::Float(if value.is_nan() { value } else { -value }));
+                },
+                opcode::DNEG => {
+                    let value = pop!(Value::Double);
+                    push!(Value::Double(if value.is_nan() { value } else { -value }));
+                },
                 opcode::ISHL => {
                     let Wrapping(s) = pop!(Value::Int);
                     let v = pop!(Value::Int);
@@ -405,13 +886,11 @@ impl<'a> Frame<'a> {
                 opcode::IINC => {
                     let index = self.read_next_byte();
                     let c = self.read_next_byte() as i8 as i32;
-                    match self.local_variables[index as usize] {
-                        Some(Value::Int(ref mut v)) => *v += Wrapping(c),
-                        Some(ref v) => panic!("IINC: Expected an int, but was {:?}", v),
-                        None => panic!("IINC: Not a local variable at index {}", index),
-                    }
+                    do_iinc!(index, c);
                 },
 
+                // `as` casts from floating-point to integral types saturate, and convert NaN to
+                // zero, which is exactly the behavior required of f2i, f2l, d2i and d2l
                 opcode::I2L => map_top!(Value::Int(Wrapping(n)), Value::Long(Wrapping(n as i64))),
                 opcode::I2F => map_top!(Value::Int(Wrapping(n)), Value::Float(n as f32)),
                 opcode::I2D => map_top!(Value::Int(Wrapping(n)), Value::Double(n as f64)),
@@ -428,6 +907,12 @@ impl<'a> Frame<'a> {
                 opcode::I2C => map_top!(Value::Int(Wrapping(n)), Value::Int(Wrapping(n as u16 as i32))),
                 opcode::I2S => map_top!(Value::Int(Wrapping(n)), Value::Int(Wrapping(n as i16 as i32))),
 
+                opcode::LCMP => do_compare!(Value::Long),
+                opcode::FCMPL => do_compare!(Value::Float, -1),
+                opcode::FCMPG => do_compare!(Value::Float, 1),
+                opcode::DCMPL => do_compare!(Value::Double, -1),
+                opcode::DCMPG => do_compare!(Value::Double, 1),
+
                 opcode::IFEQ => do_if_int!(Wrapping::<i32>::eq),
                 opcode::IFNE => do_if_int!(Wrapping::<i32>::ne),
                 opcode::IFLT => do_if_int!(Wrapping::<i32>::lt),
@@ -442,50 +927,8 @@ impl<'a> Frame<'a> {
                 opcode::IF_ICMPGE => do_if_icmp!(Wrapping::<i32>::ge),
                 opcode::IF_ICMPLE => do_if_icmp!(Wrapping::<i32>::le),
 
-                opcode::IF_ACMPEQ => {
-                    let branch_offset = self.read_next_short() as i16;
-                    let v2 = pop!();
-                    let v1 = pop!();
-                    match (v1, v2) {
-                        (Value::ArrayReference(x), Value::ArrayReference(y)) => {
-                            if x.as_ref() as *const RefCell<_> == y.as_ref() as *const RefCell<_> {
-                                // 3 byte long instruction; read* operations move the PC.
-                                let this_pc_start = self.pc - 3;
-                                self.pc = (this_pc_start as i32 + branch_offset as i32) as u16;
-                            }
-                        },
-                        (Value::ScalarReference(x), Value::ScalarReference(y)) => {
-                            if x.as_ref() as *const RefCell<_> == y.as_ref() as *const RefCell<_> {
-                                // 3 byte long instruction; read* operations move the PC.
-                                let this_pc_start = self.pc - 3;
-                                self.pc = (this_pc_start as i32 + branch_offset as i32) as u16;
-                            }
-                        }
-                        _ => (),
-                    }
-                },
-                opcode::IF_ACMPNE => {
-                    let branch_offset = self.read_next_short() as i16;
-                    let v2 = pop!();
-                    let v1 = pop!();
-                    match (v1, v2) {
-                        (Value::ArrayReference(x), Value::ArrayReference(y)) => {
-                            if x.as_ref() as *const RefCell<_> != y.as_ref() as *const RefCell<_> {
-                                // 3 byte long instruction; read* operations move the PC.
-                                let this_pc_start = self.pc - 3;
-                                self.pc = (this_pc_start as i32 + branch_offset as i32) as u16;
-                            }
-                        },
-                        (Value::ScalarReference(x), Value::ScalarReference(y)) => {
-                            if x.as_ref() as *const RefCell<_> != y.as_ref() as *const RefCell<_> {
-                                // 3 byte long instruction; read* operations move the PC.
-                                let this_pc_start = self.pc - 3;
-                                self.pc = (this_pc_start as i32 + branch_offset as i32) as u16;
-                            }
-                        },
-                        _ => (),
-                    }
-                },
+                opcode::IF_ACMPEQ => do_if_acmp!(same_reference),
+                opcode::IF_ACMPNE => do_if_acmp!(|v1, v2| !same_reference(v1, v2)),
 
                 opcode::GOTO => {
                     let branch_offset = self.read_next_short() as i16;
@@ -493,187 +936,229 @@ impl<'a> Frame<'a> {
                     self.pc = (this_pc_start as i32 + branch_offset as i32) as u16;
                 },
 
-                opcode::JSR => unimplemented!(),
-                opcode::RET => unimplemented!(),
+                opcode::JSR => do_jsr!(self.read_next_short() as i16 as i32, 3),
+                opcode::RET => with!(read_next_byte, do_ret),
 
-                opcode::TABLESWITCH => unimplemented!(),
-                opcode::LOOKUPSWITCH => unimplemented!(),
+                opcode::TABLESWITCH => {
+                    let this_pc_start = self.pc - 1;
+                    self.skip_switch_padding();
+                    let default = self.read_next_int() as i32;
+                    let low = self.read_next_int() as i32;
+                    let high = self.read_next_int() as i32;
+                    let Wrapping(index) = pop!(Value::Int);
+                    let branch_offset = if index < low || index > high {
+                        default
+                    } else {
+                        self.pc += ((index - low) * 4) as u16;
+                        self.read_next_int() as i32
+                    };
+                    self.pc = (this_pc_start as i32 + branch_offset) as u16;
+                },
+                opcode::LOOKUPSWITCH => {
+                    let this_pc_start = self.pc - 1;
+                    self.skip_switch_padding();
+                    let default = self.read_next_int() as i32;
+                    let npairs = self.read_next_int();
+                    let pairs = (0..npairs).map(|_| {
+                        (self.read_next_int() as i32, self.read_next_int() as i32)
+                    }).collect::<Vec<_>>();
+                    let Wrapping(key) = pop!(Value::Int);
+                    // the pairs are sorted by key, so we can binary search them
+                    let branch_offset = match pairs.binary_search_by_key(&key, |&(k, _)| k) {
+                        Ok(i) => pairs[i].1,
+                        Err(_) => default,
+                    };
+                    self.pc = (this_pc_start as i32 + branch_offset) as u16;
+                },
 
                 opcode::IRETURN | opcode::LRETURN | opcode::FRETURN | opcode::DRETURN
-                        | opcode::ARETURN => return self.operand_stack.pop(),
-                opcode::RETURN => return None,
+                        | opcode::ARETURN => return Ok(self.operand_stack.pop()),
+                opcode::RETURN => return Ok(None),
 
                 opcode::GETSTATIC => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::FieldRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        let resolved_class = class_loader.resolve_class(&symref.class).unwrap();
-                        let value = resolved_class.resolve_and_get_field(symref, class_loader);
-                        push!(value)
-                    } else {
-                        panic!("getstatic refers to non-field in constant pool");
-                    }
+                    let (resolved_class, symref) = or_throw!(self.current_class.get_constant_pool()
+                        .resolve_field(index, class_loader));
+                    push!(or_throw!(resolved_class.resolve_and_get_field(symref, class_loader,
+                                                                         context)));
                 },
 
                 opcode::PUTSTATIC => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::FieldRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        let resolved_class = class_loader.resolve_class(&symref.class).unwrap();
-                        let new_value = pop!();
-                        resolved_class.resolve_and_put_field(symref, new_value, class_loader);
-                    } else {
-                        panic!("putstatic refers to non-field in constant pool");
-                    }
+                    let (resolved_class, symref) = or_throw!(self.current_class.get_constant_pool()
+                        .resolve_field(index, class_loader));
+                    let new_value = pop!();
+                    or_throw!(resolved_class.resolve_and_put_field(symref, new_value, class_loader,
+                                                                   context));
                 },
 
                 opcode::GETFIELD => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::FieldRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        match pop!() {
-                            Value::ScalarReference(object_rc) => {
-                                let value = object_rc.borrow().get_field(&symref.sig).clone();
-                                push!(value);
-                            },
-                            Value::ArrayReference(_) => panic!("getfield called on array"),
-                            Value::NullReference => panic!("NullPointerException"),
-                            _ => panic!("getfield called on a primitive value"),
-                        }
-                    } else {
-                        panic!("getfield refers to non-field in constant pool");
-                    }
+                    let (resolved_class, symref) = or_throw!(self.current_class.get_constant_pool()
+                        .resolve_field(index, class_loader));
+                    or_throw!(resolved_class.resolve_instance_field(&symref.sig));
+                    let object = pop_not_null!(Value::ScalarReference);
+                    let value = context.heap.get_scalar(object).get_field(&symref.sig);
+                    push!(value);
                 },
 
                 opcode::PUTFIELD => {
                     let index = self.read_next_short();
                     let value = pop!();
-                    if let Some(RuntimeConstantPoolEntry::FieldRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        match pop!() {
-                            Value::ScalarReference(object_rc) => {
-                                object_rc.borrow_mut().put_field(symref.sig.clone(), value);
-                            },
-                            Value::ArrayReference(_) => panic!("putfield called on array"),
-                            Value::NullReference => panic!("NullPointerException"),
-                            _ => panic!("putfield called on a primitive value"),
-                        }
-                    } else {
-                        panic!("putfield refers to non-field in constant pool");
-                    }
+                    let (resolved_class, symref) = or_throw!(self.current_class.get_constant_pool()
+                        .resolve_field(index, class_loader));
+                    or_throw!(resolved_class.resolve_instance_field(&symref.sig));
+                    let object = pop_not_null!(Value::ScalarReference);
+                    context.heap.get_scalar_mut(object).put_field(symref.sig.clone(), value);
                 },
 
                 opcode::INVOKEVIRTUAL => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::MethodRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        // TODO: this should throw Java exceptions instead of unwrapping
-                        let resolved_class = class_loader.resolve_class(&symref.class).unwrap();
-                        let resolved_method = resolved_class.resolve_method(symref);
-                        // TODO: check for <clinit> and <init>
-                        // TODO: check protected accesses
-                        let num_args = symref.sig.params.len();
-                        let args = self.pop_multi(num_args + 1);
-                        let object_class = {
-                            let object_value = &args[0];
-                            match *object_value {
-                                Value::ScalarReference(ref scalar_rc) =>
-                                    scalar_rc.borrow().get_class().clone(),
-                                Value::ArrayReference(ref array_rc) =>
-                                    array_rc.borrow().get_class().clone(),
-                                Value::NullReference => panic!("NullPointerException"),
-                                _ => panic!("invokevirtual on a primitive type"),
-                            }
-                        };
-                        match object_class.dispatch_method(resolved_method) {
-                            None => panic!("AbstractMethodError"),
-                            Some((actual_class, actual_method)) => {
-                                let result = actual_method.invoke(actual_class, class_loader, args);
-                                match result {
-                                    None => (),
-                                    Some(value) => self.operand_stack.push(value),
-                                }
+                    // TODO: this should throw Java exceptions instead of unwrapping
+                    let (resolved_class, symref) = self.current_class.get_constant_pool()
+                        .resolve_method(index, class_loader).unwrap();
+                    let resolved_method = resolved_class.resolve_method(&symref.sig)
+                        .expect("NoSuchMethodError");
+                    // TODO: check for <clinit> and <init>
+                    // TODO: check protected accesses
+                    let num_args = symref.sig.params.len();
+                    let args = self.operand_stack.pop_multi(num_args + 1);
+                    not_null!(&args[0]);
+                    let object_class = class_of(&args[0], &context.heap).unwrap();
+                    match object_class.dispatch_method(resolved_method) {
+                        Some((actual_class, actual_method)) => {
+                            push_result!(actual_method.invoke(actual_class, class_loader,
+                                                              context, args));
+                        },
+                        // the method may be a default method of a superinterface
+                        None => match object_class.lookup_interface_method(&symref.sig) {
+                            Some(default_method) if default_method.access_flags
+                                    & method_access_flags::ACC_ABSTRACT == 0 => {
+                                let default_class =
+                                    class_loader.resolve_class(&default_method.symref.class)
+                                        .unwrap();
+                                push_result!(default_method.invoke(default_class.as_ref(),
+                                                                   class_loader, context, args));
                             },
-                        }
-                    } else {
-                        panic!("invokevirtual refers to non-method in constant pool");
+                            _ => throw_new!(abstract_method_error(&object_class, symref)),
+                        },
                     }
                 },
 
                 opcode::INVOKESPECIAL => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::MethodRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        // TODO: this should throw Java exceptions instead of unwrapping
-                        let resolved_class = class_loader.resolve_class(&symref.class).unwrap();
-                        let resolved_method = resolved_class.resolve_method(symref);
-                        // TODO: check protected accesses
-                        // TODO: lots of other checks here too
-                        let num_args = symref.sig.params.len();
-                        let args = self.pop_multi(num_args + 1);
-
-                        // check the three conditions from the spec
-                        let actual_method = {
-                            if resolved_class.access_flags & class_access_flags::ACC_SUPER == 0
-                                    || !self.current_class.is_descendant(resolved_class.as_ref())
-                                    || resolved_method.symref.sig.name == "<init>" {
-                                resolved_method
-                            } else {
-                                self.current_class.superclass.as_ref().and_then(|superclass| {
-                                    superclass.find_method(&symref.sig)
-                                }).expect("AbstractMethodError")
+                    // TODO: this should throw Java exceptions instead of unwrapping
+                    let (resolved_class, symref) = self.current_class.get_constant_pool()
+                        .resolve_method(index, class_loader).unwrap();
+                    let resolved_method = resolved_class.resolve_method(&symref.sig)
+                        .expect("NoSuchMethodError");
+                    // TODO: check protected accesses
+                    // TODO: lots of other checks here too
+                    let num_args = symref.sig.params.len();
+                    let args = self.operand_stack.pop_multi(num_args + 1);
+                    not_null!(&args[0]);
+
+                    // check the three conditions from the spec; note that the resolved
+                    // class must be a proper superclass of the current class, since private
+                    // methods of the current class are also invoked this way
+                    let is_superclass = self.current_class.superclass.as_ref()
+                        .map_or(false, |superclass| {
+                            superclass.is_descendant(resolved_class.as_ref())
+                        });
+                    let actual_method = {
+                        if resolved_class.access_flags & class_access_flags::ACC_SUPER == 0
+                                || !is_superclass
+                                || resolved_method.symref.sig.name == "<init>" {
+                            resolved_method
+                        } else {
+                            let superclass = self.current_class.superclass.as_ref().unwrap();
+                            match superclass.resolve_method(&symref.sig) {
+                                Some(actual_method) => actual_method,
+                                None => {
+                                    throw_new!(abstract_method_error(superclass, symref));
+                                    continue;
+                                },
                             }
-                        };
-                        let actual_class = class_loader.resolve_class(&actual_method.symref.class).unwrap();
-                        let result = actual_method.invoke(actual_class.as_ref(), class_loader,
-                                args);
-                        match result {
-                            None => (),
-                            Some(value) => self.operand_stack.push(value),
                         }
-                    } else {
-                        panic!("invokespecial refers to non-method in constant pool");
-                    }
+                    };
+                    let actual_class =
+                        class_loader.resolve_class(&actual_method.symref.class).unwrap();
+                    push_result!(actual_method.invoke(actual_class.as_ref(), class_loader,
+                                                      context, args));
                 },
 
                 opcode::INVOKESTATIC => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::MethodRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        // TODO: this should throw Java exceptions instead of unwrapping
-                        let resolved_class = class_loader.resolve_class(&symref.class).unwrap();
-                        let resolved_method = resolved_class.resolve_method(symref);
-                        // TODO: check protected accesses
-                        // TODO: lots of other checks here too
-                        let num_args = symref.sig.params.len();
-                        let args = self.pop_multi(num_args);
-                        let result = resolved_method.invoke(resolved_class.as_ref(), class_loader,
-                                args);
-                        match result {
-                            None => (),
-                            Some(value) => self.operand_stack.push(value),
-                        }
-                    } else {
-                        panic!("invokestatic refers to non-method in constant pool");
-                    }
+                    // TODO: this should throw Java exceptions instead of unwrapping
+                    let (resolved_class, symref) = self.current_class.get_constant_pool()
+                        .resolve_method(index, class_loader).unwrap();
+                    resolved_class.initialize(class_loader, context);
+                    let resolved_method = resolved_class.resolve_static_method(&symref.sig)
+                        .expect("NoSuchMethodError");
+                    // TODO: check protected accesses
+                    // TODO: lots of other checks here too
+                    let num_args = symref.sig.params.len();
+                    let args = self.operand_stack.pop_multi(num_args);
+                    push_result!(resolved_method.invoke(resolved_class.as_ref(), class_loader,
+                                                        context, args));
                 },
 
-                opcode::NEW => {
+                opcode::INVOKEINTERFACE => {
                     let index = self.read_next_short();
-                    if let Some(RuntimeConstantPoolEntry::ClassRef(ref symref)) =
-                            self.current_class.get_constant_pool()[index] {
-                        // TODO check for interfaces, abstract classes
-                        let resolved_class = class_loader.resolve_class(symref).unwrap();
-                        resolved_class.initialize(class_loader);
-                        let object = Scalar::new(resolved_class);
-                        let object_rc = Rc::new(RefCell::new(object));
-                        push!(Value::ScalarReference(object_rc));
-                    } else {
-                        panic!("new refers to non-class in constant pool");
+                    let count = self.read_next_byte();
+                    let reserved = self.read_next_byte();
+                    // TODO: this should throw Java exceptions instead of unwrapping
+                    let (resolved_class, symref) = self.current_class.get_constant_pool()
+                        .resolve_interface_method(index, class_loader).unwrap();
+                    // the count operand must agree with the method descriptor, counting the
+                    // receiver, and the operand after it must be zero
+                    if count as usize != symref.sig.param_words() + 1 {
+                        throw_new!(exceptions::create_verify(format!(
+                            "invokeinterface count {} does not match the descriptor {}",
+                            count, symref.sig.descriptor())));
+                        continue;
+                    }
+                    if reserved != 0 {
+                        throw_new!(exceptions::create_verify(format!(
+                            "invokeinterface has non-zero fourth operand byte {}", reserved)));
+                        continue;
+                    }
+                    resolved_class.resolve_interface_method(&symref.sig)
+                        .expect("NoSuchMethodError");
+                    let num_args = symref.sig.params.len();
+                    let args = self.operand_stack.pop_multi(num_args + 1);
+                    not_null!(&args[0]);
+                    let object_class = class_of(&args[0], &context.heap).unwrap();
+                    if !object_class.implements(resolved_class.as_ref()) {
+                        throw_new!(exceptions::create_incompatible_class_change(format!(
+                            "Class {} does not implement the requested interface {}",
+                            object_class.symref.sig.name().replace('/', "."),
+                            resolved_class.symref.sig.name().replace('/', "."))));
+                        continue;
+                    }
+                    match object_class.lookup_interface_method(&symref.sig) {
+                        Some(actual_method) if actual_method.access_flags
+                                & method_access_flags::ACC_ABSTRACT == 0 => {
+                            let actual_class =
+                                class_loader.resolve_class(&actual_method.symref.class)
+                                    .unwrap();
+                            push_result!(actual_method.invoke(actual_class.as_ref(),
+                                                              class_loader, context, args));
+                        },
+                        _ => throw_new!(abstract_method_error(&object_class, symref)),
                     }
                 },
 
-                opcode::NEWARRAY => {
+                opcode::NEW => {
+                    let index = self.read_next_short();
+                    // TODO check for interfaces, abstract classes
+                    let resolved_class = self.resolve_class_ref(index, class_loader);
+                    resolved_class.initialize(class_loader, context);
+                    let object = context.heap.alloc_scalar(resolved_class);
+                    push!(Value::ScalarReference(object));
+                },
+
+                opcode::NEWARRAY => {
                     let type_tag = self.read_next_byte();
                     let component_ty = match type_tag {
                         4 => Type::Boolean,
@@ -691,30 +1176,128 @@ impl<'a> Frame<'a> {
                     let class = class_loader.resolve_class(&class_symref).unwrap();
 
                     match pop!() {
+                        Value::Int(Wrapping(length)) if length < 0 => {
+                            throw_new!(exceptions::create_negative_array_size(length));
+                        },
                         Value::Int(Wrapping(length)) => {
-                            let array = Array::new(class, length);
-                            let array_rc = Rc::new(RefCell::new(array));
-                            push!(Value::ArrayReference(array_rc));
+                            let array = context.heap.alloc_array(class, length);
+                            push!(Value::ArrayReference(array));
                         },
                         _ => panic!("newarray called with non-int length"),
                     }
                 },
 
-                opcode::ANEWARRAY => unimplemented!(),
+                opcode::ANEWARRAY => {
+                    let index = self.read_next_short();
+                    if let Some(RuntimeConstantPoolEntry::ClassRef(ref symref)) =
+                            self.current_class.get_constant_pool()[index] {
+                        // resolve the component class first, so that any errors loading it are
+                        // reported before the array class is created
+                        class_loader.resolve_class(symref).unwrap();
+                        let component_ty = Type::Reference(symref.sig.clone());
+                        let class_sig = sig::Class::Array(Box::new(component_ty));
+                        let class_symref = symref::Class { sig: class_sig };
+                        let class = class_loader.resolve_class(&class_symref).unwrap();
+                        let Wrapping(length) = pop!(Value::Int);
+                        if length < 0 {
+                            throw_new!(exceptions::create_negative_array_size(length));
+                        } else {
+                            let array = context.heap.alloc_array(class, length);
+                            push!(Value::ArrayReference(array));
+                        }
+                    } else {
+                        panic!("anewarray refers to non-class in constant pool");
+                    }
+                },
 
                 opcode::ARRAYLENGTH => {
-                    let array_rc = pop_not_null!(Value::ArrayReference);
-                    let len = array_rc.borrow().len();
+                    let array = pop_not_null!(Value::ArrayReference);
+                    let len = context.heap.get_array(array).len();
                     push!(Value::Int(Wrapping(len)));
                 },
 
-                opcode::ATHROW => unimplemented!(),
-                opcode::CHECKCAST => unimplemented!(),
-                opcode::INSTANCEOF => unimplemented!(),
-                opcode::MONITORENTER => unimplemented!(),
-                opcode::MONITOREXIT => unimplemented!(),
-                opcode::WIDE => unimplemented!(),
-                opcode::MULTIANEWARRAY => unimplemented!(),
+                opcode::ATHROW => {
+                    let exception = pop_not_null!(Value::ScalarReference);
+                    throw!(exception);
+                },
+                opcode::CHECKCAST => {
+                    let index = self.read_next_short();
+                    let value = pop!();
+                    if let Some(class) = class_of(&value, &context.heap) {
+                        let resolved_class = self.resolve_class_ref(index, class_loader);
+                        if !class.is_instance_of(&resolved_class, class_loader) {
+                            let from = class.symref.sig.name();
+                            let to = resolved_class.symref.sig.name();
+                            throw_new!(exceptions::create_class_cast(&from, &to));
+                        }
+                    }
+                    push!(value);
+                },
+
+                opcode::INSTANCEOF => {
+                    let index = self.read_next_short();
+                    let result = match class_of(&pop!(), &context.heap) {
+                        Some(class) => {
+                            let resolved_class = self.resolve_class_ref(index, class_loader);
+                            class.is_instance_of(&resolved_class, class_loader)
+                        },
+                        None => false,
+                    };
+                    push!(Value::Int(Wrapping(result as i32)));
+                },
+
+                // there is only a single thread, so monitors never need to be acquired
+                opcode::MONITORENTER => {
+                    let object = pop!();
+                    let heap_ref = not_null!(&object);
+                    context.enter_monitor(heap_ref);
+                },
+                opcode::MONITOREXIT => {
+                    let object = pop!();
+                    let heap_ref = not_null!(&object);
+                    if !context.exit_monitor(heap_ref) {
+                        throw_new!(ExceptionInfo::new("java/lang/IllegalMonitorStateException",
+                                                      None));
+                    }
+                },
+                opcode::WIDE => {
+                    // the modified instruction takes a two-byte local variable index, and `iinc`
+                    // also takes a two-byte constant
+                    let modified_opcode = self.read_next_byte();
+                    match modified_opcode {
+                        opcode::ILOAD | opcode::LLOAD | opcode::FLOAD | opcode::DLOAD
+                                | opcode::ALOAD =>
+                            with!(read_next_short, do_load),
+                        opcode::ISTORE | opcode::LSTORE | opcode::FSTORE | opcode::DSTORE
+                                | opcode::ASTORE =>
+                            with!(read_next_short, do_store),
+                        opcode::IINC => {
+                            let index = self.read_next_short();
+                            let c = self.read_next_short() as i16 as i32;
+                            do_iinc!(index, c);
+                        },
+                        opcode::RET => with!(read_next_short, do_ret),
+                        _ => panic!("wide cannot modify opcode {:#x}", modified_opcode),
+                    }
+                },
+                opcode::MULTIANEWARRAY => {
+                    let index = self.read_next_short();
+                    let dimensions = self.read_next_byte();
+                    let class = self.resolve_class_ref(index, class_loader);
+                    let lengths = self.operand_stack.pop_multi(dimensions as usize);
+                    let lengths = lengths.into_iter().map(|v| {
+                        match v {
+                            Value::Int(Wrapping(length)) => length,
+                            v => panic!("multianewarray called with non-int length {:?}", v),
+                        }
+                    }).collect::<Vec<_>>();
+                    // every length is checked, even if an outer dimension is zero
+                    match lengths.iter().find(|&&length| length < 0) {
+                        Some(&length) =>
+                            throw_new!(exceptions::create_negative_array_size(length)),
+                        None => push!(new_multi_array(class, &lengths, class_loader, context)),
+                    }
+                },
 
                 opcode::IFNULL => {
                     let branch_offset = self.read_next_short() as i16;
@@ -735,8 +1318,13 @@ impl<'a> Frame<'a> {
                     }
                 },
 
-                opcode::GOTO_W => unimplemented!(),
-                opcode::JSR_W => unimplemented!(),
+                opcode::GOTO_W => {
+                    let branch_offset = self.read_next_int() as i32;
+                    // 5 byte long instruction; read* operations move the PC.
+                    let this_pc_start = self.pc - 5;
+                    self.pc = (this_pc_start as i32).wrapping_add(branch_offset) as u16;
+                },
+                opcode::JSR_W => do_jsr!(self.read_next_int() as i32, 5),
 
                 // reserved opcodes
                 opcode::BREAKPOINT => unimplemented!(),
@@ -744,10 +1332,1728 @@ impl<'a> Frame<'a> {
                 opcode::IMPDEP2 => unimplemented!(),
 
                 _ => {
-                    println!("{}", self.code[(self.pc as usize) - 1]);
+                    debug!("{}", self.code[(self.pc as usize) - 1]);
                     panic!("unknown opcode")
                 },
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::num::Wrapping;
+
+    use model::class_file::ClassFile;
+    use model::class_file::constant_pool::ConstantPool;
+    use vm::bytecode::opcode::*;
+    use vm::class_loader::BootstrapClassLoader;
+    use vm::class_path::ClassPath;
+    use vm::context::Context;
+    use vm::constant_pool::RuntimeConstantPool;
+    use vm::java_lang::string_to_rust;
+    use vm::native::NativeRegistry;
+
+    // The classes in `data/vm` are compiled from the adjacent Java sources against the runtime
+    // library in `rt`, with their major version then patched to 50:
+    //
+    //     javac -source 7 -target 7 -bootclasspath ../../rt -sourcepath ../../rt -d . Foo.java
+    //
+    // The classes in `data/vm/java` and `data/vm/moon` are compiled in the same way from the
+    // sources in `rt`.
+
+    /// Create an empty class in whose context bytecode can be executed.
+    fn test_class() -> Class {
+        let class_file = ClassFile {
+            minor_version: 0,
+            major_version: 50,
+            constant_pool: ConstantPool::from_zero_indexed_vec(vec![]),
+            access_flags: 0,
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+        };
+        let symref = symref::Class { sig: sig::Class::Scalar(String::from("Test")) };
+        let constant_pool = RuntimeConstantPool::empty();
+        Class::new(symref, None, vec![], constant_pool, class_file, &NativeRegistry::new())
+    }
+
+    fn execute(code: &[u8], local_variables: Vec<Option<Value>>) -> Option<Value> {
+        let class = test_class();
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        Frame::new(&class, code, &[], 16, local_variables).run(&mut class_loader, &mut context)
+            .expect("uncaught exception")
+    }
+
+    fn execute_int(code: &[u8], local_variables: Vec<Option<Value>>) -> i32 {
+        match execute(code, local_variables) {
+            Some(Value::Int(Wrapping(n))) => n,
+            v => panic!("expected an int result, but was {:?}", v),
+        }
+    }
+
+    fn invoke_reference(class_loader: &mut BootstrapClassLoader, context: &mut Context,
+                        class: &Class, name: &str, descriptor: &str) -> HeapRef {
+        let method = class.find_method(&sig::Method::new(name, descriptor)).unwrap();
+        match method.invoke(class, class_loader, context, vec![]) {
+            Ok(Some(Value::ScalarReference(heap_ref))) => heap_ref,
+            v => panic!("expected a reference result, but was {:?}", v),
+        }
+    }
+
+    fn int(n: i32) -> Option<Value> {
+        Some(Value::Int(Wrapping(n)))
+    }
+
+    #[test]
+    fn test_return() {
+        assert!(execute(&[RETURN], vec![]).is_none());
+    }
+
+    #[test]
+    fn test_int_constants() {
+        assert_eq!(execute_int(&[ICONST_M1, IRETURN], vec![]), -1);
+        assert_eq!(execute_int(&[ICONST_0, IRETURN], vec![]), 0);
+        assert_eq!(execute_int(&[ICONST_5, IRETURN], vec![]), 5);
+        assert_eq!(execute_int(&[BIPUSH, 0x80, IRETURN], vec![]), -128);
+        assert_eq!(execute_int(&[SIPUSH, 0x12, 0x34, IRETURN], vec![]), 0x1234);
+        assert_eq!(execute_int(&[SIPUSH, 0xff, 0xfe, IRETURN], vec![]), -2);
+    }
+
+    #[test]
+    fn test_other_constants() {
+        match execute(&[LCONST_1, LRETURN], vec![]) {
+            Some(Value::Long(Wrapping(1))) => (),
+            v => panic!("expected 1L, but was {:?}", v),
+        }
+        match execute(&[FCONST_2, FRETURN], vec![]) {
+            Some(Value::Float(f)) => assert_eq!(f, 2.0),
+            v => panic!("expected 2.0f, but was {:?}", v),
+        }
+        match execute(&[DCONST_1, DRETURN], vec![]) {
+            Some(Value::Double(d)) => assert_eq!(d, 1.0),
+            v => panic!("expected 1.0d, but was {:?}", v),
+        }
+        match execute(&[ACONST_NULL, ARETURN], vec![]) {
+            Some(Value::NullReference) => (),
+            v => panic!("expected null, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_int_arithmetic() {
+        let locals = || vec![int(17), int(5)];
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IADD, IRETURN], locals()), 22);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, ISUB, IRETURN], locals()), 12);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IMUL, IRETURN], locals()), 85);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IDIV, IRETURN], locals()), 3);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IREM, IRETURN], locals()), 2);
+        assert_eq!(execute_int(&[ILOAD_0, INEG, IRETURN], locals()), -17);
+    }
+
+    /// Execute code which is expected to throw an exception, and return the exception along with
+    /// the heap it was allocated on.
+    fn execute_throwing(code: &[u8], local_variables: Vec<Option<Value>>) -> (Exception, Heap) {
+        let class = test_class();
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let result = Frame::new(&class, code, &[], 16, local_variables).run(&mut class_loader,
+                                                                        &mut context);
+        match result {
+            Err(exception) => (exception, context.heap),
+            Ok(v) => panic!("expected an exception, but returned {:?}", v),
+        }
+    }
+
+    fn long(n: i64) -> Option<Value> {
+        Some(Value::Long(Wrapping(n)))
+    }
+
+    fn execute_long(code: &[u8], local_variables: Vec<Option<Value>>) -> i64 {
+        match execute(code, local_variables) {
+            Some(Value::Long(Wrapping(n))) => n,
+            v => panic!("expected a long result, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_int_division() {
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IDIV, IRETURN], vec![int(-7), int(2)]), -3);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IREM, IRETURN], vec![int(5), int(2)]), 1);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IREM, IRETURN], vec![int(-7), int(2)]), -1);
+        let locals = || vec![int(i32::MIN), int(-1)];
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IDIV, IRETURN], locals()), i32::MIN);
+        assert_eq!(execute_int(&[ILOAD_0, ILOAD_1, IREM, IRETURN], locals()), 0);
+    }
+
+    #[test]
+    fn test_remainder_sign() {
+        let code = [ILOAD_0, ILOAD_1, IREM, IRETURN];
+        assert_eq!(execute_int(&code, vec![int(-7), int(-2)]), -1);
+        assert_eq!(execute_int(&code, vec![int(7), int(-2)]), 1);
+        let code = [LLOAD_0, LLOAD_2, LREM, LRETURN];
+        assert_eq!(execute_long(&code, vec![long(-7), None, long(-2), None]), -1);
+        assert_eq!(execute_long(&code, vec![long(7), None, long(-2), None]), 1);
+    }
+
+    #[test]
+    fn test_float_negation() {
+        match execute(&[FLOAD_0, FNEG, FRETURN], vec![Some(Value::Float(0.0))]) {
+            Some(Value::Float(f)) => assert_eq!(f.to_bits(), (-0.0f32).to_bits()),
+            v => panic!("expected a float result, but was {:?}", v),
+        }
+        match execute(&[FLOAD_0, FNEG, FRETURN], vec![Some(Value::Float(1.5))]) {
+            Some(Value::Float(f)) => assert_eq!(f, -1.5),
+            v => panic!("expected a float result, but was {:?}", v),
+        }
+        match execute(&[DLOAD_0, DNEG, DRETURN], vec![Some(Value::Double(f64::NAN)), None]) {
+            Some(Value::Double(d)) => assert_eq!(d.to_bits(), f64::NAN.to_bits()),
+            v => panic!("expected a double result, but was {:?}", v),
+        }
+        match execute(&[DLOAD_0, DNEG, DRETURN], vec![Some(Value::Double(-0.0)), None]) {
+            Some(Value::Double(d)) => assert_eq!(d.to_bits(), 0.0f64.to_bits()),
+            v => panic!("expected a double result, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_long_comparison() {
+        let code = [LLOAD_0, LLOAD_2, LCMP, IRETURN];
+        assert_eq!(execute_int(&code, vec![long(-3), None, long(2), None]), -1);
+        assert_eq!(execute_int(&code, vec![long(2), None, long(2), None]), 0);
+        assert_eq!(execute_int(&code, vec![long(i64::MAX), None, long(i64::MIN), None]), 1);
+    }
+
+    #[test]
+    fn test_float_comparison() {
+        let compare = |opcode, v1, v2| {
+            execute_int(&[FLOAD_0, FLOAD_1, opcode, IRETURN],
+                        vec![Some(Value::Float(v1)), Some(Value::Float(v2))])
+        };
+        assert_eq!(compare(FCMPL, 1.0, 2.0), -1);
+        assert_eq!(compare(FCMPG, 2.0, 2.0), 0);
+        assert_eq!(compare(FCMPL, 0.0, -0.0), 0);
+        assert_eq!(compare(FCMPG, 3.0, 2.0), 1);
+        assert_eq!(compare(FCMPL, f32::NAN, f32::NAN), -1);
+        assert_eq!(compare(FCMPG, f32::NAN, f32::NAN), 1);
+        assert_eq!(compare(FCMPL, f32::NAN, 1.0), -1);
+        assert_eq!(compare(FCMPG, f32::NAN, 1.0), 1);
+        assert_eq!(compare(FCMPL, 1.0, f32::NAN), -1);
+        assert_eq!(compare(FCMPG, 1.0, f32::NAN), 1);
+    }
+
+    #[test]
+    fn test_double_comparison() {
+        let compare = |opcode, v1, v2| {
+            execute_int(&[DLOAD_0, DLOAD_2, opcode, IRETURN],
+                        vec![Some(Value::Double(v1)), None, Some(Value::Double(v2)), None])
+        };
+        assert_eq!(compare(DCMPL, 1.0, 2.0), -1);
+        assert_eq!(compare(DCMPG, 2.0, 2.0), 0);
+        assert_eq!(compare(DCMPL, f64::INFINITY, 2.0), 1);
+        assert_eq!(compare(DCMPL, f64::NAN, f64::NAN), -1);
+        assert_eq!(compare(DCMPG, f64::NAN, f64::NAN), 1);
+        assert_eq!(compare(DCMPL, f64::NAN, 1.0), -1);
+        assert_eq!(compare(DCMPG, f64::NAN, 1.0), 1);
+        assert_eq!(compare(DCMPL, 1.0, f64::NAN), -1);
+        assert_eq!(compare(DCMPG, 1.0, f64::NAN), 1);
+    }
+
+    #[test]
+    fn test_long_division() {
+        let locals = || vec![long(-7), None, long(2), None];
+        assert_eq!(execute_long(&[LLOAD_0, LLOAD_2, LDIV, LRETURN], locals()), -3);
+        assert_eq!(execute_long(&[LLOAD_0, LLOAD_2, LREM, LRETURN], locals()), -1);
+        let locals = || vec![long(i64::MIN), None, long(-1), None];
+        assert_eq!(execute_long(&[LLOAD_0, LLOAD_2, LDIV, LRETURN], locals()), i64::MIN);
+        assert_eq!(execute_long(&[LLOAD_0, LLOAD_2, LREM, LRETURN], locals()), 0);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let int_locals = || vec![int(5), int(0)];
+        let long_locals = || vec![long(5), None, long(0), None];
+        let cases = [(&[ILOAD_0, ILOAD_1, IDIV, IRETURN], int_locals()),
+                     (&[ILOAD_0, ILOAD_1, IREM, IRETURN], int_locals()),
+                     (&[LLOAD_0, LLOAD_2, LDIV, LRETURN], long_locals()),
+                     (&[LLOAD_0, LLOAD_2, LREM, LRETURN], long_locals())];
+        for &(code, ref locals) in &cases {
+            let (exception, heap) = execute_throwing(code, locals.clone());
+            assert_exception(&heap, exception, "java/lang/ArithmeticException", "/ by zero");
+        }
+    }
+
+    #[test]
+    fn test_int_overflow() {
+        let locals = vec![int(i32::MAX)];
+        assert_eq!(execute_int(&[ILOAD_0, ICONST_1, IADD, IRETURN], locals), i32::MIN);
+        let locals = vec![int(i32::MIN)];
+        assert_eq!(execute_int(&[ILOAD_0, INEG, IRETURN], locals), i32::MIN);
+    }
+
+    #[test]
+    fn test_load_store() {
+        // x = 3; y = x * 7; return y - x
+        let code = [ICONST_3, ISTORE_2, ILOAD_2, BIPUSH, 7, IMUL, ISTORE, 3, ILOAD_3, ILOAD, 2,
+                    ISUB, IRETURN];
+        assert_eq!(execute_int(&code, vec![None; 4]), 18);
+    }
+
+    #[test]
+    fn test_store_slot_zero() {
+        let code = [BIPUSH, 42, ISTORE, 0, ILOAD_0, IRETURN];
+        assert_eq!(execute_int(&code, vec![None]), 42);
+    }
+
+    #[test]
+    fn test_store_category_2() {
+        // a long stored in slot 0 also occupies slot 1
+        let code = [LCONST_1, LSTORE_0, LLOAD_0, LRETURN];
+        match execute(&code, vec![None; 2]) {
+            Some(Value::Long(Wrapping(1))) => (),
+            v => panic!("expected 1L, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_store_invalidates_category_2() {
+        // overwriting the upper half of a double invalidates the whole value
+        let code = [DCONST_1, DSTORE_0, ICONST_2, ISTORE_1, DLOAD_0, DRETURN];
+        execute(&code, vec![None; 2]);
+    }
+
+    /// Run a conditional branch on the given operands, returning true if the branch was taken.
+    fn branches(op: u8, operands: Vec<Option<Value>>) -> bool {
+        let code = match operands.len() {
+            1 => vec![ALOAD_0, op, 0, 5, ICONST_0, IRETURN, ICONST_1, IRETURN],
+            2 => vec![ALOAD_0, ALOAD_1, op, 0, 5, ICONST_0, IRETURN, ICONST_1, IRETURN],
+            _ => panic!("conditional branches take one or two operands"),
+        };
+        execute_int(&code, operands) == 1
+    }
+
+    #[test]
+    fn test_if_int() {
+        for &(op, lt, eq, gt) in &[(IFEQ, false, true, false), (IFNE, true, false, true),
+                                   (IFLT, true, false, false), (IFGE, false, true, true),
+                                   (IFGT, false, false, true), (IFLE, true, true, false)] {
+            assert_eq!(branches(op, vec![int(-3)]), lt);
+            assert_eq!(branches(op, vec![int(0)]), eq);
+            assert_eq!(branches(op, vec![int(3)]), gt);
+        }
+    }
+
+    #[test]
+    fn test_if_icmp() {
+        for &(op, lt, eq, gt) in &[(IF_ICMPEQ, false, true, false),
+                                   (IF_ICMPNE, true, false, true),
+                                   (IF_ICMPLT, true, false, false),
+                                   (IF_ICMPGE, false, true, true),
+                                   (IF_ICMPGT, false, false, true),
+                                   (IF_ICMPLE, true, true, false)] {
+            assert_eq!(branches(op, vec![int(-3), int(3)]), lt);
+            assert_eq!(branches(op, vec![int(3), int(3)]), eq);
+            assert_eq!(branches(op, vec![int(3), int(-3)]), gt);
+        }
+    }
+
+    #[test]
+    fn test_if_references() {
+        let null = || Some(Value::NullReference);
+        assert!(branches(IFNULL, vec![null()]));
+        assert!(!branches(IFNONNULL, vec![null()]));
+        assert!(branches(IF_ACMPEQ, vec![null(), null()]));
+        assert!(!branches(IF_ACMPNE, vec![null(), null()]));
+    }
+
+    #[test]
+    fn test_abs() {
+        let code = [ILOAD_0, IFLT, 0, 5, ILOAD_0, IRETURN, ILOAD_0, INEG, IRETURN];
+        assert_eq!(execute_int(&code, vec![int(42)]), 42);
+        assert_eq!(execute_int(&code, vec![int(-42)]), 42);
+        assert_eq!(execute_int(&code, vec![int(0)]), 0);
+    }
+
+    #[test]
+    fn test_goto() {
+        // sum = 0; for (i = 1; i <= n; i++) { sum += i; } return sum
+        let code = [ICONST_0, ISTORE_1, ICONST_1, ISTORE_2,
+                    ILOAD_2, ILOAD_0, IF_ICMPGT, 0, 13,
+                    ILOAD_1, ILOAD_2, IADD, ISTORE_1, IINC, 2, 1,
+                    GOTO, 0xff, 0xf4,
+                    ILOAD_1, IRETURN];
+        assert_eq!(execute_int(&code, vec![int(10), None, None]), 55);
+    }
+
+    #[test]
+    fn test_goto_w() {
+        let code = [ICONST_0, ISTORE_1, ICONST_1, ISTORE_2,
+                    ILOAD_2, ILOAD_0, IF_ICMPGT, 0, 15,
+                    ILOAD_1, ILOAD_2, IADD, ISTORE_1, IINC, 2, 1,
+                    GOTO_W, 0xff, 0xff, 0xff, 0xf4,
+                    ILOAD_1, IRETURN];
+        assert_eq!(execute_int(&code, vec![int(10), None, None]), 55);
+    }
+
+    #[test]
+    fn test_local_variables() {
+        let args = vec![Value::Int(Wrapping(1)), Value::Long(Wrapping(2)), Value::Float(3.0)];
+        let locals = local_variables(5, args);
+        assert_eq!(locals.len(), 5);
+        assert!(locals[1].as_ref().unwrap().is_category_2());
+        assert!(locals[2].is_none());
+        assert!(locals[4].is_none());
+
+        let class = test_class();
+        let mut frame = Frame::new(&class, &[], &[], 2, locals);
+        assert_eq!(frame.load(2).unwrap_err(), LocalVariableError::Unset { index: 2 });
+        assert_eq!(frame.load(4).unwrap_err(), LocalVariableError::Unset { index: 4 });
+        assert_eq!(frame.load(5).unwrap_err(),
+                   LocalVariableError::OutOfBounds { index: 5, max_locals: 5 });
+
+        // storing over the second half of the long unsets it
+        frame.store(2, Value::Int(Wrapping(4))).unwrap();
+        assert_eq!(frame.load(1).unwrap_err(), LocalVariableError::Unset { index: 1 });
+        frame.store(3, Value::Double(5.0)).unwrap();
+        assert_eq!(frame.load(4).unwrap_err(), LocalVariableError::Unset { index: 4 });
+        assert!(frame.store(4, Value::Long(Wrapping(6))).is_err());
+
+        frame.push(frame.load(2).unwrap());
+        match frame.pop() {
+            Value::Int(Wrapping(4)) => (),
+            v => panic!("expected 4, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "operand stack overflow")]
+    fn test_max_stack() {
+        let class = test_class();
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let code = [ICONST_1, ICONST_2, ICONST_3, IRETURN];
+        let _ = Frame::new(&class, &code, &[], 2, vec![]).run(&mut class_loader, &mut context);
+    }
+
+    #[test]
+    fn test_subroutines() {
+        // int x = 1; try { x += 1; } finally { x *= 10; } return x;
+        let code = [ICONST_1, ISTORE_0,
+                    IINC, 0, 1,
+                    JSR, 0, 5,
+                    ILOAD_0, IRETURN,
+                    ASTORE_1, ILOAD_0, BIPUSH, 10, IMUL, ISTORE_0,
+                    RET, 1];
+        assert_eq!(execute_int(&code, vec![None, None]), 20);
+
+        // the same, with a subroutine before the instruction which calls it
+        let code = [GOTO, 0, 13,
+                    ASTORE_1, ILOAD_0, BIPUSH, 10, IMUL, ISTORE_0,
+                    WIDE, RET, 0, 1,
+                    ICONST_1, ISTORE_0,
+                    IINC, 0, 1,
+                    JSR_W, 0xff, 0xff, 0xff, 0xf1,
+                    ILOAD_0, IRETURN];
+        assert_eq!(execute_int(&code, vec![None, None]), 20);
+    }
+
+    fn push_int(code: &mut Vec<u8>, n: i32) {
+        code.extend_from_slice(&[(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]);
+    }
+
+    #[test]
+    fn test_tableswitch() {
+        // switch (n) { case 1: return 10; ... case 5: return 50; default: return -1; }
+        let mut code = vec![ILOAD_0, TABLESWITCH, 0, 0];
+        for &n in &[50, 1, 5, 35, 38, 41, 44, 47] {
+            push_int(&mut code, n);
+        }
+        for n in 1..6 {
+            code.extend_from_slice(&[BIPUSH, n * 10, IRETURN]);
+        }
+        code.extend_from_slice(&[ICONST_M1, IRETURN]);
+
+        for n in 1..6 {
+            assert_eq!(execute_int(&code, vec![int(n)]), n * 10);
+        }
+        assert_eq!(execute_int(&code, vec![int(0)]), -1);
+        assert_eq!(execute_int(&code, vec![int(6)]), -1);
+        assert_eq!(execute_int(&code, vec![int(i32::MIN)]), -1);
+    }
+
+    #[test]
+    fn test_tableswitch_alignment() {
+        // the same switch as above, but with no padding
+        let mut code = vec![ILOAD_0, NOP, NOP, TABLESWITCH];
+        for &n in &[48, 1, 5, 33, 36, 39, 42, 45] {
+            push_int(&mut code, n);
+        }
+        for n in 1..6 {
+            code.extend_from_slice(&[BIPUSH, n * 10, IRETURN]);
+        }
+        code.extend_from_slice(&[ICONST_M1, IRETURN]);
+
+        for n in 1..6 {
+            assert_eq!(execute_int(&code, vec![int(n)]), n * 10);
+        }
+        assert_eq!(execute_int(&code, vec![int(7)]), -1);
+    }
+
+    #[test]
+    fn test_lookupswitch() {
+        // switch (n) { case -100: return 1; case 1: return 2; case 7: return 3;
+        //              case 1000: return 4; default: return -1; }
+        let mut code = vec![ILOAD_0, LOOKUPSWITCH, 0, 0];
+        for &n in &[55, 4, -100, 43, 1, 46, 7, 49, 1000, 52] {
+            push_int(&mut code, n);
+        }
+        for n in 1..5 {
+            code.extend_from_slice(&[BIPUSH, n, IRETURN]);
+        }
+        code.extend_from_slice(&[ICONST_M1, IRETURN]);
+
+        assert_eq!(execute_int(&code, vec![int(-100)]), 1);
+        assert_eq!(execute_int(&code, vec![int(1)]), 2);
+        assert_eq!(execute_int(&code, vec![int(7)]), 3);
+        assert_eq!(execute_int(&code, vec![int(1000)]), 4);
+        assert_eq!(execute_int(&code, vec![int(0)]), -1);
+        assert_eq!(execute_int(&code, vec![int(8)]), -1);
+    }
+
+    /// Load the class with the given name from `data/vm`.
+    macro_rules! load_test_class {
+        ($class_loader: expr, $name: expr) => ({
+            let class_bytes = include_bytes!(concat!("../../data/vm/", $name, ".class"));
+            $class_loader.define_class($name, class_bytes).unwrap()
+        });
+    }
+
+    /// Create a class loader in which the test classes in `data/vm` can be loaded without the
+    /// compiled runtime library.
+    fn test_class_loader() -> BootstrapClassLoader {
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        load_test_class!(class_loader, "java/lang/Object");
+        load_test_class!(class_loader, "java/lang/Cloneable");
+        load_test_class!(class_loader, "java/io/Serializable");
+        class_loader
+    }
+
+    fn invoke_int(class_loader: &mut BootstrapClassLoader, context: &mut Context, class: &Class,
+                  name: &str, descriptor: &str) -> i32 {
+        let method = class.find_method(&sig::Method::new(name, descriptor)).unwrap();
+        match method.invoke(class, class_loader, context, vec![]) {
+            Ok(Some(Value::Int(Wrapping(n)))) => n,
+            v => panic!("expected an int result, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_local_variable_debug_info() {
+        let mut class_loader = test_class_loader();
+        let class = load_test_class!(class_loader, "LocalVariables");
+        let sum = class.find_method(&sig::Method::new("sum", "(I)I")).unwrap();
+        let mut frame = Frame::new(&class, &[], &[], 0, vec![]);
+        frame.set_method(sum);
+        assert_eq!(frame.local_name(0, 0), Some("n"));
+        assert_eq!(frame.local_type(0, 0), Some("I"));
+        assert_eq!(frame.local_type(2, 19), None);
+        // `total` is stored at 1, and `i` is in scope for the loop from 4 to 18
+        assert_eq!(frame.local_name(1, 1), None);
+        assert_eq!(frame.local_name(1, 2), Some("total"));
+        assert_eq!(frame.local_name(2, 3), None);
+        assert_eq!(frame.local_name(2, 4), Some("i"));
+        assert_eq!(frame.local_name(2, 18), Some("i"));
+        assert_eq!(frame.local_name(2, 19), None);
+        assert_eq!(frame.local_name(3, 4), None);
+
+        let first_sig = sig::Method::new("first", "([Ljava/lang/Object;)Ljava/lang/Object;");
+        let first = class.find_method(&first_sig).unwrap();
+        let debug_info = first.debug_info().unwrap();
+        assert_eq!(debug_info.local_name(1, 4), Some("item"));
+        assert_eq!(debug_info.local_type(1, 4), Some("Ljava/lang/Object;"));
+        assert_eq!(debug_info.local_signature(1, 4), Some("TT;"));
+        assert_eq!(debug_info.local_signature(0, 0), Some("[TT;"));
+        assert_eq!(debug_info.local_signature(1, 0), None);
+
+        let test_class = test_class();
+        assert!(Frame::new(&test_class, &[], &[], 0, vec![]).debug_info().is_none());
+    }
+
+    #[test]
+    fn test_abstract_method() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        // neither method has a Code attribute
+        let class = load_test_class!(class_loader, "Abstract");
+        assert!(class.find_method(&sig::Method::new("perimeter", "()I")).is_some());
+        let method = class.find_method(&sig::Method::new("area", "()I")).unwrap();
+        let this = Value::ScalarReference(context.heap.alloc_scalar(class.clone()));
+        match method.invoke(&class, &mut class_loader, &mut context, vec![this]) {
+            Err(exception) =>
+                assert_exception(&context.heap, exception, "java/lang/AbstractMethodError",
+                                 "Abstract.area()I"),
+            v => panic!("expected an AbstractMethodError, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_native_method_not_found() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/UnsatisfiedLinkError");
+        let class = load_test_class!(class_loader, "Abstract");
+        let method = class.find_method(&sig::Method::new("perimeter", "()I")).unwrap();
+        let this = Value::ScalarReference(context.heap.alloc_scalar(class.clone()));
+        match method.invoke(&class, &mut class_loader, &mut context, vec![this]) {
+            Err(exception) =>
+                assert_exception(&context.heap, exception, "java/lang/UnsatisfiedLinkError",
+                                 "Abstract.perimeter()I"),
+            v => panic!("expected an UnsatisfiedLinkError, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_instantiate_failures() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "BrokenException");
+        load_test_class!(class_loader, "SilentException");
+        let message = Some(String::from("message"));
+
+        let missing = ExceptionInfo::new("Missing", message.clone());
+        let exception = missing.instantiate(&mut class_loader, &mut context);
+        assert_exception_class(&context.heap, exception, "java/lang/NoClassDefFoundError");
+
+        let silent = ExceptionInfo::new("SilentException", message.clone());
+        let exception = silent.instantiate(&mut class_loader, &mut context);
+        assert_exception(&context.heap, exception, "java/lang/NoSuchMethodError",
+                         "SilentException.<init>(Ljava/lang/String;)V");
+
+        let broken = ExceptionInfo::new("BrokenException", message);
+        let exception = broken.instantiate(&mut class_loader, &mut context);
+        assert_exception(&context.heap, exception, "java/lang/ArithmeticException", "broken");
+    }
+
+    #[test]
+    fn test_invokestatic() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Invoke");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "callStatic", "()I"), 14);
+    }
+
+    #[test]
+    fn test_invokevirtual() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Invoke");
+        load_test_class!(class_loader, "InvokeSub");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "callVirtual", "()I"), 12);
+    }
+
+    #[test]
+    fn test_invokespecial() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Special");
+        load_test_class!(class_loader, "SpecialSub");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "construct", "()I"), 42);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "callPrivate", "()I"), 7);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "callSuper", "()I"), 512);
+    }
+
+    fn load_resolution(class_loader: &mut BootstrapClassLoader) -> Rc<Class> {
+        load_test_class!(class_loader, "ResolutionNamed");
+        load_test_class!(class_loader, "ResolutionBase");
+        load_test_class!(class_loader, "ResolutionMiddle");
+        load_test_class!(class_loader, "ResolutionLeaf");
+        load_test_class!(class_loader, "Resolution")
+    }
+
+    #[test]
+    fn test_resolve_method() {
+        let mut class_loader = test_class_loader();
+        load_resolution(&mut class_loader);
+        let leaf = class_loader.load_class(&sig::Class::new("ResolutionLeaf")).unwrap();
+        let value = leaf.resolve_method(&sig::Method::new("value", "()I")).unwrap();
+        assert_eq!(value.symref.class.sig, sig::Class::new("ResolutionBase"));
+        let name = leaf.resolve_method(&sig::Method::new("name", "()I")).unwrap();
+        assert_eq!(name.symref.class.sig, sig::Class::new("ResolutionNamed"));
+        assert!(leaf.resolve_method(&sig::Method::new("missing", "()I")).is_none());
+
+        let twice_sig = sig::Method::new("twice", "(I)I");
+        assert!(leaf.resolve_static_method(&twice_sig).is_some());
+        assert!(leaf.resolve_static_method(&sig::Method::new("value", "()I")).is_none());
+        assert!(leaf.resolve_static_method(&sig::Method::new("name", "()I")).is_none());
+    }
+
+    #[test]
+    fn test_invoke_inherited() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_resolution(&mut class_loader);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "inherited", "()I"), 7);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "inheritedStatic", "()I"),
+                   42);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "inheritedDefault", "()I"),
+                   11);
+    }
+
+    #[test]
+    fn test_static_fields() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Fields");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "increment", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "incrementTwice", "()I"), 3);
+    }
+
+    #[test]
+    fn test_static_field_survives_collection() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Fields");
+        let desc = "()Ljava/lang/Object;";
+        let object = invoke_reference(&mut class_loader, &mut context, &class, "storeObject", desc);
+        context.collect(&[]);
+        assert!(context.heap.is_live(object));
+        let loaded = invoke_reference(&mut class_loader, &mut context, &class, "loadObject", desc);
+        assert_eq!(loaded, object);
+    }
+
+    #[test]
+    fn test_instance_fields() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Fields");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "instance", "()I"), 8);
+    }
+
+    #[test]
+    fn test_inherited_fields() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Fields");
+        load_test_class!(class_loader, "FieldsTable");
+        let subclass = load_test_class!(class_loader, "FieldsSub");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "inherited", "()I"), 12);
+        let value = sig::Field { name: String::from("value"), ty: sig::Type::Int };
+        assert!(subclass.resolve_instance_field(&value).is_ok());
+        let counter = sig::Field { name: String::from("counter"), ty: sig::Type::Int };
+        assert!(subclass.resolve_instance_field(&counter).is_err());
+        assert_eq!(subclass.resolve_field(&counter).unwrap().symref.sig, sig::Class::new("Fields"));
+    }
+
+    #[test]
+    fn test_incompatible_fields() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/NoSuchFieldError");
+        load_test_class!(class_loader, "FieldChangesTarget");
+        let class = load_test_class!(class_loader, "FieldChanges");
+        for &name in &["getMissing", "putMissing"] {
+            let exception = invoke_throwing(&mut class_loader, &mut context, &class, name, "()I");
+            assert_exception(&context.heap, exception, "java/lang/NoSuchFieldError", "missing");
+        }
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "getFieldMissing",
+                                        "()I");
+        assert_exception(&context.heap, exception, "java/lang/NoSuchFieldError", "gone");
+        for &name in &["getStaticOfInstance", "putStaticOfInstance"] {
+            let exception = invoke_throwing(&mut class_loader, &mut context, &class, name, "()I");
+            assert_exception(&context.heap, exception, "java/lang/IncompatibleClassChangeError",
+                             "Expected static field FieldChangesTarget.counter");
+        }
+        for &name in &["getFieldOfStatic", "putFieldOfStatic"] {
+            let exception = invoke_throwing(&mut class_loader, &mut context, &class, name, "()I");
+            assert_exception(&context.heap, exception, "java/lang/IncompatibleClassChangeError",
+                             "Expected non-static field FieldChangesTarget.value");
+        }
+    }
+
+    #[test]
+    fn test_interface_fields() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Fields");
+        load_test_class!(class_loader, "FieldsTable");
+        load_test_class!(class_loader, "FieldsSub");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "interfaceField", "()I"), 2);
+    }
+
+    /// Assert that an exception is an instance of the named class.
+    fn assert_exception_class(heap: &Heap, exception: Exception, class_name: &str) {
+        let exception_class = heap.get_scalar(exception).get_class();
+        assert_eq!(exception_class.symref.sig, sig::Class::new(class_name));
+    }
+
+    #[test]
+    fn test_getfield_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "Fields");
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "getNull", "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    fn test_putfield_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "Fields");
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "putNull", "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    fn test_new_arrays() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Arrays");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "intArray", "()I"), 47);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "objectArray", "()I"), 113);
+    }
+
+    #[test]
+    fn test_multianewarray() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Arrays");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "multiArray", "()I"), 347);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "partialMultiArray", "()I"),
+                   123);
+    }
+
+    #[test]
+    fn test_new_multi_array_components() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class_symref = symref::Class { sig: sig::Class::new("[[I") };
+        let class = class_loader.resolve_class(&class_symref).unwrap();
+        let array = match new_multi_array(class, &[3, 4], &mut class_loader, &mut context) {
+            Value::ArrayReference(array) => array,
+            v => panic!("expected an array, but was {:?}", v),
+        };
+        assert_eq!(context.heap.get_array(array).len(), 3);
+        let components = (0..3).map(|i| match context.heap.get_array(array).get(i) {
+            Value::ArrayReference(component) => component,
+            v => panic!("expected an array, but was {:?}", v),
+        }).collect::<Vec<_>>();
+        for (i, &component) in components.iter().enumerate() {
+            assert_eq!(context.heap.get_array(component).len(), 4);
+            match context.heap.get_array(component).get(3) {
+                Value::Int(Wrapping(0)) => (),
+                v => panic!("expected 0, but was {:?}", v),
+            }
+            assert!(!components[..i].contains(&component));
+        }
+    }
+
+    #[test]
+    fn test_negative_array_size() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/NegativeArraySizeException");
+        let class = load_test_class!(class_loader, "Arrays");
+        for &(name, expected) in &[("negative", -1), ("negativeObject", -2),
+                                   ("negativeMulti", -3)] {
+            assert_eq!(invoke_int(&mut class_loader, &mut context, &class, name, "()I"),
+                       expected);
+        }
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class,
+                                        "negativeUncaught", "()I");
+        assert_exception(&context.heap, exception, "java/lang/NegativeArraySizeException", "-5");
+    }
+
+    #[test]
+    fn test_arraycopy() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/ArrayStoreException");
+        let class = load_test_class!(class_loader, "Arrays");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "copy", "()I"), 1123);
+        for name in &["copyToNonArray", "copyFromNonArray", "copyNull",
+                      "copyElementMismatchPartially"] {
+            assert_eq!(invoke_int(&mut class_loader, &mut context, &class, name, "()I"), -1);
+        }
+    }
+
+    #[test]
+    fn test_arraycopy_checks() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/ArrayStoreException");
+        let class = load_test_class!(class_loader, "Arrays");
+        let aiobe = "java/lang/ArrayIndexOutOfBoundsException";
+        let array_store = "java/lang/ArrayStoreException";
+        for &(name, class_name, message) in &[
+            ("copyNegativeSource", aiobe, "arraycopy: source index -1 out of bounds for int[3]"),
+            ("copyNegativeDestination", aiobe,
+             "arraycopy: destination index -2 out of bounds for int[3]"),
+            ("copyNegativeLength", aiobe, "arraycopy: length -1 is negative"),
+            ("copyPastSource", aiobe, "arraycopy: last source index 4 out of bounds for int[3]"),
+            ("copyPastDestination", aiobe,
+             "arraycopy: last destination index 4 out of bounds for object array[3]"),
+            ("copyPrimitiveMismatch", array_store,
+             "arraycopy: type mismatch: can not copy int[] into long[]"),
+            ("copyPrimitiveToObjects", array_store,
+             "arraycopy: type mismatch: can not copy int[] into object array[]"),
+            ("copyElementMismatch", array_store,
+             "arraycopy: element type mismatch: can not cast one of the elements of \
+              java.lang.Object[] to the type of the destination array, java.lang.String"),
+        ] {
+            let exception = invoke_throwing(&mut class_loader, &mut context, &class, name, "()I");
+            assert_exception(&context.heap, exception, class_name, message);
+        }
+    }
+
+    #[test]
+    fn test_array_load_store() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "ArrayAccess");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "fill", "()I"), 285);
+    }
+
+    /// Invoke a static method which is expected to throw an exception, and return the exception.
+    fn invoke_throwing(class_loader: &mut BootstrapClassLoader, context: &mut Context,
+                       class: &Class, name: &str, descriptor: &str) -> Exception {
+        let method = class.find_method(&sig::Method::new(name, descriptor)).unwrap();
+        match method.invoke(class, class_loader, context, vec![]) {
+            Err(exception) => exception,
+            v => panic!("expected an exception, but was {:?}", v),
+        }
+    }
+
+    /// Assert that an exception is an instance of the named class with the given detail message.
+    fn assert_exception(heap: &Heap, exception: Exception, class_name: &str, message: &str) {
+        assert_exception_class(heap, exception, class_name);
+        let message_sig = sig::Field {
+            name: String::from("detailMessage"),
+            ty: Type::new("Ljava/lang/String;"),
+        };
+        match heap.get_scalar(exception).get_field(&message_sig) {
+            Value::ScalarReference(string) => assert_eq!(string_to_rust(heap, string), message),
+            v => panic!("expected a String, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_array_out_of_bounds() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "ArrayAccess");
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class,
+                                        "outOfBounds", "()I");
+        assert_exception(&context.heap, exception, "java/lang/ArrayIndexOutOfBoundsException",
+                         "Index 5 out of bounds for length 3");
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "storeOutOfBounds",
+                                        "()I");
+        assert_exception(&context.heap, exception, "java/lang/ArrayIndexOutOfBoundsException",
+                         "Index -1 out of bounds for length 3");
+    }
+
+    #[test]
+    fn test_array_out_of_bounds_caught() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "ArrayAccess");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "catchOutOfBounds", "()I"),
+                   -1);
+    }
+
+    #[test]
+    fn test_array_length() {
+        assert_eq!(execute_int(&[ICONST_0, NEWARRAY, 10, ARRAYLENGTH, IRETURN], vec![]), 0);
+        assert_eq!(execute_int(&[ICONST_5, NEWARRAY, 10, ARRAYLENGTH, IRETURN], vec![]), 5);
+        let (exception, heap) = execute_throwing(&[ACONST_NULL, ARRAYLENGTH, IRETURN], vec![]);
+        assert_exception_class(&heap, exception, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    fn test_monitors() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let object_class = class_loader.load_class(&sig::Class::new("java/lang/Object")).unwrap();
+        let class = load_test_class!(class_loader, "Monitors");
+        let lock = context.heap.alloc_scalar(object_class);
+        for expected in 1..3 {
+            let method = class.find_method(&sig::Method::new("increment", "(Ljava/lang/Object;)I"))
+                .unwrap();
+            let args = vec![Value::ScalarReference(lock)];
+            match method.invoke(&class, &mut class_loader, &mut context, args) {
+                Ok(Some(Value::Int(Wrapping(n)))) => assert_eq!(n, expected),
+                v => panic!("expected an int result, but was {:?}", v),
+            }
+            assert_eq!(context.monitor_entry_count(lock), 0);
+        }
+
+        // the monitor is exited by the handler javac generates when the block throws
+        let method = class.find_method(&sig::Method::new("throwing", "(Ljava/lang/Object;)I"))
+            .unwrap();
+        let args = vec![Value::ScalarReference(lock)];
+        match method.invoke(&class, &mut class_loader, &mut context, args) {
+            Ok(Some(Value::Int(Wrapping(n)))) => assert_eq!(n, -1),
+            v => panic!("expected an int result, but was {:?}", v),
+        }
+        assert_eq!(context.monitor_entry_count(lock), 0);
+
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "nullLock", "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    fn test_unbalanced_monitor_exit() {
+        let code = [ICONST_0, NEWARRAY, 10, DUP, MONITORENTER, DUP, MONITOREXIT, MONITOREXIT,
+                    RETURN];
+        let (exception, heap) = execute_throwing(&code, vec![]);
+        assert_exception_class(&heap, exception, "java/lang/IllegalMonitorStateException");
+    }
+
+    /// Store `value` into a new array of the given `newarray` type, then load it back out.
+    fn array_round_trip(array_type: u8, store: u8, load: u8, value: Value) -> Option<Value> {
+        let code = [ICONST_1, NEWARRAY, array_type, DUP, ICONST_0, ALOAD_0, store,
+                    ICONST_0, load, ARETURN];
+        execute(&code, vec![Some(value)])
+    }
+
+    #[test]
+    fn test_array_narrowing() {
+        let narrow = |array_type, store, load, n| {
+            match array_round_trip(array_type, store, load, Value::Int(Wrapping(n))) {
+                Some(Value::Int(Wrapping(n))) => n,
+                v => panic!("expected an int, but was {:?}", v),
+            }
+        };
+        // boolean
+        assert_eq!(narrow(4, BASTORE, BALOAD, 3), 1);
+        assert_eq!(narrow(4, BASTORE, BALOAD, 2), 0);
+        // byte
+        assert_eq!(narrow(8, BASTORE, BALOAD, 0x180), -128);
+        // char
+        assert_eq!(narrow(5, CASTORE, CALOAD, -1), 0xffff);
+        // short
+        assert_eq!(narrow(9, SASTORE, SALOAD, 0x18000), -0x8000);
+        // int
+        assert_eq!(narrow(10, IASTORE, IALOAD, -0x12345678), -0x12345678);
+    }
+
+    #[test]
+    fn test_wide_arrays() {
+        match array_round_trip(11, LASTORE, LALOAD, Value::Long(Wrapping(i64::MIN))) {
+            Some(Value::Long(Wrapping(i64::MIN))) => (),
+            v => panic!("expected Long.MIN_VALUE, but was {:?}", v),
+        }
+        match array_round_trip(6, FASTORE, FALOAD, Value::Float(1.5)) {
+            Some(Value::Float(f)) => assert_eq!(f, 1.5),
+            v => panic!("expected 1.5f, but was {:?}", v),
+        }
+        match array_round_trip(7, DASTORE, DALOAD, Value::Double(-2.25)) {
+            Some(Value::Double(d)) => assert_eq!(d, -2.25),
+            v => panic!("expected -2.25d, but was {:?}", v),
+        }
+    }
+
+    fn convert(op: u8, value: Value) -> Value {
+        execute(&[ALOAD_0, op, ARETURN], vec![Some(value)]).unwrap()
+    }
+
+    macro_rules! assert_converts {
+        ($op: expr, $from: expr, $to_variant: path, $to: expr) => ({
+            match convert($op, $from) {
+                $to_variant(v) => assert_eq!(v, $to),
+                v => panic!("expected {}, but was {:?}", stringify!($to_variant), v),
+            }
+        });
+    }
+
+    #[test]
+    fn test_int_conversions() {
+        let int = |n| Value::Int(Wrapping(n));
+        assert_converts!(I2L, int(-1), Value::Long, Wrapping(-1));
+        assert_converts!(I2L, int(i32::MIN), Value::Long, Wrapping(i32::MIN as i64));
+        assert_converts!(I2F, int(16777217), Value::Float, 16777216.0);
+        assert_converts!(I2D, int(i32::MAX), Value::Double, 2147483647.0);
+        assert_converts!(I2B, int(0x17f), Value::Int, Wrapping(127));
+        assert_converts!(I2B, int(0x180), Value::Int, Wrapping(-128));
+        assert_converts!(I2C, int(-1), Value::Int, Wrapping(0xffff));
+        assert_converts!(I2C, int(0x12345), Value::Int, Wrapping(0x2345));
+        assert_converts!(I2S, int(0x8000), Value::Int, Wrapping(-0x8000));
+        assert_converts!(I2S, int(0x17fff), Value::Int, Wrapping(0x7fff));
+    }
+
+    #[test]
+    fn test_long_conversions() {
+        let long = |n| Value::Long(Wrapping(n));
+        assert_converts!(L2I, long(0x1_8000_0000), Value::Int, Wrapping(i32::MIN));
+        assert_converts!(L2I, long(-1), Value::Int, Wrapping(-1));
+        assert_converts!(L2F, long(i64::MAX), Value::Float, 9.223372e18);
+        assert_converts!(L2D, long(i64::MIN), Value::Double, -9.223372036854776e18);
+    }
+
+    #[test]
+    fn test_float_conversions() {
+        let float = |f| Value::Float(f);
+        assert_converts!(F2I, float(-1.9), Value::Int, Wrapping(-1));
+        assert_converts!(F2I, float(f32::NAN), Value::Int, Wrapping(0));
+        assert_converts!(F2I, float(f32::INFINITY), Value::Int, Wrapping(i32::MAX));
+        assert_converts!(F2I, float(f32::NEG_INFINITY), Value::Int, Wrapping(i32::MIN));
+        assert_converts!(F2I, float(1e10), Value::Int, Wrapping(i32::MAX));
+        assert_converts!(F2L, float(f32::NAN), Value::Long, Wrapping(0));
+        assert_converts!(F2L, float(f32::INFINITY), Value::Long, Wrapping(i64::MAX));
+        assert_converts!(F2L, float(f32::NEG_INFINITY), Value::Long, Wrapping(i64::MIN));
+        assert_converts!(F2D, float(0.1), Value::Double, 0.1f32 as f64);
+        match convert(F2D, float(f32::NAN)) {
+            Value::Double(d) => assert!(d.is_nan()),
+            v => panic!("expected NaN, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_double_conversions() {
+        let double = |d| Value::Double(d);
+        assert_converts!(D2I, double(2.9), Value::Int, Wrapping(2));
+        assert_converts!(D2I, double(f64::NAN), Value::Int, Wrapping(0));
+        assert_converts!(D2I, double(f64::INFINITY), Value::Int, Wrapping(i32::MAX));
+        assert_converts!(D2I, double(f64::NEG_INFINITY), Value::Int, Wrapping(i32::MIN));
+        assert_converts!(D2L, double(f64::NAN), Value::Long, Wrapping(0));
+        assert_converts!(D2L, double(-1e300), Value::Long, Wrapping(i64::MIN));
+        assert_converts!(D2L, double(1e300), Value::Long, Wrapping(i64::MAX));
+        assert_converts!(D2F, double(1e300), Value::Float, f32::INFINITY);
+        assert_converts!(D2F, double(-0.5), Value::Float, -0.5);
+    }
+
+    #[test]
+    fn test_stack_instructions() {
+        // (a, b) -> a * (a + b), using dup_x1 and swap
+        let code = [ILOAD_0, ILOAD_1, SWAP, DUP_X1, IADD, IMUL, IRETURN];
+        assert_eq!(execute_int(&code, vec![int(3), int(4)]), 21);
+
+        // the result of a long addition is kept with dup2 while a copy is discarded with pop2
+        let code = [LCONST_1, LCONST_1, LADD, DUP2, DUP2, POP2, LADD, LRETURN];
+        match execute(&code, vec![]) {
+            Some(Value::Long(Wrapping(4))) => (),
+            v => panic!("expected 4L, but was {:?}", v),
+        }
+    }
+
+    fn int_binop(op: u8, a: i32, b: i32) -> i32 {
+        execute_int(&[ILOAD_0, ILOAD_1, op, IRETURN], vec![int(a), int(b)])
+    }
+
+    /// Apply a long operation; the second operand is a long, except for shifts where it is an int.
+    fn long_binop(op: u8, a: i64, b: Value) -> i64 {
+        let locals = vec![Some(Value::Long(Wrapping(a))), Some(b)];
+        match execute(&[ALOAD_0, ALOAD_1, op, LRETURN], locals) {
+            Some(Value::Long(Wrapping(n))) => n,
+            v => panic!("expected a long result, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_int_bitwise() {
+        assert_eq!(int_binop(IAND, 0b1100, 0b1010), 0b1000);
+        assert_eq!(int_binop(IOR, 0b1100, 0b1010), 0b1110);
+        assert_eq!(int_binop(IXOR, 0b1100, 0b1010), 0b0110);
+        assert_eq!(int_binop(IXOR, -1, 0x0f0f), !0x0f0f);
+    }
+
+    #[test]
+    fn test_int_shifts() {
+        assert_eq!(int_binop(ISHL, 1, 31), i32::MIN);
+        // only the low five bits of the shift amount are used
+        assert_eq!(int_binop(ISHL, 5, 32), 5);
+        assert_eq!(int_binop(ISHL, 5, 33), 10);
+        assert_eq!(int_binop(ISHL, 5, -1), i32::MIN);
+        assert_eq!(int_binop(ISHR, -16, 2), -4);
+        assert_eq!(int_binop(ISHR, i32::MIN, 31), -1);
+        assert_eq!(int_binop(IUSHR, -16, 2), 0x3ffffffc);
+        assert_eq!(int_binop(IUSHR, i32::MIN, 31), 1);
+        assert_eq!(int_binop(IUSHR, -1, 32), -1);
+    }
+
+    #[test]
+    fn test_long_bitwise() {
+        let long = |n| Value::Long(Wrapping(n));
+        assert_eq!(long_binop(LAND, -1, long(0x1234_5678_9abc_def0)), 0x1234_5678_9abc_def0);
+        assert_eq!(long_binop(LOR, i64::MIN, long(1)), i64::MIN + 1);
+        assert_eq!(long_binop(LXOR, -1, long(i64::MAX)), i64::MIN);
+    }
+
+    #[test]
+    fn test_long_shifts() {
+        let shift = |n| Value::Int(Wrapping(n));
+        assert_eq!(long_binop(LSHL, 1, shift(63)), i64::MIN);
+        // only the low six bits of the shift amount are used
+        assert_eq!(long_binop(LSHL, 5, shift(64)), 5);
+        assert_eq!(long_binop(LSHL, 1, shift(32)), 1 << 32);
+        assert_eq!(long_binop(LSHR, i64::MIN, shift(63)), -1);
+        assert_eq!(long_binop(LUSHR, i64::MIN, shift(63)), 1);
+        assert_eq!(long_binop(LUSHR, -1, shift(1)), i64::MAX);
+        assert_eq!(long_binop(LUSHR, -1, shift(-1)), 1);
+    }
+
+    #[test]
+    fn test_ldc_numeric() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Literals");
+        let mut invoke = |name, descriptor| {
+            let method = class.find_method(&sig::Method::new(name, descriptor)).unwrap();
+            method.invoke(&class, &mut class_loader, &mut context, vec![])
+                .expect("uncaught exception")
+        };
+        match invoke("integer", "()I") {
+            Some(Value::Int(Wrapping(100000))) => (),
+            v => panic!("expected 100000, but was {:?}", v),
+        }
+        match invoke("floating", "()F") {
+            Some(Value::Float(f)) => assert_eq!(f, -1.5),
+            v => panic!("expected -1.5f, but was {:?}", v),
+        }
+        match invoke("wide", "()J") {
+            Some(Value::Long(Wrapping(-1234567890123))) => (),
+            v => panic!("expected -1234567890123L, but was {:?}", v),
+        }
+        match invoke("precise", "()D") {
+            Some(Value::Double(d)) => assert_eq!(d, 0.1),
+            v => panic!("expected 0.1d, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_ldc_w() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Literals");
+        // constant pool entry 7 of Literals is the int 100000
+        let code = [LDC_W, 0, 7, IRETURN];
+        match Frame::new(&class, &code, &[], 16, vec![]).run(&mut class_loader, &mut context) {
+            Ok(Some(Value::Int(Wrapping(100000)))) => (),
+            v => panic!("expected 100000, but was {:?}", v),
+        }
+    }
+
+    /// Load the classes needed to create strings and the exceptions thrown by the virtual machine.
+    fn load_runtime_classes(class_loader: &mut BootstrapClassLoader) {
+        load_test_class!(class_loader, "java/lang/String");
+        load_test_class!(class_loader, "java/io/OutputStream");
+        load_test_class!(class_loader, "java/io/FilterOutputStream");
+        load_test_class!(class_loader, "java/io/PrintStream");
+        load_test_class!(class_loader, "moon/RustStdout");
+        load_test_class!(class_loader, "java/lang/System");
+        load_test_class!(class_loader, "java/lang/Throwable");
+        load_test_class!(class_loader, "java/lang/Exception");
+        load_test_class!(class_loader, "java/lang/RuntimeException");
+        load_test_class!(class_loader, "java/lang/IndexOutOfBoundsException");
+        load_test_class!(class_loader, "java/lang/ArrayIndexOutOfBoundsException");
+        load_test_class!(class_loader, "java/lang/NullPointerException");
+        load_test_class!(class_loader, "java/lang/ArithmeticException");
+        load_test_class!(class_loader, "java/lang/IllegalMonitorStateException");
+        load_test_class!(class_loader, "java/lang/ClassCastException");
+        load_test_class!(class_loader, "java/lang/Class");
+        load_test_class!(class_loader, "java/lang/Error");
+        load_test_class!(class_loader, "java/lang/LinkageError");
+        load_test_class!(class_loader, "java/lang/IncompatibleClassChangeError");
+        load_test_class!(class_loader, "java/lang/AbstractMethodError");
+        load_test_class!(class_loader, "java/lang/NoSuchMethodError");
+        load_test_class!(class_loader, "java/lang/NoClassDefFoundError");
+    }
+
+    #[test]
+    fn test_ldc_string() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "Literals");
+        let method = class.find_method(&sig::Method::new("string", "()Ljava/lang/String;"))
+            .unwrap();
+        let string = match method.invoke(&class, &mut class_loader, &mut context, vec![]) {
+            Ok(Some(Value::ScalarReference(string))) => string,
+            v => panic!("expected a String, but was {:?}", v),
+        };
+        assert_eq!(string_to_rust(&context.heap, string), "h\u{e9}llo, \u{4e16}\u{754c}\u{1f600}");
+    }
+
+    #[test]
+    fn test_ldc_string_survives_collection() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "Literals");
+        class.initialize(&mut class_loader, &mut context);
+        let desc = "()Ljava/lang/String;";
+        let string = invoke_reference(&mut class_loader, &mut context, &class, "string", desc);
+        context.collect(&[]);
+        let again = invoke_reference(&mut class_loader, &mut context, &class, "string", desc);
+        assert_eq!(again, string);
+        assert_eq!(string_to_rust(&context.heap, string), "h\u{e9}llo, \u{4e16}\u{754c}\u{1f600}");
+    }
+
+    #[test]
+    fn test_string_natives() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "Strings");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "length", "()I"), 50);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "charAt", "()I"), 0xe9);
+        load_test_class!(class_loader, "java/lang/StringIndexOutOfBoundsException");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "charAtOutOfBounds", "()I"),
+                   -1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "compare", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "helloHashCode", "()I"),
+                   99162322);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "emptyHashCode", "()I"), 0);
+    }
+
+    fn invoke_string(class_loader: &mut BootstrapClassLoader, context: &mut Context, class: &Class,
+                     name: &str) -> String {
+        let method = class.find_method(&sig::Method::new(name, "()Ljava/lang/String;")).unwrap();
+        match method.invoke(class, class_loader, context, vec![]) {
+            Ok(Some(Value::ScalarReference(string))) => string_to_rust(&context.heap, string),
+            v => panic!("expected a String, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_ldc_class() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_test_class!(class_loader, "ClassLiterals");
+        assert_eq!(invoke_string(&mut class_loader, &mut context, &class, "name"), "ClassLiterals");
+        assert_eq!(invoke_string(&mut class_loader, &mut context, &class, "arrayName"),
+                   "[Ljava.lang.String;");
+        assert_eq!(invoke_string(&mut class_loader, &mut context, &class, "getClassName"),
+                   "ClassLiterals");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "same", "()Z"), 1);
+    }
+
+    #[test]
+    fn test_ldc_class_not_found() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        // ClassLiteralsOther is not loaded, and there is nowhere to load it from
+        let class = load_test_class!(class_loader, "ClassLiterals");
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "otherName",
+                                        "()Ljava/lang/String;");
+        assert_exception_class(&context.heap, exception, "java/lang/NoClassDefFoundError");
+    }
+
+    /// Load the `Exceptions` test class, along with the exception classes it uses.
+    fn load_exceptions(class_loader: &mut BootstrapClassLoader) -> Rc<Class> {
+        load_runtime_classes(class_loader);
+        load_test_class!(class_loader, "ExceptionsOther");
+        load_test_class!(class_loader, "Exceptions")
+    }
+
+    #[test]
+    fn test_athrow_caught() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_exceptions(&mut class_loader);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "catchLocal", "()I"), 20);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "catchFromCallee", "()I"),
+                   1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "skipNonMatching", "()I"),
+                   3);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "catchRethrown", "()I"), 4);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "finallyRuns", "()I"), 6);
+    }
+
+    #[test]
+    fn test_athrow_uncaught() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_exceptions(&mut class_loader);
+        let method = class.find_method(&sig::Method::new("uncaught", "()I")).unwrap();
+        match method.invoke(&class, &mut class_loader, &mut context, vec![]) {
+            Err(exception) => {
+                let exception_class = context.heap.get_scalar(exception).get_class();
+                assert_eq!(exception_class.symref.sig,
+                           sig::Class::new("java/lang/RuntimeException"));
+            },
+            Ok(v) => panic!("expected an exception, but returned {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_athrow_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_exceptions(&mut class_loader);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class,
+                                        "throwNull", "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    fn test_invoke_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_exceptions(&mut class_loader);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class,
+                                        "invokeNull", "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "catchInvokeNull", "()I"),
+                   6);
+    }
+
+    #[test]
+    fn test_arraylength_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_exceptions(&mut class_loader);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "catchArrayLengthNull",
+                              "()I"), 7);
+    }
+
+    #[test]
+    fn test_monitorenter_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_exceptions(&mut class_loader);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "synchronizeNull",
+                                        "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    fn test_object_equals() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Identity");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "equalsSelf", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "equalsOther", "()I"), 0);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "equalsNull", "()I"), 0);
+    }
+
+    #[test]
+    fn test_object_hash_code() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Identity");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "hashCodeConsistent", "()I"),
+                   1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "hashCodeDistinct", "()I"),
+                   1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "arrayHashCode", "()I"), 1);
+    }
+
+    #[test]
+    fn test_object_clone() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_test_class!(class_loader, "Clones");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "cloneObject", "()I"), 3454);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "cloneArray", "()I"), 1232);
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/CloneNotSupportedException");
+        load_test_class!(class_loader, "Uncloneable");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "cloneUncloneable", "()I"),
+                   -1);
+    }
+
+    #[test]
+    fn test_math_natives() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_test_class!(class_loader, "java/lang/Math");
+        let class = load_test_class!(class_loader, "Maths");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "absMinValue", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "roundings", "()I"), 32);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "maxMin", "()I"), 49);
+    }
+
+    #[test]
+    fn test_integer_natives() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/IllegalArgumentException");
+        load_test_class!(class_loader, "java/lang/NumberFormatException");
+        load_test_class!(class_loader, "java/lang/Integer");
+        let class = load_test_class!(class_loader, "Integers");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "parse", "()I"), 132);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "parseInvalid", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "parseOverflow", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "parseInvalidRadix", "()I"),
+                   1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "valueOfCached", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "valueOfUncached", "()I"),
+                   1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "intValue", "()I"), 995);
+    }
+
+    #[test]
+    fn test_string_builder_natives() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/StringBuilder");
+        let class = load_test_class!(class_loader, "StringBuilders");
+        assert_eq!(invoke_string(&mut class_loader, &mut context, &class, "hello"),
+                   "Hello, World!");
+        assert_eq!(invoke_string(&mut class_loader, &mut context, &class, "numbers"),
+                   "42 -7000000000null");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "length", "()I"), 5);
+    }
+
+    fn load_interfaces(class_loader: &mut BootstrapClassLoader) -> Rc<Class> {
+        load_test_class!(class_loader, "InterfacesShape");
+        load_test_class!(class_loader, "InterfacesPolygon");
+        load_test_class!(class_loader, "InterfacesTriangle");
+        load_test_class!(class_loader, "InterfacesSquare");
+        load_test_class!(class_loader, "InterfacesBigTriangle");
+        load_test_class!(class_loader, "Interfaces")
+    }
+
+    #[test]
+    fn test_invokeinterface() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_interfaces(&mut class_loader);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "polymorphic", "()I"), 34);
+    }
+
+    #[test]
+    fn test_invokeinterface_default_method() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_interfaces(&mut class_loader);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "defaultMethod", "()I"),
+                   340);
+    }
+
+    #[test]
+    fn test_invokeinterface_null() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_interfaces(&mut class_loader);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "nullReceiver",
+                                        "()I");
+        assert_exception_class(&context.heap, exception, "java/lang/NullPointerException");
+    }
+
+    /// Invoke a static method of `Interfaces` taking a single object, passing it a new instance of
+    /// the named class, which need not have the type of the parameter.
+    fn invoke_interfaces_with(class_loader: &mut BootstrapClassLoader, context: &mut Context,
+                              class: &Class, name: &str, descriptor: &str, receiver: &str)
+                              -> Exception {
+        let receiver_symref = symref::Class { sig: sig::Class::new(receiver) };
+        let receiver_class = class_loader.resolve_class(&receiver_symref).unwrap();
+        let receiver = Value::ScalarReference(context.heap.alloc_scalar(receiver_class));
+        let method = class.find_method(&sig::Method::new(name, descriptor)).unwrap();
+        match method.invoke(class, class_loader, context, vec![receiver]) {
+            Err(exception) => exception,
+            v => panic!("expected an exception, but was {:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_invoke_abstract_method() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_interfaces(&mut class_loader);
+        load_test_class!(class_loader, "InterfacesAbstract");
+        let exception = invoke_interfaces_with(&mut class_loader, &mut context, &class,
+                                               "abstractSides", "(LInterfacesAbstract;)I",
+                                               "InterfacesAbstract");
+        assert_exception(&context.heap, exception, "java/lang/AbstractMethodError",
+                         "InterfacesAbstract.sides()I");
+        let exception = invoke_interfaces_with(&mut class_loader, &mut context, &class, "sides",
+                                               "(LInterfacesShape;)I", "InterfacesAbstract");
+        assert_exception(&context.heap, exception, "java/lang/AbstractMethodError",
+                         "InterfacesAbstract.sides()I");
+    }
+
+    #[test]
+    fn test_invokeinterface_incompatible_class() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_interfaces(&mut class_loader);
+        let exception = invoke_interfaces_with(&mut class_loader, &mut context, &class, "sides",
+                                               "(LInterfacesShape;)I", "java/lang/Object");
+        assert_exception(&context.heap, exception, "java/lang/IncompatibleClassChangeError",
+                         "Class java.lang.Object does not implement the requested interface \
+                          InterfacesShape");
+    }
+
+    /// Load `Interfaces` with the operands of the `invokeinterface` in its method `sides`
+    /// replaced by `count` and `reserved`, as if the class file had been assembled by hand.
+    fn load_patched_interfaces(class_loader: &mut BootstrapClassLoader, count: u8, reserved: u8)
+                               -> Rc<Class> {
+        load_test_class!(class_loader, "InterfacesShape");
+        load_test_class!(class_loader, "InterfacesPolygon");
+        load_test_class!(class_loader, "InterfacesTriangle");
+        load_test_class!(class_loader, "InterfacesSquare");
+        let mut class_bytes = include_bytes!("../../data/vm/Interfaces.class").to_vec();
+        // `sides` is the first method, and its code is `aload_0; invokeinterface #n, 1; ireturn`
+        let offset = class_bytes.windows(7).position(|code| {
+            code[0] == ALOAD_0 && code[1] == INVOKEINTERFACE && code[4] == 1 && code[5] == 0
+                && code[6] == IRETURN
+        }).unwrap();
+        class_bytes[offset + 4] = count;
+        class_bytes[offset + 5] = reserved;
+        class_loader.define_class("Interfaces", &class_bytes).unwrap()
+    }
+
+    #[test]
+    fn test_invokeinterface_wrong_count() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/VerifyError");
+        let class = load_patched_interfaces(&mut class_loader, 2, 0);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "polymorphic",
+                                        "()I");
+        assert_exception(&context.heap, exception, "java/lang/VerifyError",
+                         "invokeinterface count 2 does not match the descriptor ()I");
+    }
+
+    #[test]
+    fn test_invokeinterface_nonzero_reserved_byte() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        load_test_class!(class_loader, "java/lang/VerifyError");
+        let class = load_patched_interfaces(&mut class_loader, 1, 7);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class, "polymorphic",
+                                        "()I");
+        assert_exception(&context.heap, exception, "java/lang/VerifyError",
+                         "invokeinterface has non-zero fourth operand byte 7");
+    }
+
+    fn load_casts(class_loader: &mut BootstrapClassLoader) -> Rc<Class> {
+        load_test_class!(class_loader, "CastsShape");
+        load_test_class!(class_loader, "CastsA");
+        load_test_class!(class_loader, "CastsB");
+        load_test_class!(class_loader, "CastsC");
+        load_test_class!(class_loader, "Casts")
+    }
+
+    #[test]
+    fn test_instanceof() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_casts(&mut class_loader);
+        let expected = 1 | 4 | 8 | 64 | 128 | 256 | 512;
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "instanceOf", "()I"),
+                   expected);
+    }
+
+    #[test]
+    fn test_checkcast() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let class = load_casts(&mut class_loader);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "checkCastNull", "()I"), 1);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "checkCastSubclass", "()I"),
+                   1);
+    }
+
+    #[test]
+    fn test_checkcast_fails() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        load_runtime_classes(&mut class_loader);
+        let class = load_casts(&mut class_loader);
+        let exception = invoke_throwing(&mut class_loader, &mut context, &class,
+                                        "checkCastSuperclass", "()I");
+        assert_exception(&context.heap, exception, "java/lang/ClassCastException",
+                         "class CastsA cannot be cast to class CastsB");
+    }
+
+    #[test]
+    fn test_iinc() {
+        // i = 0; while (i < 10) { i += 1; } return i
+        let code = [ICONST_0, ISTORE_0,
+                    ILOAD_0, BIPUSH, 10, IF_ICMPGE, 0, 9,
+                    IINC, 0, 1,
+                    GOTO, 0xff, 0xf7,
+                    ILOAD_0, IRETURN];
+        assert_eq!(execute_int(&code, vec![None]), 10);
+        assert_eq!(execute_int(&[IINC, 0, 0xff, ILOAD_0, IRETURN], vec![int(5)]), 4);
+        assert_eq!(execute_int(&[IINC, 0, 1, ILOAD_0, IRETURN], vec![int(i32::MAX)]), i32::MIN);
+    }
+
+    #[test]
+    fn test_wide() {
+        let mut locals = vec![None; 301];
+        locals[300] = int(7);
+        let code = [WIDE, IINC, 0x01, 0x2c, 0x03, 0xe8,
+                    WIDE, ILOAD, 0x01, 0x2c, IRETURN];
+        assert_eq!(execute_int(&code, locals.clone()), 1007);
+        let code = [WIDE, IINC, 0x01, 0x2c, 0xfc, 0x18,
+                    WIDE, ILOAD, 0x01, 0x2c, IRETURN];
+        assert_eq!(execute_int(&code, locals), -993);
+
+        let code = [LCONST_1, WIDE, LSTORE, 0x01, 0x00,
+                    WIDE, LLOAD, 0x01, 0x00, LRETURN];
+        match execute(&code, vec![None; 258]) {
+            Some(Value::Long(Wrapping(1))) => (),
+            v => panic!("expected a long result of 1, but was {:?}", v),
+        }
+
+        let code = [BIPUSH, 42, WIDE, ISTORE, 0x01, 0x00,
+                    WIDE, ILOAD, 0x01, 0x00, IRETURN];
+        assert_eq!(execute_int(&code, vec![None; 257]), 42);
+    }
+
+    fn load_init(class_loader: &mut BootstrapClassLoader) -> (Rc<Class>, Rc<Class>) {
+        let class = load_test_class!(class_loader, "Init");
+        load_test_class!(class_loader, "InitOther");
+        let subclass = load_test_class!(class_loader, "InitSub");
+        (class, subclass)
+    }
+
+    #[test]
+    fn test_clinit() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let (class, _) = load_init(&mut class_loader);
+        assert!(!class.initialized.get());
+        class.initialize(&mut class_loader, &mut context);
+        assert!(class.initialized.get());
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "getValue", "()I"), 42);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "getObserved", "()I"), 42);
+        class.initialize(&mut class_loader, &mut context);
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &class, "getInitializations", "()I"),
+                   1);
+    }
+
+    #[test]
+    fn test_invokestatic_initializes_superclass() {
+        let mut class_loader = test_class_loader();
+        let mut context = Context::new();
+        let (class, subclass) = load_init(&mut class_loader);
+        let caller = load_test_class!(class_loader, "InitCaller");
+        assert_eq!(invoke_int(&mut class_loader, &mut context, &caller, "callSubclass", "()I"), 7);
+        assert!(subclass.initialized.get());
+        assert!(class.initialized.get());
+    }
+}
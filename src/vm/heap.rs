@@ -0,0 +1,398 @@
+//! The heap, in which every object created by the virtual machine is allocated.
+//!
+//! Objects on the heap are referred to by `HeapRef` handles rather than by pointers, so the heap
+//! knows about every object which has been allocated and can reclaim those which are no longer
+//! reachable. Garbage collection uses a simple _mark-sweep_ algorithm: every object reachable
+//! from a set of roots is marked by tracing the references held in its fields or components, and
+//! every unmarked object is then freed. The slot of a freed object is reused by later allocations,
+//! and its generation is incremented, so that a handle to the freed object can be told apart from
+//! a handle to the object which reuses its slot.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use vm::class::Class;
+use vm::value::{Array, Scalar};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A handle to an object allocated on the heap. Two handles are equal exactly when they refer to
+/// the same object.
+pub struct HeapRef {
+    /// The index of the slot holding the object.
+    index: usize,
+    /// The generation of the slot when the object was allocated in it.
+    generation: u32,
+}
+
+impl HeapRef {
+    /// The identity hash code of the object referred to, as returned by `Object.hashCode()`.
+    /// Objects are never moved, so this is the index of the slot holding the object, which is
+    /// unique among the objects on the heap and stable for the lifetime of the object.
+    pub fn identity_hash_code(&self) -> i32 {
+        self.index as i32
+    }
+}
+
+#[derive(Debug)]
+/// An object allocated on the heap.
+pub enum Object {
+    Scalar(Scalar),
+    Array(Array),
+}
+
+impl Object {
+    /// Returns a shallow copy of the object, as made by `Object.clone()`.
+    pub fn clone_fields(&self) -> Object {
+        match *self {
+            Object::Scalar(ref scalar) => Object::Scalar(scalar.clone_fields()),
+            Object::Array(ref array) => Object::Array(array.clone_data()),
+        }
+    }
+
+    /// Returns the handles to the objects referred to by this object.
+    fn references(&self) -> Vec<HeapRef> {
+        match *self {
+            Object::Scalar(ref scalar) => scalar.references(),
+            Object::Array(ref array) => array.references(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A slot in which an object may be allocated.
+struct Slot {
+    /// The number of objects in this slot which have been freed.
+    generation: u32,
+    /// The object in this slot, or `None` if the slot is free.
+    object: Option<Box<Object>>,
+}
+
+#[derive(Debug)]
+/// The heap of the virtual machine, which owns every object it allocates.
+pub struct Heap {
+    /// The slots of the heap, indexed by the handles which refer to the objects in them.
+    objects: Vec<Slot>,
+    /// The indices of the slots in `objects` which are free to be reused.
+    free_slots: Vec<usize>,
+    /// The UTF-16 contents of the `java/lang/StringBuilder` objects which have been appended to.
+    string_builders: HashMap<HeapRef, Vec<u16>>,
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Heap {
+            objects: vec![],
+            free_slots: vec![],
+            string_builders: HashMap::new(),
+        }
+    }
+
+    fn alloc(&mut self, object: Object) -> HeapRef {
+        let object = Some(Box::new(object));
+        match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.objects[index];
+                slot.object = object;
+                HeapRef { index: index, generation: slot.generation }
+            },
+            None => {
+                self.objects.push(Slot { generation: 0, object: object });
+                HeapRef { index: self.objects.len() - 1, generation: 0 }
+            },
+        }
+    }
+
+    /// Allocate a new instance of a non-array class, with its fields set to their default values.
+    pub fn alloc_scalar(&mut self, class: Rc<Class>) -> HeapRef {
+        self.alloc(Object::Scalar(Scalar::new(class)))
+    }
+
+    /// Allocate a new array of an array class, with its components set to their default values.
+    pub fn alloc_array(&mut self, class: Rc<Class>, length: i32) -> HeapRef {
+        self.alloc(Object::Array(Array::new(class, length)))
+    }
+
+    /// Allocate a shallow copy of an object, which is distinct from the original.
+    pub fn clone_object(&mut self, heap_ref: HeapRef) -> HeapRef {
+        let object = self.get(heap_ref).clone_fields();
+        self.alloc(object)
+    }
+
+    /// Returns the object referred to by a handle. Panics if the object has been freed, even if
+    /// its slot has since been reused.
+    fn get(&self, heap_ref: HeapRef) -> &Object {
+        let slot = &self.objects[heap_ref.index];
+        match slot.object {
+            Some(ref object) if slot.generation == heap_ref.generation => object,
+            _ => panic!("dangling heap reference"),
+        }
+    }
+
+    fn get_mut(&mut self, heap_ref: HeapRef) -> &mut Object {
+        let slot = &mut self.objects[heap_ref.index];
+        match slot.object {
+            Some(ref mut object) if slot.generation == heap_ref.generation => object,
+            _ => panic!("dangling heap reference"),
+        }
+    }
+
+    pub fn get_scalar(&self, heap_ref: HeapRef) -> &Scalar {
+        match *self.get(heap_ref) {
+            Object::Scalar(ref scalar) => scalar,
+            Object::Array(_) => panic!("expected a scalar object, but was an array"),
+        }
+    }
+
+    pub fn get_scalar_mut(&mut self, heap_ref: HeapRef) -> &mut Scalar {
+        match *self.get_mut(heap_ref) {
+            Object::Scalar(ref mut scalar) => scalar,
+            Object::Array(_) => panic!("expected a scalar object, but was an array"),
+        }
+    }
+
+    pub fn get_array(&self, heap_ref: HeapRef) -> &Array {
+        match *self.get(heap_ref) {
+            Object::Array(ref array) => array,
+            Object::Scalar(_) => panic!("expected an array, but was a scalar object"),
+        }
+    }
+
+    pub fn get_array_mut(&mut self, heap_ref: HeapRef) -> &mut Array {
+        match *self.get_mut(heap_ref) {
+            Object::Array(ref mut array) => array,
+            Object::Scalar(_) => panic!("expected an array, but was a scalar object"),
+        }
+    }
+
+    /// Returns the contents of a `java/lang/StringBuilder`, which are empty until it is first
+    /// appended to.
+    pub fn string_builder(&self, builder: HeapRef) -> &[u16] {
+        self.string_builders.get(&builder).map_or(&[], |chars| &chars[..])
+    }
+
+    pub fn string_builder_mut(&mut self, builder: HeapRef) -> &mut Vec<u16> {
+        self.string_builders.entry(builder).or_default()
+    }
+
+    pub fn live_count(&self) -> usize {
+        self.objects.len() - self.free_slots.len()
+    }
+
+    /// Returns true if the object referred to by a handle has not been freed.
+    pub fn is_live(&self, heap_ref: HeapRef) -> bool {
+        let slot = &self.objects[heap_ref.index];
+        slot.object.is_some() && slot.generation == heap_ref.generation
+    }
+
+    /// Free every object which is not reachable from one of the given roots.
+    pub fn collect(&mut self, roots: &[HeapRef]) {
+        // mark
+        let mut marked = vec![false; self.objects.len()];
+        let mut pending = roots.to_vec();
+        while let Some(heap_ref) = pending.pop() {
+            if !marked[heap_ref.index] {
+                marked[heap_ref.index] = true;
+                pending.extend(self.get(heap_ref).references());
+            }
+        }
+
+        // sweep
+        for (index, slot) in self.objects.iter_mut().enumerate() {
+            if slot.object.is_some() && !marked[index] {
+                slot.object = None;
+                slot.generation += 1;
+                self.free_slots.push(index);
+            }
+        }
+        self.string_builders.retain(|heap_ref, _| marked[heap_ref.index]);
+    }
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap::new()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use std::num::Wrapping;
+
+    use model::class_file::{access_flags, ClassFile};
+    use model::class_file::constant_pool::ConstantPool;
+    use vm::{sig, symref};
+    use vm::constant_pool::RuntimeConstantPool;
+    use vm::native::NativeRegistry;
+    use vm::value::Value;
+
+    /// Create an empty class with the given name and no superclass.
+    pub fn new_class(name: &str) -> Rc<Class> {
+        let class_file = ClassFile {
+            minor_version: 0,
+            major_version: 50,
+            constant_pool: ConstantPool::from(vec![]),
+            access_flags: access_flags::class_access_flags::ACC_SUPER,
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+        };
+        let constant_pool = RuntimeConstantPool::empty();
+        let symref = symref::Class { sig: sig::Class::new(name) };
+        Rc::new(Class::new(symref, None, vec![], constant_pool, class_file, &NativeRegistry::new()))
+    }
+
+    fn new_array_class(component_ty: sig::Type) -> Rc<Class> {
+        Rc::new(Class::new_array(new_class("java/lang/Object"), vec![], 0x0001, component_ty))
+    }
+
+    /// Allocate a scalar object whose class has a single reference field, `next`, which refers to
+    /// `next`.
+    fn alloc_node(heap: &mut Heap, class: &Rc<Class>, next: Option<HeapRef>) -> HeapRef {
+        let node = heap.alloc_scalar(class.clone());
+        let value = next.map_or(Value::NullReference, Value::ScalarReference);
+        heap.get_scalar_mut(node).put_field(next_field(), value);
+        node
+    }
+
+    fn next_field() -> sig::Field {
+        sig::Field { name: String::from("next"), ty: sig::Type::new("LNode;") }
+    }
+
+    #[test]
+    fn test_alloc() {
+        let mut heap = Heap::new();
+        let array_class = new_array_class(sig::Type::Int);
+        let array = heap.alloc_array(array_class, 3);
+        heap.get_array_mut(array).put(1, Value::Int(Wrapping(7)));
+        let other = heap.alloc_array(new_array_class(sig::Type::Int), 3);
+        assert!(array != other);
+        assert_eq!(heap.get_array(array).len(), 3);
+        assert_eq!(heap.get_array(other).len(), 3);
+        match heap.get_array(array).get(1) {
+            Value::Int(Wrapping(7)) => (),
+            v => panic!("expected 7, but was {:?}", v),
+        }
+        assert_eq!(heap.live_count(), 2);
+    }
+
+    #[test]
+    fn test_clone_object() {
+        let mut heap = Heap::new();
+        let class = new_class("Point");
+        let x = sig::Field { name: String::from("x"), ty: sig::Type::Int };
+        let y = sig::Field { name: String::from("y"), ty: sig::Type::Int };
+        let point = heap.alloc_scalar(class);
+        heap.get_scalar_mut(point).put_field(x.clone(), Value::Int(Wrapping(3)));
+        heap.get_scalar_mut(point).put_field(y.clone(), Value::Int(Wrapping(4)));
+        let copy = heap.clone_object(point);
+        assert!(copy != point);
+        heap.get_scalar_mut(copy).put_field(x.clone(), Value::Int(Wrapping(5)));
+        let field = |heap: &Heap, object, field| match heap.get_scalar(object).get_field(field) {
+            Value::Int(Wrapping(n)) => n,
+            v => panic!("expected an int, but was {:?}", v),
+        };
+        assert_eq!((field(&heap, point, &x), field(&heap, point, &y)), (3, 4));
+        assert_eq!((field(&heap, copy, &x), field(&heap, copy, &y)), (5, 4));
+
+        let array = heap.alloc_array(new_array_class(sig::Type::Int), 2);
+        let array_copy = heap.clone_object(array);
+        heap.get_array_mut(array_copy).put(0, Value::Int(Wrapping(7)));
+        assert_eq!(heap.get_array(array).get(0).to_string(), "0");
+        assert_eq!(heap.get_array(array_copy).get(0).to_string(), "7");
+        assert_eq!(heap.live_count(), 4);
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut heap = Heap::new();
+        let class = new_class("Node");
+        // a list of three nodes, and a cycle of two nodes which is unreachable
+        let tail = alloc_node(&mut heap, &class, None);
+        let middle = alloc_node(&mut heap, &class, Some(tail));
+        let head = alloc_node(&mut heap, &class, Some(middle));
+        let first = alloc_node(&mut heap, &class, None);
+        let second = alloc_node(&mut heap, &class, Some(first));
+        heap.get_scalar_mut(first).put_field(next_field(), Value::ScalarReference(second));
+        assert_eq!(heap.live_count(), 5);
+
+        heap.collect(&[head]);
+        assert_eq!(heap.live_count(), 3);
+        assert_eq!(heap.get_scalar(tail).get_class().symref.sig, sig::Class::new("Node"));
+
+        // drop the reference from the head of the list to the rest of it
+        heap.get_scalar_mut(head).put_field(next_field(), Value::NullReference);
+        heap.collect(&[head]);
+        assert_eq!(heap.live_count(), 1);
+
+        heap.collect(&[]);
+        assert_eq!(heap.live_count(), 0);
+    }
+
+    #[test]
+    fn test_collect_arrays() {
+        let mut heap = Heap::new();
+        let class = new_class("Node");
+        let array_class = new_array_class(sig::Type::Reference(sig::Class::new("Node")));
+        let array = heap.alloc_array(array_class, 2);
+        let node = alloc_node(&mut heap, &class, None);
+        heap.get_array_mut(array).put(0, Value::ScalarReference(node));
+        alloc_node(&mut heap, &class, None);
+        heap.collect(&[array]);
+        assert_eq!(heap.live_count(), 2);
+        heap.get_scalar(node);
+    }
+
+    #[test]
+    fn test_reuse_slots() {
+        let mut heap = Heap::new();
+        let class = new_class("Node");
+        let garbage = alloc_node(&mut heap, &class, None);
+        let live = alloc_node(&mut heap, &class, None);
+        heap.collect(&[live]);
+        let reused = alloc_node(&mut heap, &class, None);
+        assert_eq!(reused.index, garbage.index);
+        assert!(reused != garbage);
+        assert_eq!(heap.objects.len(), 2);
+        heap.get_scalar(reused);
+    }
+
+    #[test]
+    #[should_panic(expected = "dangling heap reference")]
+    fn test_stale_reference_to_reused_slot() {
+        let mut heap = Heap::new();
+        let class = new_class("Node");
+        let garbage = alloc_node(&mut heap, &class, None);
+        heap.collect(&[]);
+        alloc_node(&mut heap, &class, None);
+        heap.get_scalar(garbage);
+    }
+
+    #[test]
+    fn test_collect_string_builders() {
+        let mut heap = Heap::new();
+        let class = new_class("java/lang/StringBuilder");
+        let garbage = heap.alloc_scalar(class.clone());
+        let live = heap.alloc_scalar(class);
+        assert!(heap.string_builder(live).is_empty());
+        heap.string_builder_mut(garbage).extend(vec![0x68, 0x69]);
+        heap.string_builder_mut(live).push(0x21);
+        heap.collect(&[live]);
+        assert_eq!(heap.string_builder(live), &[0x21]);
+        assert!(!heap.string_builders.contains_key(&garbage));
+    }
+
+    #[test]
+    #[should_panic(expected = "dangling heap reference")]
+    fn test_dangling_reference() {
+        let mut heap = Heap::new();
+        let class = new_class("Node");
+        let node = alloc_node(&mut heap, &class, None);
+        heap.collect(&[]);
+        heap.get_scalar(node);
+    }
+}
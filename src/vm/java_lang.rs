@@ -0,0 +1,120 @@
+//! Conversions between Rust values and instances of the classes of the `java.lang` package.
+
+use std::num::Wrapping;
+
+use vm::{sig, symref};
+use vm::class::Class;
+use vm::class_loader::{self, ClassLoader};
+use vm::constant_pool::new_string;
+use vm::context::Context;
+use vm::exceptions::ExceptionInfo;
+use vm::heap::{Heap, HeapRef};
+use vm::value::Value;
+
+/// Returns true if the object is an instance of `java/lang/String`.
+pub fn is_string(heap: &Heap, object: HeapRef) -> bool {
+    heap.get_scalar(object).get_class().symref.sig == sig::Class::new("java/lang/String")
+}
+
+/// Returns the `char[]` in the field `value` of an instance of `java/lang/String`, which holds
+/// the contents of the string as UTF-16.
+pub fn string_value(heap: &Heap, string: HeapRef) -> HeapRef {
+    let value_sig = sig::Field {
+        name: String::from("value"),
+        ty: sig::Type::new("[C"),
+    };
+    match heap.get_scalar(string).get_field(&value_sig) {
+        Value::ArrayReference(array) => array,
+        v => panic!("expected a char array, but was {:?}", v),
+    }
+}
+
+/// Read the UTF-16 contents of an instance of `java/lang/String` on the heap.
+pub fn string_to_utf16(heap: &Heap, string: HeapRef) -> Vec<u16> {
+    let array = heap.get_array(string_value(heap, string));
+    (0..array.len()).map(|i| {
+        match array.get(i) {
+            Value::Int(Wrapping(c)) => c as u16,
+            v => panic!("expected a char, but was {:?}", v),
+        }
+    }).collect()
+}
+
+/// Read the contents of an instance of `java/lang/String` on the heap. Unpaired surrogates are
+/// replaced with U+FFFD.
+pub fn string_to_rust(heap: &Heap, string: HeapRef) -> String {
+    String::from_utf16_lossy(&string_to_utf16(heap, string))
+}
+
+/// Returns the detail message of an instance of `java/lang/Throwable`, held in its field
+/// `detailMessage`, or `None` if it has none.
+pub fn throwable_message(heap: &Heap, throwable: HeapRef) -> Option<String> {
+    let message_sig = sig::Field {
+        name: String::from("detailMessage"),
+        ty: sig::Type::new("Ljava/lang/String;"),
+    };
+    match heap.get_scalar(throwable).get_field(&message_sig) {
+        Value::ScalarReference(message) => Some(string_to_rust(heap, message)),
+        Value::NullReference => None,
+        v => panic!("expected a String, but was {:?}", v),
+    }
+}
+
+/// Returns the instance of `java/lang/Class` which represents a class, creating it the first time
+/// it is requested. The `name` field of the mirror holds the binary name of the class with `/`
+/// replaced by `.`, as returned by `Class.getName()`.
+pub fn class_mirror(class_loader: &mut dyn ClassLoader, context: &mut Context, class: &Class)
+                    -> Result<HeapRef, ExceptionInfo> {
+    let class_sig = &class.symref.sig;
+    if let Some(mirror) = context.class_mirror(class_sig) {
+        return Ok(mirror);
+    }
+    let name = class_sig.name().replace('/', ".");
+    let name = try!(new_string(name.encode_utf16().collect(), class_loader, context));
+    let mirror_symref = symref::Class { sig: sig::Class::new("java/lang/Class") };
+    let mirror_class = try!(class_loader.resolve_class(&mirror_symref));
+    let mirror = context.heap.alloc_scalar(mirror_class);
+    let name_sig = sig::Field {
+        name: String::from("name"),
+        ty: sig::Type::new("Ljava/lang/String;"),
+    };
+    context.heap.get_scalar_mut(mirror).put_field(name_sig, name);
+    context.set_class_mirror(class_sig.clone(), mirror);
+    Ok(mirror)
+}
+
+/// The least and greatest values whose `java/lang/Integer` objects are cached by `valueOf`.
+const INTEGER_CACHE_RANGE: (i32, i32) = (-128, 127);
+
+fn integer_value_sig() -> sig::Field {
+    sig::Field { name: String::from("value"), ty: sig::Type::Int }
+}
+
+/// Returns an instance of `java/lang/Integer` holding a value, as returned by `Integer.valueOf`.
+/// Values from -128 to 127 are boxed once, and the same object is returned every time they are
+/// requested; other values are boxed in a new object each time.
+pub fn integer_value_of(class_loader: &mut dyn ClassLoader, context: &mut Context, value: i32)
+                        -> Result<HeapRef, class_loader::Error> {
+    if let Some(integer) = context.boxed_integer(value) {
+        return Ok(integer);
+    }
+    let integer_symref = symref::Class { sig: sig::Class::new("java/lang/Integer") };
+    let integer_class = try!(class_loader.resolve_class(&integer_symref));
+    integer_class.initialize(class_loader, context);
+    let integer = context.heap.alloc_scalar(integer_class);
+    let field_value = Value::Int(Wrapping(value));
+    context.heap.get_scalar_mut(integer).put_field(integer_value_sig(), field_value);
+    let (low, high) = INTEGER_CACHE_RANGE;
+    if value >= low && value <= high {
+        context.set_boxed_integer(value, integer);
+    }
+    Ok(integer)
+}
+
+/// Returns the value held by an instance of `java/lang/Integer`.
+pub fn integer_value(heap: &Heap, integer: HeapRef) -> i32 {
+    match heap.get_scalar(integer).get_field(&integer_value_sig()) {
+        Value::Int(Wrapping(value)) => value,
+        v => panic!("expected an int, but was {:?}", v),
+    }
+}
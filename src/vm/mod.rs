@@ -1,14 +1,39 @@
 //! The public interface for the Java virtual machine.
 
-mod bytecode;
+pub mod bytecode;
 mod class;
-mod class_loader;
+pub mod class_loader;
+pub mod class_path;
 mod constant_pool;
+pub mod context;
+pub mod debug;
+mod exceptions;
 mod frame;
-mod native;
+pub mod heap;
+pub mod java_lang;
+pub mod native;
+pub mod profiler;
+pub mod source_map;
+mod stack;
+mod stack_trace;
 mod value;
 
-use self::class_loader::ClassLoader;
+use std::{error, fmt};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use self::class_loader::{BootstrapClassLoader, ClassLoader};
+use self::class_path::ClassPath;
+use self::context::Context;
+use self::debug::DebugAgent;
+use self::frame::Exception;
+use self::native::NativeRegistry;
+use self::profiler::Profiler;
+
+pub use self::exceptions::ExceptionInfo;
+pub use self::frame::{FrameDebugInfo, LocalVariableDebugInfo};
+pub use self::stack_trace::{StackTrace, StackTraceElement};
+pub use self::value::Value;
 
 /// A symbolic reference to an entity in the runtime constant pool (§5.1). Symbolic references
 /// must be resolved (§5.4.3) before their usage by the interpreter.
@@ -63,56 +88,26 @@ pub mod sig {
 
     impl Type {
         pub fn new(type_str: &str) -> Self {
-            let (ty, rest) = Self::new_partial(type_str).unwrap();
-            if rest.len() > 0 {
-                panic!("extra content at end of type descriptor")
-            } else {
-                ty
-            }
+            parse_field_descriptor(type_str).unwrap_or_else(|error| {
+                panic!("invalid type descriptor {}: {:?}", type_str, error)
+            })
         }
 
-        /// Compute a sequence of types from a JVM-internal string representation.
-        pub fn new_multi(multi_type_str: &str) -> (Vec<Self>, &str) {
-            let mut types = vec![];
-            let mut remainder = multi_type_str;
-            {
-                let mut result = Ok(&mut types);
-                while let Ok(types) = result {
-                    result = Self::new_partial(remainder).map(|(ty, new_remainder)| {
-                        types.push(ty);
-                        remainder = new_remainder;
-                        types
-                    });
-                }
-            }
-            (types, remainder)
-        }
-
-        /// Compute a type from a JVM-internal string representation.
-        fn new_partial(type_str: &str) -> Result<(Self, &str), ()> {
-            let (specifier, rest) = type_str.split_at(1);
-            match specifier {
-                "B" => Ok((Type::Byte, rest)),
-                "C" => Ok((Type::Char, rest)),
-                "D" => Ok((Type::Double, rest)),
-                "F" => Ok((Type::Float, rest)),
-                "I" => Ok((Type::Int, rest)),
-                "J" => Ok((Type::Long, rest)),
-                "S" => Ok((Type::Short, rest)),
-                "Z" => Ok((Type::Boolean, rest)),
-                "L" => {
-                    let end_index = rest.find(';').unwrap();
-                    let (name_slice, rest) = rest.split_at(end_index);
-                    let name = String::from(name_slice);
-                    let scalar_type = Type::Reference(Class::Scalar(name));
-                    Ok((scalar_type, rest.split_at(1).1))
-                },
-                "[" => {
-                    let (component_type, rest) = try!(Self::new_partial(rest));
-                    let array_type = Type::Reference(Class::Array(Box::new(component_type)));
-                    Ok((array_type, rest))
-                },
-                _ => Err(())
+        /// Compute the JVM-internal string representation of the type. This is the inverse of
+        /// `Type::new`.
+        pub fn descriptor(&self) -> String {
+            match *self {
+                Type::Byte => String::from("B"),
+                Type::Char => String::from("C"),
+                Type::Double => String::from("D"),
+                Type::Float => String::from("F"),
+                Type::Int => String::from("I"),
+                Type::Long => String::from("J"),
+                Type::Short => String::from("S"),
+                Type::Boolean => String::from("Z"),
+                Type::Reference(Class::Scalar(ref name)) => format!("L{};", name),
+                Type::Reference(Class::Array(ref component_ty)) =>
+                    format!("[{}", component_ty.descriptor()),
             }
         }
 
@@ -128,6 +123,93 @@ pub mod sig {
                 Type::Reference(_) => Value::NullReference,
             }
         }
+
+        /// The number of words occupied by a value of the type in local variables and on the
+        /// operand stack: two for `long` and `double`, and one for all other types.
+        pub fn size_in_words(&self) -> usize {
+            self.default_value().size_in_words()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    /// The reasons for which a field or method descriptor (§4.3) may be invalid.
+    pub enum DescriptorError {
+        /// The descriptor ended where a type was expected.
+        UnexpectedEnd,
+        /// A type began with a character which is not a valid base type or the start of a
+        /// reference type.
+        InvalidType(char),
+        /// The name of a class was not terminated by a `;`.
+        UnterminatedClassName,
+        /// The name of a class was empty.
+        EmptyClassName,
+        /// A method descriptor did not begin with `(`.
+        MissingParameters,
+        /// The descriptor continued after a complete type.
+        TrailingContent(String),
+    }
+
+    /// Parse a field descriptor (§4.3.2), such as `I` or `[Ljava/lang/String;`, into a type.
+    pub fn parse_field_descriptor(descriptor: &str) -> Result<Type, DescriptorError> {
+        let (ty, rest) = try!(parse_type(descriptor));
+        if rest.is_empty() {
+            Ok(ty)
+        } else {
+            Err(DescriptorError::TrailingContent(String::from(rest)))
+        }
+    }
+
+    /// Parse a method descriptor (§4.3.3), such as `(ILjava/lang/String;[D)V`, into the types of
+    /// the parameters of the method and its return type, which is `None` if the method is `void`.
+    pub fn parse_descriptor(descriptor: &str)
+                            -> Result<(Vec<Type>, Option<Type>), DescriptorError> {
+        if !descriptor.starts_with('(') {
+            return Err(DescriptorError::MissingParameters);
+        }
+        let mut params = vec![];
+        let mut rest = &descriptor[1..];
+        while !rest.starts_with(')') {
+            let (ty, new_rest) = try!(parse_type(rest));
+            params.push(ty);
+            rest = new_rest;
+        }
+        let return_ty = match &rest[1..] {
+            "V" => None,
+            return_ty_str => Some(try!(parse_field_descriptor(return_ty_str))),
+        };
+        Ok((params, return_ty))
+    }
+
+    /// Parse the type at the start of a descriptor, returning it along with the remainder of the
+    /// descriptor.
+    fn parse_type(descriptor: &str) -> Result<(Type, &str), DescriptorError> {
+        let mut chars = descriptor.chars();
+        let specifier = try!(chars.next().ok_or(DescriptorError::UnexpectedEnd));
+        let rest = chars.as_str();
+        let ty = match specifier {
+            'B' => Type::Byte,
+            'C' => Type::Char,
+            'D' => Type::Double,
+            'F' => Type::Float,
+            'I' => Type::Int,
+            'J' => Type::Long,
+            'S' => Type::Short,
+            'Z' => Type::Boolean,
+            'L' => {
+                let end_index = try!(rest.find(';').ok_or(DescriptorError::UnterminatedClassName));
+                if end_index == 0 {
+                    return Err(DescriptorError::EmptyClassName);
+                }
+                let name = String::from(&rest[..end_index]);
+                return Ok((Type::Reference(Class::Scalar(name)), &rest[end_index + 1..]));
+            },
+            '[' => {
+                let (component_ty, rest) = try!(parse_type(rest));
+                return Ok((Type::Reference(Class::Array(Box::new(component_ty))), rest));
+            },
+            c => return Err(DescriptorError::InvalidType(c)),
+        };
+        Ok((ty, rest))
     }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -150,6 +232,15 @@ pub mod sig {
             }
         }
 
+        /// The binary name of the class, in which the names of array classes are the descriptors
+        /// of their types. This is the inverse of `Class::new`.
+        pub fn name(&self) -> String {
+            match *self {
+                Class::Scalar(ref name) => name.clone(),
+                Class::Array(ref component_ty) => format!("[{}", component_ty.descriptor()),
+            }
+        }
+
         pub fn get_package(&self) -> Option<String> {
             match *self {
                 Class::Scalar(ref name) => {
@@ -185,67 +276,548 @@ pub mod sig {
 
     impl Method {
         pub fn new(name: &str, descriptor: &str) -> Self {
-            if !descriptor.starts_with('(') {
-                panic!("invalid method descriptor");
-            }
-            let params_str = descriptor.split_at(1).1;
-            let (params, remainder) = Type::new_multi(params_str);
-            let (close_paren, return_ty_str) = remainder.split_at(1);
-            if close_paren != ")" {
-                panic!("invalid method descriptor");
-            }
-            let return_ty =
-                if return_ty_str == "V" {
-                    None
-                } else {
-                    Some(Type::new(return_ty_str))
-                };
+            let (params, return_ty) = parse_descriptor(descriptor).unwrap_or_else(|error| {
+                panic!("invalid method descriptor {}: {:?}", descriptor, error)
+            });
             Method {
                 name: String::from(name),
                 params: params,
                 return_ty: return_ty
             }
         }
+
+        /// Compute the JVM-internal string representation of the method's parameter and return
+        /// types. This is the inverse of the descriptor given to `Method::new`.
+        pub fn descriptor(&self) -> String {
+            let params = self.params.iter().map(Type::descriptor).collect::<Vec<_>>().concat();
+            let return_ty = self.return_ty.as_ref().map_or(String::from("V"), Type::descriptor);
+            format!("({}){}", params, return_ty)
+        }
+
+        /// The number of words occupied by the arguments of the method, not counting the object
+        /// on which an instance method is invoked.
+        pub fn param_words(&self) -> usize {
+            self.params.iter().map(Type::size_in_words).sum()
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        use std::collections::HashMap;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn round_trip_field(descriptor: &str) {
+            assert_eq!(parse_field_descriptor(descriptor).unwrap().descriptor(), descriptor);
+        }
+
+        fn round_trip_method(descriptor: &str) {
+            assert_eq!(Method::new("m", descriptor).descriptor(), descriptor);
+        }
+
+        #[test]
+        fn test_parse_field_descriptor() {
+            assert_eq!(parse_field_descriptor("I"), Ok(Type::Int));
+            let string_ty = Type::Reference(Class::Scalar(String::from("java/lang/String")));
+            assert_eq!(parse_field_descriptor("Ljava/lang/String;"), Ok(string_ty.clone()));
+            let array_ty = Type::Reference(Class::Array(Box::new(string_ty)));
+            let nested_array_ty = Type::Reference(Class::Array(Box::new(array_ty)));
+            assert_eq!(parse_field_descriptor("[[Ljava/lang/String;"), Ok(nested_array_ty));
+        }
+
+        #[test]
+        fn test_parse_descriptor() {
+            let (params, return_ty) = parse_descriptor("(ILjava/lang/String;[D)V").unwrap();
+            assert_eq!(params, vec![
+                Type::Int,
+                Type::Reference(Class::Scalar(String::from("java/lang/String"))),
+                Type::Reference(Class::Array(Box::new(Type::Double))),
+            ]);
+            assert_eq!(return_ty, None);
+            assert_eq!(parse_descriptor("()J"), Ok((vec![], Some(Type::Long))));
+        }
+
+        #[test]
+        fn test_parse_invalid_descriptors() {
+            assert_eq!(parse_field_descriptor(""), Err(DescriptorError::UnexpectedEnd));
+            assert_eq!(parse_field_descriptor("["), Err(DescriptorError::UnexpectedEnd));
+            assert_eq!(parse_field_descriptor("V"), Err(DescriptorError::InvalidType('V')));
+            assert_eq!(parse_field_descriptor("Ljava/lang/String"),
+                       Err(DescriptorError::UnterminatedClassName));
+            assert_eq!(parse_field_descriptor("L;"), Err(DescriptorError::EmptyClassName));
+            assert_eq!(parse_field_descriptor("II"),
+                       Err(DescriptorError::TrailingContent(String::from("I"))));
+            assert_eq!(parse_descriptor("I)V"), Err(DescriptorError::MissingParameters));
+            assert_eq!(parse_descriptor("(I"), Err(DescriptorError::UnexpectedEnd));
+            assert_eq!(parse_descriptor("(I)"), Err(DescriptorError::UnexpectedEnd));
+            assert_eq!(parse_descriptor("(V)V"), Err(DescriptorError::InvalidType('V')));
+        }
+
+        #[test]
+        fn test_round_trip() {
+            for descriptor in &["B", "C", "D", "F", "I", "J", "S", "Z", "Ljava/lang/Object;",
+                                "[I", "[[[J", "[Ljava/util/Map$Entry;", "Lcom/example/Outer$1;"] {
+                round_trip_field(descriptor);
+            }
+            for descriptor in &["()V", "(I)I", "(ILjava/lang/String;[D)V",
+                                "([[Z[Ljava/lang/Object;)J",
+                                "(Ljava/util/Map$Entry;)Ljava/util/Map$Entry;"] {
+                round_trip_method(descriptor);
+            }
+        }
+
+        #[test]
+        fn test_param_words() {
+            assert_eq!(Method::new("m", "()V").param_words(), 0);
+            assert_eq!(Method::new("m", "(I[JLjava/lang/Object;)V").param_words(), 3);
+            assert_eq!(Method::new("m", "(JID)J").param_words(), 5);
+        }
+
+        fn hash<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        #[test]
+        fn test_method_keys() {
+            let mut methods = HashMap::new();
+            methods.insert(Method::new("valueOf", "(I)Ljava/lang/String;"), 1);
+            methods.insert(Method::new("valueOf", "(J)Ljava/lang/String;"), 2);
+            methods.insert(Method::new("valueOf", "(I)Ljava/lang/Object;"), 3);
+            methods.insert(Method::new("valueOf", "(I)Ljava/lang/String;"), 4);
+            assert_eq!(methods.len(), 3);
+            assert_eq!(methods[&Method::new("valueOf", "(I)Ljava/lang/String;")], 4);
+            assert_eq!(methods[&Method::new("valueOf", "(J)Ljava/lang/String;")], 2);
+            assert_eq!(methods[&Method::new("valueOf", "(I)Ljava/lang/Object;")], 3);
+            assert!(!methods.contains_key(&Method::new("toString", "(I)Ljava/lang/String;")));
+        }
+
+        #[test]
+        fn test_hash_consistent_with_eq() {
+            let method = Method::new("m", "([ILjava/lang/String;)J");
+            assert_eq!(method, method.clone());
+            assert_eq!(hash(&method), hash(&Method::new("m", "([ILjava/lang/String;)J")));
+            let field = Field { name: String::from("f"), ty: Type::new("[[D") };
+            assert_eq!(hash(&field), hash(&field.clone()));
+            assert_eq!(hash(&Class::new("[Ljava/lang/Object;")),
+                       hash(&Class::Array(Box::new(Type::new("Ljava/lang/Object;")))));
+            assert_eq!(hash(&Type::new("Z")), hash(&Type::Boolean));
+        }
     }
 }
 
 #[derive(Debug)]
-/// The top-level virtual machine. The virtual machine contains a reference to its _bootstrap class
-/// loader_, which is used to load the main class and all of that class's dependencies.
-///
-/// The virtual machine's `start` method causes initialization of the boostrap class loader, which
-/// loads the main class and invokes the `main(String[])` method to begin execution of the program.
-/// The virtual machine terminates either when the program completes successfully, or when there is
-/// an uncaught error in the program or the internal implementation of the virtual machine itself.
-pub struct VirtualMachine {
-    /// The bootstrap class loader used to initialize the virtual machine.
-    bootstrap_class_loader: ClassLoader,
+/// An error which prevents the interpreter from running a method to completion.
+pub enum Error {
+    /// The class containing the method could not be loaded.
+    ClassLoader(class_loader::Error),
+    /// The method descriptor is not valid.
+    InvalidDescriptor(sig::DescriptorError),
+    /// The class does not contain a static method with the given name and descriptor.
+    NoSuchMethod { class: String, name: String, descriptor: String },
+    /// The method completed abruptly by throwing an exception of the given class, with the given
+    /// detail message, which propagated out of the frames of `stack_trace`.
+    UncaughtException { class: String, message: Option<String>, stack_trace: StackTrace },
+    /// No method of the class with the given name has code compiled from the given line.
+    NoSuchLine { class: String, method: String, line: u16 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ClassLoader(ref error) => write!(f, "{}", error),
+            Error::InvalidDescriptor(ref error) => write!(f, "invalid descriptor: {:?}", error),
+            Error::NoSuchMethod { ref class, ref name, ref descriptor } =>
+                write!(f, "NoSuchMethod: {}.{}{}", class, name, descriptor),
+            Error::UncaughtException { ref class, message: None, .. } =>
+                write!(f, "{}", class.replace('/', ".")),
+            Error::UncaughtException { ref class, message: Some(ref message), .. } =>
+                write!(f, "{}: {}", class.replace('/', "."), message),
+            Error::NoSuchLine { ref class, ref method, line } =>
+                write!(f, "no code for line {} in {}.{}", line, class, method),
+        }
+    }
 }
 
-impl VirtualMachine {
-    pub fn new() -> Self {
-        VirtualMachine {
-            bootstrap_class_loader: ClassLoader::new(),
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::ClassLoader(_) => "the class could not be loaded",
+            Error::InvalidDescriptor(_) => "invalid method descriptor",
+            Error::NoSuchMethod { .. } => "no such static method",
+            Error::UncaughtException { .. } => "uncaught exception",
+            Error::NoSuchLine { .. } => "no code for line",
         }
     }
 
-    /// Begin execution of the virtual machine instance's `main(String[])` method.
-    pub fn start(mut self, main_class: symref::Class) {
-        let class = self.bootstrap_class_loader.load_class(&main_class.sig).unwrap();
-        class.initialize(&mut self.bootstrap_class_loader);
-        let string_ty = sig::Type::Reference(sig::Class::Scalar(String::from("java/lang/String")));
-        let string_array_ty = sig::Type::Reference(sig::Class::Array(Box::new(string_ty)));
-        let main_sig = sig::Method {
-            name: String::from("main"),
-            params: vec![string_array_ty],
-            return_ty: None,
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::ClassLoader(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<class_loader::Error> for Error {
+    fn from(error: class_loader::Error) -> Self {
+        Error::ClassLoader(error)
+    }
+}
+
+/// The top-level virtual machine, and the entry point for embedding it. The interpreter owns the
+/// class loader which is used to load the main class and all of that class's dependencies, and
+/// the heap on which every object created by the program is allocated.
+pub struct Interpreter {
+    /// The class loader used to load the classes invoked through the interpreter.
+    class_loader: Box<dyn ClassLoader>,
+    /// The heap on which every object created by the program is allocated, and the state of the
+    /// program beyond its objects.
+    context: Context,
+}
+
+impl Interpreter {
+    /// Create an interpreter whose bootstrap class loader searches the given class path.
+    pub fn new(class_path: ClassPath) -> Self {
+        Self::with_natives(class_path, NativeRegistry::new())
+    }
+
+    /// Create an interpreter whose bootstrap class loader searches the given class path, and
+    /// binds native methods to their implementations in `natives`.
+    pub fn with_natives(class_path: ClassPath, natives: NativeRegistry) -> Self {
+        Interpreter {
+            class_loader: Box::new(BootstrapClassLoader::with_natives(class_path, natives)),
+            context: Context::new(),
+        }
+    }
+
+    /// Set the greatest number of method invocations which may be in progress at once. Invoking a
+    /// method beyond this depth throws a `StackOverflowError` instead.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.context.set_max_call_depth(depth);
+        self
+    }
+
+    /// Record every method invocation in `profiler`, which the caller may share in order to read
+    /// the records once the program has run.
+    pub fn with_profiler(mut self, profiler: Rc<RefCell<Profiler>>) -> Self {
+        self.context.set_profiler(profiler);
+        self
+    }
+
+    /// Attach a debug agent, which is notified of breakpoints and exceptions, and of every
+    /// instruction executed while single-stepping.
+    pub fn set_debug_agent(&mut self, agent: Box<dyn DebugAgent>) {
+        self.context.debugger_mut().set_agent(agent);
+    }
+
+    /// Enable or disable single-stepping, in which the debug agent is notified of every
+    /// instruction before it is executed.
+    pub fn set_step_mode(&mut self, stepping: bool) {
+        self.context.debugger_mut().set_stepping(stepping);
+    }
+
+    /// Set a breakpoint at the first instruction compiled from a line of the source file, in each
+    /// method with the given name of the class with the given binary name. The line is found in
+    /// the `LineNumberTable` attributes of the methods, so the class must have been compiled with
+    /// line numbers.
+    pub fn add_breakpoint(&mut self, class: &str, method: &str, line: u16) -> Result<(), Error> {
+        let resolved_class = try!(self.class_loader.load_class(&sig::Class::new(class)));
+        let mut found = false;
+        for resolved_method in resolved_class.methods_named(method) {
+            let pc = resolved_method.debug_info()
+                .and_then(|debug_info| debug_info.source_map.as_ref())
+                .and_then(|source_map| source_map.first_pc_of_line(line));
+            if let Some(pc) = pc {
+                let debugger = self.context.debugger_mut();
+                debugger.add_breakpoint(resolved_class.symref.sig.clone(),
+                                        resolved_method.symref.sig.clone(), pc as u16);
+                found = true;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(Error::NoSuchLine {
+                class: String::from(class),
+                method: String::from(method),
+                line: line,
+            })
+        }
+    }
+
+    /// Run the `main(String[])` method of the class with the given name, which may be either a
+    /// binary name (`com/example/Main`) or a fully qualified one (`com.example.Main`), passing it
+    /// an array of the given arguments. The interpreter terminates either when the program
+    /// completes successfully, or when there is an uncaught exception in the program.
+    pub fn run_main(mut self, class_name: &str, args: Vec<String>) -> Result<(), Error> {
+        let string_sig = sig::Class::new("java/lang/String");
+        let string_array_sig = sig::Class::Array(Box::new(sig::Type::Reference(string_sig)));
+        let string_array_class = try!(self.class_loader.load_class(&string_array_sig));
+        let array = self.context.heap.alloc_array(string_array_class, args.len() as i32);
+        for (i, arg) in args.iter().enumerate() {
+            let chars = arg.encode_utf16().collect();
+            let string = match constant_pool::new_string(chars, &mut *self.class_loader,
+                                                         &mut self.context) {
+                Ok(string) => string,
+                Err(info) => {
+                    let exception = info.instantiate(&mut *self.class_loader, &mut self.context);
+                    return Err(self.uncaught_exception(exception));
+                },
+            };
+            self.context.heap.get_array_mut(array).put(i as i32, string);
+        }
+        let class_name = class_name.replace('.', "/");
+        let result = try!(self.call_static(&class_name, "main", "([Ljava/lang/String;)V",
+                                           vec![Value::ArrayReference(array)]));
+        match result {
+            None => Ok(()),
+            Some(value) => panic!("main returned a value: {:?}", value),
+        }
+    }
+
+    /// Invoke the static method with the given name and descriptor of the class with the given
+    /// binary name, initializing the class first if necessary. Returns the value returned by the
+    /// method, if it is not `void`.
+    pub fn call_static(&mut self, class: &str, method: &str, descriptor: &str, args: Vec<Value>)
+                       -> Result<Option<Value>, Error> {
+        let (params, return_ty) = try!(sig::parse_descriptor(descriptor)
+                                       .map_err(Error::InvalidDescriptor));
+        let method_sig = sig::Method {
+            name: String::from(method),
+            params: params,
+            return_ty: return_ty,
         };
-        let main_symref = symref::Method {
-            class: main_class,
-            sig: main_sig,
+        let resolved_class = try!(self.class_loader.load_class(&sig::Class::new(class)));
+        resolved_class.initialize(&mut *self.class_loader, &mut self.context);
+        let resolved_method = match resolved_class.resolve_static_method(&method_sig) {
+            Some(resolved_method) => resolved_method,
+            None => return Err(Error::NoSuchMethod {
+                class: String::from(class),
+                name: String::from(method),
+                descriptor: String::from(descriptor),
+            }),
         };
-        let method = class.resolve_method(&main_symref);
-        method.invoke(&class, &mut self.bootstrap_class_loader, vec![]);
+        resolved_method.invoke(&resolved_class, &mut *self.class_loader, &mut self.context, args)
+            .map_err(|exception| self.uncaught_exception(exception))
+    }
+
+    /// Describe an exception which propagated out of the program as an `Error`.
+    fn uncaught_exception(&self, exception: Exception) -> Error {
+        let exception_class = self.context.heap.get_scalar(exception).get_class();
+        let class = match exception_class.symref.sig {
+            sig::Class::Scalar(ref name) => name.clone(),
+            ref array_sig => panic!("threw an array {:?}", array_sig),
+        };
+        let message = java_lang::throwable_message(&self.context.heap, exception);
+        let stack_trace = self.context.stack_trace(exception).cloned().unwrap_or_default();
+        Error::UncaughtException { class: class, message: message, stack_trace: stack_trace }
+    }
+
+    /// Format a stack trace as Java prints it below the exception: one `\tat` line per frame,
+    /// innermost first.
+    pub fn format_stack_trace(trace: &StackTrace) -> String {
+        trace.elements.iter().map(|element| format!("\tat {}\n", element)).collect()
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::num::Wrapping;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::thread;
+
+    use vm::class_path::ClassPathEntry;
+    use vm::context::STACK_SIZE;
+    use vm::native::NativeResult;
+
+    fn test_interpreter() -> Interpreter {
+        Interpreter::new(ClassPath::new(vec![ClassPathEntry::Directory(PathBuf::from("data/vm"))]))
+    }
+
+    #[test]
+    fn test_call_static() {
+        let mut interpreter = test_interpreter();
+        let args = vec![Value::Int(Wrapping(3)), Value::Int(Wrapping(4))];
+        match interpreter.call_static("Invoke", "add", "(II)I", args) {
+            Ok(Some(Value::Int(Wrapping(7)))) => (),
+            result => panic!("expected 7, but was {:?}", result),
+        }
+        match interpreter.call_static("Invoke", "callStatic", "()I", vec![]) {
+            Ok(Some(Value::Int(Wrapping(14)))) => (),
+            result => panic!("expected 14, but was {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_call_static_errors() {
+        let mut interpreter = test_interpreter();
+        match interpreter.call_static("Invoke", "missing", "()V", vec![]) {
+            Err(Error::NoSuchMethod { ref name, .. }) if name == "missing" => (),
+            result => panic!("expected NoSuchMethod, but was {:?}", result),
+        }
+        match interpreter.call_static("Invoke", "add", "(II", vec![]) {
+            Err(Error::InvalidDescriptor(_)) => (),
+            result => panic!("expected InvalidDescriptor, but was {:?}", result),
+        }
+        match interpreter.call_static("Missing", "main", "()V", vec![]) {
+            Err(Error::ClassLoader(class_loader::Error::ClassNotFound { .. })) => (),
+            result => panic!("expected ClassNotFound, but was {:?}", result),
+        }
+    }
+
+    fn answer(_: &mut dyn ClassLoader, _: &mut Context, _: Vec<Value>) -> NativeResult {
+        Ok(Some(Value::Int(Wrapping(42))))
+    }
+
+    fn plus(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>) -> NativeResult {
+        match (&args[0], &args[1]) {
+            (&Value::ScalarReference(object), &Value::Int(n)) => {
+                let class = context.heap.get_scalar(object).get_class();
+                assert_eq!(class.symref.sig, sig::Class::new("Natives"));
+                Ok(Some(Value::Int(n + Wrapping(1))))
+            },
+            _ => panic!("unexpected arguments {:?}", args),
+        }
+    }
+
+    #[test]
+    fn test_register_natives() {
+        let mut natives = NativeRegistry::new();
+        natives.register("Natives", "answer", "()I", answer);
+        natives.register("Natives", "plus", "(I)I", plus);
+        let class_path = ClassPath::new(vec![ClassPathEntry::Directory(PathBuf::from("data/vm"))]);
+        let mut interpreter = Interpreter::with_natives(class_path, natives);
+        match interpreter.call_static("Natives", "answer", "()I", vec![]) {
+            Ok(Some(Value::Int(Wrapping(42)))) => (),
+            result => panic!("expected 42, but was {:?}", result),
+        }
+        match interpreter.call_static("Natives", "callNatives", "()I", vec![]) {
+            Ok(Some(Value::Int(Wrapping(43)))) => (),
+            result => panic!("expected 43, but was {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let mut interpreter = test_interpreter();
+        match interpreter.call_static("Recursion", "fib", "(I)I", vec![Value::Int(Wrapping(15))]) {
+            Ok(Some(Value::Int(Wrapping(610)))) => (),
+            result => panic!("expected 610, but was {:?}", result),
+        }
+
+        // test threads have small native stacks, which the default depth would overflow
+        let overflow = thread::Builder::new().stack_size(STACK_SIZE).spawn(|| {
+            let mut interpreter = test_interpreter();
+            match interpreter.call_static("Recursion", "overflow", "()I", vec![]) {
+                Ok(Some(Value::Int(Wrapping(-1)))) => (),
+                result => panic!("expected -1, but was {:?}", result),
+            }
+            let args = vec![Value::Int(Wrapping(20))];
+            match interpreter.call_static("Recursion", "fib", "(I)I", args) {
+                Ok(Some(Value::Int(Wrapping(6765)))) => (),
+                result => panic!("expected 6765, but was {:?}", result),
+            }
+        }).unwrap();
+        overflow.join().unwrap();
+
+        let mut interpreter = test_interpreter().with_max_depth(10);
+        let args = vec![Value::Int(Wrapping(15))];
+        let error = interpreter.call_static("Recursion", "fib", "(I)I", args).unwrap_err();
+        assert_eq!(error.to_string(), "java.lang.StackOverflowError");
+    }
+
+    #[test]
+    fn test_profiler() {
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        let mut interpreter = test_interpreter().with_profiler(profiler.clone());
+        match interpreter.call_static("Recursion", "fib", "(I)I", vec![Value::Int(Wrapping(10))]) {
+            Ok(Some(Value::Int(Wrapping(55)))) => (),
+            result => panic!("expected 55, but was {:?}", result),
+        }
+        // the number of calls made to evaluate fib(n), counting the first
+        fn calls(n: u64) -> u64 {
+            if n < 2 { 1 } else { 1 + calls(n - 1) + calls(n - 2) }
+        }
+        let profiler = profiler.borrow();
+        assert_eq!(profiler.get("Recursion", "fib", "(I)I").unwrap().call_count, calls(10));
+        let report = profiler.report();
+        assert_eq!((&*report[0].class, &*report[0].method), ("Recursion", "fib"));
+        assert_eq!(report[0].call_count, 177);
+    }
+
+    #[test]
+    fn test_call_static_uncaught_exception() {
+        let mut interpreter = test_interpreter();
+        let error = interpreter.call_static("Exceptions", "throwNull", "()I", vec![]).unwrap_err();
+        assert_eq!(error.to_string(), "java.lang.NullPointerException");
+
+        let error = interpreter.call_static("StackTraces", "twoLevels", "()I", vec![]).unwrap_err();
+        assert_eq!(error.to_string(), "java.lang.ArithmeticException: / by zero");
+    }
+
+    #[test]
+    fn test_stack_trace() {
+        let mut interpreter = test_interpreter();
+        match interpreter.call_static("StackTraces", "twoLevels", "()I", vec![]) {
+            Err(Error::UncaughtException { ref class, ref stack_trace, .. })
+                    if class == "java/lang/ArithmeticException" => {
+                assert_eq!(stack_trace.elements[0], StackTraceElement {
+                    class_name: String::from("StackTraces"),
+                    method_name: String::from("divide"),
+                    source_file: Some(String::from("StackTraces.java")),
+                    line_number: Some(3),
+                });
+                assert_eq!(Interpreter::format_stack_trace(stack_trace),
+                           "\tat StackTraces.divide(StackTraces.java:3)\n\
+                            \tat StackTraces.twoLevels(StackTraces.java:7)\n");
+            },
+            result => panic!("expected an ArithmeticException, but was {:?}", result),
+        }
+
+        // an exception which is caught stops propagating
+        match interpreter.call_static("StackTraces", "caught", "()I", vec![]) {
+            Ok(Some(Value::Int(Wrapping(-1)))) => (),
+            result => panic!("expected -1, but was {:?}", result),
+        }
+
+        let mut trace = StackTrace::default();
+        trace.elements.push(StackTraceElement {
+            class_name: String::from("pkg/Generated"),
+            method_name: String::from("run"),
+            source_file: None,
+            line_number: None,
+        });
+        assert_eq!(Interpreter::format_stack_trace(&trace),
+                   "\tat pkg.Generated.run(Unknown Source)\n");
+    }
+
+    /// A debug agent which records the exceptions thrown.
+    struct ExceptionAgent(Rc<RefCell<Vec<(String, u32)>>>);
+
+    impl DebugAgent for ExceptionAgent {
+        fn on_step(&mut self, _: &str, _: &str, _: u32, _: Option<u16>) {}
+
+        fn on_breakpoint(&mut self, _: &str, _: &str, _: u32) {}
+
+        fn on_exception(&mut self, exception_class: &str, pc: u32) {
+            self.0.borrow_mut().push((String::from(exception_class), pc));
+        }
+    }
+
+    #[test]
+    fn test_debug_agent_exception() {
+        let mut interpreter = test_interpreter();
+        let exceptions = Rc::new(RefCell::new(vec![]));
+        interpreter.set_debug_agent(Box::new(ExceptionAgent(exceptions.clone())));
+        interpreter.call_static("StackTraces", "caught", "()I", vec![]).unwrap();
+        // reported once, at the idiv in divide, and not again as it propagates
+        assert_eq!(*exceptions.borrow(), vec![(String::from("java/lang/ArithmeticException"), 2)]);
+    }
+}
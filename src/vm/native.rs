@@ -1,12 +1,32 @@
+//! Native methods, which are implemented by the virtual machine rather than in Java bytecode.
+//!
+//! When a class is loaded, each of its `native` methods is bound to a Rust function found in the
+//! `NativeRegistry` of the class loader. A registry is created with the native methods needed by
+//! the runtime library, and more may be registered before any class using them is loaded.
+
+use std::cmp;
+use std::collections::HashMap;
 use std::fmt;
 use std::io;
 use std::io::Write;
 use std::num::Wrapping;
 
-use vm::{sig, symref};
+use vm::{constant_pool, exceptions, java_lang, sig, symref};
+use vm::class_loader::ClassLoader;
+use vm::context::Context;
+use vm::heap::{Heap, HeapRef};
 use vm::value::Value;
 
-pub struct NativeMethod(&'static Fn(Vec<Value>) -> Option<Value>);
+/// The result of a native method: the value it returns, if it is not `void`, or the exception it
+/// throws.
+pub type NativeResult = Result<Option<Value>, exceptions::ExceptionInfo>;
+
+/// The Rust implementation of a native method. It is passed the arguments of the method, starting
+/// with the object on which it is invoked if the method is not `static`.
+pub type NativeFn = fn(&mut dyn ClassLoader, &mut Context, Vec<Value>) -> NativeResult;
+
+/// A native method bound to its implementation.
+pub struct NativeMethod(NativeFn);
 
 impl fmt::Debug for NativeMethod {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -15,46 +35,145 @@ impl fmt::Debug for NativeMethod {
 }
 
 impl NativeMethod {
-    pub fn invoke(&self, args: Vec<Value>) -> Option<Value> {
-        self.0(args)
-    }
-}
-
-const ARRAYCOPY: &'static Fn(Vec<Value>) -> Option<Value> = &(|args| {
-    if let Value::ArrayReference(ref src_rc) = args[0] {
-        if let Value::Int(Wrapping(src_offset)) = args[1] {
-            if let Value::ArrayReference(ref dest_rc) = args[2] {
-                if let Value::Int(Wrapping(dest_offset)) = args[3] {
-                    if let Value::Int(Wrapping(len)) = args[4] {
-                        let src = src_rc.borrow();
-                        let mut dest = dest_rc.borrow_mut();
-                        for i in 0..len {
-                            let value = src.get(src_offset + i);
-                            dest.put(dest_offset + i, value);
-                        }
-                    } else {
-                        panic!("length must be an int");
-                    }
-                } else {
-                    panic!("dest_offset must be an int");
+    pub fn invoke(&self, class_loader: &mut dyn ClassLoader, context: &mut Context,
+                  args: Vec<Value>) -> NativeResult {
+        exceptions::catch_thrown(|| self.0(class_loader, context, args))
+    }
+}
+
+/// A native method which does nothing, such as the `registerNatives` methods which the standard
+/// library uses to bind its native methods.
+fn no_op(_: &mut dyn ClassLoader, _: &mut Context, _: Vec<Value>) -> NativeResult {
+    Ok(None)
+}
+
+/// `Class.desiredAssertionStatus0(Class)`; assertions are always disabled.
+fn desired_assertion_status(_: &mut dyn ClassLoader, _: &mut Context, _: Vec<Value>)
+                            -> NativeResult {
+    Ok(Some(Value::Int(Wrapping(0))))
+}
+
+/// Returns the array passed to `System.arraycopy` as its source or destination, which is
+/// described by `role`.
+fn arraycopy_array(heap: &Heap, value: &Value, role: &str)
+                   -> Result<HeapRef, exceptions::ExceptionInfo> {
+    match *value {
+        Value::ArrayReference(array) => Ok(array),
+        Value::NullReference => Err(exceptions::create_npe()),
+        Value::ScalarReference(object) => {
+            let class_name = heap.get_scalar(object).get_class().symref.sig.name();
+            Err(exceptions::create_array_store(format!("arraycopy: {} type {} is not an array",
+                                                       role, class_name.replace('/', "."))))
+        },
+        ref v => panic!("expected an object, but was {:?}", v),
+    }
+}
+
+/// Returns the component type of the array class of a `System.arraycopy` argument.
+fn arraycopy_component_type(heap: &Heap, array: HeapRef) -> sig::Type {
+    match heap.get_array(array).get_class().symref.sig {
+        sig::Class::Array(ref component_ty) => (**component_ty).clone(),
+        ref class_sig => panic!("expected an array class, but was {:?}", class_sig),
+    }
+}
+
+/// Describes the component type of an array, as the messages of the exceptions thrown by
+/// `System.arraycopy` do.
+fn arraycopy_component_name(component_ty: &sig::Type) -> &'static str {
+    match *component_ty {
+        sig::Type::Byte => "byte",
+        sig::Type::Char => "char",
+        sig::Type::Double => "double",
+        sig::Type::Float => "float",
+        sig::Type::Int => "int",
+        sig::Type::Long => "long",
+        sig::Type::Short => "short",
+        sig::Type::Boolean => "boolean",
+        sig::Type::Reference(_) => "object array",
+    }
+}
+
+/// `System.arraycopy(Object, int, Object, int, int)`. The arguments are checked as
+/// `System.arraycopy` specifies before anything is copied, except that each component copied
+/// between arrays of references is checked only when it is reached.
+fn arraycopy(class_loader: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+             -> NativeResult {
+    let src = try!(arraycopy_array(&context.heap, &args[0], "source"));
+    let dest = try!(arraycopy_array(&context.heap, &args[2], "destination"));
+    let (src_offset, dest_offset, len) = (int_arg(&args, 1), int_arg(&args, 3), int_arg(&args, 4));
+
+    let src_ty = arraycopy_component_type(&context.heap, src);
+    let dest_ty = arraycopy_component_type(&context.heap, dest);
+    let (src_name, dest_name) = (arraycopy_component_name(&src_ty),
+                                 arraycopy_component_name(&dest_ty));
+    // the name of the source component class and the destination component class, if the
+    // components must be checked one by one
+    let dest_component = match (&src_ty, &dest_ty) {
+        (&sig::Type::Reference(ref src_sig), &sig::Type::Reference(ref dest_sig)) => {
+            // the component class was loaded along with the array class
+            Some((src_sig.name(), class_loader.load_class(dest_sig).unwrap()))
+        },
+        _ if src_ty == dest_ty => None,
+        _ => return Err(exceptions::create_array_store(format!(
+            "arraycopy: type mismatch: can not copy {}[] into {}[]", src_name, dest_name))),
+    };
+
+    let src_length = context.heap.get_array(src).len();
+    let dest_length = context.heap.get_array(dest).len();
+    let src_end = src_offset as i64 + len as i64;
+    let dest_end = dest_offset as i64 + len as i64;
+    let out_of_bounds = if src_offset < 0 {
+        Some(format!("arraycopy: source index {} out of bounds for {}[{}]", src_offset,
+                     src_name, src_length))
+    } else if dest_offset < 0 {
+        Some(format!("arraycopy: destination index {} out of bounds for {}[{}]", dest_offset,
+                     dest_name, dest_length))
+    } else if len < 0 {
+        Some(format!("arraycopy: length {} is negative", len))
+    } else if src_end > src_length as i64 {
+        Some(format!("arraycopy: last source index {} out of bounds for {}[{}]", src_end,
+                     src_name, src_length))
+    } else if dest_end > dest_length as i64 {
+        Some(format!("arraycopy: last destination index {} out of bounds for {}[{}]", dest_end,
+                     dest_name, dest_length))
+    } else {
+        None
+    };
+    if let Some(message) = out_of_bounds {
+        return Err(exceptions::create_array_range_out_of_bounds(message));
+    }
+
+    // read every value before writing any, in case src and dest are the same
+    let values: Vec<_> = (0..len).map(|i| {
+        context.heap.get_array(src).get(src_offset + i)
+    }).collect();
+    for (i, value) in values.into_iter().enumerate() {
+        if let Some((ref src_component_name, ref dest_component)) = dest_component {
+            let value_class = match value {
+                Value::ScalarReference(object) => Some(context.heap.get_scalar(object).get_class()),
+                Value::ArrayReference(array) => Some(context.heap.get_array(array).get_class()),
+                _ => None,
+            };
+            if let Some(value_class) = value_class {
+                if !value_class.is_instance_of(dest_component, class_loader) {
+                    return Err(exceptions::create_array_store(format!(
+                        "arraycopy: element type mismatch: can not cast one of the elements of \
+                         {}[] to the type of the destination array, {}",
+                        src_component_name.replace('/', "."),
+                        dest_component.symref.sig.name().replace('/', "."))));
                 }
-            } else {
-                panic!("ArrayStoreException");
             }
-        } else {
-            panic!("src_offset must be an int");
         }
-    } else {
-        panic!("ArrayStoreException");
+        context.heap.get_array_mut(dest).put(dest_offset + i as i32, value);
     }
-    None
-});
+    Ok(None)
+}
 
-const WRITE: &'static Fn(Vec<Value>) -> Option<Value> = &(|args| {
-    if let Value::ArrayReference(ref b_rc) = args[1] {
+fn write(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>) -> NativeResult {
+    if let Value::ArrayReference(b) = args[1] {
         if let Value::Int(Wrapping(off)) = args[2] {
             if let Value::Int(Wrapping(len)) = args[3] {
-                let b = b_rc.borrow();
+                let b = context.heap.get_array(b);
                 let mut bytes = vec![];
                 for i in 0..len {
                     // TODO error condition is probably not right here
@@ -66,7 +185,7 @@ const WRITE: &'static Fn(Vec<Value>) -> Option<Value> = &(|args| {
                     }
                 }
                 io::stdout().write_all(&bytes).expect("IOException");
-                None
+                Ok(None)
             } else {
                 panic!("len must be an int")
             }
@@ -76,43 +195,607 @@ const WRITE: &'static Fn(Vec<Value>) -> Option<Value> = &(|args| {
     } else {
         panic!("b must be an array")
     }
-});
+}
 
-pub fn bind(symref: &symref::Method) -> Option<NativeMethod> {
-    let system_symref = symref::Class {
-        sig: sig::Class::Scalar(String::from("java/lang/System")),
-    };
-    let object_ty = sig::Type::Reference(sig::Class::Scalar(String::from("java/lang/Object")));
-    let arraycopy_sig = sig::Method {
-        name: String::from("arraycopy"),
-        params: vec![object_ty.clone(), sig::Type::Int, object_ty.clone(), sig::Type::Int,
-                     sig::Type::Int],
-        return_ty: None,
-    };
-    let arraycopy_symref = symref::Method {
-        class: system_symref.clone(),
-        sig: arraycopy_sig,
+/// `Object.equals(Object)`, which compares the identities of two objects.
+fn object_equals(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    let equal = args[0].as_heap_ref() == args[1].as_heap_ref();
+    Ok(Some(Value::Int(Wrapping(equal as i32))))
+}
+
+/// `Object.hashCode()`, which returns the identity hash code of an object.
+fn object_hash_code(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    let object = args[0].as_heap_ref()
+        .unwrap_or_else(|| exceptions::throw(exceptions::create_npe()));
+    Ok(Some(Value::Int(Wrapping(object.identity_hash_code()))))
+}
+
+/// `Object.getClass()`, which returns the `java/lang/Class` object representing the runtime class
+/// of an object.
+fn object_get_class(class_loader: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                    -> NativeResult {
+    let class = match args[0] {
+        Value::ScalarReference(object) => context.heap.get_scalar(object).get_class(),
+        Value::ArrayReference(array) => context.heap.get_array(array).get_class(),
+        ref v => panic!("getClass invoked on {:?}", v),
     };
+    let mirror = try!(java_lang::class_mirror(class_loader, context, &class));
+    Ok(Some(Value::ScalarReference(mirror)))
+}
+
+/// `Object.clone()`, which allocates a shallow copy of an array, or of an object whose class
+/// implements `Cloneable`.
+fn object_clone(class_loader: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                -> NativeResult {
+    match args[0] {
+        Value::ScalarReference(object) => {
+            let cloneable =
+                try!(class_loader.load_class(&sig::Class::new("java/lang/Cloneable")));
+            let class = context.heap.get_scalar(object).get_class();
+            if !class.implements(&cloneable) {
+                let class_name = class.symref.sig.name();
+                return Err(exceptions::create_clone_not_supported(&class_name));
+            }
+            Ok(Some(Value::ScalarReference(context.heap.clone_object(object))))
+        },
+        Value::ArrayReference(array) =>
+            Ok(Some(Value::ArrayReference(context.heap.clone_object(array)))),
+        ref v => panic!("clone invoked on {:?}", v),
+    }
+}
+
+fn int_arg(args: &[Value], index: usize) -> i32 {
+    match args[index] {
+        Value::Int(Wrapping(n)) => n,
+        ref v => panic!("expected an int, but was {:?}", v),
+    }
+}
+
+fn long_arg(args: &[Value], index: usize) -> i64 {
+    match args[index] {
+        Value::Long(Wrapping(n)) => n,
+        ref v => panic!("expected a long, but was {:?}", v),
+    }
+}
+
+fn float_arg(args: &[Value], index: usize) -> f32 {
+    match args[index] {
+        Value::Float(n) => n,
+        ref v => panic!("expected a float, but was {:?}", v),
+    }
+}
+
+fn double_arg(args: &[Value], index: usize) -> f64 {
+    match args[index] {
+        Value::Double(n) => n,
+        ref v => panic!("expected a double, but was {:?}", v),
+    }
+}
+
+/// The greater of two floating-point values, as defined by `Math.max`: NaN if either value is NaN,
+/// and positive zero is greater than negative zero. Converting `float` values to `double` and
+/// back preserves them exactly, so this also serves for `float`.
+fn java_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == 0.0 && b == 0.0 {
+        if a.is_sign_negative() { b } else { a }
+    } else if a >= b {
+        a
+    } else {
+        b
+    }
+}
+
+/// The lesser of two floating-point values, as defined by `Math.min`: NaN if either value is NaN,
+/// and negative zero is less than positive zero.
+fn java_min(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == 0.0 && b == 0.0 {
+        if a.is_sign_negative() { a } else { b }
+    } else if a <= b {
+        a
+    } else {
+        b
+    }
+}
+
+/// `a` raised to the power `b`, as defined by `Math.pow`. This differs from the IEEE 754 `pow`
+/// which `f64::powf` implements in two cases, in which Java gives NaN: when `b` is NaN, and when
+/// `a` is 1 or -1 and `b` is infinite.
+fn java_pow(a: f64, b: f64) -> f64 {
+    if b.is_nan() || (a.abs() == 1.0 && b.is_infinite()) {
+        f64::NAN
+    } else {
+        a.powf(b)
+    }
+}
+
+/// `Math.abs(int)`. The absolute value of `Integer.MIN_VALUE` cannot be represented, so it is
+/// returned unchanged.
+fn math_abs_int(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Int(Wrapping(int_arg(&args, 0).wrapping_abs()))))
+}
+
+/// `Math.abs(long)`, which returns `Long.MIN_VALUE` unchanged.
+fn math_abs_long(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Long(Wrapping(long_arg(&args, 0).wrapping_abs()))))
+}
+
+fn math_abs_float(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Float(float_arg(&args, 0).abs())))
+}
+
+fn math_abs_double(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(double_arg(&args, 0).abs())))
+}
+
+fn math_max_int(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Int(Wrapping(cmp::max(int_arg(&args, 0), int_arg(&args, 1))))))
+}
+
+fn math_max_long(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Long(Wrapping(cmp::max(long_arg(&args, 0), long_arg(&args, 1))))))
+}
+
+fn math_max_float(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    let (a, b) = (float_arg(&args, 0), float_arg(&args, 1));
+    Ok(Some(Value::Float(java_max(a as f64, b as f64) as f32)))
+}
+
+fn math_max_double(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(java_max(double_arg(&args, 0), double_arg(&args, 1)))))
+}
+
+fn math_min_int(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Int(Wrapping(cmp::min(int_arg(&args, 0), int_arg(&args, 1))))))
+}
+
+fn math_min_long(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Long(Wrapping(cmp::min(long_arg(&args, 0), long_arg(&args, 1))))))
+}
+
+fn math_min_float(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    let (a, b) = (float_arg(&args, 0), float_arg(&args, 1));
+    Ok(Some(Value::Float(java_min(a as f64, b as f64) as f32)))
+}
+
+fn math_min_double(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(java_min(double_arg(&args, 0), double_arg(&args, 1)))))
+}
 
-    let stdout_symref = symref::Class {
-        sig: sig::Class::Scalar(String::from("moon/RustStdout")),
+/// `Math.sqrt(double)`, which is NaN for negative values and NaN.
+fn math_sqrt(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(double_arg(&args, 0).sqrt())))
+}
+
+fn math_floor(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(double_arg(&args, 0).floor())))
+}
+
+fn math_ceil(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(double_arg(&args, 0).ceil())))
+}
+
+fn math_pow(_: &mut dyn ClassLoader, _: &mut Context, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Double(java_pow(double_arg(&args, 0), double_arg(&args, 1)))))
+}
+
+/// Parse a `String` as an `int` in the given radix, as `Integer.parseInt` does, throwing a
+/// `NumberFormatException` if the string is `null` or not a number in range, or if the radix is
+/// not between 2 and 36.
+fn parse_int(heap: &Heap, string: &Value, radix: i32) -> NativeResult {
+    let string = match *string {
+        Value::ScalarReference(string) => java_lang::string_to_rust(heap, string),
+        Value::NullReference =>
+            return Err(exceptions::create_number_format(String::from("Cannot parse null string"))),
+        ref v => panic!("expected a String, but was {:?}", v),
     };
-    let byte_array_ty = sig::Type::Reference(sig::Class::Array(Box::new(sig::Type::Byte)));
-    let write_sig = sig::Method {
-        name: String::from("write"),
-        params: vec![byte_array_ty, sig::Type::Int, sig::Type::Int],
-        return_ty: None,
+    if !(2..37).contains(&radix) {
+        let message = format!("radix {} out of range from 2 to 36", radix);
+        return Err(exceptions::create_number_format(message));
+    }
+    match i32::from_str_radix(&string, radix as u32) {
+        Ok(n) => Ok(Some(Value::Int(Wrapping(n)))),
+        Err(_) => {
+            let message = format!("For input string: \"{}\"", string);
+            Err(exceptions::create_number_format(message))
+        },
+    }
+}
+
+/// `Integer.parseInt(String)`, which parses a decimal `int`.
+fn integer_parse_int(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                     -> NativeResult {
+    parse_int(&context.heap, &args[0], 10)
+}
+
+/// `Integer.parseInt(String, int)`, which parses an `int` in the given radix.
+fn integer_parse_int_radix(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                           -> NativeResult {
+    parse_int(&context.heap, &args[0], int_arg(&args, 1))
+}
+
+/// `Integer.valueOf(int)`, which boxes an `int`, returning the same object for small values.
+fn integer_value_of(class_loader: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                    -> NativeResult {
+    let integer = try!(java_lang::integer_value_of(class_loader, context, int_arg(&args, 0)));
+    Ok(Some(Value::ScalarReference(integer)))
+}
+
+/// `Integer.intValue()`, which returns the value held by an `Integer`.
+fn integer_int_value(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                     -> NativeResult {
+    match args[0] {
+        Value::ScalarReference(integer) =>
+            Ok(Some(Value::Int(Wrapping(java_lang::integer_value(&context.heap, integer))))),
+        ref v => panic!("expected an Integer, but was {:?}", v),
+    }
+}
+
+/// Returns the `String` on which a native method of `java/lang/String` is invoked.
+fn this_string(args: &[Value]) -> HeapRef {
+    match args[0] {
+        Value::ScalarReference(string) => string,
+        ref v => panic!("expected a String, but was {:?}", v),
+    }
+}
+
+/// `String.length()`, which returns the number of UTF-16 code units in the string.
+fn string_length(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>) -> NativeResult {
+    let value = java_lang::string_value(&context.heap, this_string(&args));
+    Ok(Some(Value::Int(Wrapping(context.heap.get_array(value).len()))))
+}
+
+/// `String.charAt(int)`, which returns the UTF-16 code unit at an index of the string.
+fn string_char_at(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                  -> NativeResult {
+    let value = context.heap.get_array(java_lang::string_value(&context.heap, this_string(&args)));
+    match args[1] {
+        Value::Int(Wrapping(index)) if index >= 0 && index < value.len() =>
+            Ok(Some(value.get(index))),
+        Value::Int(Wrapping(index)) => Err(exceptions::create_sioobe(index)),
+        ref v => panic!("index must be an int, but was {:?}", v),
+    }
+}
+
+/// `String.equals(Object)`, which compares the contents of two strings.
+fn string_equals(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>) -> NativeResult {
+    let heap = &context.heap;
+    let string = this_string(&args);
+    let equal = match args[1] {
+        Value::ScalarReference(other) if java_lang::is_string(heap, other) =>
+            java_lang::string_to_utf16(heap, string) == java_lang::string_to_utf16(heap, other),
+        _ => false,
     };
-    let write_symref = symref::Method {
-        class: stdout_symref.clone(),
-        sig: write_sig,
+    Ok(Some(Value::Int(Wrapping(equal as i32))))
+}
+
+/// `String.hashCode()`, which computes `s[0]*31^(n-1) + s[1]*31^(n-2) + ... + s[n-1]` over the
+/// UTF-16 code units of the string.
+fn string_hash_code(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                    -> NativeResult {
+    let chars = java_lang::string_to_utf16(&context.heap, this_string(&args));
+    let hash = chars.into_iter().fold(Wrapping(0i32), |hash, c| {
+        hash * Wrapping(31) + Wrapping(c as i32)
+    });
+    Ok(Some(Value::Int(hash)))
+}
+
+/// Returns the `StringBuilder` on which a native method of `java/lang/StringBuilder` is invoked.
+fn this_string_builder(args: &[Value]) -> HeapRef {
+    match args[0] {
+        Value::ScalarReference(builder) => builder,
+        ref v => panic!("expected a StringBuilder, but was {:?}", v),
+    }
+}
+
+/// `StringBuilder()` and `StringBuilder(int)`, which give the new builder empty contents. The
+/// initial capacity is ignored, as the contents grow as needed.
+fn string_builder_init(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                       -> NativeResult {
+    context.heap.string_builder_mut(this_string_builder(&args)).clear();
+    Ok(None)
+}
+
+/// Append UTF-16 code units to a `StringBuilder`, and return the builder, as each of the `append`
+/// methods does.
+fn append_utf16<I>(context: &mut Context, args: &[Value], chars: I) -> NativeResult
+    where I: IntoIterator<Item = u16> {
+    let builder = this_string_builder(args);
+    context.heap.string_builder_mut(builder).extend(chars);
+    Ok(Some(Value::ScalarReference(builder)))
+}
+
+/// Append the UTF-16 code units of a Rust string to a `StringBuilder`, and return the builder.
+fn append_str(context: &mut Context, args: &[Value], s: &str) -> NativeResult {
+    append_utf16(context, args, s.encode_utf16())
+}
+
+/// `StringBuilder.append(String)`, which appends `null` if the string is `null`.
+fn string_builder_append_string(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                                -> NativeResult {
+    match args[1] {
+        Value::ScalarReference(string) => {
+            let chars = java_lang::string_to_utf16(&context.heap, string);
+            append_utf16(context, &args, chars)
+        },
+        Value::NullReference => append_str(context, &args, "null"),
+        ref v => panic!("expected a String, but was {:?}", v),
+    }
+}
+
+fn string_builder_append_int(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                             -> NativeResult {
+    append_str(context, &args, &int_arg(&args, 1).to_string())
+}
+
+fn string_builder_append_long(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                              -> NativeResult {
+    append_str(context, &args, &long_arg(&args, 1).to_string())
+}
+
+/// `StringBuilder.append(char)`, which appends a single UTF-16 code unit, so that a surrogate
+/// pair may be appended one half at a time.
+fn string_builder_append_char(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                              -> NativeResult {
+    let c = int_arg(&args, 1) as u16;
+    append_utf16(context, &args, Some(c))
+}
+
+/// `StringBuilder.length()`, which returns the number of UTF-16 code units appended so far.
+fn string_builder_length(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>)
+                         -> NativeResult {
+    let length = context.heap.string_builder(this_string_builder(&args)).len();
+    Ok(Some(Value::Int(Wrapping(length as i32))))
+}
+
+/// `StringBuilder.toString()`, which returns a new `String` holding the contents of the builder.
+fn string_builder_to_string(class_loader: &mut dyn ClassLoader, context: &mut Context,
+                            args: Vec<Value>) -> NativeResult {
+    let chars = context.heap.string_builder(this_string_builder(&args)).to_vec();
+    Ok(Some(try!(constant_pool::new_string(chars, class_loader, context))))
+}
+
+/// `PrintStream.println(String)`, which writes the string and a line separator to the standard
+/// output of the virtual machine.
+fn println(_: &mut dyn ClassLoader, context: &mut Context, args: Vec<Value>) -> NativeResult {
+    let line = match args[1] {
+        Value::ScalarReference(string) => java_lang::string_to_rust(&context.heap, string),
+        Value::NullReference => String::from("null"),
+        ref v => panic!("expected a String, but was {:?}", v),
     };
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).expect("IOException");
+    Ok(None)
+}
 
-    if *symref == arraycopy_symref {
-        Some(NativeMethod(ARRAYCOPY))
-    } else if *symref == write_symref {
-        Some(NativeMethod(WRITE))
-    } else {
-        None
+#[derive(Debug, Clone)]
+/// A table of the implementations of native methods, by the binary name of their class and their
+/// name and descriptor.
+pub struct NativeRegistry {
+    methods: HashMap<(String, String, String), NativeFn>,
+}
+
+impl NativeRegistry {
+    /// Create a registry containing the native methods of the runtime library.
+    pub fn new() -> Self {
+        let mut registry = NativeRegistry { methods: HashMap::new() };
+        registry.register("java/lang/Object", "registerNatives", "()V", no_op);
+        registry.register("java/lang/Object", "equals", "(Ljava/lang/Object;)Z", object_equals);
+        registry.register("java/lang/Object", "hashCode", "()I", object_hash_code);
+        registry.register("java/lang/Object", "getClass", "()Ljava/lang/Class;",
+                          object_get_class);
+        registry.register("java/lang/Object", "clone", "()Ljava/lang/Object;", object_clone);
+        registry.register("java/lang/Integer", "parseInt", "(Ljava/lang/String;)I",
+                          integer_parse_int);
+        registry.register("java/lang/Integer", "parseInt", "(Ljava/lang/String;I)I",
+                          integer_parse_int_radix);
+        registry.register("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;",
+                          integer_value_of);
+        registry.register("java/lang/Integer", "intValue", "()I", integer_int_value);
+        registry.register("java/lang/Math", "abs", "(I)I", math_abs_int);
+        registry.register("java/lang/Math", "abs", "(J)J", math_abs_long);
+        registry.register("java/lang/Math", "abs", "(F)F", math_abs_float);
+        registry.register("java/lang/Math", "abs", "(D)D", math_abs_double);
+        registry.register("java/lang/Math", "max", "(II)I", math_max_int);
+        registry.register("java/lang/Math", "max", "(JJ)J", math_max_long);
+        registry.register("java/lang/Math", "max", "(FF)F", math_max_float);
+        registry.register("java/lang/Math", "max", "(DD)D", math_max_double);
+        registry.register("java/lang/Math", "min", "(II)I", math_min_int);
+        registry.register("java/lang/Math", "min", "(JJ)J", math_min_long);
+        registry.register("java/lang/Math", "min", "(FF)F", math_min_float);
+        registry.register("java/lang/Math", "min", "(DD)D", math_min_double);
+        registry.register("java/lang/Math", "sqrt", "(D)D", math_sqrt);
+        registry.register("java/lang/Math", "floor", "(D)D", math_floor);
+        registry.register("java/lang/Math", "ceil", "(D)D", math_ceil);
+        registry.register("java/lang/Math", "pow", "(DD)D", math_pow);
+        registry.register("java/lang/String", "length", "()I", string_length);
+        registry.register("java/lang/String", "charAt", "(I)C", string_char_at);
+        registry.register("java/lang/String", "equals", "(Ljava/lang/Object;)Z", string_equals);
+        registry.register("java/lang/String", "hashCode", "()I", string_hash_code);
+        registry.register("java/lang/StringBuilder", "<init>", "()V", string_builder_init);
+        registry.register("java/lang/StringBuilder", "<init>", "(I)V", string_builder_init);
+        registry.register("java/lang/StringBuilder", "append",
+                          "(Ljava/lang/String;)Ljava/lang/StringBuilder;",
+                          string_builder_append_string);
+        registry.register("java/lang/StringBuilder", "append", "(I)Ljava/lang/StringBuilder;",
+                          string_builder_append_int);
+        registry.register("java/lang/StringBuilder", "append", "(J)Ljava/lang/StringBuilder;",
+                          string_builder_append_long);
+        registry.register("java/lang/StringBuilder", "append", "(C)Ljava/lang/StringBuilder;",
+                          string_builder_append_char);
+        registry.register("java/lang/StringBuilder", "length", "()I", string_builder_length);
+        registry.register("java/lang/StringBuilder", "toString", "()Ljava/lang/String;",
+                          string_builder_to_string);
+        registry.register("java/lang/System", "registerNatives", "()V", no_op);
+        registry.register("java/lang/Class", "desiredAssertionStatus0", "(Ljava/lang/Class;)Z",
+                          desired_assertion_status);
+        registry.register("java/lang/System", "arraycopy",
+                          "(Ljava/lang/Object;ILjava/lang/Object;II)V", arraycopy);
+        registry.register("moon/RustStdout", "write", "([BII)V", write);
+        registry.register("java/io/PrintStream", "println", "(Ljava/lang/String;)V", println);
+        registry
+    }
+
+    /// Register the implementation of the native method with the given name and descriptor in
+    /// the class with the given binary name, replacing any previous implementation.
+    pub fn register(&mut self, class: &str, method: &str, descriptor: &str, f: NativeFn) {
+        let key = (String::from(class), String::from(method), String::from(descriptor));
+        self.methods.insert(key, f);
+    }
+
+    /// Find the implementation of the native method referred to by a symbolic reference.
+    pub fn bind(&self, symref: &symref::Method) -> Option<NativeMethod> {
+        let class_name = match symref.class.sig {
+            sig::Class::Scalar(ref name) => name.clone(),
+            sig::Class::Array(_) => return None,
+        };
+        let key = (class_name, symref.sig.name.clone(), symref.sig.descriptor());
+        self.methods.get(&key).map(|&f| NativeMethod(f))
+    }
+}
+
+impl Default for NativeRegistry {
+    fn default() -> Self {
+        NativeRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use vm::class_path::ClassPath;
+    use vm::class_loader::BootstrapClassLoader;
+    use vm::heap::test::new_class;
+
+    /// Invoke the native method with the given name and descriptor in `java/lang/Math`.
+    fn math(name: &str, descriptor: &str, args: Vec<Value>) -> Value {
+        let symref = symref::Method {
+            class: symref::Class { sig: sig::Class::new("java/lang/Math") },
+            sig: sig::Method::new(name, descriptor),
+        };
+        let native_method = NativeRegistry::new().bind(&symref).unwrap();
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        native_method.invoke(&mut class_loader, &mut Context::new(), args).unwrap().unwrap()
+    }
+
+    fn int(n: i32) -> Value {
+        Value::Int(Wrapping(n))
+    }
+
+    fn long(n: i64) -> Value {
+        Value::Long(Wrapping(n))
+    }
+
+    /// The result of a `double` method, as a string so that NaN and the sign of zero are compared.
+    fn double(name: &str, descriptor: &str, args: &[f64]) -> String {
+        let args = args.iter().map(|&n| Value::Double(n)).collect();
+        match math(name, descriptor, args) {
+            Value::Double(n) if n == 0.0 && n.is_sign_negative() => String::from("-0.0"),
+            v => v.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_math_abs() {
+        assert_eq!(math("abs", "(I)I", vec![int(-3)]).to_string(), "3");
+        assert_eq!(math("abs", "(I)I", vec![int(i32::MIN)]).to_string(), i32::MIN.to_string());
+        assert_eq!(math("abs", "(J)J", vec![long(i64::MIN)]).to_string(), i64::MIN.to_string());
+        assert_eq!(math("abs", "(F)F", vec![Value::Float(f32::NEG_INFINITY)]).to_string(),
+                   "Infinity");
+        assert_eq!(double("abs", "(D)D", &[-0.0]), "0.0");
+        assert_eq!(double("abs", "(D)D", &[f64::NAN]), "NaN");
+    }
+
+    #[test]
+    fn test_math_max_min() {
+        assert_eq!(math("max", "(II)I", vec![int(i32::MIN), int(-1)]).to_string(), "-1");
+        assert_eq!(math("min", "(II)I", vec![int(i32::MIN), int(i32::MAX)]).to_string(),
+                   i32::MIN.to_string());
+        assert_eq!(math("max", "(JJ)J", vec![long(1), long(i64::MAX)]).to_string(),
+                   i64::MAX.to_string());
+        assert_eq!(math("min", "(JJ)J", vec![long(1), long(2)]).to_string(), "1");
+        assert_eq!(math("max", "(FF)F", vec![Value::Float(1.0), Value::Float(f32::NAN)])
+                       .to_string(), "NaN");
+        assert_eq!(math("min", "(FF)F", vec![Value::Float(1.0), Value::Float(-2.5)]).to_string(),
+                   "-2.5");
+        assert_eq!(double("max", "(DD)D", &[f64::NAN, 1.0]), "NaN");
+        assert_eq!(double("min", "(DD)D", &[1.0, f64::NAN]), "NaN");
+        assert_eq!(double("max", "(DD)D", &[-0.0, 0.0]), "0.0");
+        assert_eq!(double("max", "(DD)D", &[0.0, -0.0]), "0.0");
+        assert_eq!(double("min", "(DD)D", &[0.0, -0.0]), "-0.0");
+        assert_eq!(double("min", "(DD)D", &[-0.0, 0.0]), "-0.0");
+        assert_eq!(double("max", "(DD)D", &[f64::NEG_INFINITY, f64::INFINITY]), "Infinity");
+        assert_eq!(double("min", "(DD)D", &[f64::NEG_INFINITY, f64::INFINITY]), "-Infinity");
+    }
+
+    #[test]
+    fn test_math_sqrt_floor_ceil() {
+        assert_eq!(double("sqrt", "(D)D", &[16.0]), "4.0");
+        assert_eq!(double("sqrt", "(D)D", &[-1.0]), "NaN");
+        assert_eq!(double("sqrt", "(D)D", &[-0.0]), "-0.0");
+        assert_eq!(double("sqrt", "(D)D", &[f64::INFINITY]), "Infinity");
+        assert_eq!(double("sqrt", "(D)D", &[f64::NAN]), "NaN");
+        assert_eq!(double("floor", "(D)D", &[-1.5]), "-2.0");
+        assert_eq!(double("floor", "(D)D", &[f64::NEG_INFINITY]), "-Infinity");
+        assert_eq!(double("ceil", "(D)D", &[-0.5]), "-0.0");
+        assert_eq!(double("ceil", "(D)D", &[1.2]), "2.0");
+        assert_eq!(double("ceil", "(D)D", &[f64::NAN]), "NaN");
+    }
+
+    #[test]
+    fn test_math_pow() {
+        assert_eq!(double("pow", "(DD)D", &[2.0, 10.0]), "1024.0");
+        assert_eq!(double("pow", "(DD)D", &[f64::NAN, 0.0]), "1.0");
+        assert_eq!(double("pow", "(DD)D", &[f64::NAN, -0.0]), "1.0");
+        assert_eq!(double("pow", "(DD)D", &[1.0, f64::NAN]), "NaN");
+        assert_eq!(double("pow", "(DD)D", &[-1.0, f64::INFINITY]), "NaN");
+        assert_eq!(double("pow", "(DD)D", &[1.0, f64::NEG_INFINITY]), "NaN");
+        assert_eq!(double("pow", "(DD)D", &[3.0, 1.0]), "3.0");
+        assert_eq!(double("pow", "(DD)D", &[0.5, f64::INFINITY]), "0.0");
+        assert_eq!(double("pow", "(DD)D", &[-0.0, -1.0]), "-Infinity");
+        assert_eq!(double("pow", "(DD)D", &[-2.0, 0.5]), "NaN");
+    }
+
+    #[test]
+    fn test_string_builder_init() {
+        let registry = NativeRegistry::new();
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        let mut context = Context::new();
+        for descriptor in &["()V", "(I)V"] {
+            let symref = symref::Method {
+                class: symref::Class { sig: sig::Class::new("java/lang/StringBuilder") },
+                sig: sig::Method::new("<init>", descriptor),
+            };
+            let native_method = registry.bind(&symref).unwrap();
+            let builder = context.heap.alloc_scalar(new_class("java/lang/StringBuilder"));
+            context.heap.string_builder_mut(builder).extend("stale".encode_utf16());
+            let mut args = vec![Value::ScalarReference(builder)];
+            if *descriptor == "(I)V" {
+                args.push(Value::Int(Wrapping(16)));
+            }
+            let result = native_method.invoke(&mut class_loader, &mut context, args);
+            assert!(result.unwrap().is_none());
+            assert!(context.heap.string_builder(builder).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_string_builder_to_string_without_string_class() {
+        let symref = symref::Method {
+            class: symref::Class { sig: sig::Class::new("java/lang/StringBuilder") },
+            sig: sig::Method::new("toString", "()Ljava/lang/String;"),
+        };
+        let native_method = NativeRegistry::new().bind(&symref).unwrap();
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![]));
+        let mut context = Context::new();
+        let builder = context.heap.alloc_scalar(new_class("java/lang/StringBuilder"));
+        let args = vec![Value::ScalarReference(builder)];
+        let error = native_method.invoke(&mut class_loader, &mut context, args).unwrap_err();
+        assert_eq!(error.class_name, "java/lang/NoClassDefFoundError");
     }
 }
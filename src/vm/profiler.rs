@@ -0,0 +1,108 @@
+//! Counts of the calls to each method, and the time spent in them.
+//!
+//! The time recorded for a call runs from the method being invoked until it returns or throws, so
+//! it includes the time spent in the methods which it calls. The calls of a recursive method are
+//! each timed, so its total time counts the time spent in the innermost calls once for every
+//! level of recursion above them.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use vm::symref;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The calls recorded for one method.
+pub struct ProfileRecord {
+    /// The number of times the method was invoked.
+    pub call_count: u64,
+    /// The total time spent in the method, in nanoseconds.
+    pub total_ns: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The calls recorded for one method, along with the method they were recorded for.
+pub struct ProfileReport {
+    /// The binary name of the class which declares the method.
+    pub class: String,
+    pub method: String,
+    pub descriptor: String,
+    pub call_count: u64,
+    pub total_ns: u64,
+}
+
+#[derive(Debug, Default)]
+/// A record of the calls to each method, keyed by the binary name of its class, its name, and its
+/// descriptor.
+pub struct Profiler {
+    records: HashMap<(String, String, String), ProfileRecord>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    /// Record a call to a method which took the given time.
+    pub fn record(&mut self, method: &symref::Method, elapsed: Duration) {
+        let key = (method.class.sig.name(), method.sig.name.clone(), method.sig.descriptor());
+        let record = self.records.entry(key).or_default();
+        record.call_count += 1;
+        record.total_ns += elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+    }
+
+    /// Returns the calls recorded for a method, if it has been called.
+    pub fn get(&self, class: &str, method: &str, descriptor: &str) -> Option<&ProfileRecord> {
+        let key = (String::from(class), String::from(method), String::from(descriptor));
+        self.records.get(&key)
+    }
+
+    /// Returns the calls recorded for each method which has been called, sorted by the total time
+    /// spent in the method, longest first.
+    pub fn report(&self) -> Vec<ProfileReport> {
+        let mut reports = self.records.iter().map(|(key, record)| {
+            let (ref class, ref method, ref descriptor) = *key;
+            ProfileReport {
+                class: class.clone(),
+                method: method.clone(),
+                descriptor: descriptor.clone(),
+                call_count: record.call_count,
+                total_ns: record.total_ns,
+            }
+        }).collect::<Vec<_>>();
+        reports.sort_by_key(|report| Reverse(report.total_ns));
+        reports
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use vm::sig;
+
+    fn method(class: &str, name: &str, descriptor: &str) -> symref::Method {
+        symref::Method {
+            class: symref::Class { sig: sig::Class::new(class) },
+            sig: sig::Method::new(name, descriptor),
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let mut profiler = Profiler::new();
+        let fast = method("A", "fast", "()V");
+        let slow = method("B", "slow", "(I)I");
+        profiler.record(&fast, Duration::from_millis(1));
+        profiler.record(&slow, Duration::new(1, 5));
+        profiler.record(&fast, Duration::from_millis(2));
+        assert_eq!(profiler.get("A", "fast", "()V"),
+                   Some(&ProfileRecord { call_count: 2, total_ns: 3_000_000 }));
+        assert_eq!(profiler.get("A", "fast", "(I)V"), None);
+        let report = profiler.report();
+        assert_eq!(report.len(), 2);
+        assert_eq!((&*report[0].method, report[0].call_count, report[0].total_ns),
+                   ("slow", 1, 1_000_000_005));
+        assert_eq!((&*report[1].class, &*report[1].descriptor), ("A", "()V"));
+    }
+}
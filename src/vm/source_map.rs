@@ -0,0 +1,103 @@
+//! The mapping from the bytecode offsets of a method to the lines of the source code they were
+//! compiled from, which is used to give the line numbers of stack traces and breakpoints.
+
+use model::class_file::{AttributeInfo, MethodInfo};
+use model::class_file::attribute::LineNumberInfo;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A mapping from the bytecode offsets of a method to the lines of its source code, as recorded
+/// in the `LineNumberTable` attributes of its `Code` attribute (§4.7.12).
+pub struct SourceMap {
+    /// The `(start_pc, line_number)` entries of the tables, sorted by `start_pc`.
+    lines: Vec<(u32, u16)>,
+}
+
+impl SourceMap {
+    /// Builds the source map of a method from the entries of its `LineNumberTable` attributes,
+    /// which may be given in any order.
+    pub fn new(line_number_table: &[LineNumberInfo]) -> SourceMap {
+        let mut lines = line_number_table.iter()
+            .map(|info| (info.start_pc as u32, info.line_number))
+            .collect::<Vec<_>>();
+        lines.sort();
+        SourceMap { lines: lines }
+    }
+
+    /// Builds the source map of `method`, or returns `None` if it has no code or its code has no
+    /// `LineNumberTable` attribute.
+    pub fn from_method(method: &MethodInfo) -> Option<SourceMap> {
+        let mut table = vec![];
+        for attr in &method.attributes {
+            if let AttributeInfo::Code { ref attributes, .. } = *attr {
+                for code_attr in attributes {
+                    if let AttributeInfo::LineNumberTable { ref line_number_table } = *code_attr {
+                        table.extend(line_number_table.iter().cloned());
+                    }
+                }
+            }
+        }
+        if table.is_empty() { None } else { Some(SourceMap::new(&table)) }
+    }
+
+    /// Returns the line of the source code which compiled to the instruction at `pc`: that of the
+    /// last entry which starts at or before `pc`. Returns `None` if every entry starts after
+    /// `pc`.
+    pub fn line_at(&self, pc: u32) -> Option<u16> {
+        match self.lines.binary_search_by(|&(start_pc, _)| start_pc.cmp(&pc)) {
+            Ok(index) => Some(self.lines[index].1),
+            Err(0) => None,
+            Err(index) => Some(self.lines[index - 1].1),
+        }
+    }
+
+    /// Returns the offset of the first instruction compiled from the given line of the source
+    /// code, or `None` if no instruction was.
+    pub fn first_pc_of_line(&self, line: u16) -> Option<u32> {
+        self.lines.iter()
+            .filter(|&&(_, line_number)| line_number == line)
+            .map(|&(start_pc, _)| start_pc)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use model::class_file::method_access_flags;
+    use parser::class_file::parse_class_file;
+
+    #[test]
+    fn test_source_map() {
+        let class = parse_class_file(include_bytes!("../../data/HelloWorld.class")).unwrap();
+        let main = class.get_method("main", "([Ljava/lang/String;)V").unwrap();
+        let source_map = SourceMap::from_method(main).unwrap();
+        assert_eq!(source_map.line_at(0), Some(5));
+        assert_eq!(source_map.line_at(7), Some(5));
+        assert_eq!(source_map.line_at(8), Some(6));
+        assert_eq!(source_map.line_at(100), Some(6));
+        assert_eq!(source_map.first_pc_of_line(6), Some(8));
+        assert_eq!(source_map.first_pc_of_line(7), None);
+
+        // a line split around a loop has several entries, given out of order
+        let source_map = SourceMap::new(&[
+            LineNumberInfo { start_pc: 13, line_number: 4 },
+            LineNumberInfo { start_pc: 2, line_number: 4 },
+            LineNumberInfo { start_pc: 9, line_number: 5 },
+            LineNumberInfo { start_pc: 4, line_number: 3 },
+        ]);
+        assert_eq!(source_map.line_at(1), None);
+        assert_eq!(source_map.line_at(3), Some(4));
+        assert_eq!(source_map.line_at(10), Some(5));
+        assert_eq!(source_map.line_at(13), Some(4));
+        assert_eq!(source_map.first_pc_of_line(4), Some(2));
+
+        let abstract_method = MethodInfo {
+            access_flags: method_access_flags::ACC_ABSTRACT,
+            name_index: 0,
+            descriptor_index: 0,
+            attributes: vec![],
+        };
+        assert_eq!(SourceMap::from_method(&abstract_method), None);
+    }
+}
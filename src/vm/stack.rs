@@ -0,0 +1,223 @@
+//! The operand stack of a frame.
+//!
+//! The JVM specification measures the operand stack in _words_: values of type `long` and
+//! `double` (_category 2_ values) occupy two words, while all other values (_category 1_ values)
+//! occupy one. Here every value is stored as a single entry, and the instructions which
+//! manipulate the stack without regard to the types of its values (such as `dup2` and `pop2`)
+//! are expressed in terms of words, so that they can never split a category 2 value in half.
+
+use vm::value::Value;
+
+/// The operand stack manipulated by the instructions of a method.
+#[derive(Debug)]
+pub struct OperandStack {
+    values: Vec<Value>,
+    /// The number of words occupied by `values`.
+    words: usize,
+    /// The maximum number of words which may be on the stack at once, as given by the `max_stack`
+    /// item of the method's `Code` attribute.
+    max_words: usize,
+}
+
+impl OperandStack {
+    pub fn new(max_words: usize) -> Self {
+        OperandStack {
+            values: vec![],
+            words: 0,
+            max_words: max_words,
+        }
+    }
+
+    /// Push a value onto the stack. Panics if this would exceed the maximum depth of the stack.
+    pub fn push(&mut self, value: Value) {
+        self.words += value.size_in_words();
+        if self.words > self.max_words {
+            panic!("operand stack overflow");
+        }
+        self.values.push(value);
+    }
+
+    pub fn pop(&mut self) -> Option<Value> {
+        let value = self.values.pop();
+        if let Some(ref value) = value {
+            self.words -= value.size_in_words();
+        }
+        value
+    }
+
+    /// Remove every value from the stack.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.words = 0;
+    }
+
+    /// Remove `count` values from the top of the stack, returning them in the order in which
+    /// they were pushed.
+    pub fn pop_multi(&mut self, count: usize) -> Vec<Value> {
+        let start_index = self.values.len() - count;
+        let popped: Vec<Value> = self.values.drain(start_index..).collect();
+        self.words -= popped.iter().map(Value::size_in_words).sum::<usize>();
+        popped
+    }
+
+    /// Remove the values which make up the top `words` words of the stack, returning them in the
+    /// order in which they were pushed. Panics if this would split a category 2 value.
+    pub fn pop_words(&mut self, words: usize) -> Vec<Value> {
+        let mut popped = vec![];
+        let mut remaining = words;
+        while remaining > 0 {
+            let value = self.pop().expect("operand stack underflow");
+            let size = value.size_in_words();
+            if size > remaining {
+                panic!("cannot split a category 2 value on the operand stack");
+            }
+            remaining -= size;
+            popped.push(value);
+        }
+        popped.reverse();
+        popped
+    }
+
+    /// Duplicate the top `words` words of the stack, inserting the copy beneath the `depth`
+    /// words below them. This implements all six forms of `dup`; for example, `dup_x1` is
+    /// `dup(1, 1)` and `dup2_x2` is `dup(2, 2)`.
+    pub fn dup(&mut self, words: usize, depth: usize) {
+        let top = self.pop_words(words);
+        let below = self.pop_words(depth);
+        for value in top.iter().cloned().chain(below).chain(top.iter().cloned()) {
+            self.push(value);
+        }
+    }
+
+    /// Swap the top two values of the stack, both of which must be category 1 values.
+    pub fn swap(&mut self) {
+        let value1 = self.pop_words(1);
+        let value2 = self.pop_words(1);
+        for value in value1.into_iter().chain(value2) {
+            self.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::num::Wrapping;
+
+    /// Create a stack from ints and longs. Numbers less than 100 are pushed as ints, and the rest
+    /// as longs.
+    fn new_stack(numbers: &[i64]) -> OperandStack {
+        let mut stack = OperandStack::new(16);
+        for &n in numbers {
+            if n < 100 {
+                stack.push(Value::Int(Wrapping(n as i32)));
+            } else {
+                stack.push(Value::Long(Wrapping(n)));
+            }
+        }
+        stack
+    }
+
+    fn contents(stack: &OperandStack) -> Vec<i64> {
+        stack.values.iter().map(|value| {
+            match *value {
+                Value::Int(Wrapping(n)) => n as i64,
+                Value::Long(Wrapping(n)) => n,
+                ref v => panic!("unexpected value {:?}", v),
+            }
+        }).collect()
+    }
+
+    fn dup(numbers: &[i64], words: usize, depth: usize) -> Vec<i64> {
+        let mut stack = new_stack(numbers);
+        stack.dup(words, depth);
+        contents(&stack)
+    }
+
+    #[test]
+    fn test_dup() {
+        assert_eq!(dup(&[1, 2], 1, 0), [1, 2, 2]);
+    }
+
+    #[test]
+    fn test_dup_x1() {
+        assert_eq!(dup(&[1, 2, 3], 1, 1), [1, 3, 2, 3]);
+    }
+
+    #[test]
+    fn test_dup_x2() {
+        assert_eq!(dup(&[1, 2, 3, 4], 1, 2), [1, 4, 2, 3, 4]);
+        assert_eq!(dup(&[1, 200, 3], 1, 2), [1, 3, 200, 3]);
+    }
+
+    #[test]
+    fn test_dup2() {
+        assert_eq!(dup(&[1, 2, 3], 2, 0), [1, 2, 3, 2, 3]);
+        assert_eq!(dup(&[1, 200], 2, 0), [1, 200, 200]);
+    }
+
+    #[test]
+    fn test_dup2_x1() {
+        assert_eq!(dup(&[1, 2, 3, 4], 2, 1), [1, 3, 4, 2, 3, 4]);
+        assert_eq!(dup(&[1, 2, 300], 2, 1), [1, 300, 2, 300]);
+    }
+
+    #[test]
+    fn test_dup2_x2() {
+        assert_eq!(dup(&[1, 2, 3, 4], 2, 2), [3, 4, 1, 2, 3, 4]);
+        assert_eq!(dup(&[1, 2, 300], 2, 2), [300, 1, 2, 300]);
+        assert_eq!(dup(&[100, 2, 3], 2, 2), [2, 3, 100, 2, 3]);
+        assert_eq!(dup(&[100, 200], 2, 2), [200, 100, 200]);
+    }
+
+    #[test]
+    #[should_panic(expected = "category 2")]
+    fn test_dup_category_2() {
+        dup(&[100], 1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "category 2")]
+    fn test_dup_x1_category_2() {
+        dup(&[100, 2], 1, 1);
+    }
+
+    #[test]
+    fn test_pop_words() {
+        let mut stack = new_stack(&[1, 2, 3]);
+        stack.pop_words(2);
+        assert_eq!(contents(&stack), [1]);
+
+        let mut stack = new_stack(&[1, 200]);
+        stack.pop_words(2);
+        assert_eq!(contents(&stack), [1]);
+    }
+
+    #[test]
+    fn test_max_words() {
+        let mut stack = new_stack(&[1, 200]);
+        assert_eq!(stack.words, 3);
+        stack.dup(2, 1);
+        assert_eq!(stack.words, 5);
+        stack.pop_multi(2);
+        assert_eq!(stack.words, 2);
+        stack.clear();
+        assert_eq!(stack.words, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "operand stack overflow")]
+    fn test_overflow() {
+        let mut stack = OperandStack::new(2);
+        stack.push(Value::Int(Wrapping(1)));
+        stack.push(Value::Long(Wrapping(2)));
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut stack = new_stack(&[1, 2, 3]);
+        stack.swap();
+        assert_eq!(contents(&stack), [1, 3, 2]);
+    }
+}
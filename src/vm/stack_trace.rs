@@ -0,0 +1,37 @@
+//! The stack traces of exceptions, which record the frames of the call stack that an exception
+//! propagated out of.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A frame of a stack trace: a method which was executing when an exception was thrown, or which
+/// an exception propagated out of.
+pub struct StackTraceElement {
+    /// The binary name of the class declaring the method.
+    pub class_name: String,
+    pub method_name: String,
+    /// The name of the source file of the class, from its `SourceFile` attribute.
+    pub source_file: Option<String>,
+    /// The line of the source file which was executing, from the `LineNumberTable` of the method.
+    pub line_number: Option<u16>,
+}
+
+/// Formats the element as Java does, such as `java.lang.String.charAt(String.java:658)`.
+impl fmt::Display for StackTraceElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}.{}(", self.class_name.replace('/', "."), self.method_name));
+        match (self.source_file.as_ref(), self.line_number) {
+            (Some(source_file), Some(line_number)) => try!(write!(f, "{}:{}", source_file,
+                                                                   line_number)),
+            (Some(source_file), None) => try!(write!(f, "{}", source_file)),
+            (None, _) => try!(write!(f, "Unknown Source")),
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The frames which an exception propagated out of, innermost first.
+pub struct StackTrace {
+    pub elements: Vec<StackTraceElement>,
+}
@@ -1,13 +1,17 @@
 //! Internal JVM representations of Java values.
 
-use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::num::Wrapping;
 use std::rc::Rc;
 
 use vm::class::Class;
+use vm::heap::{Heap, HeapRef};
 use vm::sig;
 
+/// The depth to which `Value::display` expands the fields and components of referenced objects.
+const DISPLAY_DEPTH: usize = 2;
+
 /// A value in the Java virtual machine.
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -21,11 +25,126 @@ pub enum Value {
     /// A 64-bit floating-point type, representing the Java type `double`.
     Double(f64),
     /// A reference to a scalar Java object in the heap.
-    ScalarReference(Rc<RefCell<Scalar>>),
+    ScalarReference(HeapRef),
     /// A reference to a Java array in the heap.
-    ArrayReference(Rc<RefCell<Array>>),
+    ArrayReference(HeapRef),
     /// A reference to a Java object which is `null`.
     NullReference,
+    /// The address of an instruction, pushed by `jsr` and `jsr_w` and branched to by `ret`. Only
+    /// class files older than version 50 use these instructions, to implement `finally` blocks.
+    ReturnAddress(u32),
+}
+
+impl Value {
+    /// Returns true if the value is of a category 2 type (`long` or `double`), which occupies two
+    /// local variable slots or two words of the operand stack.
+    pub fn is_category_2(&self) -> bool {
+        match *self {
+            Value::Long(_) | Value::Double(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the number of words occupied by the value in local variables and on the operand
+    /// stack: two for category 2 values, and one for all others.
+    pub fn size_in_words(&self) -> usize {
+        if self.is_category_2() { 2 } else { 1 }
+    }
+
+    /// Returns the heap reference held by this value, if it is a non-null reference.
+    pub fn as_heap_ref(&self) -> Option<HeapRef> {
+        match *self {
+            Value::ScalarReference(heap_ref) | Value::ArrayReference(heap_ref) => Some(heap_ref),
+            _ => None,
+        }
+    }
+
+    /// Returns an object which displays the value, looking up the objects it refers to in `heap`
+    /// to show their classes and the values of their fields or components.
+    pub fn display<'a>(&'a self, heap: &'a Heap) -> HeapValue<'a> {
+        HeapValue {
+            value: self,
+            heap: heap,
+        }
+    }
+
+    /// Writes the value to `f`, expanding referenced objects to at most `depth` levels of fields
+    /// or components. Objects beyond that depth are shown by their class alone, which also keeps
+    /// cyclic structures from being expanded forever.
+    pub fn display_depth(&self, f: &mut fmt::Formatter, heap: &Heap, depth: usize)
+                         -> fmt::Result {
+        match *self {
+            Value::ScalarReference(heap_ref) => {
+                let scalar = heap.get_scalar(heap_ref);
+                try!(write!(f, "{} {{", scalar.class.symref.sig.name()));
+                if depth == 0 {
+                    return write!(f, " .. }}");
+                }
+                let mut fields = scalar.fields.iter().collect::<Vec<_>>();
+                fields.sort_by(|&(a, _), &(b, _)| a.name.cmp(&b.name));
+                for (i, &(sig, value)) in fields.iter().enumerate() {
+                    try!(write!(f, "{} {}: ", if i == 0 { "" } else { "," }, sig.name));
+                    try!(value.display_depth(f, heap, depth - 1));
+                }
+                write!(f, " }}")
+            },
+            Value::ArrayReference(heap_ref) => {
+                let array = heap.get_array(heap_ref);
+                try!(write!(f, "{} [", array.class.symref.sig.name()));
+                if depth == 0 {
+                    return write!(f, "..]");
+                }
+                for (i, value) in array.array.iter().enumerate() {
+                    try!(write!(f, "{}", if i == 0 { "" } else { ", " }));
+                    try!(value.display_depth(f, heap, depth - 1));
+                }
+                write!(f, "]")
+            },
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+/// Writes a floating-point value as Java's `Double.toString` would name its special values.
+fn fmt_floating(f: &mut fmt::Formatter, value: f64) -> fmt::Result {
+    if value.is_nan() {
+        write!(f, "NaN")
+    } else if value.is_infinite() {
+        write!(f, "{}Infinity", if value < 0.0 { "-" } else { "" })
+    } else {
+        write!(f, "{:?}", value)
+    }
+}
+
+/// Without access to the heap, references are shown by their identity hash codes.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Int(Wrapping(n)) => write!(f, "{}", n),
+            Value::Float(n) if n.is_nan() || n.is_infinite() => fmt_floating(f, n as f64),
+            Value::Float(n) => write!(f, "{:?}", n),
+            Value::Long(Wrapping(n)) => write!(f, "{}", n),
+            Value::Double(n) => fmt_floating(f, n),
+            Value::ScalarReference(heap_ref) =>
+                write!(f, "object@{}", heap_ref.identity_hash_code()),
+            Value::ArrayReference(heap_ref) =>
+                write!(f, "array@{}", heap_ref.identity_hash_code()),
+            Value::NullReference => write!(f, "null"),
+            Value::ReturnAddress(address) => write!(f, "return address {}", address),
+        }
+    }
+}
+
+/// A value displayed along with the objects it refers to, as returned by `Value::display`.
+pub struct HeapValue<'a> {
+    value: &'a Value,
+    heap: &'a Heap,
+}
+
+impl<'a> fmt::Display for HeapValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.display_depth(f, self.heap, DISPLAY_DEPTH)
+    }
 }
 
 #[derive(Debug)]
@@ -68,6 +187,20 @@ impl Scalar {
     pub fn put_field(&mut self, sig: sig::Field, value: Value) {
         self.fields.insert(sig, value);
     }
+
+    /// Returns a shallow copy of the object, of the same class and with the same field values.
+    /// The objects referred to by the fields are shared, not copied.
+    pub fn clone_fields(&self) -> Self {
+        Scalar {
+            class: self.class.clone(),
+            fields: self.fields.clone(),
+        }
+    }
+
+    /// Returns the heap references held by the fields of the object.
+    pub fn references(&self) -> Vec<HeapRef> {
+        self.fields.values().filter_map(Value::as_heap_ref).collect()
+    }
 }
 
 #[derive(Debug)]
@@ -81,9 +214,8 @@ pub struct Array {
 
 impl Array {
     pub fn new(class: Rc<Class>, length: i32) -> Self {
-        if length < 0 {
-            panic!("NegativeArraySizeException");
-        }
+        // the instructions which create arrays throw a `NegativeArraySizeException` first
+        assert!(length >= 0, "negative array length {}", length);
         match class.symref.sig {
             sig::Class::Scalar(_) => panic!("can't construct array from scalar class"),
             sig::Class::Array(ref component_ty) => {
@@ -110,15 +242,97 @@ impl Array {
         self.array[index as usize].clone()
     }
 
+    /// Store a value in the array. Values stored in `boolean`, `byte`, `char`, and `short` arrays
+    /// are truncated to the width of the component type.
     pub fn put(&mut self, index: i32, value: Value) {
         if index < 0 || (index as usize) >= self.array.len() {
             panic!("ArrayIndexOutOfBoundsException");
         }
+        let value = match (&self.class.symref.sig, value) {
+            (&sig::Class::Array(ref component_ty), Value::Int(Wrapping(n))) => {
+                let narrowed = match **component_ty {
+                    sig::Type::Boolean => n & 1,
+                    sig::Type::Byte => n as i8 as i32,
+                    sig::Type::Char => n as u16 as i32,
+                    sig::Type::Short => n as i16 as i32,
+                    _ => n,
+                };
+                Value::Int(Wrapping(narrowed))
+            },
+            (_, value) => value,
+        };
         self.array[index as usize] = value;
     }
 
     pub fn len(&self) -> i32 {
         self.array.len() as i32
     }
+
+    /// Returns a shallow copy of the array, of the same class and with the same components.
+    pub fn clone_data(&self) -> Self {
+        Array {
+            class: self.class.clone(),
+            array: self.array.clone(),
+        }
+    }
+
+    /// Returns the heap references held by the components of the array.
+    pub fn references(&self) -> Vec<HeapRef> {
+        self.array.iter().filter_map(Value::as_heap_ref).collect()
+    }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use builder::class_file::ClassFileBuilder;
+    use vm::class_loader::BootstrapClassLoader;
+    use vm::class_path::{ClassPath, ClassPathEntry};
+    use writer::class_file::write_class_file;
+
+    #[test]
+    fn test_display_primitives() {
+        assert_eq!(Value::Int(Wrapping(-3)).to_string(), "-3");
+        assert_eq!(Value::Long(Wrapping(i64::MAX)).to_string(), "9223372036854775807");
+        assert_eq!(Value::Float(1.5).to_string(), "1.5");
+        assert_eq!(Value::Float(f32::NEG_INFINITY).to_string(), "-Infinity");
+        assert_eq!(Value::Double(1.0).to_string(), "1.0");
+        assert_eq!(Value::Double(f64::INFINITY).to_string(), "Infinity");
+        assert_eq!(Value::Double(f64::NAN).to_string(), "NaN");
+        assert_eq!(format!("{:?}", Value::NullReference), "NullReference");
+    }
+
+    #[test]
+    fn test_display_object() {
+        let data_dir = ClassPathEntry::Directory(PathBuf::from("data/vm"));
+        let mut class_loader = BootstrapClassLoader::new(ClassPath::new(vec![data_dir]));
+        let point = ClassFileBuilder::new("Point", "java/lang/Object")
+            .add_field("x", "I", 0)
+            .add_field("y", "I", 0)
+            .add_field("next", "LPoint;", 0)
+            .build();
+        let class = class_loader.define_class("Point", &write_class_file(&point)).unwrap();
+        let mut heap = Heap::new();
+        let first = heap.alloc_scalar(class.clone());
+        let second = heap.alloc_scalar(class);
+        let sig = |name: &str, descriptor: &str| sig::Field {
+            name: String::from(name),
+            ty: sig::Type::new(descriptor),
+        };
+        heap.get_scalar_mut(first).put_field(sig("x", "I"), Value::Int(Wrapping(3)));
+        heap.get_scalar_mut(first).put_field(sig("y", "I"), Value::Int(Wrapping(4)));
+        let value = Value::ScalarReference(first);
+        assert_eq!(value.display(&heap).to_string(), "Point { next: null, x: 3, y: 4 }");
+        assert_eq!(value.to_string(), format!("object@{}", first.identity_hash_code()));
+
+        // A cycle of references is expanded only to the display depth.
+        let next = Value::ScalarReference(second);
+        heap.get_scalar_mut(first).put_field(sig("next", "LPoint;"), next);
+        heap.get_scalar_mut(second).put_field(sig("next", "LPoint;"), value.clone());
+        assert_eq!(value.display(&heap).to_string(),
+                   "Point { next: Point { next: Point { .. }, x: 0, y: 0 }, x: 3, y: 4 }");
+    }
+}
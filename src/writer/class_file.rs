@@ -0,0 +1,577 @@
+//! A writer for a Java class file.
+
+use model::class_file::{u1, u2, u4};
+use model::class_file::{AttributeInfo, ClassFile, FieldInfo, MethodInfo};
+use model::class_file::attribute;
+use model::class_file::attribute::annotation;
+use model::class_file::constant_pool::{ConstantPool, ConstantPoolInfo, MethodReference};
+use model::class_file::constant_pool::reference_kind;
+use model::class_file::constant_pool::tags;
+
+/// The output type of the writer.
+pub type Output = Vec<u8>;
+
+fn write_u1(out: &mut Output, value: u1) {
+    out.push(value);
+}
+
+fn write_u2(out: &mut Output, value: u2) {
+    out.push((value >> 8) as u8);
+    out.push(value as u8);
+}
+
+fn write_u4(out: &mut Output, value: u4) {
+    out.push((value >> 24) as u8);
+    out.push((value >> 16) as u8);
+    out.push((value >> 8) as u8);
+    out.push(value as u8);
+}
+
+/// Writes the length of a table as a `u2` followed by each of its entries.
+fn write_table<T, F>(out: &mut Output, table: &[T], mut write_entry: F)
+    where F: FnMut(&mut Output, &T) {
+    write_u2(out, table.len() as u2);
+    for entry in table {
+        write_entry(out, entry);
+    }
+}
+
+fn write_indices(out: &mut Output, indices: &[u2]) {
+    write_table(out, indices, |out, &i| write_u2(out, i));
+}
+
+fn method_reference(out: &mut Output, reference: &MethodReference) {
+    let (kind, reference_index) = match *reference {
+        MethodReference::GetField { reference_index } =>
+            (reference_kind::tags::GET_FIELD, reference_index),
+        MethodReference::GetStatic { reference_index } =>
+            (reference_kind::tags::GET_STATIC, reference_index),
+        MethodReference::PutField { reference_index } =>
+            (reference_kind::tags::PUT_FIELD, reference_index),
+        MethodReference::PutStatic { reference_index } =>
+            (reference_kind::tags::PUT_STATIC, reference_index),
+        MethodReference::InvokeVirtual { reference_index } =>
+            (reference_kind::tags::INVOKE_VIRTUAL, reference_index),
+        MethodReference::InvokeStatic { reference_index } =>
+            (reference_kind::tags::INVOKE_STATIC, reference_index),
+        MethodReference::InvokeSpecial { reference_index } =>
+            (reference_kind::tags::INVOKE_SPECIAL, reference_index),
+        MethodReference::NewInvokeSpecial { reference_index } =>
+            (reference_kind::tags::NEW_INVOKE_SPECIAL, reference_index),
+        MethodReference::InvokeInterface { reference_index } =>
+            (reference_kind::tags::INVOKE_INTERFACE, reference_index),
+    };
+    write_u1(out, kind);
+    write_u2(out, reference_index);
+}
+
+fn cp_info(out: &mut Output, info: &ConstantPoolInfo) {
+    match *info {
+        ConstantPoolInfo::Class { name_index } => {
+            write_u1(out, tags::CLASS);
+            write_u2(out, name_index);
+        },
+        ConstantPoolInfo::FieldRef { class_index, name_and_type_index } => {
+            write_u1(out, tags::FIELD_REF);
+            write_u2(out, class_index);
+            write_u2(out, name_and_type_index);
+        },
+        ConstantPoolInfo::MethodRef { class_index, name_and_type_index } => {
+            write_u1(out, tags::METHOD_REF);
+            write_u2(out, class_index);
+            write_u2(out, name_and_type_index);
+        },
+        ConstantPoolInfo::InterfaceMethodRef { class_index, name_and_type_index } => {
+            write_u1(out, tags::INTERFACE_METHOD_REF);
+            write_u2(out, class_index);
+            write_u2(out, name_and_type_index);
+        },
+        ConstantPoolInfo::String { string_index } => {
+            write_u1(out, tags::STRING);
+            write_u2(out, string_index);
+        },
+        ConstantPoolInfo::Integer { bytes } => {
+            write_u1(out, tags::INTEGER);
+            write_u4(out, bytes);
+        },
+        ConstantPoolInfo::Float { bytes } => {
+            write_u1(out, tags::FLOAT);
+            write_u4(out, bytes);
+        },
+        ConstantPoolInfo::Long { high_bytes, low_bytes } => {
+            write_u1(out, tags::LONG);
+            write_u4(out, high_bytes);
+            write_u4(out, low_bytes);
+        },
+        ConstantPoolInfo::Double { high_bytes, low_bytes } => {
+            write_u1(out, tags::DOUBLE);
+            write_u4(out, high_bytes);
+            write_u4(out, low_bytes);
+        },
+        ConstantPoolInfo::NameAndType { name_index, descriptor_index } => {
+            write_u1(out, tags::NAME_AND_TYPE);
+            write_u2(out, name_index);
+            write_u2(out, descriptor_index);
+        },
+        ConstantPoolInfo::Utf8 { ref bytes } => {
+            write_u1(out, tags::UTF_8);
+            write_u2(out, bytes.len() as u2);
+            out.extend_from_slice(bytes);
+        },
+        ConstantPoolInfo::MethodHandle { ref reference } => {
+            write_u1(out, tags::METHOD_HANDLE);
+            method_reference(out, reference);
+        },
+        ConstantPoolInfo::MethodType { descriptor_index } => {
+            write_u1(out, tags::METHOD_TYPE);
+            write_u2(out, descriptor_index);
+        },
+        ConstantPoolInfo::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } => {
+            write_u1(out, tags::INVOKE_DYNAMIC);
+            write_u2(out, bootstrap_method_attr_index);
+            write_u2(out, name_and_type_index);
+        },
+        ConstantPoolInfo::Module { name_index } => {
+            write_u1(out, tags::MODULE);
+            write_u2(out, name_index);
+        },
+        ConstantPoolInfo::Package { name_index } => {
+            write_u1(out, tags::PACKAGE);
+            write_u2(out, name_index);
+        },
+        // The second slot of a Long or Double constant has no representation in the class file.
+        ConstantPoolInfo::Unusable => (),
+    }
+}
+
+fn constant_pool(out: &mut Output, constant_pool: &ConstantPool) {
+    // `Unusable` entries are skipped when writing, but still occupy an index in the pool.
+    write_u2(out, constant_pool.len() as u2 + 1);
+    for info in constant_pool {
+        cp_info(out, info);
+    }
+}
+
+/// Returns the index of the `ConstantPoolInfo::Utf8` entry in the constant pool whose contents
+/// are `name`, panicking if there is no such entry.
+fn utf8_index(constant_pool: &ConstantPool, name: &str) -> u2 {
+    constant_pool.iter()
+        .position(|info| match *info {
+            ConstantPoolInfo::Utf8 { ref bytes } => bytes.as_slice() == name.as_bytes(),
+            _ => false,
+        })
+        .map(|i| (i + 1) as u2)
+        .unwrap_or_else(|| panic!("no Utf8 constant for attribute name {}", name))
+}
+
+fn exception_table_entry(out: &mut Output, entry: &attribute::ExceptionTableEntry) {
+    write_u2(out, entry.start_pc);
+    write_u2(out, entry.end_pc);
+    write_u2(out, entry.handler_pc);
+    write_u2(out, entry.catch_type);
+}
+
+fn verification_type_info(out: &mut Output,
+                          info: &attribute::stack_map_frame::VerificationTypeInfo) {
+    use model::class_file::attribute::stack_map_frame::VerificationTypeInfo;
+    match *info {
+        VerificationTypeInfo::Top => write_u1(out, 0),
+        VerificationTypeInfo::Integer => write_u1(out, 1),
+        VerificationTypeInfo::Float => write_u1(out, 2),
+        VerificationTypeInfo::Double => write_u1(out, 3),
+        VerificationTypeInfo::Long => write_u1(out, 4),
+        VerificationTypeInfo::Null => write_u1(out, 5),
+        VerificationTypeInfo::UninitializedThis => write_u1(out, 6),
+        VerificationTypeInfo::Object { class_index } => {
+            write_u1(out, 7);
+            write_u2(out, class_index);
+        },
+        VerificationTypeInfo::Uninitialized { offset } => {
+            write_u1(out, 8);
+            write_u2(out, offset);
+        },
+    }
+}
+
+fn stack_map_frame(out: &mut Output, frame: &attribute::StackMapFrame) {
+    use model::class_file::attribute::StackMapFrame;
+    match *frame {
+        StackMapFrame::SameFrame { offset_delta } => write_u1(out, offset_delta),
+        StackMapFrame::SameLocals1StackItemFrame { offset_delta, ref stack_item } => {
+            write_u1(out, 64 + offset_delta);
+            verification_type_info(out, stack_item);
+        },
+        StackMapFrame::SameLocals1StackItemFrameExtended { offset_delta, ref stack_item } => {
+            write_u1(out, 247);
+            write_u2(out, offset_delta);
+            verification_type_info(out, stack_item);
+        },
+        StackMapFrame::ChopFrame { offset_delta, num_chopped } => {
+            write_u1(out, 251 - num_chopped);
+            write_u2(out, offset_delta);
+        },
+        StackMapFrame::SameFrameExtended { offset_delta } => {
+            write_u1(out, 251);
+            write_u2(out, offset_delta);
+        },
+        StackMapFrame::AppendFrame { offset_delta, ref locals } => {
+            write_u1(out, 251 + locals.len() as u1);
+            write_u2(out, offset_delta);
+            for local in locals {
+                verification_type_info(out, local);
+            }
+        },
+        StackMapFrame::FullFrame { offset_delta, ref locals, ref stack } => {
+            write_u1(out, 255);
+            write_u2(out, offset_delta);
+            write_table(out, locals, verification_type_info);
+            write_table(out, stack, verification_type_info);
+        },
+    }
+}
+
+fn element_value(out: &mut Output, value: &annotation::ElementValue) {
+    use model::class_file::attribute::annotation::ElementValue;
+    match *value {
+        ElementValue::Byte { const_value_index } => {
+            write_u1(out, b'B');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Char { const_value_index } => {
+            write_u1(out, b'C');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Double { const_value_index } => {
+            write_u1(out, b'D');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Float { const_value_index } => {
+            write_u1(out, b'F');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Int { const_value_index } => {
+            write_u1(out, b'I');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Long { const_value_index } => {
+            write_u1(out, b'J');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Short { const_value_index } => {
+            write_u1(out, b'S');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Boolean { const_value_index } => {
+            write_u1(out, b'Z');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::String { const_value_index } => {
+            write_u1(out, b's');
+            write_u2(out, const_value_index);
+        },
+        ElementValue::Enum { type_name_index, const_name_index } => {
+            write_u1(out, b'e');
+            write_u2(out, type_name_index);
+            write_u2(out, const_name_index);
+        },
+        ElementValue::Class { class_info_index } => {
+            write_u1(out, b'c');
+            write_u2(out, class_info_index);
+        },
+        ElementValue::Annotation { ref annotation_value } => {
+            write_u1(out, b'@');
+            annotation(out, annotation_value);
+        },
+        ElementValue::Array { ref values } => {
+            write_u1(out, b'[');
+            write_table(out, values, element_value);
+        },
+    }
+}
+
+fn element_value_pair(out: &mut Output, pair: &annotation::ElementValuePair) {
+    write_u2(out, pair.element_name_index);
+    element_value(out, &pair.value);
+}
+
+fn annotation(out: &mut Output, annotation: &annotation::Annotation) {
+    write_u2(out, annotation.type_index);
+    write_table(out, &annotation.element_value_pairs, element_value_pair);
+}
+
+fn annotations(out: &mut Output, annotations: &[annotation::Annotation]) {
+    write_table(out, annotations, annotation);
+}
+
+fn parameter_annotations(out: &mut Output, parameter_annotations: &[Vec<annotation::Annotation>]) {
+    write_u1(out, parameter_annotations.len() as u1);
+    for annots in parameter_annotations {
+        annotations(out, annots);
+    }
+}
+
+fn target_info(out: &mut Output, target_info: &annotation::TargetInfo) {
+    use model::class_file::attribute::annotation::TargetInfo;
+    match *target_info {
+        TargetInfo::TypeParameter { type_parameter_index } => write_u1(out, type_parameter_index),
+        TargetInfo::Supertype { supertype_index } => write_u2(out, supertype_index),
+        TargetInfo::TypeParameterBound { type_parameter_index, bound_index } => {
+            write_u1(out, type_parameter_index);
+            write_u1(out, bound_index);
+        },
+        TargetInfo::Empty => (),
+        TargetInfo::FormalParameter { formal_parameter_index } =>
+            write_u1(out, formal_parameter_index),
+        TargetInfo::Throws { throws_type_index } => write_u2(out, throws_type_index),
+        TargetInfo::LocalVariable { ref table } => write_table(out, table, |out, entry| {
+            write_u2(out, entry.start_pc);
+            write_u2(out, entry.length);
+            write_u2(out, entry.index);
+        }),
+        TargetInfo::Catch { exception_table_index } => write_u2(out, exception_table_index),
+        TargetInfo::Offset { offset } => write_u2(out, offset),
+        TargetInfo::TypeArgument { offset, type_argument_index } => {
+            write_u2(out, offset);
+            write_u1(out, type_argument_index);
+        },
+    }
+}
+
+fn type_annotation(out: &mut Output, type_annotation: &annotation::TypeAnnotation) {
+    write_u1(out, type_annotation.target_type);
+    target_info(out, &type_annotation.target_info);
+    write_u1(out, type_annotation.target_path.path.len() as u1);
+    for part in &type_annotation.target_path.path {
+        write_u1(out, part.type_path_kind);
+        write_u1(out, part.type_argument_index);
+    }
+    write_u2(out, type_annotation.type_index);
+    write_table(out, &type_annotation.element_value_pairs, element_value_pair);
+}
+
+fn attribute_info(out: &mut Output, attr: &AttributeInfo, constant_pool: &ConstantPool) {
+    match *attr {
+        AttributeInfo::ConstantValue { constant_value_index } =>
+            write_u2(out, constant_value_index),
+
+        AttributeInfo::Code { max_stack, max_locals, ref code, ref exception_table,
+                              ref attributes } => {
+            write_u2(out, max_stack);
+            write_u2(out, max_locals);
+            write_u4(out, code.len() as u4);
+            out.extend_from_slice(code);
+            write_table(out, exception_table, exception_table_entry);
+            write_table(out, attributes, |out, attr| attribute(out, attr, constant_pool));
+        },
+
+        AttributeInfo::StackMapTable { ref entries } => write_table(out, entries, stack_map_frame),
+
+        AttributeInfo::Exceptions { ref exception_index_table } =>
+            write_indices(out, exception_index_table),
+
+        AttributeInfo::BootstrapMethods { ref bootstrap_methods } =>
+            write_table(out, bootstrap_methods, |out, bootstrap_method| {
+                write_u2(out, bootstrap_method.bootstrap_method_ref);
+                write_indices(out, &bootstrap_method.bootstrap_arguments);
+            }),
+
+        AttributeInfo::InnerClasses { ref classes } =>
+            write_table(out, classes, |out, class| {
+                write_u2(out, class.inner_class_info_index);
+                write_u2(out, class.outer_class_info_index);
+                write_u2(out, class.inner_name_index);
+                write_u2(out, class.inner_class_access_flags);
+            }),
+
+        AttributeInfo::EnclosingMethod { class_index, method_index } => {
+            write_u2(out, class_index);
+            write_u2(out, method_index);
+        },
+
+        AttributeInfo::Synthetic | AttributeInfo::Deprecated => (),
+
+        AttributeInfo::Signature { signature_index } => write_u2(out, signature_index),
+
+        AttributeInfo::RuntimeVisibleAnnotations { annotations: ref annots } |
+        AttributeInfo::RuntimeInvisibleAnnotations { annotations: ref annots } =>
+            annotations(out, annots),
+
+        AttributeInfo::RuntimeVisibleParameterAnnotations { parameter_annotations: ref annots } |
+        AttributeInfo::RuntimeInvisibleParameterAnnotations { parameter_annotations: ref annots } =>
+            parameter_annotations(out, annots),
+
+        AttributeInfo::RuntimeVisibleTypeAnnotations { annotations: ref annots } |
+        AttributeInfo::RuntimeInvisibleTypeAnnotations { annotations: ref annots } =>
+            write_table(out, annots, type_annotation),
+
+        AttributeInfo::AnnotationDefault { ref default_value } => element_value(out, default_value),
+
+        AttributeInfo::MethodParameters { ref parameters } => {
+            write_u1(out, parameters.len() as u1);
+            for parameter in parameters {
+                write_u2(out, parameter.name_index);
+                write_u2(out, parameter.access_flags);
+            }
+        },
+
+        AttributeInfo::SourceFile { sourcefile_index } => write_u2(out, sourcefile_index),
+
+        AttributeInfo::SourceDebugExtension { ref debug_extension } =>
+            out.extend_from_slice(debug_extension),
+
+        AttributeInfo::LineNumberTable { ref line_number_table } =>
+            write_table(out, line_number_table, |out, info| {
+                write_u2(out, info.start_pc);
+                write_u2(out, info.line_number);
+            }),
+
+        AttributeInfo::LocalVariableTable { ref local_variable_table } =>
+            write_table(out, local_variable_table, |out, info| {
+                write_u2(out, info.start_pc);
+                write_u2(out, info.length);
+                write_u2(out, info.name_index);
+                write_u2(out, info.descriptor_index);
+                write_u2(out, info.index);
+            }),
+
+        AttributeInfo::LocalVariableTypeTable { ref local_variable_type_table } =>
+            write_table(out, local_variable_type_table, |out, info| {
+                write_u2(out, info.start_pc);
+                write_u2(out, info.length);
+                write_u2(out, info.name_index);
+                write_u2(out, info.signature_index);
+                write_u2(out, info.index);
+            }),
+
+        AttributeInfo::Module { module_name_index, module_flags, module_version_index,
+                                ref requires, ref exports, ref opens, ref uses_index,
+                                ref provides } => {
+            write_u2(out, module_name_index);
+            write_u2(out, module_flags);
+            write_u2(out, module_version_index);
+            write_table(out, requires, |out, r| {
+                write_u2(out, r.requires_index);
+                write_u2(out, r.requires_flags);
+                write_u2(out, r.requires_version_index);
+            });
+            write_table(out, exports, |out, e| {
+                write_u2(out, e.exports_index);
+                write_u2(out, e.exports_flags);
+                write_indices(out, &e.exports_to_index);
+            });
+            write_table(out, opens, |out, o| {
+                write_u2(out, o.opens_index);
+                write_u2(out, o.opens_flags);
+                write_indices(out, &o.opens_to_index);
+            });
+            write_indices(out, uses_index);
+            write_table(out, provides, |out, p| {
+                write_u2(out, p.provides_index);
+                write_indices(out, &p.provides_with_index);
+            });
+        },
+
+        AttributeInfo::NestHost { host_class_index } => write_u2(out, host_class_index),
+
+        AttributeInfo::NestMembers { ref classes } |
+        AttributeInfo::PermittedSubclasses { ref classes } => write_indices(out, classes),
+
+        AttributeInfo::Record { ref components } =>
+            write_table(out, components, |out, component| {
+                write_u2(out, component.name_index);
+                write_u2(out, component.descriptor_index);
+                write_table(out, &component.attributes,
+                            |out, attr| attribute(out, attr, constant_pool));
+            }),
+
+        AttributeInfo::Unknown { ref info, .. } => out.extend_from_slice(info),
+    }
+}
+
+fn attribute(out: &mut Output, attr: &AttributeInfo, constant_pool: &ConstantPool) {
+    let attribute_name_index = match *attr {
+        AttributeInfo::Unknown { attribute_name_index, .. } => attribute_name_index,
+        _ => utf8_index(constant_pool, attr.name().unwrap()),
+    };
+    let mut info = vec![];
+    attribute_info(&mut info, attr, constant_pool);
+    write_u2(out, attribute_name_index);
+    write_u4(out, info.len() as u4);
+    out.extend(info);
+}
+
+fn field(out: &mut Output, field: &FieldInfo, constant_pool: &ConstantPool) {
+    write_u2(out, field.access_flags);
+    write_u2(out, field.name_index);
+    write_u2(out, field.descriptor_index);
+    write_table(out, &field.attributes, |out, attr| attribute(out, attr, constant_pool));
+}
+
+fn method(out: &mut Output, method: &MethodInfo, constant_pool: &ConstantPool) {
+    write_u2(out, method.access_flags);
+    write_u2(out, method.name_index);
+    write_u2(out, method.descriptor_index);
+    write_table(out, &method.attributes, |out, attr| attribute(out, attr, constant_pool));
+}
+
+/// Writes a Java class file.
+///
+/// The model does not record the name of most attributes, so the name of each attribute other
+/// than `AttributeInfo::Unknown` is looked up in the constant pool of `class`.
+///
+/// # Panics
+///
+/// Panics if the constant pool of `class` does not contain a `ConstantPoolInfo::Utf8` entry
+/// holding the name of one of the attributes to be written.
+pub fn write_class_file(class: &ClassFile) -> Output {
+    let cp = &class.constant_pool;
+    let mut out = vec![];
+    write_u4(&mut out, 0xCAFEBABE);
+    write_u2(&mut out, class.minor_version);
+    write_u2(&mut out, class.major_version);
+    constant_pool(&mut out, cp);
+    write_u2(&mut out, class.access_flags);
+    write_u2(&mut out, class.this_class);
+    write_u2(&mut out, class.super_class);
+    write_indices(&mut out, &class.interfaces);
+    write_table(&mut out, &class.fields, |out, f| field(out, f, cp));
+    write_table(&mut out, &class.methods, |out, m| method(out, m, cp));
+    write_table(&mut out, &class.attributes, |out, attr| attribute(out, attr, cp));
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::class_file::parse_class_file;
+
+    /// Asserts that writing out the parsed form of `data` reproduces `data` exactly, which also
+    /// guarantees that parsing the written bytes yields the same `ClassFile`.
+    fn assert_round_trip(data: &[u8]) {
+        let class = parse_class_file(data).unwrap();
+        let written = write_class_file(&class);
+        assert_eq!(format!("{:?}", class),
+                   format!("{:?}", parse_class_file(&written).unwrap()));
+        assert_eq!(data, written.as_slice());
+    }
+
+    #[test]
+    fn test_hello_world() {
+        assert_round_trip(include_bytes!("../../data/HelloWorld.class"));
+    }
+
+    #[test]
+    fn test_java_lang_string() {
+        assert_round_trip(include_bytes!("../../data/String.class"));
+    }
+
+    #[test]
+    fn test_newer_attributes() {
+        assert_round_trip(include_bytes!("../../data/Lambda.class"));
+        assert_round_trip(include_bytes!("../../data/module-info.class"));
+        assert_round_trip(include_bytes!("../../data/Outer.class"));
+        assert_round_trip(include_bytes!("../../data/Outer$Inner.class"));
+        assert_round_trip(include_bytes!("../../data/Point.class"));
+        assert_round_trip(include_bytes!("../../data/Shape.class"));
+    }
+}
@@ -0,0 +1,15 @@
+//! Contains a writer for a Java class file.
+//!
+//! # Examples
+//!
+//! Basic usage:
+//! ```
+//! use rust_jvm::parser::class_file::parse_class_file;
+//! use rust_jvm::writer::class_file::write_class_file;
+//!
+//! let data = include_bytes!("../../data/HelloWorld.class");
+//! let class = parse_class_file(data).unwrap();
+//! assert_eq!(&data[..], &write_class_file(&class)[..]);
+//! ```
+
+pub mod class_file;
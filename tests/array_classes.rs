@@ -0,0 +1,40 @@
+#![cfg(test)]
+
+extern crate rust_jvm;
+
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use self::rust_jvm::vm::sig;
+use self::rust_jvm::vm::class_loader::{BootstrapClassLoader, ClassLoader};
+use self::rust_jvm::vm::class_path::{ClassPath, ClassPathEntry};
+
+fn class_loader() -> BootstrapClassLoader {
+    BootstrapClassLoader::new(ClassPath::new(vec![
+        ClassPathEntry::Directory(PathBuf::from("data/vm")),
+    ]))
+}
+
+#[test]
+fn test_load_int_array_class() {
+    let mut class_loader = class_loader();
+    let class = class_loader.load_class(&sig::Class::new("[I")).unwrap();
+    assert_eq!(class.symref.sig, sig::Class::Array(Box::new(sig::Type::Int)));
+    let superclass = class.superclass.as_ref().expect("array class has no superclass");
+    assert_eq!(superclass.symref.sig, sig::Class::new("java/lang/Object"));
+    let length = sig::Field { name: String::from("length"), ty: sig::Type::Int };
+    assert!(class.resolve_instance_field(&length).is_ok());
+    let interfaces = class.interfaces.iter().map(|interface| interface.symref.sig.name())
+        .collect::<Vec<_>>();
+    assert_eq!(interfaces, ["java/lang/Cloneable", "java/io/Serializable"]);
+}
+
+#[test]
+fn test_load_nested_array_class() {
+    let mut class_loader = class_loader();
+    let array_sig = sig::Class::new("[[Ljava/lang/String;");
+    let class = class_loader.load_class(&array_sig).unwrap();
+    let superclass = class.superclass.as_ref().unwrap();
+    assert_eq!(superclass.symref.sig, sig::Class::new("java/lang/Object"));
+    assert!(Rc::ptr_eq(&class, &class_loader.load_class(&array_sig).unwrap()));
+}
@@ -3,12 +3,74 @@
 extern crate rust_jvm;
 extern crate nom;
 
-use self::rust_jvm::vm::{sig, symref, VirtualMachine};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::process::Command;
+use std::rc::Rc;
+
+use self::rust_jvm::vm::Interpreter;
+use self::rust_jvm::vm::debug::DebugAgent;
+use self::rust_jvm::vm::class_path::{ClassPath, ClassPathEntry};
 
 #[test]
 fn test_hello_world() {
-    let class_sig = sig::Class::new("HelloWorld");
-    let class_symref = symref::Class { sig: class_sig };
-    let vm = VirtualMachine::new();
-    vm.start(class_symref);
+    let class_path = ClassPath::new(vec![ClassPathEntry::Directory(PathBuf::from("rt"))]);
+    let interpreter = Interpreter::new(class_path);
+    interpreter.run_main("HelloWorld", vec![]).unwrap();
+}
+
+#[test]
+fn test_hello_world_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_main")).args(["-cp", "rt", "HelloWorld"])
+        .output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "Hello, world!\n");
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    Step { pc: u32, line: Option<u16> },
+    Breakpoint { pc: u32 },
+}
+
+/// A debug agent which records the events in `HelloWorld.main`.
+struct RecordingAgent(Rc<RefCell<Vec<Event>>>);
+
+impl DebugAgent for RecordingAgent {
+    fn on_step(&mut self, class: &str, method: &str, pc: u32, line: Option<u16>) {
+        if class == "HelloWorld" && method == "main" {
+            self.0.borrow_mut().push(Event::Step { pc: pc, line: line });
+        }
+    }
+
+    fn on_breakpoint(&mut self, class: &str, method: &str, pc: u32) {
+        if class == "HelloWorld" && method == "main" {
+            self.0.borrow_mut().push(Event::Breakpoint { pc: pc });
+        }
+    }
+
+    fn on_exception(&mut self, exception_class: &str, _: u32) {
+        panic!("unexpected exception {}", exception_class);
+    }
+}
+
+#[test]
+fn test_hello_world_single_step() {
+    let class_path = ClassPath::new(vec![ClassPathEntry::Directory(PathBuf::from("rt"))]);
+    let mut interpreter = Interpreter::new(class_path);
+    let events = Rc::new(RefCell::new(vec![]));
+    interpreter.set_debug_agent(Box::new(RecordingAgent(events.clone())));
+    interpreter.set_step_mode(true);
+    interpreter.add_breakpoint("HelloWorld", "main", 4).unwrap();
+    assert!(interpreter.add_breakpoint("HelloWorld", "main", 100).is_err());
+    interpreter.run_main("HelloWorld", vec![]).unwrap();
+    // getstatic, ldc and invokevirtual on line 3, then return on line 4
+    assert_eq!(*events.borrow(), vec![
+        Event::Step { pc: 0, line: Some(3) },
+        Event::Step { pc: 3, line: Some(3) },
+        Event::Step { pc: 5, line: Some(3) },
+        Event::Breakpoint { pc: 8 },
+        Event::Step { pc: 8, line: Some(4) },
+    ]);
 }
@@ -1 +1,5 @@
+mod array_classes;
 mod hello_world;
+mod round_trip;
+mod rt_jar;
+mod validation;
@@ -0,0 +1,372 @@
+#![cfg(test)]
+
+//! Property tests of the class file parser and writer: every class file which the writer
+//! produces parses back to the class it was written from, and no corruption of a class file
+//! makes the parser panic.
+
+extern crate proptest;
+extern crate rust_jvm;
+
+use self::proptest::collection::vec;
+use self::proptest::prelude::*;
+
+use self::rust_jvm::model::class_file::{AttributeInfo, ClassFile, FieldInfo, MethodInfo};
+use self::rust_jvm::model::class_file::attribute::ExceptionTableEntry;
+use self::rust_jvm::model::class_file::constant_pool::{ConstantPool, ConstantPoolInfo};
+use self::rust_jvm::parser::class_file::parse_class_file;
+use self::rust_jvm::util::modified_utf8::to_modified_utf8;
+use self::rust_jvm::writer::class_file::write_class_file;
+
+/// The names of the attributes which generated classes may have. The writer looks up the name of
+/// each attribute in the constant pool, so these are always added to it. `Custom` is not a
+/// standard attribute, so it is parsed as an `AttributeInfo::Unknown`.
+const ATTRIBUTE_NAMES: &[&str] =
+    &["Code", "ConstantValue", "Deprecated", "SourceFile", "Synthetic", "Custom"];
+
+/// A constant pool entry to be added to a generated constant pool. The entries to which it refers
+/// are chosen by taking the `usize` selectors modulo the number of earlier entries of the
+/// required kind, so that every reference is to an existing entry.
+#[derive(Debug, Clone)]
+enum Entry {
+    Utf8(String),
+    Class(usize),
+    String(usize),
+    Integer(u32),
+    Float(u32),
+    Long(u64),
+    Double(u64),
+    NameAndType(usize, usize),
+    FieldRef(usize, usize),
+    MethodRef(usize, usize),
+    MethodType(usize),
+}
+
+/// An attribute to be added to a generated class, field or method, with selectors like those of
+/// `Entry`.
+#[derive(Debug, Clone)]
+enum Attribute {
+    Code { max_stack: u16, max_locals: u16, code: Vec<u8>, handlers: Vec<(u16, u16, u16)> },
+    ConstantValue(usize),
+    Deprecated,
+    SourceFile(usize),
+    Synthetic,
+    Custom(Vec<u8>),
+}
+
+/// A field or method to be added to a generated class.
+#[derive(Debug, Clone)]
+struct Member {
+    access_flags: u16,
+    name: usize,
+    descriptor: usize,
+    attributes: Vec<Attribute>,
+}
+
+/// A constant pool under construction, along with the indices of its entries of each kind.
+#[derive(Default)]
+struct PoolBuilder {
+    entries: Vec<ConstantPoolInfo>,
+    utf8s: Vec<u16>,
+    classes: Vec<u16>,
+    integers: Vec<u16>,
+    names_and_types: Vec<u16>,
+}
+
+/// Returns the index selected from `indices`, or `None` if there are none to select from.
+fn select(indices: &[u16], selector: usize) -> Option<u16> {
+    if indices.is_empty() {
+        None
+    } else {
+        Some(indices[selector % indices.len()])
+    }
+}
+
+impl PoolBuilder {
+    /// Add an entry, returning its one-based index.
+    fn push(&mut self, entry: ConstantPoolInfo) -> u16 {
+        let wide = match entry {
+            ConstantPoolInfo::Long { .. } | ConstantPoolInfo::Double { .. } => true,
+            _ => false,
+        };
+        self.entries.push(entry);
+        let index = self.entries.len() as u16;
+        if wide {
+            self.entries.push(ConstantPoolInfo::Unusable);
+        }
+        index
+    }
+
+    fn add_utf8(&mut self, s: &str) -> u16 {
+        let index = self.push(ConstantPoolInfo::Utf8 { bytes: to_modified_utf8(s) });
+        self.utf8s.push(index);
+        index
+    }
+
+    /// Add an entry, unless it refers to a kind of entry of which there are none yet.
+    fn add(&mut self, entry: &Entry) {
+        match *entry {
+            Entry::Utf8(ref s) => {
+                self.add_utf8(s);
+            },
+            Entry::Class(name) => {
+                let name_index = select(&self.utf8s, name).unwrap();
+                let index = self.push(ConstantPoolInfo::Class { name_index: name_index });
+                self.classes.push(index);
+            },
+            Entry::String(string) => {
+                let string_index = select(&self.utf8s, string).unwrap();
+                self.push(ConstantPoolInfo::String { string_index: string_index });
+            },
+            Entry::Integer(bytes) => {
+                let index = self.push(ConstantPoolInfo::Integer { bytes: bytes });
+                self.integers.push(index);
+            },
+            Entry::Float(bytes) => {
+                self.push(ConstantPoolInfo::Float { bytes: bytes });
+            },
+            Entry::Long(bits) => {
+                self.push(ConstantPoolInfo::Long {
+                    high_bytes: (bits >> 32) as u32,
+                    low_bytes: bits as u32,
+                });
+            },
+            Entry::Double(bits) => {
+                self.push(ConstantPoolInfo::Double {
+                    high_bytes: (bits >> 32) as u32,
+                    low_bytes: bits as u32,
+                });
+            },
+            Entry::NameAndType(name, descriptor) => {
+                let name_index = select(&self.utf8s, name).unwrap();
+                let descriptor_index = select(&self.utf8s, descriptor).unwrap();
+                let index = self.push(ConstantPoolInfo::NameAndType {
+                    name_index: name_index,
+                    descriptor_index: descriptor_index,
+                });
+                self.names_and_types.push(index);
+            },
+            Entry::FieldRef(class, name_and_type) | Entry::MethodRef(class, name_and_type) => {
+                let class_index = match select(&self.classes, class) {
+                    Some(index) => index,
+                    None => return,
+                };
+                let name_and_type_index = match select(&self.names_and_types, name_and_type) {
+                    Some(index) => index,
+                    None => return,
+                };
+                self.push(match *entry {
+                    Entry::FieldRef(..) => ConstantPoolInfo::FieldRef {
+                        class_index: class_index,
+                        name_and_type_index: name_and_type_index,
+                    },
+                    _ => ConstantPoolInfo::MethodRef {
+                        class_index: class_index,
+                        name_and_type_index: name_and_type_index,
+                    },
+                });
+            },
+            Entry::MethodType(descriptor) => {
+                let descriptor_index = select(&self.utf8s, descriptor).unwrap();
+                self.push(ConstantPoolInfo::MethodType { descriptor_index: descriptor_index });
+            },
+        }
+    }
+
+    /// Create the attribute described by `attribute`, unless it refers to a kind of entry of which
+    /// there are none.
+    fn attribute(&self, attribute: &Attribute) -> Option<AttributeInfo> {
+        let attribute = match *attribute {
+            Attribute::Code { max_stack, max_locals, ref code, ref handlers } => {
+                let exception_table = handlers.iter().map(|&(start_pc, end_pc, handler_pc)| {
+                    ExceptionTableEntry {
+                        start_pc: start_pc,
+                        end_pc: end_pc,
+                        handler_pc: handler_pc,
+                        catch_type: select(&self.classes, handler_pc as usize).unwrap_or(0),
+                    }
+                }).collect();
+                AttributeInfo::Code {
+                    max_stack: max_stack,
+                    max_locals: max_locals,
+                    code: code.clone(),
+                    exception_table: exception_table,
+                    attributes: vec![],
+                }
+            },
+            Attribute::ConstantValue(value) => AttributeInfo::ConstantValue {
+                constant_value_index: match select(&self.integers, value) {
+                    Some(index) => index,
+                    None => return None,
+                },
+            },
+            Attribute::Deprecated => AttributeInfo::Deprecated,
+            Attribute::SourceFile(name) => AttributeInfo::SourceFile {
+                sourcefile_index: select(&self.utf8s, name).unwrap(),
+            },
+            Attribute::Synthetic => AttributeInfo::Synthetic,
+            Attribute::Custom(ref info) => AttributeInfo::Unknown {
+                attribute_name_index: self.utf8_index("Custom"),
+                info: info.clone(),
+            },
+        };
+        Some(attribute)
+    }
+
+    fn attributes(&self, attributes: &[Attribute]) -> Vec<AttributeInfo> {
+        attributes.iter().filter_map(|attribute| self.attribute(attribute)).collect()
+    }
+
+    /// Returns the index of the first `Utf8` entry holding `s`.
+    fn utf8_index(&self, s: &str) -> u16 {
+        let bytes = to_modified_utf8(s);
+        let position = self.entries.iter().position(|entry| match *entry {
+            ConstantPoolInfo::Utf8 { bytes: ref entry_bytes } => *entry_bytes == bytes,
+            _ => false,
+        });
+        position.unwrap() as u16 + 1
+    }
+}
+
+fn arb_entry() -> impl Strategy<Value = Entry> {
+    prop_oneof![
+        "[a-zA-Z0-9_$/;()<>\\[\u{0}\u{e9}\u{4e16}\u{1f600}]{0,12}".prop_map(Entry::Utf8),
+        any::<usize>().prop_map(Entry::Class),
+        any::<usize>().prop_map(Entry::String),
+        any::<u32>().prop_map(Entry::Integer),
+        any::<u32>().prop_map(Entry::Float),
+        any::<u64>().prop_map(Entry::Long),
+        any::<u64>().prop_map(Entry::Double),
+        (any::<usize>(), any::<usize>()).prop_map(|(n, d)| Entry::NameAndType(n, d)),
+        (any::<usize>(), any::<usize>()).prop_map(|(c, n)| Entry::FieldRef(c, n)),
+        (any::<usize>(), any::<usize>()).prop_map(|(c, n)| Entry::MethodRef(c, n)),
+        any::<usize>().prop_map(Entry::MethodType),
+    ]
+}
+
+prop_compose! {
+    fn arb_code()(max_stack in any::<u16>(), max_locals in any::<u16>(),
+                  code in vec(any::<u8>(), 1..32),
+                  handlers in vec(any::<(u16, u16, u16)>(), 0..3)) -> Attribute {
+        Attribute::Code {
+            max_stack: max_stack,
+            max_locals: max_locals,
+            code: code,
+            handlers: handlers,
+        }
+    }
+}
+
+fn arb_attribute() -> impl Strategy<Value = Attribute> {
+    prop_oneof![
+        arb_code(),
+        any::<usize>().prop_map(Attribute::ConstantValue),
+        Just(Attribute::Deprecated),
+        any::<usize>().prop_map(Attribute::SourceFile),
+        Just(Attribute::Synthetic),
+        vec(any::<u8>(), 0..16).prop_map(Attribute::Custom),
+    ]
+}
+
+prop_compose! {
+    fn arb_member()(access_flags in any::<u16>(), name in any::<usize>(),
+                    descriptor in any::<usize>(),
+                    attributes in vec(arb_attribute(), 0..3)) -> Member {
+        Member {
+            access_flags: access_flags,
+            name: name,
+            descriptor: descriptor,
+            attributes: attributes,
+        }
+    }
+}
+
+prop_compose! {
+    /// A class file whose constant pool is built up from `entries`, each of which refers only to
+    /// entries before it, and whose other indices all refer to entries of the right kind.
+    fn arb_class_file()(minor_version in any::<u16>(), major_version in 45u16..70,
+                        entries in vec(arb_entry(), 0..40), access_flags in any::<u16>(),
+                        this_class in any::<usize>(),
+                        super_class in proptest::option::of(any::<usize>()),
+                        interfaces in vec(any::<usize>(), 0..4),
+                        fields in vec(arb_member(), 0..4), methods in vec(arb_member(), 0..4),
+                        attributes in vec(arb_attribute(), 0..3)) -> ClassFile {
+        let mut pool = PoolBuilder::default();
+        for name in ATTRIBUTE_NAMES {
+            pool.add_utf8(name);
+        }
+        pool.add(&Entry::Class(0));
+        for entry in &entries {
+            pool.add(entry);
+        }
+        let member = |pool: &PoolBuilder, member: &Member| (
+            member.access_flags,
+            select(&pool.utf8s, member.name).unwrap(),
+            select(&pool.utf8s, member.descriptor).unwrap(),
+            pool.attributes(&member.attributes),
+        );
+        ClassFile {
+            minor_version: minor_version,
+            major_version: major_version,
+            access_flags: access_flags,
+            this_class: select(&pool.classes, this_class).unwrap(),
+            super_class: super_class.map_or(0, |class| select(&pool.classes, class).unwrap()),
+            interfaces: interfaces.iter().map(|&i| select(&pool.classes, i).unwrap()).collect(),
+            fields: fields.iter().map(|field| {
+                let (access_flags, name_index, descriptor_index, attributes) =
+                    member(&pool, field);
+                FieldInfo {
+                    access_flags: access_flags,
+                    name_index: name_index,
+                    descriptor_index: descriptor_index,
+                    attributes: attributes,
+                }
+            }).collect(),
+            methods: methods.iter().map(|method| {
+                let (access_flags, name_index, descriptor_index, attributes) =
+                    member(&pool, method);
+                MethodInfo {
+                    access_flags: access_flags,
+                    name_index: name_index,
+                    descriptor_index: descriptor_index,
+                    attributes: attributes,
+                }
+            }).collect(),
+            attributes: pool.attributes(&attributes),
+            constant_pool: ConstantPool::from_zero_indexed_vec(pool.entries),
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_write_then_parse(ref class in arb_class_file()) {
+        let written = write_class_file(class);
+        prop_assert_eq!(&parse_class_file(&written).unwrap(), class);
+    }
+
+    #[test]
+    fn test_parse_corrupted(ref corruptions in vec((any::<usize>(), any::<u8>()), 1..8)) {
+        let mut bytes = include_bytes!("../data/HelloWorld.class").to_vec();
+        let len = bytes.len();
+        for &(index, byte) in corruptions {
+            bytes[index % len] = byte;
+        }
+        let _ = parse_class_file(&bytes);
+    }
+}
+
+/// Replace each byte of a class file in turn with a few other values, and check that the parser
+/// returns rather than panicking, and that truncating the file anywhere is an error.
+#[test]
+fn test_parse_every_corrupted_byte() {
+    let original = include_bytes!("../data/HelloWorld.class");
+    for index in 0..original.len() {
+        let byte = original[index];
+        for &replacement in &[0x00, 0xff, byte ^ 0x80, byte.wrapping_add(1)] {
+            let mut bytes = original.to_vec();
+            bytes[index] = replacement;
+            let _ = parse_class_file(&bytes);
+        }
+        assert!(parse_class_file(&original[..index]).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "rt-jar")]
+
+extern crate rust_jvm;
+
+use std::env;
+use std::path::PathBuf;
+
+use self::rust_jvm::parser::class_file;
+use self::rust_jvm::vm::class_path::{ClassPath, ClassPathEntry};
+
+/// The path to the `rt.jar` to test against, which must contain class files of version 50.0.
+fn rt_jar() -> PathBuf {
+    PathBuf::from(env::var("RT_JAR").expect("RT_JAR must name the rt.jar to test against"))
+}
+
+#[test]
+fn test_load_object_from_rt_jar() {
+    let class_path = ClassPath::new(vec![ClassPathEntry::JarFile(rt_jar())]);
+    let bytes = class_path.find_class("java/lang/Object").expect("java/lang/Object not found");
+    let class = class_file::parse_class_file(&bytes).unwrap();
+    assert_eq!(class.major_version, 50);
+    assert_eq!(class.super_class, 0);
+}
@@ -0,0 +1,35 @@
+extern crate rust_jvm;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use self::rust_jvm::model::class_file;
+use self::rust_jvm::parser::class_file::parse_class_file;
+
+/// Find every class file in a directory and its subdirectories.
+fn find_class_files(directory: &Path, class_files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            find_class_files(&path, class_files);
+        } else if path.extension().map_or(false, |extension| extension == "class") {
+            class_files.push(path);
+        }
+    }
+}
+
+#[test]
+fn test_validate_data() {
+    let mut class_files = vec![];
+    find_class_files(Path::new("data"), &mut class_files);
+    assert!(!class_files.is_empty());
+    for path in class_files {
+        let mut bytes = vec![];
+        File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+        let class = parse_class_file(&bytes).unwrap();
+        if let Err(error) = class_file::validate(&class) {
+            panic!("{} is invalid: {}", path.display(), error);
+        }
+    }
+}